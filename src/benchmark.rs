@@ -0,0 +1,369 @@
+//! Storage throughput measurements for the `benchmark` command.
+//!
+//! Organizing reads files in more than one way: whole files front to back when
+//! hashing, small scattered chunks when comparing partial hashes, and several
+//! chunks of one file at once with [`network_io::parallel_chunked_read`]. Network
+//! mounts often handle these very differently, so each [`ReadPattern`] is timed
+//! separately. Copying into the destination is mostly writing, so [`run`] times
+//! creating the test file too.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! # use std::path::Path;
+//! # use sift::benchmark::{self, BenchmarkOptions};
+//! let options = BenchmarkOptions { size_mb: 200, ..BenchmarkOptions::default() };
+//! let report = benchmark::run(Path::new("/mnt/smb"), &options, |line| println!("{}", line))?;
+//! print!("{}", report);
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use clap::ValueEnum;
+
+use crate::network_io;
+
+/// Bytes per read in the random-access pattern, as when comparing partial hashes.
+const RANDOM_READ_SIZE: usize = 65536;
+
+/// Bytes per read in the parallel pattern.
+const PARALLEL_CHUNK_SIZE: usize = 4 * 1_048_576;
+
+/// Reads kept in flight at once in the parallel pattern.
+const PARALLEL_READS: usize = 8;
+
+/// File name of the test file, created in the benchmarked folder and removed afterwards.
+pub const TEST_FILE: &str = ".sift_benchmark.tmp";
+
+/// Default size of each write when creating the test file, in KB.
+pub const DEFAULT_WRITE_CHUNK_KB: usize = 1024;
+
+/// How the benchmark reads its test file.
+///
+/// * `Seq` - One buffered sequential read of the whole file
+/// * `Random` - 64KB reads at every offset of the file, in shuffled order
+/// * `Parallel` - 4MB chunks read 8 at a time, as [`network_io::parallel_chunked_read`] does
+/// * `All` - Each of the above in turn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum ReadPattern {
+    Seq,
+    Random,
+    Parallel,
+    #[default]
+    All,
+}
+
+impl ReadPattern {
+    /// Returns the patterns to measure: all three for `All`, otherwise just this one.
+    pub fn expand(self) -> Vec<ReadPattern> {
+        match self {
+            ReadPattern::All => vec![ReadPattern::Seq, ReadPattern::Random, ReadPattern::Parallel],
+            pattern => vec![pattern],
+        }
+    }
+}
+
+impl fmt::Display for ReadPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            ReadPattern::Seq => "sequential",
+            ReadPattern::Random => "random",
+            ReadPattern::Parallel => "parallel",
+            ReadPattern::All => "all",
+        })
+    }
+}
+
+/// Reads the whole file at `path` once using `pattern` and returns the bytes read.
+///
+/// `uncached` bypasses the page cache for the sequential pattern (Unix only); the
+/// other patterns always go through the cache, so repeated iterations over a
+/// small file may measure memory rather than storage.
+///
+/// # Errors
+///
+/// Fails if the file cannot be read, or with `InvalidInput` for [`ReadPattern::All`].
+pub fn read_pass(path: &Path, pattern: ReadPattern, uncached: bool) -> io::Result<u64> {
+    match pattern {
+        ReadPattern::Seq => read_sequential(path, uncached).map(|data| data.len() as u64),
+        ReadPattern::Random => {
+            let len = std::fs::metadata(path)?.len();
+            let mut total = 0;
+            for offset in shuffled_offsets(len, RANDOM_READ_SIZE as u64) {
+                total += network_io::read_file_chunk(path, offset, RANDOM_READ_SIZE)?.len() as u64;
+            }
+            Ok(total)
+        }
+        ReadPattern::Parallel => network_io::parallel_chunked_read(path, PARALLEL_CHUNK_SIZE, PARALLEL_READS)
+            .map(|data| data.len() as u64),
+        ReadPattern::All => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "read_pass takes a single pattern; see ReadPattern::expand",
+        )),
+    }
+}
+
+/// Writes a `size` byte test file at `path` in `chunk_size` byte writes and
+/// returns the bytes written. With `fsync`, the data is flushed to storage
+/// before returning, so the time includes what the OS would otherwise buffer.
+pub fn write_pass(path: &Path, size: u64, chunk_size: usize, fsync: bool) -> io::Result<u64> {
+    if chunk_size == 0 {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "chunk_size must be greater than zero"));
+    }
+    let chunk = vec![0u8; chunk_size];
+    let mut file = File::create(path)?;
+    let mut written = 0;
+    while written < size {
+        let n = chunk_size.min((size - written) as usize);
+        file.write_all(&chunk[..n])?;
+        written += n as u64;
+    }
+    if fsync {
+        file.sync_all()?;
+    }
+    Ok(written)
+}
+
+/// Settings for [`run`].
+///
+/// # Fields
+///
+/// * `size_mb` - Size of the test file, in MB
+/// * `iterations` - Timed passes per measurement
+/// * `pattern` - Read patterns to time
+/// * `uncached` - Bypass the page cache for sequential reads (see [`read_pass`])
+/// * `write_chunk_kb` - Size of each write when creating the test file, in KB
+/// * `fsync` - Flush each written test file to storage before stopping the clock
+#[derive(Debug, Clone)]
+pub struct BenchmarkOptions {
+    pub size_mb: usize,
+    pub iterations: usize,
+    pub pattern: ReadPattern,
+    pub uncached: bool,
+    pub write_chunk_kb: usize,
+    pub fsync: bool,
+}
+
+impl Default for BenchmarkOptions {
+    fn default() -> Self {
+        BenchmarkOptions {
+            size_mb: 100,
+            iterations: 5,
+            pattern: ReadPattern::All,
+            uncached: false,
+            write_chunk_kb: DEFAULT_WRITE_CHUNK_KB,
+            fsync: false,
+        }
+    }
+}
+
+/// Timings of one measured operation, one per iteration.
+#[derive(Debug, Clone)]
+pub struct Measurement {
+    /// What was measured, e.g. `write` or `sequential read`
+    pub label: String,
+    /// Bytes moved by each pass
+    pub bytes_per_pass: u64,
+    /// Time taken by each pass
+    pub durations: Vec<Duration>,
+}
+
+impl Measurement {
+    /// Returns the mean time per pass.
+    pub fn average(&self) -> Duration {
+        let total: Duration = self.durations.iter().sum();
+        total / self.durations.len().max(1) as u32
+    }
+
+    /// Returns the throughput over all passes, in MB/s.
+    pub fn throughput_mb_s(&self) -> f64 {
+        let total: Duration = self.durations.iter().sum();
+        throughput_mb_s(self.bytes_per_pass * self.durations.len() as u64, total)
+    }
+}
+
+/// Results of a [`run`]: the write measurement, then one per read pattern.
+#[derive(Debug, Clone)]
+pub struct BenchmarkReport {
+    pub write: Measurement,
+    pub reads: Vec<Measurement>,
+}
+
+impl fmt::Display for BenchmarkReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Benchmark Results:")?;
+        for measurement in std::iter::once(&self.write).chain(&self.reads) {
+            writeln!(
+                f,
+                "  {:<16}  avg {:>12?}  {:>10.2} MB/s",
+                measurement.label,
+                measurement.average(),
+                measurement.throughput_mb_s()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Removes the file at its path when dropped, whether or not the benchmark succeeded.
+struct TempFileGuard(PathBuf);
+
+impl Drop for TempFileGuard {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+/// Times writing and then reading a test file in `dir`.
+///
+/// The test file is written `iterations` times, then read `iterations` times
+/// per pattern. `progress` receives one line per timed pass. The test file is
+/// removed before returning, including on error.
+pub fn run(
+    dir: &Path,
+    options: &BenchmarkOptions,
+    mut progress: impl FnMut(&str),
+) -> io::Result<BenchmarkReport> {
+    let test_file = TempFileGuard(dir.join(TEST_FILE));
+    let size = options.size_mb as u64 * 1_048_576;
+    let iterations = options.iterations.max(1);
+
+    let mut time = |label: String, pass: &mut dyn FnMut() -> io::Result<u64>| -> io::Result<Measurement> {
+        let mut measurement = Measurement { label, bytes_per_pass: 0, durations: Vec::new() };
+        for i in 1..=iterations {
+            let start = Instant::now();
+            measurement.bytes_per_pass = pass()?;
+            let elapsed = start.elapsed();
+            progress(&format!("{} {}/{}: {:?}", measurement.label, i, iterations, elapsed));
+            measurement.durations.push(elapsed);
+        }
+        Ok(measurement)
+    };
+
+    let write_chunk = options.write_chunk_kb * 1024;
+    let write = time("write".to_string(), &mut || {
+        write_pass(&test_file.0, size, write_chunk, options.fsync)
+    })?;
+    let mut reads = Vec::new();
+    for pattern in options.pattern.expand() {
+        reads.push(time(format!("{} read", pattern), &mut || {
+            read_pass(&test_file.0, pattern, options.uncached)
+        })?);
+    }
+    Ok(BenchmarkReport { write, reads })
+}
+
+/// Returns throughput in MB/s (MiB, as the test file size is given in).
+pub fn throughput_mb_s(bytes: u64, elapsed: Duration) -> f64 {
+    bytes as f64 / 1_048_576.0 / elapsed.as_secs_f64().max(f64::EPSILON)
+}
+
+/// Reads the file in one pass, bypassing the page cache when requested and supported.
+fn read_sequential(path: &Path, uncached: bool) -> io::Result<Vec<u8>> {
+    #[cfg(unix)]
+    if uncached {
+        return network_io::uncached_read_file(path);
+    }
+
+    let _ = uncached;
+    network_io::buffered_read_file(path)
+}
+
+/// Returns every multiple of `block` below `len`, in a fixed pseudo-random order.
+///
+/// A fixed seed keeps runs comparable; the shuffle only has to defeat readahead.
+fn shuffled_offsets(len: u64, block: u64) -> Vec<u64> {
+    let mut offsets: Vec<u64> = (0..len).step_by(block as usize).collect();
+    // Fisher-Yates driven by xorshift64
+    let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+    for i in (1..offsets.len()).rev() {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        offsets.swap(i, (state % (i as u64 + 1)) as usize);
+    }
+    offsets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_tiny_benchmark_reports_throughput() -> io::Result<()> {
+        let dir = TempDir::new()?;
+        let options = BenchmarkOptions {
+            size_mb: 1,
+            iterations: 2,
+            write_chunk_kb: 64,
+            fsync: true,
+            ..BenchmarkOptions::default()
+        };
+        let mut lines = 0;
+        let report = run(dir.path(), &options, |_| lines += 1)?;
+        assert_eq!(lines, 8); // 2 writes + 2 passes of each of 3 read patterns
+
+        let rates: Vec<f64> = report
+            .to_string()
+            .lines()
+            .filter_map(|line| line.strip_suffix(" MB/s")?.split_whitespace().last()?.parse().ok())
+            .collect();
+        assert_eq!(rates.len(), 4);
+        assert!(rates.iter().all(|&rate| rate > 0.0), "{:?}", rates);
+        assert_eq!(report.write.bytes_per_pass, 1_048_576);
+        assert_eq!(fs::read_dir(dir.path())?.count(), 0, "test file is cleaned up");
+        Ok(())
+    }
+
+    #[test]
+    fn test_test_file_removed_on_error() -> io::Result<()> {
+        let dir = TempDir::new()?;
+        let path = dir.path().join(TEST_FILE);
+        {
+            let _guard = TempFileGuard(path.clone());
+            write_pass(&path, 1024, 256, false)?;
+            // A failing pass returns early through `?` with the guard in scope
+            assert!(write_pass(&path, 1024, 0, false).is_err());
+            assert!(path.exists());
+        }
+        assert!(!path.exists());
+
+        let options = BenchmarkOptions { size_mb: 1, write_chunk_kb: 0, ..BenchmarkOptions::default() };
+        assert!(run(dir.path(), &options, |_| {}).is_err());
+        assert_eq!(fs::read_dir(dir.path())?.count(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_shuffled_offsets_cover_file() {
+        let mut offsets = shuffled_offsets(10 * 65536 + 1, 65536);
+        assert_ne!(offsets, (0..11).map(|i| i * 65536).collect::<Vec<_>>());
+        offsets.sort();
+        assert_eq!(offsets, (0..11).map(|i| i * 65536).collect::<Vec<_>>());
+        assert!(shuffled_offsets(0, 65536).is_empty());
+    }
+
+    #[test]
+    fn test_every_pattern_reads_whole_file() -> io::Result<()> {
+        let dir = TempDir::new()?;
+        let path = dir.path().join("test.bin");
+        let len = 5 * 1_048_576 + 123;
+        fs::write(&path, vec![7u8; len])?;
+
+        let patterns = ReadPattern::All.expand();
+        assert_eq!(patterns.len(), 3);
+        for pattern in patterns {
+            assert_eq!(read_pass(&path, pattern, false)?, len as u64, "{}", pattern);
+        }
+        assert!(read_pass(&path, ReadPattern::All, false).is_err());
+        assert!((throughput_mb_s(2 * 1_048_576, Duration::from_secs(2)) - 1.0).abs() < 1e-9);
+        Ok(())
+    }
+}
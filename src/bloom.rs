@@ -0,0 +1,203 @@
+//! Bloom filter over the hashes of an index.
+//!
+//! A Bloom filter answers "definitely absent" or "possibly present" from a
+//! compact bit array, so lookups of content that was never organized can skip
+//! the index map entirely. It is saved next to the index as a sidecar,
+//! `<index>.bloom` (e.g. `.sift_index.bin.bloom`):
+//!
+//! | Bytes  | Content                                          |
+//! |--------|--------------------------------------------------|
+//! | 0..8   | Magic `SIFTBLM\0`                                |
+//! | 8..40  | Checksum of the index payload the filter matches |
+//! | 40..48 | Capacity in entries (`u64`, little-endian)       |
+//! | 48..52 | Number of hash functions (`u32`, little-endian)  |
+//! | 52..   | Bit array, as little-endian `u64` words          |
+//!
+//! A filter whose checksum does not match the index it sits next to is stale
+//! and is rejected on load, since it could miss entries added since it was
+//! written.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::index;
+
+const BLOOM_MAGIC: &[u8; 8] = b"SIFTBLM\0";
+
+/// Size of the sidecar header, before the bit array.
+const HEADER_LEN: usize = 8 + 32 + 8 + 4;
+
+/// Target false-positive rate while the filter holds at most its capacity.
+const FALSE_POSITIVE_RATE: f64 = 0.01;
+
+/// Smallest capacity a filter is sized for, so small indexes can grow a little
+/// before the filter needs rebuilding.
+const MIN_CAPACITY: usize = 1024;
+
+/// Returns the sidecar path of the Bloom filter of the index at `index_path`.
+pub fn sidecar_path(index_path: &Path) -> PathBuf {
+    let mut name = index_path.file_name().unwrap_or_default().to_os_string();
+    name.push(".bloom");
+    index_path.with_file_name(name)
+}
+
+/// A Bloom filter over string keys.
+///
+/// Inserted keys are always reported as possibly present; other keys are
+/// reported present with a probability of about 1% while no more than
+/// `capacity` keys have been inserted, rising beyond it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BloomFilter {
+    bits: Vec<u64>,
+    num_hashes: u32,
+    capacity: usize,
+}
+
+impl BloomFilter {
+    /// Creates an empty filter sized for `capacity` keys.
+    pub fn with_capacity(capacity: usize) -> Self {
+        let capacity = capacity.max(MIN_CAPACITY);
+        let ln2 = std::f64::consts::LN_2;
+        let num_bits = (-(capacity as f64) * FALSE_POSITIVE_RATE.ln() / (ln2 * ln2)).ceil() as usize;
+        let num_hashes = ((num_bits as f64 / capacity as f64) * ln2).round().max(1.0) as u32;
+        BloomFilter {
+            bits: vec![0; num_bits.div_ceil(64)],
+            num_hashes,
+            capacity,
+        }
+    }
+
+    /// Number of keys the filter was sized for.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Adds `key` to the filter.
+    pub fn insert(&mut self, key: &str) {
+        for bit in self.positions(key) {
+            self.bits[bit / 64] |= 1 << (bit % 64);
+        }
+    }
+
+    /// Returns `false` if `key` was never inserted, `true` if it may have been.
+    pub fn might_contain(&self, key: &str) -> bool {
+        self.positions(key).all(|bit| self.bits[bit / 64] & (1 << (bit % 64)) != 0)
+    }
+
+    /// Bit positions of `key`, by double hashing two 64-bit halves of its Blake3 hash.
+    fn positions(&self, key: &str) -> impl Iterator<Item = usize> + use<> {
+        let digest = blake3::hash(key.as_bytes());
+        let bytes = digest.as_bytes();
+        let h1 = u64::from_le_bytes(bytes[0..8].try_into().unwrap());
+        let h2 = u64::from_le_bytes(bytes[8..16].try_into().unwrap()) | 1;
+        let num_bits = self.bits.len() as u64 * 64;
+        (0..self.num_hashes as u64).map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % num_bits) as usize)
+    }
+
+    /// Encodes the filter as a sidecar for the index payload with `index_checksum`.
+    pub fn encode(&self, index_checksum: &[u8; 32]) -> Vec<u8> {
+        let mut data = Vec::with_capacity(HEADER_LEN + self.bits.len() * 8);
+        data.extend_from_slice(BLOOM_MAGIC);
+        data.extend_from_slice(index_checksum);
+        data.extend_from_slice(&(self.capacity as u64).to_le_bytes());
+        data.extend_from_slice(&self.num_hashes.to_le_bytes());
+        for word in &self.bits {
+            data.extend_from_slice(&word.to_le_bytes());
+        }
+        data
+    }
+
+    /// Decodes a sidecar written by [`BloomFilter::encode`].
+    ///
+    /// # Errors
+    ///
+    /// Fails with `InvalidData` if the data is not a Bloom filter sidecar, or if
+    /// it was written for a different index payload than `index_checksum`.
+    pub fn decode(data: &[u8], index_checksum: &[u8; 32]) -> io::Result<Self> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+        if data.len() < HEADER_LEN || !data.starts_with(BLOOM_MAGIC) {
+            return Err(invalid("not a Bloom filter sidecar"));
+        }
+        if &data[8..40] != index_checksum {
+            return Err(invalid("Bloom filter is stale: the index changed since it was written"));
+        }
+        let capacity = u64::from_le_bytes(data[40..48].try_into().unwrap()) as usize;
+        let num_hashes = u32::from_le_bytes(data[48..52].try_into().unwrap());
+        let words = &data[HEADER_LEN..];
+        if words.is_empty() || !words.len().is_multiple_of(8) || num_hashes == 0 {
+            return Err(invalid("corrupt Bloom filter: bad bit array"));
+        }
+        let bits = words
+            .chunks_exact(8)
+            .map(|word| u64::from_le_bytes(word.try_into().unwrap()))
+            .collect();
+        Ok(BloomFilter { bits, num_hashes, capacity })
+    }
+
+    /// Writes the filter to `path` (see [`BloomFilter::encode`]), synced and
+    /// renamed into place as the index is (see [`index::write_atomic`]).
+    pub fn save(&self, path: &Path, index_checksum: &[u8; 32]) -> io::Result<()> {
+        index::write_atomic(path, &self.encode(index_checksum))
+    }
+
+    /// Reads the filter saved at `path` (see [`BloomFilter::decode`]).
+    pub fn load(path: &Path, index_checksum: &[u8; 32]) -> io::Result<Self> {
+        Self::decode(&fs::read(path)?, index_checksum)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_no_false_negatives() {
+        let mut filter = BloomFilter::with_capacity(10_000);
+        let keys: Vec<String> = (0..10_000u32)
+            .map(|i| blake3::hash(&i.to_le_bytes()).to_hex().to_string())
+            .collect();
+        for key in &keys {
+            filter.insert(key);
+        }
+        assert!(keys.iter().all(|key| filter.might_contain(key)));
+
+        let false_positives = (0..10_000)
+            .filter(|i| filter.might_contain(&format!("absent-{}", i)))
+            .count();
+        assert!(false_positives < 300, "{} false positives", false_positives);
+    }
+
+    #[test]
+    fn test_overfilled_filter_still_finds_every_key() {
+        let mut filter = BloomFilter::with_capacity(0);
+        assert_eq!(filter.capacity(), MIN_CAPACITY);
+        for i in 0..5 * MIN_CAPACITY {
+            filter.insert(&i.to_string());
+        }
+        assert!((0..5 * MIN_CAPACITY).all(|i| filter.might_contain(&i.to_string())));
+    }
+
+    #[test]
+    fn test_sidecar_round_trip_and_staleness() -> io::Result<()> {
+        let dir = tempdir()?;
+        let index_path = dir.path().join(".sift_index.bin");
+        let path = sidecar_path(&index_path);
+        assert_eq!(path, dir.path().join(".sift_index.bin.bloom"));
+
+        let mut filter = BloomFilter::with_capacity(10);
+        filter.insert("abc123");
+        let checksum = [7; 32];
+        filter.save(&path, &checksum)?;
+        assert_eq!(BloomFilter::load(&path, &checksum)?, filter);
+        assert!(!dir.path().join(".sift_index.bin.bloom.tmp").exists());
+
+        let stale = BloomFilter::load(&path, &[8; 32]).unwrap_err();
+        assert_eq!(stale.kind(), io::ErrorKind::InvalidData);
+        let mut data = fs::read(&path)?;
+        data.truncate(HEADER_LEN + 3);
+        assert!(BloomFilter::decode(&data, &checksum).is_err());
+        Ok(())
+    }
+}
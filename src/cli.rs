@@ -17,8 +17,19 @@
 //! sift benchmark /mnt/smb --size-mb 500 --iterations 10
 //! ```
 
-use clap::{Parser, Subcommand};
-use std::path::PathBuf;
+use chrono::NaiveDate;
+use clap::{Parser, Subcommand, ValueEnum};
+use std::num::NonZeroUsize;
+use std::path::{Component, PathBuf};
+
+use crate::benchmark::ReadPattern;
+use crate::config;
+use crate::hash::{self, HashAlgorithm};
+use crate::index::{EntryOrder, IndexBackend};
+use crate::metadata::Granularity;
+use crate::organization::FileOp;
+use crate::organize;
+use crate::template::PathTemplate;
 
 /// The main CLI struct containing the command and global options.
 ///
@@ -46,6 +57,30 @@ pub struct Cli {
     /// Enable verbose output for debugging
     #[arg(short, long, global = true)]
     pub verbose: bool,
+
+    /// Only print results, the final summary and errors (no progress bars, per-file
+    /// lines or progress notes such as "Scanning for photos...")
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    pub quiet: bool,
+
+    /// Print newline-delimited JSON events to stdout instead of human-readable
+    /// logs (organize only)
+    #[arg(long, global = true)]
+    pub json: bool,
+
+    /// Retry failed network reads and copies up to N times (0 disables retrying)
+    #[arg(long, global = true, value_name = "N", default_value_t = 3)]
+    pub max_retries: usize,
+
+    /// Delay before the first retry in milliseconds; doubles on each further retry
+    #[arg(long, global = true, value_name = "MS", default_value_t = 100)]
+    pub retry_delay_ms: u64,
+
+    /// Read default options from this file instead of `./sift.toml` or
+    /// `~/.config/sift/sift.toml`; options on the command line still win, but a
+    /// flag set to `true` there cannot be turned off from the command line
+    #[arg(long, global = true, value_name = "FILE")]
+    pub config: Option<PathBuf>,
 }
 
 /// Available CLI commands for Sift.
@@ -75,24 +110,240 @@ pub enum Commands {
         #[arg(short = 'j', long)]
         jobs: Option<usize>,
 
+        /// Most files open at once while hashing and transferring, independent of
+        /// --jobs; lower it when a network share stalls under many connections
+        #[arg(long, value_name = "N")]
+        max_open: Option<NonZeroUsize>,
+
         /// Path to load/save index file
         #[arg(short, long)]
         index: Option<PathBuf>,
 
+        /// Storage format of the index: `bincode` rewrites the whole file on save,
+        /// `sqlite` keeps a database and only writes the entries a run adds
+        #[arg(long, value_enum, value_name = "FORMAT", default_value_t = IndexBackend::Bincode)]
+        index_format: IndexBackend,
+
+        /// Where to write the manifest listing what happened to each file
+        /// (default: DESTINATION/manifest.json)
+        #[arg(long, value_name = "PATH")]
+        manifest: Option<PathBuf>,
+
         /// Preview changes without copying files
         #[arg(short, long)]
         dry_run: bool,
+
+        /// How files are transferred into the destination
+        #[arg(short, long, value_enum, default_value_t = FileOp::Copy)]
+        mode: FileOp,
+
+        /// Give copies (including moves across filesystems) the time they were
+        /// made, instead of the source's modification and access times
+        #[arg(long)]
+        no_preserve_times: bool,
+
+        /// Scan subdirectories of the source recursively
+        #[arg(short, long)]
+        recursive: bool,
+
+        /// Append a destination → source line per organized file to
+        /// `.sift_manifest.jsonl` at the destination root (audit trail)
+        #[arg(long, visible_alias = "organize-link-back")]
+        link_back: bool,
+
+        /// Skip files whose destination already exists with the same size, without
+        /// hashing them. Size-only check: assumes unique, date-derived filenames
+        #[arg(long)]
+        skip_existing_destination: bool,
+
+        /// Hash and check every file, overriding --skip-existing-destination
+        #[arg(long)]
+        force: bool,
+
+        /// Only fully hash files that share a size and first 64KB with another file;
+        /// the rest are hashed from their copy after transfer, so the source is read once
+        #[arg(long)]
+        fast_dedup: bool,
+
+        /// Treat JPEG and PNG files as duplicates when their decoded pixels match once
+        /// rotated upright, even if their metadata differs. Slower: every image is decoded
+        #[arg(long, conflicts_with = "fast_dedup")]
+        pixel_dedup: bool,
+
+        /// Also organize hidden files and files in hidden folders (names starting
+        /// with `.`); Sift's own index and manifest files are always skipped
+        #[arg(long)]
+        include_hidden: bool,
+
+        /// With --recursive, also walk into symlinked folders (links that loop
+        /// back to a parent folder are skipped)
+        #[arg(long, requires = "recursive")]
+        follow_symlinks: bool,
+
+        /// Also scan files already inside the destination when it is nested in the
+        /// source (by default they are ignored as already organized)
+        #[arg(long)]
+        include_destination: bool,
+
+        /// Before organizing, hash the photos already in the destination that the
+        /// index does not know, so content organized by another tool is skipped.
+        /// Reads the whole destination once
+        #[arg(long)]
+        scan_dest: bool,
+
+        /// Only organize photos rated at least this many stars (XMP/EXIF rating, 0-5);
+        /// unrated photos still pass unless --require-rating is set
+        #[arg(long, value_name = "STARS", value_parser = clap::value_parser!(u8).range(0..=5))]
+        min_rating: Option<u8>,
+
+        /// Skip photos that carry no star rating
+        #[arg(long)]
+        require_rating: bool,
+
+        /// Only organize files taken on or after this date (YYYY-MM-DD)
+        #[arg(long, value_name = "DATE", value_parser = parse_date)]
+        since: Option<NaiveDate>,
+
+        /// Only organize files taken on or before this date (YYYY-MM-DD)
+        #[arg(long, value_name = "DATE", value_parser = parse_date)]
+        until: Option<NaiveDate>,
+
+        /// With --since or --until, also organize files whose date is unknown
+        #[arg(long)]
+        include_undated: bool,
+
+        /// Skip files smaller than this, e.g. thumbnails (bytes, or with a KB/MB/GB suffix)
+        #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+        min_size: Option<u64>,
+
+        /// Skip files larger than this (bytes, or with a KB/MB/GB suffix)
+        #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+        max_size: Option<u64>,
+
+        /// GeoNames cities file (e.g. cities1000.txt) used to name `{location}` folders
+        /// of a --template, instead of the built-in list of major cities
+        #[arg(long, value_name = "FILE")]
+        geonames: Option<PathBuf>,
+
+        /// Name location folders after the most populous place within 25 km
+        /// rather than the nearest one (e.g. "Paris" rather than a suburb)
+        #[arg(long)]
+        geo_prefer_population: bool,
+
+        /// Date folder depth: `year` (2023), `month` (2023/10) or `day` (2023/10/15)
+        #[arg(long, value_enum, default_value_t = Granularity::Day, conflicts_with = "template")]
+        granularity: Granularity,
+
+        /// Put each camera's photos in its own folder: `Camera/YYYY/MM/DD`
+        #[arg(long, conflicts_with = "template")]
+        by_camera: bool,
+
+        /// Folder layout under the destination, built from {year}, {month}, {day},
+        /// {location} and {camera} (default: {year}/{month}/{day})
+        #[arg(long, value_name = "TEMPLATE")]
+        template: Option<PathTemplate>,
+
+        /// Folder, relative to the destination, for files without a date
+        #[arg(
+            long,
+            value_name = "DIR",
+            default_value = organize::DEFAULT_UNDATED_DIR,
+            value_parser = parse_undated_dir
+        )]
+        undated_dir: PathBuf,
+
+        /// Analyze everything first, show the plan and ask for confirmation before
+        /// changing any files
+        #[arg(long, visible_alias = "organize-two-phase")]
+        two_phase: bool,
+
+        /// Answer yes to the --two-phase confirmation prompt
+        #[arg(short, long, requires = "two_phase")]
+        yes: bool,
+
+        /// Save the index after every N organized files so an interrupted run can
+        /// resume (0 saves only at the end)
+        #[arg(long, value_name = "N", default_value_t = organize::DEFAULT_CHECKPOINT_EVERY)]
+        checkpoint_every: usize,
+
+        /// With --dry-run, exit with status 1 if any file would be organized
+        /// and 0 if the destination is already up to date (like `git diff --exit-code`)
+        #[arg(long, visible_alias = "organize-dry-run-exit-code", requires = "dry_run")]
+        exit_code: bool,
+
+        /// Extra photo extensions to pick up, comma-separated (e.g. `cr2,nef,dng`)
+        #[arg(long, value_name = "EXT", value_delimiter = ',')]
+        ext: Vec<String>,
+
+        /// Use only the --ext extensions instead of adding them to the defaults
+        #[arg(long, requires = "ext")]
+        ext_only: bool,
+
+        /// Pick photos by their first bytes (JPEG, PNG, HEIC, TIFF-based RAW) instead
+        /// of their extension, so misnamed files are sorted correctly. Skips videos
+        #[arg(long, conflicts_with = "ext")]
+        by_content: bool,
     },
 
     /// Hash a file or directory
     Hash {
-        /// File or directory to hash
+        /// File or directory to hash (with --check, the listing to verify)
         #[arg(value_name = "PATH")]
         path: PathBuf,
 
         /// Compute hash for all files in directory recursively
         #[arg(short, long)]
         recursive: bool,
+
+        /// Hash algorithm; sha256 matches `sha256sum`, for comparing with
+        /// checksums from other tools [default: blake3, or with --check, the
+        /// one the listing records]
+        #[arg(short, long, value_enum)]
+        algorithm: Option<HashAlgorithm>,
+
+        /// Memory-map files and hash each on all cores; faster on local SSDs,
+        /// usually slower on network shares (Blake3 only)
+        #[arg(long)]
+        mmap: bool,
+
+        /// Also hash hidden files and files in hidden folders of a directory
+        #[arg(long)]
+        include_hidden: bool,
+
+        /// With --recursive, also walk into symlinked folders (links that loop
+        /// back to a parent folder are skipped)
+        #[arg(long, requires = "recursive")]
+        follow_symlinks: bool,
+
+        /// Hash each file at least this many bytes on all cores (default
+        /// when given without a value: 16MB; Blake3 only)
+        #[arg(
+            long,
+            value_name = "BYTES",
+            num_args = 0..=1,
+            default_missing_value = hash::DEFAULT_PARALLEL_THRESHOLD.to_string(),
+            conflicts_with = "mmap"
+        )]
+        parallel_threshold: Option<u64>,
+
+        /// Write a `<hash>  <path>` listing to this file instead of printing
+        /// the hashes, for verifying them later with --check
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+
+        /// Re-hash the files listed in PATH (as written by --output) and report
+        /// any that changed; exits with status 1 if one did
+        #[arg(long, conflicts_with_all = ["recursive", "output"])]
+        check: bool,
+
+        /// Print paths relative to this directory (paths outside it are
+        /// printed in full)
+        #[arg(long, value_name = "BASE", conflicts_with_all = ["output", "check"])]
+        relative_to: Option<PathBuf>,
+
+        /// Print only file names, for a flat report
+        #[arg(long, conflicts_with_all = ["relative_to", "output", "check"])]
+        flatten: bool,
     },
 
     /// Show index contents
@@ -101,9 +352,119 @@ pub enum Commands {
         #[arg(value_name = "INDEX_FILE")]
         path: PathBuf,
 
-        /// Number of entries to display
+        /// Number of entries to display (text format only)
         #[arg(short, long, default_value = "10")]
         limit: usize,
+
+        /// Number of entries to skip before displaying, to page through a large
+        /// index (text format only)
+        #[arg(long, value_name = "N", default_value_t = 0)]
+        skip: usize,
+
+        /// Order entries are displayed in
+        #[arg(long, value_enum, default_value_t = EntryOrder::Hash)]
+        sort: EntryOrder,
+
+        /// List every entry whose hash starts with QUERY, or with a path containing
+        /// it (case-insensitive), instead of paging through the index
+        #[arg(long, value_name = "QUERY", conflicts_with_all = ["skip", "format"])]
+        find: Option<String>,
+
+        /// Output format; `json` dumps every entry as a JSON array
+        #[arg(short, long, value_enum, default_value_t = IndexFormat::Text)]
+        format: IndexFormat,
+    },
+
+    /// List files in a directory whose contents are already in the index
+    ///
+    /// Prints one `FILE<TAB>ORIGINAL[<TAB>ORIGINAL...]` line per duplicate, listing every
+    /// known location of its contents, sorted by file path.
+    Duplicates {
+        /// Directory to scan (recursively)
+        #[arg(value_name = "DIR")]
+        dir: PathBuf,
+
+        /// Path to index file
+        #[arg(short, long, value_name = "INDEX_FILE")]
+        index: PathBuf,
+    },
+
+    /// Check that the files recorded in an index still exist (and, optionally, are unchanged)
+    ///
+    /// Exits with status 1 if any indexed file is missing or changed.
+    Verify {
+        /// Path to index file
+        #[arg(short, long, value_name = "INDEX_FILE")]
+        index: PathBuf,
+
+        /// Also re-hash every file and compare against the indexed hash
+        #[arg(long)]
+        rehash: bool,
+
+        /// Remove missing files from the index and save it
+        #[arg(long)]
+        prune: bool,
+    },
+
+    /// Summarize a photo folder: counts by year, camera and extension, and how many
+    /// photos lack a date or GPS position. Nothing is moved
+    Stats {
+        /// Directory to summarize
+        #[arg(value_name = "DIR")]
+        dir: PathBuf,
+
+        /// Include subdirectories
+        #[arg(short, long)]
+        recursive: bool,
+    },
+
+    /// Reverse an organize run from its manifest: delete copied or linked files and
+    /// move moved files back
+    ///
+    /// Files changed since the run are skipped with a warning, and folders left
+    /// empty are removed. Exits with status 1 if anything was skipped.
+    Undo {
+        /// Manifest written by the run (DESTINATION/manifest.json by default)
+        #[arg(value_name = "MANIFEST")]
+        manifest: PathBuf,
+
+        /// Destination the run organized into; empty folders are only removed below it
+        /// (default: the folder containing the manifest)
+        #[arg(short, long, value_name = "DIR")]
+        destination: Option<PathBuf>,
+
+        /// Index to drop the undone files from (default: DESTINATION/.sift_index.bin, if present)
+        #[arg(short, long, value_name = "INDEX_FILE")]
+        index: Option<PathBuf>,
+    },
+
+    /// Upgrade an index file to the current on-disk format
+    Migrate {
+        /// Path to index file
+        #[arg(value_name = "INDEX_FILE")]
+        index: PathBuf,
+
+        /// Also build a Bloom filter sidecar (INDEX_FILE.bloom) to speed up
+        /// duplicate checks on large indexes; later saves keep it up to date
+        #[arg(long)]
+        bloom: bool,
+    },
+
+    /// Combine two index files, e.g. from libraries organized on two machines
+    ///
+    /// Paths of content both indexes know are kept together in one entry.
+    IndexMerge {
+        /// First index file
+        #[arg(value_name = "INDEX_A")]
+        first: PathBuf,
+
+        /// Second index file, merged into the first
+        #[arg(value_name = "INDEX_B")]
+        second: PathBuf,
+
+        /// Where to write the merged index (may be one of the inputs)
+        #[arg(short, long, value_name = "OUTPUT")]
+        output: PathBuf,
     },
 
     /// Perform geographic clustering on EXIF data
@@ -115,6 +476,69 @@ pub enum Commands {
         /// Show cluster details
         #[arg(short, long)]
         details: bool,
+
+        /// Ignore photos whose EXIF GPS positioning error exceeds this many meters
+        #[arg(long, visible_alias = "cluster-min-gps-accuracy", value_name = "METERS")]
+        min_gps_accuracy: Option<f64>,
+
+        /// Photos within this many kilometers of each other are neighbors
+        #[arg(long, value_name = "KM", default_value_t = 1.0, value_parser = parse_eps_km, conflicts_with = "by_time")]
+        eps_km: f64,
+
+        /// Photos (itself included) a photo needs within --eps-km to start a
+        /// cluster; lower values make smaller clusters
+        #[arg(long, value_name = "N", default_value = "3", conflicts_with = "by_time")]
+        min_points: NonZeroUsize,
+
+        /// GeoNames cities file (e.g. cities1000.txt) used to name clusters,
+        /// instead of the built-in list of major cities
+        #[arg(long, value_name = "FILE")]
+        geonames: Option<PathBuf>,
+
+        /// Name clusters after the most populous place within 25 km rather than
+        /// the nearest one
+        #[arg(long)]
+        geo_prefer_population: bool,
+
+        /// Group photos into events by capture time instead of by location
+        /// (works without GPS data)
+        #[arg(long)]
+        by_time: bool,
+
+        /// With --by-time, start a new event after a pause longer than this many hours
+        /// (1 to 8760, a year)
+        #[arg(
+            long,
+            value_name = "HOURS",
+            default_value_t = 12,
+            value_parser = clap::value_parser!(i64).range(1..=8760),
+            requires = "by_time"
+        )]
+        gap: i64,
+
+        /// Write the clusters to a map file: KML if it ends in `.kml`, GeoJSON otherwise
+        #[arg(long, value_name = "FILE", conflicts_with = "by_time")]
+        export: Option<PathBuf>,
+
+        /// Only cluster photos taken on or after this date (YYYY-MM-DD)
+        #[arg(long, value_name = "DATE", value_parser = parse_date)]
+        since: Option<NaiveDate>,
+
+        /// Only cluster photos taken on or before this date (YYYY-MM-DD)
+        #[arg(long, value_name = "DATE", value_parser = parse_date)]
+        until: Option<NaiveDate>,
+
+        /// With --since or --until, also cluster photos whose date is unknown
+        #[arg(long)]
+        include_undated: bool,
+
+        /// Extra photo extensions to pick up, comma-separated (e.g. `cr2,nef,dng`)
+        #[arg(long, value_name = "EXT", value_delimiter = ',')]
+        ext: Vec<String>,
+
+        /// Use only the --ext extensions instead of adding them to the defaults
+        #[arg(long, requires = "ext")]
+        ext_only: bool,
     },
 
     /// Test performance on network share
@@ -130,14 +554,87 @@ pub enum Commands {
         /// Number of test iterations
         #[arg(short = 'n', long, default_value = "5")]
         iterations: usize,
+
+        /// Bypass the OS page cache so every sequential read comes from storage
+        /// (Unix only: O_DIRECT on Linux, F_NOCACHE on macOS)
+        #[arg(long)]
+        uncached: bool,
+
+        /// Read pattern to measure: sequential, random 64KB reads, parallel 4MB
+        /// chunks, or each in turn
+        #[arg(short, long, value_enum, default_value_t = ReadPattern::All)]
+        pattern: ReadPattern,
+
+        /// Size of each write when creating the test file, in KB
+        #[arg(long, value_name = "KB", default_value = "1024")]
+        write_chunk_kb: NonZeroUsize,
+
+        /// Flush the test file to storage before timing each write ends, as a
+        /// durable copy would
+        #[arg(long)]
+        fsync: bool,
     },
 }
 
+/// Output format of the `index` command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum IndexFormat {
+    /// One `hash: path` line per entry, up to `--limit`
+    Text,
+    /// All entries as a JSON array of `{hash, file_paths, ...}` objects
+    Json,
+}
+
+/// Parses a `--since` / `--until` date.
+fn parse_date(value: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| format!("invalid date {:?}: expected YYYY-MM-DD, e.g. 2024-03-31", value))
+}
+
+/// Parses a `--min-size` / `--max-size` value: a number of bytes, optionally
+/// followed by `KB`, `MB` or `GB` (powers of 1024, case-insensitive).
+fn parse_size(value: &str) -> Result<u64, String> {
+    let invalid = || format!("invalid size {:?}: expected bytes or a KB, MB or GB suffix, e.g. 500KB", value);
+    let value = value.trim();
+    let (number, unit) = value.split_at(value.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(value.len()));
+    let multiplier: u64 = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "KB" => 1 << 10,
+        "MB" => 1 << 20,
+        "GB" => 1 << 30,
+        _ => return Err(invalid()),
+    };
+    let number: f64 = number.parse().map_err(|_| invalid())?;
+    Ok((number * multiplier as f64).round() as u64)
+}
+
+/// Parses a `--eps-km` value: a distance in kilometers, greater than zero.
+fn parse_eps_km(value: &str) -> Result<f64, String> {
+    match value.trim().parse::<f64>() {
+        Ok(km) if km > 0.0 && km.is_finite() => Ok(km),
+        _ => Err(format!("invalid distance {:?}: expected kilometers greater than 0, e.g. 0.5", value)),
+    }
+}
+
+/// Parses an `--undated-dir` value: a relative path that stays inside the
+/// destination, so no absolute paths and no `..`.
+fn parse_undated_dir(value: &str) -> Result<PathBuf, String> {
+    let path = PathBuf::from(value);
+    let mut components = path.components().filter(|component| *component != Component::CurDir).peekable();
+    if components.peek().is_some() && components.all(|component| matches!(component, Component::Normal(_))) {
+        Ok(path)
+    } else {
+        Err(format!("invalid folder {:?}: expected a path relative to the destination, without `..`", value))
+    }
+}
+
 impl Cli {
     /// Parses command-line arguments into a Cli struct.
     ///
-    /// Uses Clap's default parsing mechanism to read arguments from std::env::args().
-    /// Automatically prints help and exits on parse errors or --help.
+    /// Uses Clap's default parsing mechanism to read arguments from std::env::args(),
+    /// then fills in options they leave unset from the config file (see
+    /// [`config`](crate::config)). Automatically prints help and exits on parse
+    /// errors, config errors or --help.
     ///
     /// # Returns
     ///
@@ -153,7 +650,8 @@ impl Cli {
     /// }
     /// ```
     pub fn parse_args() -> Self {
-        Parser::parse()
+        config::parse_with_config(std::env::args_os().collect(), &config::search_paths())
+            .unwrap_or_else(|err| err.exit())
     }
 }
 
@@ -174,6 +672,10 @@ mod tests {
                 jobs,
                 index,
                 dry_run,
+                mode,
+                recursive,
+                exit_code,
+                ..
             } => {
                 assert_eq!(source.to_str().unwrap(), "/source");
                 assert_eq!(destination.to_str().unwrap(), "/dest");
@@ -181,6 +683,9 @@ mod tests {
                 assert!(jobs.is_none());
                 assert!(index.is_none());
                 assert!(!dry_run);
+                assert_eq!(mode, FileOp::Copy);
+                assert!(!recursive);
+                assert!(!exit_code);
             }
             _ => panic!("Expected Organize command"),
         }
@@ -235,7 +740,7 @@ mod tests {
         let cli = Cli::try_parse_from(args).unwrap();
 
         match cli.command {
-            Commands::Hash { path, recursive } => {
+            Commands::Hash { path, recursive, .. } => {
                 assert_eq!(path.to_str().unwrap(), "/photos");
                 assert!(recursive);
             }
@@ -249,7 +754,7 @@ mod tests {
         let cli = Cli::try_parse_from(args).unwrap();
 
         match cli.command {
-            Commands::Hash { path, recursive } => {
+            Commands::Hash { path, recursive, .. } => {
                 assert_eq!(path.to_str().unwrap(), "/photo.jpg");
                 assert!(!recursive);
             }
@@ -263,9 +768,12 @@ mod tests {
         let cli = Cli::try_parse_from(args).unwrap();
 
         match cli.command {
-            Commands::Index { path, limit } => {
+            Commands::Index { path, limit, skip, sort, format, .. } => {
                 assert_eq!(path.to_str().unwrap(), "index.bin");
                 assert_eq!(limit, 50);
+                assert_eq!(skip, 0);
+                assert_eq!(sort, EntryOrder::Hash);
+                assert_eq!(format, IndexFormat::Text);
             }
             _ => panic!("Expected Index command"),
         }
@@ -277,7 +785,7 @@ mod tests {
         let cli = Cli::try_parse_from(args).unwrap();
 
         match cli.command {
-            Commands::Cluster { source, details } => {
+            Commands::Cluster { source, details, .. } => {
                 assert_eq!(source.to_str().unwrap(), "/photos");
                 assert!(details);
             }
@@ -303,10 +811,18 @@ mod tests {
                 path,
                 size_mb,
                 iterations,
+                uncached,
+                pattern,
+                write_chunk_kb,
+                fsync,
             } => {
                 assert_eq!(path.to_str().unwrap(), "/mnt/smb");
                 assert_eq!(size_mb, 200);
                 assert_eq!(iterations, 10);
+                assert!(!uncached);
+                assert_eq!(pattern, ReadPattern::All);
+                assert_eq!(write_chunk_kb.get(), crate::benchmark::DEFAULT_WRITE_CHUNK_KB);
+                assert!(!fsync);
             }
             _ => panic!("Expected Benchmark command"),
         }
@@ -328,6 +844,14 @@ mod tests {
         assert!(!cli.verbose);
     }
 
+    #[test]
+    fn test_quiet_flag() {
+        let cli = Cli::try_parse_from(vec!["sift", "organize", "/source", "/dest", "-q"]).unwrap();
+        assert!(cli.quiet);
+        assert!(!cli.verbose);
+        assert!(Cli::try_parse_from(vec!["sift", "--quiet", "--verbose", "organize", "/source", "/dest"]).is_err());
+    }
+
     #[test]
     fn test_organize_with_all_options() {
         let args = vec![
@@ -354,6 +878,7 @@ mod tests {
                 jobs,
                 index,
                 dry_run,
+                ..
             } => {
                 assert_eq!(source.to_str().unwrap(), "/src");
                 assert_eq!(destination.to_str().unwrap(), "/dst");
@@ -391,4 +916,837 @@ mod tests {
             _ => panic!("Expected Organize command"),
         }
     }
+
+    #[test]
+    fn test_organize_mode_flag() {
+        let args = vec!["sift", "organize", "/source", "/dest", "--mode", "hardlink"];
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        match cli.command {
+            Commands::Organize { mode, .. } => {
+                assert_eq!(mode, FileOp::Hardlink);
+            }
+            _ => panic!("Expected Organize command"),
+        }
+    }
+
+    #[test]
+    fn test_organize_mode_invalid() {
+        let args = vec!["sift", "organize", "/source", "/dest", "--mode", "teleport"];
+        assert!(Cli::try_parse_from(args).is_err());
+    }
+
+    #[test]
+    fn test_organize_exit_code_with_dry_run() {
+        let args = vec!["sift", "organize", "/source", "/dest", "--dry-run", "--exit-code"];
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        match cli.command {
+            Commands::Organize { dry_run, exit_code, .. } => {
+                assert!(dry_run);
+                assert!(exit_code);
+            }
+            _ => panic!("Expected Organize command"),
+        }
+    }
+
+    #[test]
+    fn test_organize_exit_code_requires_dry_run() {
+        let args = vec!["sift", "organize", "/source", "/dest", "--exit-code"];
+        assert!(Cli::try_parse_from(args).is_err());
+    }
+
+    #[test]
+    fn test_organize_dry_run_exit_code_alias() {
+        let args = vec!["sift", "organize", "/source", "/dest", "--dry-run", "--organize-dry-run-exit-code"];
+        match Cli::try_parse_from(args).unwrap().command {
+            Commands::Organize { exit_code, .. } => assert!(exit_code),
+            _ => panic!("Expected Organize command"),
+        }
+    }
+
+    #[test]
+    fn test_benchmark_uncached_flag() {
+        let args = vec!["sift", "benchmark", "/mnt/nfs", "--uncached"];
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        match cli.command {
+            Commands::Benchmark { uncached, .. } => {
+                assert!(uncached);
+            }
+            _ => panic!("Expected Benchmark command"),
+        }
+    }
+
+    #[test]
+    fn test_organize_recursive_flag() {
+        let args = vec!["sift", "organize", "/source", "/dest", "-r"];
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        match cli.command {
+            Commands::Organize { recursive, .. } => {
+                assert!(recursive);
+            }
+            _ => panic!("Expected Organize command"),
+        }
+    }
+
+    #[test]
+    fn test_organize_link_back_flag() {
+        let args = vec!["sift", "organize", "/source", "/dest", "--link-back"];
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        match cli.command {
+            Commands::Organize { link_back, .. } => {
+                assert!(link_back);
+            }
+            _ => panic!("Expected Organize command"),
+        }
+    }
+
+    #[test]
+    fn test_organize_link_back_alias() {
+        let args = vec!["sift", "organize", "/source", "/dest", "--organize-link-back"];
+        match Cli::try_parse_from(args).unwrap().command {
+            Commands::Organize { link_back, .. } => assert!(link_back),
+            _ => panic!("Expected Organize command"),
+        }
+    }
+
+    #[test]
+    fn test_organize_ext_list() {
+        let args = vec!["sift", "organize", "/source", "/dest", "--ext", "cr2,dng"];
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        match cli.command {
+            Commands::Organize { ext, ext_only, .. } => {
+                assert_eq!(ext, vec!["cr2".to_string(), "dng".to_string()]);
+                assert!(!ext_only);
+            }
+            _ => panic!("Expected Organize command"),
+        }
+    }
+
+    #[test]
+    fn test_ext_only_requires_ext() {
+        let args = vec!["sift", "organize", "/source", "/dest", "--ext-only"];
+        assert!(Cli::try_parse_from(args).is_err());
+    }
+
+    #[test]
+    fn test_cluster_ext_override() {
+        let args = vec!["sift", "cluster", "/photos", "--ext", "nef", "--ext-only"];
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        match cli.command {
+            Commands::Cluster { ext, ext_only, .. } => {
+                assert_eq!(ext, vec!["nef".to_string()]);
+                assert!(ext_only);
+            }
+            _ => panic!("Expected Cluster command"),
+        }
+    }
+
+    #[test]
+    fn test_cluster_min_gps_accuracy() {
+        let args = vec!["sift", "cluster", "/photos", "--min-gps-accuracy", "50"];
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        match cli.command {
+            Commands::Cluster { min_gps_accuracy, .. } => {
+                assert_eq!(min_gps_accuracy, Some(50.0));
+            }
+            _ => panic!("Expected Cluster command"),
+        }
+
+        let args = vec!["sift", "cluster", "/photos", "--cluster-min-gps-accuracy", "5"];
+        match Cli::try_parse_from(args).unwrap().command {
+            Commands::Cluster { min_gps_accuracy, .. } => assert_eq!(min_gps_accuracy, Some(5.0)),
+            _ => panic!("Expected Cluster command"),
+        }
+    }
+
+    #[test]
+    fn test_migrate_command() {
+        let args = vec!["sift", "migrate", "/dest/.sift_index.bin"];
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        match cli.command {
+            Commands::Migrate { index, bloom } => {
+                assert_eq!(index.to_str().unwrap(), "/dest/.sift_index.bin");
+                assert!(!bloom);
+            }
+            _ => panic!("Expected Migrate command"),
+        }
+    }
+
+    #[test]
+    fn test_index_json_format() {
+        let args = vec!["sift", "index", "index.bin", "--format", "json"];
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        match cli.command {
+            Commands::Index { format, .. } => {
+                assert_eq!(format, IndexFormat::Json);
+            }
+            _ => panic!("Expected Index command"),
+        }
+    }
+
+    #[test]
+    fn test_organize_skip_existing_destination() {
+        let args = vec![
+            "sift",
+            "organize",
+            "/source",
+            "/dest",
+            "--skip-existing-destination",
+            "--force",
+        ];
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        match cli.command {
+            Commands::Organize { skip_existing_destination, force, .. } => {
+                assert!(skip_existing_destination);
+                assert!(force);
+            }
+            _ => panic!("Expected Organize command"),
+        }
+    }
+
+    #[test]
+    fn test_organize_include_destination() {
+        let args = vec!["sift", "organize", "/photos", "/photos/Organized", "-r", "--include-destination"];
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        match cli.command {
+            Commands::Organize { include_destination, recursive, .. } => {
+                assert!(include_destination);
+                assert!(recursive);
+            }
+            _ => panic!("Expected Organize command"),
+        }
+    }
+
+    #[test]
+    fn test_organize_min_rating() {
+        let args = vec!["sift", "organize", "/source", "/dest", "--min-rating", "3", "--require-rating"];
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        match cli.command {
+            Commands::Organize { min_rating, require_rating, .. } => {
+                assert_eq!(min_rating, Some(3));
+                assert!(require_rating);
+            }
+            _ => panic!("Expected Organize command"),
+        }
+
+        let args = vec!["sift", "organize", "/source", "/dest", "--min-rating", "6"];
+        assert!(Cli::try_parse_from(args).is_err());
+    }
+
+    #[test]
+    fn test_duplicates_command() {
+        let args = vec!["sift", "duplicates", "/incoming", "--index", "/dest/.sift_index.bin"];
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        match cli.command {
+            Commands::Duplicates { dir, index } => {
+                assert_eq!(dir.to_str().unwrap(), "/incoming");
+                assert_eq!(index.to_str().unwrap(), "/dest/.sift_index.bin");
+            }
+            _ => panic!("Expected Duplicates command"),
+        }
+
+        assert!(Cli::try_parse_from(vec!["sift", "duplicates", "/incoming"]).is_err());
+    }
+
+    #[test]
+    fn test_organize_two_phase() {
+        let args = vec!["sift", "organize", "/source", "/dest", "--two-phase", "--yes"];
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        match cli.command {
+            Commands::Organize { two_phase, yes, .. } => {
+                assert!(two_phase);
+                assert!(yes);
+            }
+            _ => panic!("Expected Organize command"),
+        }
+
+        let args = vec!["sift", "organize", "/source", "/dest", "--organize-two-phase"];
+        match Cli::try_parse_from(args).unwrap().command {
+            Commands::Organize { two_phase, .. } => assert!(two_phase),
+            _ => panic!("Expected Organize command"),
+        }
+
+        let args = vec!["sift", "organize", "/source", "/dest", "--yes"];
+        assert!(Cli::try_parse_from(args).is_err());
+    }
+
+    #[test]
+    fn test_cluster_by_time() {
+        let args = vec!["sift", "cluster", "/photos", "--by-time", "--gap", "6"];
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        match cli.command {
+            Commands::Cluster { by_time, gap, .. } => {
+                assert!(by_time);
+                assert_eq!(gap, 6);
+            }
+            _ => panic!("Expected Cluster command"),
+        }
+
+        let cli = Cli::try_parse_from(vec!["sift", "cluster", "/photos", "--by-time"]).unwrap();
+        assert!(matches!(cli.command, Commands::Cluster { gap: 12, .. }));
+        assert!(Cli::try_parse_from(vec!["sift", "cluster", "/photos", "--gap", "6"]).is_err());
+
+        // Out of range gaps are rejected rather than overflowing the duration
+        for gap in ["0", "-3", "8761", "9223372036854775807"] {
+            assert!(Cli::try_parse_from(vec!["sift", "cluster", "/photos", "--by-time", "--gap", gap]).is_err());
+        }
+        let cli = Cli::try_parse_from(vec!["sift", "cluster", "/photos", "--by-time", "--gap", "8760"]).unwrap();
+        assert!(matches!(cli.command, Commands::Cluster { gap: 8760, .. }));
+    }
+
+    #[test]
+    fn test_geonames_flag() {
+        let args = vec!["sift", "organize", "/source", "/dest", "--geonames", "cities1000.txt"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        match cli.command {
+            Commands::Organize { geonames, .. } => {
+                assert_eq!(geonames, Some(PathBuf::from("cities1000.txt")));
+            }
+            _ => panic!("Expected Organize command"),
+        }
+
+        let args = vec!["sift", "cluster", "/photos", "--geonames", "cities5000.txt"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        match cli.command {
+            Commands::Cluster { geonames, geo_prefer_population, .. } => {
+                assert_eq!(geonames, Some(PathBuf::from("cities5000.txt")));
+                assert!(!geo_prefer_population);
+            }
+            _ => panic!("Expected Cluster command"),
+        }
+    }
+
+    #[test]
+    fn test_geo_prefer_population_flag() {
+        let args = vec!["sift", "organize", "/source", "/dest", "--with-clustering", "--geo-prefer-population"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        assert!(matches!(cli.command, Commands::Organize { geo_prefer_population: true, .. }));
+
+        let args = vec!["sift", "cluster", "/photos", "--geo-prefer-population"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        assert!(matches!(cli.command, Commands::Cluster { geo_prefer_population: true, .. }));
+    }
+
+    #[test]
+    fn test_retry_flags() {
+        let args = vec!["sift", "organize", "/source", "/dest", "--max-retries", "0", "--retry-delay-ms", "250"];
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        assert_eq!(cli.max_retries, 0);
+        assert_eq!(cli.retry_delay_ms, 250);
+
+        let cli = Cli::try_parse_from(vec!["sift", "hash", "/photo.jpg"]).unwrap();
+        assert_eq!(cli.max_retries, 3);
+        assert_eq!(cli.retry_delay_ms, 100);
+    }
+
+    #[test]
+    fn test_verify_command() {
+        let args = vec!["sift", "verify", "--index", "/dest/.sift_index.bin", "--rehash"];
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        match cli.command {
+            Commands::Verify { index, rehash, prune } => {
+                assert_eq!(index.to_str().unwrap(), "/dest/.sift_index.bin");
+                assert!(rehash);
+                assert!(!prune);
+            }
+            _ => panic!("Expected Verify command"),
+        }
+    }
+
+    #[test]
+    fn test_checkpoint_every_flag() {
+        let cli = Cli::try_parse_from(vec!["sift", "organize", "/source", "/dest"]).unwrap();
+        match cli.command {
+            Commands::Organize { checkpoint_every, .. } => assert_eq!(checkpoint_every, 500),
+            _ => panic!("Expected Organize command"),
+        }
+
+        let args = vec!["sift", "organize", "/source", "/dest", "--checkpoint-every", "50"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        match cli.command {
+            Commands::Organize { checkpoint_every, .. } => assert_eq!(checkpoint_every, 50),
+            _ => panic!("Expected Organize command"),
+        }
+    }
+
+    #[test]
+    fn test_template_flag() {
+        let args = vec!["sift", "organize", "/source", "/dest", "--template", "{year}/{location}"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        match cli.command {
+            Commands::Organize { template, .. } => {
+                assert_eq!(template.unwrap().to_string(), "{year}/{location}");
+            }
+            _ => panic!("Expected Organize command"),
+        }
+
+        let args = vec!["sift", "organize", "/source", "/dest", "--template", "{year}/{yaer}"];
+        let err = Cli::try_parse_from(args).err().unwrap();
+        assert!(err.to_string().contains("unknown path template token {yaer}"));
+    }
+
+    #[test]
+    fn test_granularity_flag() {
+        let cli = Cli::try_parse_from(vec!["sift", "organize", "/source", "/dest"]).unwrap();
+        match cli.command {
+            Commands::Organize { granularity, .. } => assert_eq!(granularity, Granularity::Day),
+            _ => panic!("Expected Organize command"),
+        }
+
+        let args = vec!["sift", "organize", "/source", "/dest", "--granularity", "month"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        match cli.command {
+            Commands::Organize { granularity, .. } => assert_eq!(granularity, Granularity::Month),
+            _ => panic!("Expected Organize command"),
+        }
+
+        let args = vec!["sift", "organize", "/s", "/d", "--granularity", "year", "--template", "{year}"];
+        assert!(Cli::try_parse_from(args).is_err());
+    }
+
+    #[test]
+    fn test_by_camera_flag() {
+        let args = vec!["sift", "organize", "/source", "/dest", "--by-camera"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        match cli.command {
+            Commands::Organize { by_camera, .. } => assert!(by_camera),
+            _ => panic!("Expected Organize command"),
+        }
+
+        let args = vec!["sift", "organize", "/s", "/d", "--by-camera", "--template", "{camera}"];
+        assert!(Cli::try_parse_from(args).is_err());
+    }
+
+    #[test]
+    fn test_hash_mmap_flag() {
+        let cli = Cli::try_parse_from(vec!["sift", "hash", "/photos", "--mmap"]).unwrap();
+        match cli.command {
+            Commands::Hash { mmap, .. } => assert!(mmap),
+            _ => panic!("Expected Hash command"),
+        }
+    }
+
+    #[test]
+    fn test_hash_output_and_check() {
+        let cli = Cli::try_parse_from(vec!["sift", "hash", "/photos", "-r", "--output", "sums.txt"]).unwrap();
+        match cli.command {
+            Commands::Hash { output, check, .. } => {
+                assert_eq!(output, Some(PathBuf::from("sums.txt")));
+                assert!(!check);
+            }
+            _ => panic!("Expected Hash command"),
+        }
+
+        let cli = Cli::try_parse_from(vec!["sift", "hash", "--check", "sums.txt"]).unwrap();
+        match cli.command {
+            Commands::Hash { path, check, .. } => {
+                assert_eq!(path, PathBuf::from("sums.txt"));
+                assert!(check);
+            }
+            _ => panic!("Expected Hash command"),
+        }
+        assert!(Cli::try_parse_from(vec!["sift", "hash", "--check", "sums.txt", "--recursive"]).is_err());
+        assert!(Cli::try_parse_from(vec!["sift", "hash", "--check", "sums.txt", "-o", "again.txt"]).is_err());
+    }
+
+    #[test]
+    fn test_hash_algorithm() {
+        let cli = Cli::try_parse_from(vec!["sift", "hash", "/photos"]).unwrap();
+        match cli.command {
+            Commands::Hash { algorithm, .. } => assert_eq!(algorithm, None),
+            _ => panic!("Expected Hash command"),
+        }
+
+        let cli = Cli::try_parse_from(vec!["sift", "hash", "--check", "sums.txt", "--algorithm", "sha256"]).unwrap();
+        match cli.command {
+            Commands::Hash { algorithm, check, .. } => {
+                assert_eq!(algorithm, Some(HashAlgorithm::Sha256));
+                assert!(check);
+            }
+            _ => panic!("Expected Hash command"),
+        }
+        assert!(Cli::try_parse_from(vec!["sift", "hash", "/photos", "--algorithm", "md5"]).is_err());
+    }
+
+    #[test]
+    fn test_hash_path_styles() {
+        let cli = Cli::try_parse_from(vec!["sift", "hash", "/photos", "-r", "--relative-to", "/photos"]).unwrap();
+        match cli.command {
+            Commands::Hash { relative_to, flatten, .. } => {
+                assert_eq!(relative_to, Some(PathBuf::from("/photos")));
+                assert!(!flatten);
+            }
+            _ => panic!("Expected Hash command"),
+        }
+
+        let cli = Cli::try_parse_from(vec!["sift", "hash", "/photos", "-r", "--flatten"]).unwrap();
+        match cli.command {
+            Commands::Hash { relative_to, flatten, .. } => {
+                assert_eq!(relative_to, None);
+                assert!(flatten);
+            }
+            _ => panic!("Expected Hash command"),
+        }
+        assert!(Cli::try_parse_from(vec!["sift", "hash", "/photos", "--flatten", "--relative-to", "/"]).is_err());
+        // File names alone cannot be checked later
+        assert!(Cli::try_parse_from(vec!["sift", "hash", "/photos", "--flatten", "-o", "sums.txt"]).is_err());
+        // Nor can paths relative to a folder --check would not resolve them against
+        assert!(Cli::try_parse_from(vec!["sift", "hash", "/photos", "--relative-to", "/", "-o", "sums.txt"]).is_err());
+    }
+
+    #[test]
+    fn test_hash_parallel_threshold() {
+        let cli = Cli::try_parse_from(vec!["sift", "hash", "/photos", "--parallel-threshold"]).unwrap();
+        match cli.command {
+            Commands::Hash { parallel_threshold, .. } => {
+                assert_eq!(parallel_threshold, Some(crate::hash::DEFAULT_PARALLEL_THRESHOLD))
+            }
+            _ => panic!("Expected Hash command"),
+        }
+
+        let cli = Cli::try_parse_from(vec!["sift", "hash", "/photos", "--parallel-threshold", "1024"]).unwrap();
+        match cli.command {
+            Commands::Hash { parallel_threshold, .. } => assert_eq!(parallel_threshold, Some(1024)),
+            _ => panic!("Expected Hash command"),
+        }
+
+        assert!(Cli::try_parse_from(vec!["sift", "hash", "/photos", "--mmap", "--parallel-threshold"]).is_err());
+    }
+
+    #[test]
+    fn test_organize_fast_dedup() {
+        let cli = Cli::try_parse_from(vec!["sift", "organize", "/src", "/dst", "--fast-dedup"]).unwrap();
+        match cli.command {
+            Commands::Organize { fast_dedup, .. } => assert!(fast_dedup),
+            _ => panic!("Expected Organize command"),
+        }
+    }
+
+    #[test]
+    fn test_stats_command() {
+        let cli = Cli::try_parse_from(vec!["sift", "stats", "/photos", "-r"]).unwrap();
+        match cli.command {
+            Commands::Stats { dir, recursive } => {
+                assert_eq!(dir.to_str().unwrap(), "/photos");
+                assert!(recursive);
+            }
+            _ => panic!("Expected Stats command"),
+        }
+    }
+
+    #[test]
+    fn test_json_flag_is_global() {
+        let cli = Cli::try_parse_from(vec!["sift", "organize", "/src", "/dst", "--json"]).unwrap();
+        assert!(cli.json);
+    }
+
+    #[test]
+    fn test_organize_undated_dir() {
+        let cli = Cli::try_parse_from(vec!["sift", "organize", "/src", "/dst"]).unwrap();
+        match cli.command {
+            Commands::Organize { undated_dir, .. } => assert_eq!(undated_dir, PathBuf::from("Unsorted")),
+            _ => panic!("Expected Organize command"),
+        }
+
+        let cli = Cli::try_parse_from(vec!["sift", "organize", "/src", "/dst", "--undated-dir", "Misc/No Date"])
+            .unwrap();
+        match cli.command {
+            Commands::Organize { undated_dir, .. } => assert_eq!(undated_dir, PathBuf::from("Misc/No Date")),
+            _ => panic!("Expected Organize command"),
+        }
+
+        // Folders outside the destination are rejected
+        for escaping in ["/tmp/undated", "../undated", "Misc/../../undated", ".", ""] {
+            let args = vec!["sift", "organize", "/src", "/dst", "--undated-dir", escaping];
+            assert!(Cli::try_parse_from(args).is_err(), "{:?} must be rejected", escaping);
+        }
+    }
+
+    #[test]
+    fn test_include_hidden_flag() {
+        let cli = Cli::try_parse_from(vec!["sift", "organize", "/src", "/dst", "--include-hidden"]).unwrap();
+        match cli.command {
+            Commands::Organize { include_hidden, .. } => assert!(include_hidden),
+            _ => panic!("Expected Organize command"),
+        }
+
+        let cli = Cli::try_parse_from(vec!["sift", "hash", "/photos", "-r"]).unwrap();
+        match cli.command {
+            Commands::Hash { include_hidden, .. } => assert!(!include_hidden),
+            _ => panic!("Expected Hash command"),
+        }
+    }
+
+    #[test]
+    fn test_organize_pixel_dedup() {
+        let cli = Cli::try_parse_from(vec!["sift", "organize", "/src", "/dst", "--pixel-dedup"]).unwrap();
+        match cli.command {
+            Commands::Organize { pixel_dedup, fast_dedup, .. } => assert!(pixel_dedup && !fast_dedup),
+            _ => panic!("Expected Organize command"),
+        }
+        assert!(Cli::try_parse_from(vec!["sift", "organize", "/src", "/dst", "--pixel-dedup", "--fast-dedup"]).is_err());
+    }
+
+    #[test]
+    fn test_organize_max_open() {
+        let cli = Cli::try_parse_from(vec!["sift", "organize", "/src", "/dst", "-j", "8", "--max-open", "2"]).unwrap();
+        match cli.command {
+            Commands::Organize { jobs, max_open, .. } => assert_eq!((jobs, max_open.map(NonZeroUsize::get)), (Some(8), Some(2))),
+            _ => panic!("Expected Organize command"),
+        }
+        assert!(Cli::try_parse_from(vec!["sift", "organize", "/src", "/dst", "--max-open", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_organize_manifest_path() {
+        let cli = Cli::try_parse_from(vec!["sift", "organize", "/src", "/dst", "--manifest", "/tmp/run.json"]).unwrap();
+        match cli.command {
+            Commands::Organize { manifest, .. } => assert_eq!(manifest, Some(PathBuf::from("/tmp/run.json"))),
+            _ => panic!("Expected Organize command"),
+        }
+    }
+
+    #[test]
+    fn test_undo_command() {
+        let cli = Cli::try_parse_from(vec!["sift", "undo", "/dst/manifest.json"]).unwrap();
+        match cli.command {
+            Commands::Undo { manifest, destination, index } => {
+                assert_eq!(manifest, PathBuf::from("/dst/manifest.json"));
+                assert!(destination.is_none() && index.is_none());
+            }
+            _ => panic!("Expected Undo command"),
+        }
+    }
+
+    #[test]
+    fn test_benchmark_pattern() {
+        let cli = Cli::try_parse_from(vec!["sift", "benchmark", "/mnt/nfs", "--pattern", "random"]).unwrap();
+        match cli.command {
+            Commands::Benchmark { pattern, .. } => assert_eq!(pattern, ReadPattern::Random),
+            _ => panic!("Expected Benchmark command"),
+        }
+        assert!(Cli::try_parse_from(vec!["sift", "benchmark", "/mnt/nfs", "--pattern", "mmap"]).is_err());
+    }
+
+    #[test]
+    fn test_benchmark_write_options() {
+        let cli = Cli::try_parse_from(vec!["sift", "benchmark", "/mnt/smb", "--write-chunk-kb", "64", "--fsync"]).unwrap();
+        match cli.command {
+            Commands::Benchmark { write_chunk_kb, fsync, .. } => assert_eq!((write_chunk_kb.get(), fsync), (64, true)),
+            _ => panic!("Expected Benchmark command"),
+        }
+        assert!(Cli::try_parse_from(vec!["sift", "benchmark", "/mnt/smb", "--write-chunk-kb", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_migrate_bloom() {
+        let args = vec!["sift", "migrate", "index.bin", "--bloom"];
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        match cli.command {
+            Commands::Migrate { bloom, .. } => assert!(bloom),
+            _ => panic!("Expected Migrate command"),
+        }
+    }
+
+    #[test]
+    fn test_organize_index_format() {
+        let cli = Cli::try_parse_from(vec!["sift", "organize", "/src", "/dst"]).unwrap();
+        match cli.command {
+            Commands::Organize { index_format, .. } => assert_eq!(index_format, IndexBackend::Bincode),
+            _ => panic!("Expected Organize command"),
+        }
+
+        let cli = Cli::try_parse_from(vec!["sift", "organize", "/src", "/dst", "--index-format", "sqlite"]).unwrap();
+        match cli.command {
+            Commands::Organize { index_format, .. } => assert_eq!(index_format, IndexBackend::Sqlite),
+            _ => panic!("Expected Organize command"),
+        }
+        assert!(Cli::try_parse_from(vec!["sift", "organize", "/src", "/dst", "--index-format", "json"]).is_err());
+    }
+
+    #[test]
+    fn test_date_range_flags() {
+        let args = vec!["sift", "organize", "/src", "/dst", "--since", "2024-01-01", "--until", "2024-03-31"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        match cli.command {
+            Commands::Organize { since, until, include_undated, .. } => {
+                assert_eq!(since, NaiveDate::from_ymd_opt(2024, 1, 1));
+                assert_eq!(until, NaiveDate::from_ymd_opt(2024, 3, 31));
+                assert!(!include_undated);
+            }
+            _ => panic!("Expected Organize command"),
+        }
+
+        let cli = Cli::try_parse_from(vec!["sift", "cluster", "/photos", "--since", "2024-01-01", "--include-undated"]).unwrap();
+        match cli.command {
+            Commands::Cluster { since, include_undated, .. } => {
+                assert_eq!(since, NaiveDate::from_ymd_opt(2024, 1, 1));
+                assert!(include_undated);
+            }
+            _ => panic!("Expected Cluster command"),
+        }
+
+        let Err(err) = Cli::try_parse_from(vec!["sift", "organize", "/src", "/dst", "--since", "01/02/2024"]) else {
+            panic!("Expected a bad date to be rejected");
+        };
+        assert!(err.to_string().contains("expected YYYY-MM-DD"), "{}", err);
+        assert!(Cli::try_parse_from(vec!["sift", "organize", "/src", "/dst", "--until", "2024-02-30"]).is_err());
+    }
+
+    #[test]
+    fn test_cluster_export() {
+        let cli = Cli::try_parse_from(vec!["sift", "cluster", "/photos", "--export", "clusters.geojson"]).unwrap();
+        match cli.command {
+            Commands::Cluster { export, .. } => assert_eq!(export, Some(PathBuf::from("clusters.geojson"))),
+            _ => panic!("Expected Cluster command"),
+        }
+        assert!(Cli::try_parse_from(vec!["sift", "cluster", "/photos", "--by-time", "--export", "events.kml"]).is_err());
+    }
+
+    #[test]
+    fn test_follow_symlinks_requires_recursive() {
+        let cli = Cli::try_parse_from(vec!["sift", "hash", "/photos", "-r", "--follow-symlinks"]).unwrap();
+        match cli.command {
+            Commands::Hash { follow_symlinks, .. } => assert!(follow_symlinks),
+            _ => panic!("Expected Hash command"),
+        }
+        assert!(Cli::try_parse_from(vec!["sift", "hash", "/photos", "--follow-symlinks"]).is_err());
+        assert!(Cli::try_parse_from(vec!["sift", "organize", "/src", "/dst", "-r", "--follow-symlinks"]).is_ok());
+    }
+
+    #[test]
+    fn test_index_paging() {
+        let args = vec!["sift", "index", "index.bin", "--skip", "2", "--limit", "2", "--sort", "path"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        match cli.command {
+            Commands::Index { skip, limit, sort, .. } => assert_eq!((skip, limit, sort), (2, 2, EntryOrder::Path)),
+            _ => panic!("Expected Index command"),
+        }
+    }
+
+    #[test]
+    fn test_index_find() {
+        let cli = Cli::try_parse_from(vec!["sift", "index", "index.bin", "--find", "af13"]).unwrap();
+        match cli.command {
+            Commands::Index { find, .. } => assert_eq!(find.as_deref(), Some("af13")),
+            _ => panic!("Expected Index command"),
+        }
+        assert!(Cli::try_parse_from(vec!["sift", "index", "index.bin", "--find", "af13", "--format", "json"]).is_err());
+    }
+
+    #[test]
+    fn test_index_merge_command() {
+        let args = vec!["sift", "index-merge", "a.bin", "b.bin", "-o", "out.bin"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        match cli.command {
+            Commands::IndexMerge { first, second, output } => {
+                assert_eq!(first, PathBuf::from("a.bin"));
+                assert_eq!(second, PathBuf::from("b.bin"));
+                assert_eq!(output, PathBuf::from("out.bin"));
+            }
+            _ => panic!("Expected IndexMerge command"),
+        }
+        assert!(Cli::try_parse_from(vec!["sift", "index-merge", "a.bin", "b.bin"]).is_err());
+    }
+
+    #[test]
+    fn test_organize_scan_dest() {
+        let cli = Cli::try_parse_from(["sift", "organize", "src", "dst", "--scan-dest"]).unwrap();
+        match cli.command {
+            Commands::Organize { scan_dest, .. } => assert!(scan_dest),
+            _ => panic!("Expected Organize command"),
+        }
+        let cli = Cli::try_parse_from(["sift", "organize", "src", "dst"]).unwrap();
+        match cli.command {
+            Commands::Organize { scan_dest, .. } => assert!(!scan_dest),
+            _ => panic!("Expected Organize command"),
+        }
+    }
+
+    #[test]
+    fn test_organize_by_content() {
+        let cli = Cli::try_parse_from(["sift", "organize", "src", "dst", "--by-content"]).unwrap();
+        match cli.command {
+            Commands::Organize { by_content, .. } => assert!(by_content),
+            _ => panic!("Expected Organize command"),
+        }
+        // Extensions play no part in a content scan
+        assert!(Cli::try_parse_from(["sift", "organize", "src", "dst", "--by-content", "--ext", "cr2"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_size_suffixes() {
+        assert_eq!(parse_size("0"), Ok(0));
+        assert_eq!(parse_size("1500"), Ok(1500));
+        assert_eq!(parse_size("12B"), Ok(12));
+        assert_eq!(parse_size("50KB"), Ok(50 * 1024));
+        assert_eq!(parse_size("50kb"), Ok(50 * 1024));
+        assert_eq!(parse_size("2 MB"), Ok(2 * 1024 * 1024));
+        assert_eq!(parse_size("1.5GB"), Ok(3 * 512 * 1024 * 1024));
+        for bad in ["", "MB", "-5KB", "5TB", "1.2.3MB", "ten"] {
+            let err = parse_size(bad).unwrap_err();
+            assert!(err.contains("expected bytes or a KB, MB or GB suffix"), "{}", err);
+        }
+
+        let args = vec!["sift", "organize", "/src", "/dst", "--min-size", "20KB", "--max-size", "2GB"];
+        match Cli::try_parse_from(args).unwrap().command {
+            Commands::Organize { min_size, max_size, .. } => {
+                assert_eq!(min_size, Some(20 * 1024));
+                assert_eq!(max_size, Some(2 * 1024 * 1024 * 1024));
+            }
+            _ => panic!("Expected Organize command"),
+        }
+        assert!(Cli::try_parse_from(vec!["sift", "organize", "/src", "/dst", "--min-size", "big"]).is_err());
+    }
+
+    #[test]
+    fn test_organize_no_preserve_times() {
+        let cli = Cli::try_parse_from(["sift", "organize", "src", "dst", "--no-preserve-times"]).unwrap();
+        match cli.command {
+            Commands::Organize { no_preserve_times, .. } => assert!(no_preserve_times),
+            _ => panic!("Expected Organize command"),
+        }
+    }
+
+    #[test]
+    fn test_cluster_eps_and_min_points() {
+        let cli = Cli::try_parse_from(vec!["sift", "cluster", "/photos"]).unwrap();
+        match cli.command {
+            Commands::Cluster { eps_km, min_points, .. } => assert_eq!((eps_km, min_points.get()), (1.0, 3)),
+            _ => panic!("Expected Cluster command"),
+        }
+
+        let args = vec!["sift", "cluster", "/photos", "--eps-km", "0.25", "--min-points", "1"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        match cli.command {
+            Commands::Cluster { eps_km, min_points, .. } => assert_eq!((eps_km, min_points.get()), (0.25, 1)),
+            _ => panic!("Expected Cluster command"),
+        }
+
+        let invalid = [["--eps-km", "0"], ["--eps-km", "-1"], ["--eps-km", "inf"], ["--min-points", "0"], ["--min-points", "-2"]];
+        for bad in invalid {
+            let mut args = vec!["sift", "cluster", "/photos"];
+            args.extend(bad);
+            assert!(Cli::try_parse_from(args).is_err(), "{:?}", bad);
+        }
+        assert!(Cli::try_parse_from(vec!["sift", "cluster", "/photos", "--by-time", "--eps-km", "2"]).is_err());
+    }
 }
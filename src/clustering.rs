@@ -2,7 +2,8 @@
 //!
 //! This module implements the DBSCAN clustering algorithm with Haversine
 //! distance metric for grouping photos by geographic location. It also provides
-//! reverse geocoding to find the nearest named location for a cluster.
+//! reverse geocoding to find the nearest named location for a cluster, and
+//! time-based clustering of photos into events when no GPS data is available.
 //!
 //! # Examples
 //!
@@ -17,6 +18,7 @@
 //! println!("Found {} clusters", clusters.len());
 //! ```
 
+use chrono::{Duration, NaiveDateTime};
 use std::collections::{HashMap, HashSet};
 
 /// A geographic point with latitude and longitude coordinates.
@@ -33,6 +35,33 @@ pub struct GeoPoint {
     pub longitude: f64,
 }
 
+/// A geographic point with the time the photo was taken.
+///
+/// # Fields
+///
+/// * `id` - Unique identifier for the point, equal to its index in the input slice
+/// * `latitude` - Latitude in decimal degrees (-90 to 90)
+/// * `longitude` - Longitude in decimal degrees (-180 to 180)
+/// * `timestamp` - Capture time
+#[derive(Debug, Clone)]
+pub struct GeoPointTime {
+    pub id: usize,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub timestamp: NaiveDateTime,
+}
+
+impl GeoPointTime {
+    /// Returns the spatial part of the point.
+    pub fn geo_point(&self) -> GeoPoint {
+        GeoPoint {
+            id: self.id,
+            latitude: self.latitude,
+            longitude: self.longitude,
+        }
+    }
+}
+
 /// A named geographic location from the GeoNames database.
 ///
 /// # Fields
@@ -49,6 +78,17 @@ pub struct GeoNameEntry {
     pub population: u32,
 }
 
+impl GeoNameEntry {
+    /// Returns the location's position as a point.
+    pub fn geo_point(&self) -> GeoPoint {
+        GeoPoint {
+            id: 0,
+            latitude: self.latitude,
+            longitude: self.longitude,
+        }
+    }
+}
+
 /// Calculates the distance in kilometers between two geographic points.
 ///
 /// Uses the Haversine formula to compute great-circle distance on Earth.
@@ -107,11 +147,15 @@ pub fn haversine_distance(point1: &GeoPoint, point2: &GeoPoint) -> f64 {
 ///
 /// * `points` - Slice of geographic points to cluster
 /// * `eps_km` - Maximum distance in kilometers between points in a cluster
-/// * `min_points` - Minimum number of points to form a cluster
+/// * `min_points` - Minimum number of points within `eps_km` of a core point,
+///   counting the point itself
 ///
 /// # Returns
 ///
-/// A HashMap where keys are cluster IDs and values are vectors of point IDs
+/// A HashMap where keys are cluster IDs and values are vectors of point IDs.
+/// IDs are numbered from 0 by cluster centroid, south to north then west to
+/// east, and members are in ascending order, so the result does not depend on
+/// the order of `points`.
 ///
 /// # Examples
 ///
@@ -126,59 +170,557 @@ pub fn haversine_distance(point1: &GeoPoint, point2: &GeoPoint) -> f64 {
 /// // Points 0 and 1 are close and form a cluster
 /// ```
 pub fn dbscan(points: &[GeoPoint], eps_km: f64, min_points: usize) -> HashMap<usize, Vec<usize>> {
-    let mut clusters: HashMap<usize, Vec<usize>> = HashMap::new();
-    let mut visited = HashSet::new();
-    let mut cluster_id = 0;
+    if eps_km > 0.0 {
+        let grid = SpatialGrid::new(points, eps_km);
+        dbscan_with(points, min_points, |point| grid.neighbors(point, points, eps_km))
+    } else {
+        dbscan_with(points, min_points, |point| find_neighbors(point, points, eps_km))
+    }
+}
+
+/// Performs DBSCAN clustering like [`dbscan`], with the radius in meters.
+///
+/// Convenient at street scale, e.g. `50.0` rather than an `eps_km` of `0.05`.
+///
+/// # Examples
+///
+/// ```
+/// # use sift::clustering::{GeoPoint, dbscan_meters};
+/// let points = vec![
+///     GeoPoint { id: 0, latitude: 48.85660, longitude: 2.3522 },
+///     GeoPoint { id: 1, latitude: 48.85687, longitude: 2.3522 }, // ~30 m north
+/// ];
+/// assert_eq!(dbscan_meters(&points, 50.0, 2).len(), 1);
+/// assert!(dbscan_meters(&points, 10.0, 2).is_empty());
+/// ```
+pub fn dbscan_meters(points: &[GeoPoint], eps_m: f64, min_points: usize) -> HashMap<usize, Vec<usize>> {
+    dbscan(points, eps_m / 1000.0, min_points)
+}
+
+/// The outcome of [`dbscan_with_noise`]: the clusters and the points left out of them.
+///
+/// # Fields
+///
+/// * `clusters` - Cluster ID to member point IDs, as returned by [`dbscan`]
+/// * `noise` - IDs of the points in no cluster, in ascending order
+#[derive(Debug, Clone, Default)]
+pub struct DbscanResult {
+    pub clusters: HashMap<usize, Vec<usize>>,
+    pub noise: Vec<usize>,
+}
+
+/// Performs DBSCAN clustering like [`dbscan`] and also reports the noise points.
+///
+/// Useful to tell users which photos ended up in no cluster, so they can still
+/// be filed somewhere.
+///
+/// # Examples
+///
+/// ```
+/// # use sift::clustering::{GeoPoint, dbscan_with_noise};
+/// let points = vec![
+///     GeoPoint { id: 0, latitude: 0.0, longitude: 0.0 },
+///     GeoPoint { id: 1, latitude: 0.001, longitude: 0.001 },
+///     GeoPoint { id: 2, latitude: 10.0, longitude: 10.0 },
+/// ];
+/// let result = dbscan_with_noise(&points, 1.0, 2);
+/// assert_eq!(result.clusters.len(), 1);
+/// assert_eq!(result.noise, vec![2]);
+/// ```
+pub fn dbscan_with_noise(points: &[GeoPoint], eps_km: f64, min_points: usize) -> DbscanResult {
+    let clusters = dbscan(points, eps_km, min_points);
+    let clustered: HashSet<usize> = clusters.values().flatten().copied().collect();
+    let mut noise: Vec<usize> = points
+        .iter()
+        .map(|p| p.id)
+        .filter(|id| !clustered.contains(id))
+        .collect();
+    noise.sort_unstable();
+    DbscanResult { clusters, noise }
+}
+
+/// DBSCAN over `points`, with the neighbor search supplied by the caller.
+fn dbscan_with<F>(points: &[GeoPoint], min_points: usize, find_neighbors: F) -> HashMap<usize, Vec<usize>>
+where
+    F: Fn(&GeoPoint) -> Vec<usize>,
+{
+    let clusters = expand_clusters(points.len(), min_points, |id| find_neighbors(&points[id]));
+    number_clusters(points, clusters)
+}
 
-    for point in points {
-        if visited.contains(&point.id) {
+/// DBSCAN state of a point.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Label {
+    /// Not looked at yet
+    Undefined,
+    /// Not dense enough to seed a cluster; may still become a border point
+    Noise,
+    /// Member of the cluster at this position
+    Cluster(usize),
+}
+
+/// Canonical DBSCAN over points `0..len`, returning the members of each cluster.
+///
+/// `neighbors_of` returns the points within reach of a point, excluding the
+/// point itself. A point is a core point when it has at least `min_points`
+/// points within reach *counting itself*; clusters grow from core points, and
+/// take in any non-core point they reach as a border point, even one already
+/// labeled noise.
+fn expand_clusters<F>(len: usize, min_points: usize, neighbors_of: F) -> Vec<Vec<usize>>
+where
+    F: Fn(usize) -> Vec<usize>,
+{
+    let mut labels = vec![Label::Undefined; len];
+    let mut clusters: Vec<Vec<usize>> = Vec::new();
+
+    for id in 0..len {
+        if labels[id] != Label::Undefined {
+            continue;
+        }
+        let neighbors = neighbors_of(id);
+        if neighbors.len() + 1 < min_points {
+            labels[id] = Label::Noise;
             continue;
         }
 
-        let neighbors = find_neighbors(point, points, eps_km);
+        let cluster = clusters.len();
+        labels[id] = Label::Cluster(cluster);
+        let mut members = vec![id];
+        let mut seeds = neighbors;
+        while let Some(seed) = seeds.pop() {
+            match labels[seed] {
+                Label::Cluster(_) => continue,
+                Label::Noise => {
+                    // A border point: joins, but does not extend the cluster
+                    labels[seed] = Label::Cluster(cluster);
+                    members.push(seed);
+                    continue;
+                }
+                Label::Undefined => {}
+            }
+            labels[seed] = Label::Cluster(cluster);
+            members.push(seed);
 
-        if neighbors.len() < min_points {
-            // Mark as noise, not assigned to any cluster
-            visited.insert(point.id);
-            continue;
+            let reachable = neighbors_of(seed);
+            if reachable.len() + 1 >= min_points {
+                seeds.extend(reachable.into_iter().filter(|&n| !matches!(labels[n], Label::Cluster(_))));
+            }
         }
+        clusters.push(members);
+    }
 
-        // Start a new cluster
-        let mut current_cluster = vec![point.id];
-        visited.insert(point.id);
+    clusters
+}
 
-        let mut seed_set = neighbors;
-        while let Some(current_point_id) = seed_set.pop() {
-            
+/// Numbers clusters by centroid latitude, then longitude, and sorts their members.
+///
+/// Clusters found in whatever order the input and the seed expansion produce
+/// get the same IDs on every run. Clusters sharing a centroid (e.g. visits to
+/// one place at different times) are ordered by their members.
+fn number_clusters(points: &[GeoPoint], mut clusters: Vec<Vec<usize>>) -> HashMap<usize, Vec<usize>> {
+    for members in &mut clusters {
+        members.sort_unstable();
+    }
+    let mut keyed: Vec<(GeoPoint, Vec<usize>)> = clusters
+        .into_iter()
+        .map(|members| (cluster_centroid(points, &members), members))
+        .collect();
+    keyed.sort_by(|(a, a_members), (b, b_members)| {
+        a.latitude
+            .total_cmp(&b.latitude)
+            .then(a.longitude.total_cmp(&b.longitude))
+            .then_with(|| a_members.cmp(b_members))
+    });
+    keyed.into_iter().map(|(_, members)| members).enumerate().collect()
+}
 
-            if !visited.contains(&current_point_id) {
-                visited.insert(current_point_id);
+/// Computes the geographic center of a cluster.
+///
+/// Points are averaged as unit vectors on the sphere, so a cluster straddling
+/// the antimeridian is centered near ±180° rather than near 0°. Use it as the
+/// cluster's position when reverse geocoding; the first member is an arbitrary
+/// and often peripheral stand-in. An empty `ids` slice yields (0, 0).
+///
+/// # Arguments
+///
+/// * `points` - All points, indexed by id
+/// * `ids` - Ids of the cluster's members (as returned by [`dbscan`])
+///
+/// # Examples
+///
+/// ```
+/// # use sift::clustering::{GeoPoint, cluster_centroid};
+/// let points = vec![
+///     GeoPoint { id: 0, latitude: 10.0, longitude: 179.0 },
+///     GeoPoint { id: 1, latitude: 10.0, longitude: -179.0 },
+/// ];
+/// let center = cluster_centroid(&points, &[0, 1]);
+/// assert!((center.longitude.abs() - 180.0).abs() < 1e-9);
+/// ```
+pub fn cluster_centroid(points: &[GeoPoint], ids: &[usize]) -> GeoPoint {
+    let (mut x, mut y, mut z) = (0.0, 0.0, 0.0);
+    for &id in ids {
+        let lat = points[id].latitude.to_radians();
+        let lon = points[id].longitude.to_radians();
+        x += lat.cos() * lon.cos();
+        y += lat.cos() * lon.sin();
+        z += lat.sin();
+    }
 
-                let current_point = &points[current_point_id];
-                let neighbors_of_current = find_neighbors(current_point, points, eps_km);
+    GeoPoint {
+        id: 0,
+        latitude: z.atan2(x.hypot(y)).to_degrees(),
+        longitude: y.atan2(x).to_degrees(),
+    }
+}
 
-                if neighbors_of_current.len() >= min_points {
-                    for neighbor_id in neighbors_of_current {
-                        if !visited.contains(&neighbor_id) {
-                            seed_set.push(neighbor_id);
-                        }
-                    }
-                }
+/// Exports clusters as a GeoJSON `FeatureCollection`, for viewing on a map.
+///
+/// Each cluster becomes a `Point` feature at its [`cluster_centroid`], in
+/// cluster ID order, with properties `id`, `name` (from `names`, or `null`) and
+/// `count`. Coordinates are `[longitude, latitude]`, as GeoJSON requires.
+///
+/// # Examples
+///
+/// ```
+/// # use std::collections::HashMap;
+/// # use sift::clustering::{GeoPoint, dbscan, export_clusters_geojson};
+/// let points = vec![
+///     GeoPoint { id: 0, latitude: 48.8566, longitude: 2.3522 },
+///     GeoPoint { id: 1, latitude: 48.8567, longitude: 2.3523 },
+/// ];
+/// let clusters = dbscan(&points, 1.0, 2);
+/// let names = HashMap::from([(0, "Paris".to_string())]);
+/// let geojson = export_clusters_geojson(&clusters, &points, &names);
+/// assert!(geojson.contains("FeatureCollection"));
+/// ```
+pub fn export_clusters_geojson(
+    clusters: &HashMap<usize, Vec<usize>>,
+    points: &[GeoPoint],
+    names: &HashMap<usize, String>,
+) -> String {
+    let features: Vec<serde_json::Value> = sorted_ids(clusters)
+        .into_iter()
+        .map(|id| {
+            let center = cluster_centroid(points, &clusters[&id]);
+            serde_json::json!({
+                "type": "Feature",
+                "geometry": {
+                    "type": "Point",
+                    "coordinates": [center.longitude, center.latitude],
+                },
+                "properties": {
+                    "id": id,
+                    "name": names.get(&id),
+                    "count": clusters[&id].len(),
+                },
+            })
+        })
+        .collect();
+    let collection = serde_json::json!({ "type": "FeatureCollection", "features": features });
+    serde_json::to_string_pretty(&collection).expect("JSON values always serialize")
+}
 
-                current_cluster.push(current_point_id);
-            }
+/// Exports clusters as a KML document, one placemark per cluster centroid.
+///
+/// Placemarks are named after `names` (or `Cluster <id>`) and describe the
+/// number of photos, for Google Earth and other KML viewers.
+pub fn export_clusters_kml(
+    clusters: &HashMap<usize, Vec<usize>>,
+    points: &[GeoPoint],
+    names: &HashMap<usize, String>,
+) -> String {
+    let mut kml = String::from(concat!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+        "<kml xmlns=\"http://www.opengis.net/kml/2.2\">\n",
+        "<Document>\n",
+    ));
+    for id in sorted_ids(clusters) {
+        let center = cluster_centroid(points, &clusters[&id]);
+        let name = names.get(&id).cloned().unwrap_or_else(|| format!("Cluster {}", id));
+        kml.push_str(&format!(
+            concat!(
+                "  <Placemark>\n",
+                "    <name>{}</name>\n",
+                "    <description>{} photos</description>\n",
+                "    <Point><coordinates>{},{}</coordinates></Point>\n",
+                "  </Placemark>\n",
+            ),
+            escape_xml(&name),
+            clusters[&id].len(),
+            center.longitude,
+            center.latitude,
+        ));
+    }
+    kml.push_str("</Document>\n</kml>\n");
+    kml
+}
+
+fn sorted_ids(clusters: &HashMap<usize, Vec<usize>>) -> Vec<usize> {
+    let mut ids: Vec<usize> = clusters.keys().copied().collect();
+    ids.sort_unstable();
+    ids
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Buckets points into a lat/lon grid so neighbor queries only look at nearby cells.
+///
+/// Cells are at least `eps_km` across in both directions, so every point within
+/// `eps_km` of a query lies in its cell or one of the 8 surrounding ones. The
+/// longitude size accounts for meridians converging towards the poles (using the
+/// highest latitude present) and columns wrap around the antimeridian.
+struct SpatialGrid {
+    lat_size: f64,
+    lon_size: f64,
+    lon_cells: i64,
+    cells: HashMap<(i64, i64), Vec<usize>>,
+}
+
+impl SpatialGrid {
+    fn new(points: &[GeoPoint], eps_km: f64) -> Self {
+        const EARTH_RADIUS_KM: f64 = 6371.0;
+        let half_angle = (eps_km / (2.0 * EARTH_RADIUS_KM)).min(std::f64::consts::FRAC_PI_2);
+        let lat_size = (2.0 * half_angle).to_degrees();
+
+        // Two points at latitude <= max_lat and `dlon` apart are at least
+        // 2R·asin(cos(max_lat)·sin(dlon/2)) apart, whatever their latitudes
+        let max_lat = points
+            .iter()
+            .map(|p| p.latitude.abs())
+            .filter(|lat| lat.is_finite())
+            .fold(0.0_f64, f64::max)
+            .min(90.0);
+        let ratio = half_angle.sin() / max_lat.to_radians().cos();
+        let lon_size = if ratio >= 1.0 { 360.0 } else { (2.0 * ratio.asin()).to_degrees() };
+        let lon_cells = ((360.0 / lon_size).floor() as i64).max(1);
+
+        let mut grid = SpatialGrid {
+            lat_size,
+            lon_size: 360.0 / lon_cells as f64,
+            lon_cells,
+            cells: HashMap::new(),
+        };
+        for (index, point) in points.iter().enumerate() {
+            let cell = grid.cell_of(point);
+            grid.cells.entry(cell).or_default().push(index);
         }
+        grid
+    }
+
+    fn cell_of(&self, point: &GeoPoint) -> (i64, i64) {
+        let row = ((point.latitude + 90.0) / self.lat_size).floor() as i64;
+        let col = ((point.longitude + 180.0) / self.lon_size).floor() as i64;
+        (row, col.rem_euclid(self.lon_cells))
+    }
 
-        if !current_cluster.is_empty() {
-            clusters.insert(cluster_id, current_cluster);
-            cluster_id += 1;
+    /// Returns the ids of all points within `eps_km` of `point`, excluding itself.
+    fn neighbors(&self, point: &GeoPoint, points: &[GeoPoint], eps_km: f64) -> Vec<usize> {
+        let (row, col) = self.cell_of(point);
+        let mut searched = HashSet::new();
+        let mut neighbors = Vec::new();
+        for dr in -1..=1 {
+            for dc in -1..=1 {
+                let cell = (row + dr, (col + dc).rem_euclid(self.lon_cells));
+                if !searched.insert(cell) {
+                    continue;
+                }
+                let Some(members) = self.cells.get(&cell) else {
+                    continue;
+                };
+                neighbors.extend(
+                    members
+                        .iter()
+                        .map(|&index| &points[index])
+                        .filter(|p| p.id != point.id && haversine_distance(point, p) <= eps_km)
+                        .map(|p| p.id),
+                );
+            }
         }
+        neighbors
+    }
+}
+
+/// Performs DBSCAN clustering on points in space and time.
+///
+/// Two points are neighbors only if they are within `eps_km` of each other
+/// *and* were taken at most `eps_hours` apart, so repeated visits to the same
+/// place on different trips form separate clusters. Otherwise behaves like
+/// [`dbscan`].
+///
+/// # Arguments
+///
+/// * `points` - Slice of timestamped points to cluster
+/// * `eps_km` - Maximum distance in kilometers between neighboring points
+/// * `eps_hours` - Maximum time difference in hours between neighboring points
+/// * `min_points` - Minimum number of points within reach, counting itself, for a
+///   point to seed a cluster
+///
+/// # Returns
+///
+/// A HashMap where keys are cluster IDs and values are vectors of point IDs,
+/// numbered as by [`dbscan`]
+///
+/// # Examples
+///
+/// ```
+/// # use sift::clustering::{GeoPointTime, dbscan_spatiotemporal};
+/// # use chrono::NaiveDate;
+/// let paris = |id, year| GeoPointTime {
+///     id,
+///     latitude: 48.8566,
+///     longitude: 2.3522,
+///     timestamp: NaiveDate::from_ymd_opt(year, 5, 1).unwrap().and_hms_opt(12, 0, 0).unwrap(),
+/// };
+/// let points = vec![paris(0, 2021), paris(1, 2021), paris(2, 2024), paris(3, 2024)];
+/// let clusters = dbscan_spatiotemporal(&points, 1.0, 48.0, 2);
+/// assert_eq!(clusters.len(), 2);
+/// ```
+pub fn dbscan_spatiotemporal(
+    points: &[GeoPointTime],
+    eps_km: f64,
+    eps_hours: f64,
+    min_points: usize,
+) -> HashMap<usize, Vec<usize>> {
+    let neighbors_of = |id: usize| -> Vec<usize> {
+        let point = &points[id];
+        let here = point.geo_point();
+        points
+            .iter()
+            .filter(|p| {
+                p.id != point.id
+                    && hours_between(point.timestamp, p.timestamp) <= eps_hours
+                    && haversine_distance(&here, &p.geo_point()) <= eps_km
+            })
+            .map(|p| p.id)
+            .collect()
+    };
+
+    let clusters = expand_clusters(points.len(), min_points, neighbors_of);
+    let geo_points: Vec<GeoPoint> = points.iter().map(GeoPointTime::geo_point).collect();
+    number_clusters(&geo_points, clusters)
+}
+
+/// Absolute time difference between two timestamps, in hours.
+fn hours_between(a: NaiveDateTime, b: NaiveDateTime) -> f64 {
+    (a - b).num_seconds().abs() as f64 / 3600.0
+}
+
+/// Groups photos into events by capture time.
+///
+/// Timestamps are sorted and a new cluster starts whenever the gap between two
+/// consecutive photos exceeds `gap_hours`. Unlike [`dbscan`] there is no noise:
+/// every photo belongs to an event, even a lone one.
+///
+/// # Arguments
+///
+/// * `dates` - `(photo id, capture time)` pairs, in any order
+/// * `gap_hours` - Longest pause, in hours, still considered part of the same event
+///
+/// # Returns
+///
+/// A HashMap where keys are cluster IDs, numbered in chronological order, and
+/// values are the photo IDs of each event sorted by capture time
+///
+/// # Examples
+///
+/// ```
+/// # use sift::clustering::cluster_by_time;
+/// # use chrono::NaiveDate;
+/// let at = |day, hour| NaiveDate::from_ymd_opt(2024, 3, day).unwrap().and_hms_opt(hour, 0, 0).unwrap();
+/// let dates = vec![(0, at(9, 10)), (1, at(9, 14)), (2, at(12, 9))];
+/// let events = cluster_by_time(&dates, 12);
+/// assert_eq!(events[&0], vec![0, 1]);
+/// assert_eq!(events[&1], vec![2]);
+/// ```
+pub fn cluster_by_time(dates: &[(usize, NaiveDateTime)], gap_hours: i64) -> HashMap<usize, Vec<usize>> {
+    let mut sorted = dates.to_vec();
+    sorted.sort_by_key(|&(id, time)| (time, id));
+
+    let gap = Duration::hours(gap_hours);
+    let mut clusters: HashMap<usize, Vec<usize>> = HashMap::new();
+    let mut cluster_id = 0;
+    let mut previous: Option<NaiveDateTime> = None;
+
+    for (id, time) in sorted {
+        if let Some(prev) = previous
+            && time - prev > gap {
+                cluster_id += 1;
+            }
+        clusters.entry(cluster_id).or_default().push(id);
+        previous = Some(time);
     }
 
     clusters
 }
 
-/// Find all neighbors within eps_km of a point
+/// Names an event folder after the days it spans, e.g. `2024-03-09` or
+/// `2024-03-09_to_2024-03-11`.
+///
+/// # Examples
+///
+/// ```
+/// # use sift::clustering::event_folder_name;
+/// # use chrono::NaiveDate;
+/// let start = NaiveDate::from_ymd_opt(2024, 3, 9).unwrap().and_hms_opt(10, 0, 0).unwrap();
+/// let end = NaiveDate::from_ymd_opt(2024, 3, 11).unwrap().and_hms_opt(18, 0, 0).unwrap();
+/// assert_eq!(event_folder_name(start, end), "2024-03-09_to_2024-03-11");
+/// ```
+pub fn event_folder_name(start: NaiveDateTime, end: NaiveDateTime) -> String {
+    let (first, last) = (start.date(), end.date());
+    if first == last {
+        first.format("%Y-%m-%d").to_string()
+    } else {
+        format!("{}_to_{}", first.format("%Y-%m-%d"), last.format("%Y-%m-%d"))
+    }
+}
+
+/// Returns `true` if a GPS fix is trustworthy enough to take part in clustering.
+///
+/// Rejects coordinates that are out of range or exactly (0, 0) — "null island",
+/// which cameras write when they have no fix at all. When `max_error_m` is given,
+/// fixes whose recorded horizontal positioning error exceeds it are rejected too;
+/// fixes without a recorded error are kept.
+///
+/// # Arguments
+///
+/// * `latitude` - Latitude in decimal degrees
+/// * `longitude` - Longitude in decimal degrees
+/// * `horizontal_error_m` - EXIF `GPSHPositioningError` in meters, if present
+/// * `max_error_m` - Largest acceptable positioning error in meters
+///
+/// # Examples
+///
+/// ```
+/// # use sift::clustering::is_plausible_fix;
+/// assert!(!is_plausible_fix(0.0, 0.0, None, None));
+/// assert!(is_plausible_fix(48.8566, 2.3522, Some(5.0), Some(50.0)));
+/// assert!(!is_plausible_fix(48.8566, 2.3522, Some(500.0), Some(50.0)));
+/// ```
+pub fn is_plausible_fix(
+    latitude: f64,
+    longitude: f64,
+    horizontal_error_m: Option<f64>,
+    max_error_m: Option<f64>,
+) -> bool {
+    if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+        return false;
+    }
+    if latitude == 0.0 && longitude == 0.0 {
+        return false;
+    }
+    match (horizontal_error_m, max_error_m) {
+        (Some(error), Some(max)) => error <= max,
+        _ => true,
+    }
+}
+
+/// Find all neighbors within eps_km of a point by scanning every point
 fn find_neighbors(point: &GeoPoint, points: &[GeoPoint], eps_km: f64) -> Vec<usize> {
     points
         .iter()
@@ -246,6 +788,58 @@ pub fn find_closest_location(point: &GeoPoint, locations: &[GeoNameEntry]) -> Op
         .map(|(name, _)| name)
 }
 
+/// Finds the most populous named location within `max_km` of a point.
+///
+/// Nearest-neighbor lookup labels a photo taken in a suburb with the suburb's
+/// name; preferring population labels it with the recognizable city nearby.
+/// Ties in population go to the closer entry. When no entry lies within
+/// `max_km`, falls back to [`find_closest_location`].
+///
+/// # Examples
+///
+/// ```
+/// # use sift::clustering::{GeoPoint, GeoNameEntry, find_closest_location_weighted};
+/// let point = GeoPoint { id: 0, latitude: 48.8400, longitude: 2.2400 };
+/// let locations = vec![
+///     GeoNameEntry { name: "Paris".to_string(), latitude: 48.8566, longitude: 2.3522, population: 2_161_000 },
+///     GeoNameEntry { name: "Boulogne-Billancourt".to_string(), latitude: 48.8352, longitude: 2.2410, population: 121_000 },
+/// ];
+/// assert_eq!(find_closest_location_weighted(&point, &locations, 15.0), Some("Paris".to_string()));
+/// ```
+pub fn find_closest_location_weighted(
+    point: &GeoPoint,
+    locations: &[GeoNameEntry],
+    max_km: f64,
+) -> Option<String> {
+    locations
+        .iter()
+        .map(|loc| (loc, haversine_distance(point, &loc.geo_point())))
+        .filter(|(_, distance)| *distance <= max_km)
+        .max_by(|(a, da), (b, db)| {
+            a.population
+                .cmp(&b.population)
+                .then(db.partial_cmp(da).unwrap_or(std::cmp::Ordering::Equal))
+        })
+        .map(|(loc, _)| loc.name.clone())
+        .or_else(|| find_closest_location(point, locations))
+}
+
+/// Radius searched for a larger place when naming locations by population.
+pub const POPULATION_SEARCH_RADIUS_KM: f64 = 25.0;
+
+/// Names a location with the strategy chosen on the command line.
+///
+/// With `prefer_population`, the most populous place within
+/// [`POPULATION_SEARCH_RADIUS_KM`] wins (see [`find_closest_location_weighted`]);
+/// otherwise the nearest one does.
+pub fn name_location(point: &GeoPoint, locations: &[GeoNameEntry], prefer_population: bool) -> Option<String> {
+    if prefer_population {
+        find_closest_location_weighted(point, locations, POPULATION_SEARCH_RADIUS_KM)
+    } else {
+        find_closest_location(point, locations)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -317,6 +911,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::len_zero)]
     fn test_dbscan_clustering_basic() {
         let points = vec![
             GeoPoint { id: 0, latitude: 0.0, longitude: 0.0 },
@@ -351,6 +946,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::len_zero)]
     fn test_dbscan_tight_cluster() {
         // Points very close together
         let points = vec![
@@ -484,4 +1080,439 @@ mod tests {
         assert_eq!(entry.name, "Paris");
         assert_eq!(entry.population, 2_161_000);
     }
+
+    #[test]
+    fn test_is_plausible_fix() {
+        assert!(!is_plausible_fix(0.0, 0.0, None, None));
+        assert!(!is_plausible_fix(f64::NAN, 2.0, None, None));
+        assert!(!is_plausible_fix(91.0, 2.0, None, None));
+        assert!(is_plausible_fix(0.0, 2.0, None, None));
+        assert!(is_plausible_fix(48.8566, 2.3522, None, Some(10.0)));
+        assert!(is_plausible_fix(48.8566, 2.3522, Some(10.0), Some(10.0)));
+        assert!(!is_plausible_fix(48.8566, 2.3522, Some(10.5), Some(10.0)));
+    }
+
+    #[test]
+    fn test_null_island_excluded_from_clustering() {
+        let fixes = [(0.0, 0.0, None), (48.8566, 2.3522, Some(8.0)), (48.8567, 2.3523, Some(900.0))];
+
+        let points: Vec<GeoPoint> = fixes
+            .iter()
+            .filter(|(lat, lon, err)| is_plausible_fix(*lat, *lon, *err, Some(50.0)))
+            .enumerate()
+            .map(|(id, (lat, lon, _))| GeoPoint { id, latitude: *lat, longitude: *lon })
+            .collect();
+
+        assert_eq!(points.len(), 1);
+        assert_eq!(points[0].latitude, 48.8566);
+
+        let clusters = dbscan(&points, 1.0, 0);
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[&0], vec![0]);
+    }
+
+    fn at(day: u32, hour: u32) -> NaiveDateTime {
+        chrono::NaiveDate::from_ymd_opt(2024, 3, day)
+            .unwrap()
+            .and_hms_opt(hour, 0, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_cluster_by_time_splits_on_gaps() {
+        // Unsorted input: a morning burst, an evening shot 8h later, then a trip 3 days on
+        let dates = vec![(3, at(12, 10)), (0, at(9, 9)), (2, at(9, 18)), (1, at(9, 10)), (4, at(12, 11))];
+
+        let events = cluster_by_time(&dates, 12);
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[&0], vec![0, 1, 2]);
+        assert_eq!(events[&1], vec![3, 4]);
+
+        // A tighter gap separates the evening shot from the morning burst
+        let events = cluster_by_time(&dates, 4);
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[&1], vec![2]);
+    }
+
+    #[test]
+    fn test_cluster_by_time_gap_is_inclusive() {
+        let dates = vec![(0, at(9, 0)), (1, at(9, 12))];
+        assert_eq!(cluster_by_time(&dates, 12).len(), 1);
+        assert!(cluster_by_time(&[], 12).is_empty());
+    }
+
+    #[test]
+    fn test_event_folder_name() {
+        assert_eq!(event_folder_name(at(9, 9), at(9, 23)), "2024-03-09");
+        assert_eq!(event_folder_name(at(9, 9), at(10, 1)), "2024-03-09_to_2024-03-10");
+    }
+
+    fn visit(id: usize, latitude: f64, longitude: f64, year: i32, hour: u32) -> GeoPointTime {
+        GeoPointTime {
+            id,
+            latitude,
+            longitude,
+            timestamp: chrono::NaiveDate::from_ymd_opt(year, 7, 14)
+                .unwrap()
+                .and_hms_opt(hour, 0, 0)
+                .unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_dbscan_spatiotemporal_separates_trips() {
+        // Paris in 2021 and again in 2024, plus London in 2021
+        let points = vec![
+            visit(0, 48.8566, 2.3522, 2021, 10),
+            visit(1, 48.8570, 2.3530, 2021, 12),
+            visit(2, 48.8560, 2.3515, 2021, 15),
+            visit(3, 48.8566, 2.3522, 2024, 9),
+            visit(4, 48.8569, 2.3525, 2024, 11),
+            visit(5, 48.8562, 2.3519, 2024, 16),
+            visit(6, 51.5074, -0.1278, 2021, 11),
+        ];
+
+        let mut clusters: Vec<Vec<usize>> = dbscan_spatiotemporal(&points, 1.0, 24.0, 2)
+            .into_values()
+            .map(|mut members| {
+                members.sort();
+                members
+            })
+            .collect();
+        clusters.sort();
+        assert_eq!(clusters, vec![vec![0, 1, 2], vec![3, 4, 5]]);
+
+        // Space-only DBSCAN merges both trips
+        let geo: Vec<GeoPoint> = points.iter().map(GeoPointTime::geo_point).collect();
+        let merged = dbscan(&geo, 1.0, 2);
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[&0].len(), 6);
+    }
+
+    #[test]
+    fn test_dbscan_spatiotemporal_time_window() {
+        let points = vec![visit(0, 48.8566, 2.3522, 2021, 8), visit(1, 48.8566, 2.3522, 2021, 20)];
+        assert_eq!(dbscan_spatiotemporal(&points, 1.0, 12.0, 2).len(), 1);
+        assert_eq!(dbscan_spatiotemporal(&points, 1.0, 11.0, 2).len(), 0);
+        assert!(dbscan_spatiotemporal(&[], 1.0, 12.0, 2).is_empty());
+    }
+
+    /// Sorts members and clusters so results can be compared regardless of order.
+    fn normalized(clusters: HashMap<usize, Vec<usize>>) -> Vec<Vec<usize>> {
+        let mut clusters: Vec<Vec<usize>> = clusters
+            .into_values()
+            .map(|mut members| {
+                members.sort();
+                members
+            })
+            .collect();
+        clusters.sort();
+        clusters
+    }
+
+    /// Deterministic pseudo-random points: `groups` tight groups around the globe
+    /// (including near the antimeridian and the poles) plus scattered noise.
+    fn synthetic_points(count: usize, groups: usize) -> Vec<GeoPoint> {
+        let mut state: u64 = 0x2545_F491_4F6C_DD1D;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state >> 11) as f64 / (1u64 << 53) as f64
+        };
+        let centers: Vec<(f64, f64)> = (0..groups)
+            .map(|g| match g % 4 {
+                0 => (next() * 170.0 - 85.0, next() * 360.0 - 180.0),
+                1 => (next() * 10.0 - 5.0, 179.99),
+                2 => (89.0 + next() * 0.9, next() * 360.0 - 180.0),
+                _ => (next() * 120.0 - 60.0, next() * 360.0 - 180.0),
+            })
+            .collect();
+        (0..count)
+            .map(|id| {
+                let (latitude, longitude) = if id % 10 == 0 {
+                    (next() * 180.0 - 90.0, next() * 360.0 - 180.0)
+                } else {
+                    let (lat, lon) = centers[id % groups];
+                    let lon = lon + (next() - 0.5) * 0.04;
+                    let lon = if lon > 180.0 { lon - 360.0 } else { lon };
+                    (lat + (next() - 0.5) * 0.02, lon)
+                };
+                GeoPoint { id, latitude, longitude }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_grid_dbscan_matches_naive() {
+        let points = synthetic_points(600, 12);
+        for eps_km in [0.5, 2.0, 50.0, 5000.0] {
+            let naive = dbscan_with(&points, 3, |point| find_neighbors(point, &points, eps_km));
+            assert_eq!(normalized(dbscan(&points, eps_km, 3)), normalized(naive), "eps_km = {}", eps_km);
+        }
+    }
+
+    #[test]
+    fn test_grid_dbscan_scales() {
+        let points = synthetic_points(10_000, 100);
+        let clusters = dbscan(&points, 1.0, 3);
+        assert!(clusters.len() >= 50, "expected the synthetic groups, got {}", clusters.len());
+
+        // Count the distance checks the grid leaves: the points in each query's 3x3 cells
+        let grid = SpatialGrid::new(&points, 1.0);
+        let examined: usize = points
+            .iter()
+            .map(|point| {
+                let (row, col) = grid.cell_of(point);
+                let cells: HashSet<(i64, i64)> = (-1..=1)
+                    .flat_map(|dr| (-1..=1).map(move |dc| (row + dr, (col + dc).rem_euclid(grid.lon_cells))))
+                    .collect();
+                cells.iter().filter_map(|cell| grid.cells.get(cell)).map(Vec::len).sum::<usize>()
+            })
+            .sum();
+        let naive = points.len() * points.len();
+        assert!(examined * 50 < naive, "examined {} of {} pairs", examined, naive);
+    }
+
+    fn place(name: &str, latitude: f64, longitude: f64, population: u32) -> GeoNameEntry {
+        GeoNameEntry { name: name.to_string(), latitude, longitude, population }
+    }
+
+    #[test]
+    fn test_weighted_location_prefers_population() {
+        // A photo in Versailles' suburbs: nearest is a small town, Paris is ~15 km away
+        let point = GeoPoint { id: 0, latitude: 48.8200, longitude: 2.1800 };
+        let locations = vec![
+            place("Paris", 48.8566, 2.3522, 2_161_000),
+            place("Chaville", 48.8086, 2.1886, 20_000),
+            place("Versailles", 48.8049, 2.1204, 85_000),
+            place("Lyon", 45.7485, 4.8467, 522_000),
+        ];
+
+        assert_eq!(find_closest_location(&point, &locations), Some("Chaville".to_string()));
+        assert_eq!(find_closest_location_weighted(&point, &locations, 20.0), Some("Paris".to_string()));
+        assert_eq!(find_closest_location_weighted(&point, &locations, 6.0), Some("Versailles".to_string()));
+        assert_eq!(name_location(&point, &locations, false), Some("Chaville".to_string()));
+        assert_eq!(name_location(&point, &locations, true), Some("Paris".to_string()));
+    }
+
+    #[test]
+    fn test_weighted_location_falls_back_to_nearest() {
+        let point = GeoPoint { id: 0, latitude: 0.5, longitude: 0.5 };
+        let locations = vec![place("Far", 10.0, 10.0, 1_000_000), place("Less far", 3.0, 3.0, 10)];
+        assert_eq!(find_closest_location_weighted(&point, &locations, 5.0), Some("Less far".to_string()));
+        assert_eq!(find_closest_location_weighted(&point, &[], 5.0), None);
+    }
+
+    #[test]
+    fn test_cluster_centroid() {
+        let points = vec![
+            GeoPoint { id: 0, latitude: 0.0, longitude: 0.0 },
+            GeoPoint { id: 1, latitude: 0.0, longitude: 2.0 },
+            GeoPoint { id: 2, latitude: 60.0, longitude: 179.5 },
+            GeoPoint { id: 3, latitude: 60.0, longitude: -179.5 },
+        ];
+
+        let center = cluster_centroid(&points, &[0, 1]);
+        assert!(center.latitude.abs() < 1e-9);
+        assert!((center.longitude - 1.0).abs() < 1e-9);
+
+        // A naive mean of longitudes would land at 0°, on the other side of the Earth
+        let center = cluster_centroid(&points, &[2, 3]);
+        assert!((center.longitude.abs() - 180.0).abs() < 1e-9);
+        assert!((center.latitude - 60.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_centroid_names_cluster_better_than_first_point() {
+        // A chain of photos from a suburb into the city center; the first one is at the edge
+        let points: Vec<GeoPoint> = [
+            (48.8040, 2.1300),
+            (48.8100, 2.1450),
+            (48.8170, 2.1600),
+            (48.8230, 2.1750),
+            (48.8300, 2.1900),
+            (48.8350, 2.2050),
+            (48.8400, 2.2200),
+        ]
+        .iter()
+        .enumerate()
+        .map(|(id, &(latitude, longitude))| GeoPoint { id, latitude, longitude })
+        .collect();
+        let locations = vec![
+            GeoNameEntry { name: "Versailles".to_string(), latitude: 48.8049, longitude: 2.1204, population: 85_000 },
+            GeoNameEntry { name: "Sèvres".to_string(), latitude: 48.8240, longitude: 2.1760, population: 23_000 },
+        ];
+
+        let clusters = dbscan(&points, 2.0, 1);
+        assert_eq!(clusters.len(), 1);
+        let members = &clusters[&0];
+        assert_eq!(members[0], 0);
+
+        let by_first = find_closest_location(&points[members[0]], &locations);
+        let by_centroid = find_closest_location(&cluster_centroid(&points, members), &locations);
+        assert_eq!(by_first, Some("Versailles".to_string()));
+        assert_eq!(by_centroid, Some("Sèvres".to_string()));
+    }
+
+    #[test]
+    fn test_dbscan_reports_isolated_point_as_noise() {
+        let points = vec![
+            GeoPoint { id: 0, latitude: 48.8566, longitude: 2.3522 },
+            GeoPoint { id: 1, latitude: 48.8567, longitude: 2.3523 },
+            GeoPoint { id: 2, latitude: 35.6762, longitude: 139.6503 },
+            GeoPoint { id: 3, latitude: 48.8568, longitude: 2.3524 },
+        ];
+
+        let result = dbscan_with_noise(&points, 1.0, 2);
+        assert_eq!(result.noise, vec![2]);
+        assert_eq!(result.clusters.len(), 1);
+        assert!(result.clusters.values().all(|members| !members.contains(&2)));
+        assert_eq!(result.clusters[&0].len(), 3);
+
+        assert!(dbscan_with_noise(&[], 1.0, 2).noise.is_empty());
+    }
+
+    #[test]
+    fn test_cluster_ids_do_not_depend_on_input_order() {
+        let coordinates: Vec<(f64, f64)> = synthetic_points(300, 8)
+            .into_iter()
+            .map(|p| (p.latitude, p.longitude))
+            .collect();
+        // Cluster ID to its members' coordinates, which survive reordering
+        let clusters_of = |order: &[usize]| -> Vec<(usize, Vec<(f64, f64)>)> {
+            let points: Vec<GeoPoint> = order
+                .iter()
+                .enumerate()
+                .map(|(id, &i)| GeoPoint { id, latitude: coordinates[i].0, longitude: coordinates[i].1 })
+                .collect();
+            let mut clusters: Vec<(usize, Vec<(f64, f64)>)> = dbscan(&points, 2.0, 3)
+                .into_iter()
+                .map(|(id, members)| {
+                    assert!(members.is_sorted());
+                    let mut coords: Vec<(f64, f64)> =
+                        members.iter().map(|&m| (points[m].latitude, points[m].longitude)).collect();
+                    coords.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.total_cmp(&b.1)));
+                    (id, coords)
+                })
+                .collect();
+            clusters.sort_by_key(|(id, _)| *id);
+            clusters
+        };
+
+        let mut order: Vec<usize> = (0..coordinates.len()).collect();
+        let expected = clusters_of(&order);
+        assert!(expected.len() >= 4, "expected several clusters, got {}", expected.len());
+        assert!(expected.windows(2).all(|pair| {
+            let a = cluster_centroid_of(&pair[0].1);
+            let b = cluster_centroid_of(&pair[1].1);
+            a <= b
+        }));
+
+        order.reverse();
+        assert_eq!(clusters_of(&order), expected);
+        let mut state = 7usize;
+        for i in (1..order.len()).rev() {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            order.swap(i, (state >> 33) % (i + 1));
+        }
+        assert_eq!(clusters_of(&order), expected);
+    }
+
+    /// Centroid latitude of a set of coordinates, the primary key IDs are ordered by.
+    fn cluster_centroid_of(coords: &[(f64, f64)]) -> f64 {
+        let points: Vec<GeoPoint> = coords
+            .iter()
+            .enumerate()
+            .map(|(id, &(latitude, longitude))| GeoPoint { id, latitude, longitude })
+            .collect();
+        let ids: Vec<usize> = (0..points.len()).collect();
+        cluster_centroid(&points, &ids).latitude
+    }
+
+    #[test]
+    fn test_dbscan_counts_the_point_itself() {
+        let points = vec![
+            GeoPoint { id: 0, latitude: 48.8566, longitude: 2.3522 },
+            GeoPoint { id: 1, latitude: 48.8567, longitude: 2.3523 },
+        ];
+        assert_eq!(dbscan(&points, 1.0, 2)[&0], vec![0, 1]);
+        assert!(dbscan(&points, 1.0, 3).is_empty());
+    }
+
+    #[test]
+    fn test_dbscan_noise_point_joins_as_border() {
+        // A line of points ~0.9 km apart: with eps 1 km and min_points 3, only the
+        // inner two are core points. The first point is visited first and labeled
+        // noise, then must join the cluster as a border point of its neighbor.
+        let step = 0.0081;
+        let points: Vec<GeoPoint> = (0..4)
+            .map(|id| GeoPoint { id, latitude: 45.0 + step * id as f64, longitude: 6.0 })
+            .collect();
+        let far = GeoPoint { id: 4, latitude: 46.0, longitude: 6.0 };
+        let points = [points, vec![far]].concat();
+
+        let result = dbscan_with_noise(&points, 1.0, 3);
+        assert_eq!(result.clusters.len(), 1);
+        assert_eq!(result.clusters[&0], vec![0, 1, 2, 3]);
+        assert_eq!(result.noise, vec![4]);
+
+        // Both neighbor searches agree
+        let naive = dbscan_with(&points, 3, |point| find_neighbors(point, &points, 1.0));
+        assert_eq!(naive[&0], vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_dbscan_meters_street_scale() {
+        // Three photos along a street, ~30 m apart
+        let points: Vec<GeoPoint> = (0..3)
+            .map(|id| GeoPoint { id, latitude: 48.8566 + 0.00027 * id as f64, longitude: 2.3522 })
+            .collect();
+        let gap_m = haversine_distance(&points[0], &points[1]) * 1000.0;
+        assert!((gap_m - 30.0).abs() < 1.0, "{} m", gap_m);
+
+        assert_eq!(dbscan_meters(&points, 50.0, 2)[&0], vec![0, 1, 2]);
+        assert!(dbscan_meters(&points, 10.0, 2).is_empty());
+        assert_eq!(dbscan_meters(&points, 50.0, 2), dbscan(&points, 0.05, 2));
+    }
+
+    #[test]
+    fn test_export_clusters_geojson() {
+        let points = vec![
+            GeoPoint { id: 0, latitude: 48.8566, longitude: 2.3522 },
+            GeoPoint { id: 1, latitude: 48.8568, longitude: 2.3524 },
+            GeoPoint { id: 2, latitude: 51.5074, longitude: -0.1278 },
+            GeoPoint { id: 3, latitude: 51.5076, longitude: -0.1276 },
+            GeoPoint { id: 4, latitude: 51.5075, longitude: -0.1277 },
+        ];
+        let clusters = dbscan(&points, 1.0, 2);
+        assert_eq!(clusters.len(), 2);
+        let names = HashMap::from([(0, "Paris".to_string())]);
+
+        let geojson: serde_json::Value = serde_json::from_str(&export_clusters_geojson(&clusters, &points, &names)).unwrap();
+        assert_eq!(geojson["type"], "FeatureCollection");
+        let features = geojson["features"].as_array().unwrap();
+        assert_eq!(features.len(), 2);
+        for feature in features {
+            assert_eq!(feature["type"], "Feature");
+            assert_eq!(feature["geometry"]["type"], "Point");
+        }
+
+        let paris = &features[0];
+        assert_eq!(paris["properties"]["name"], "Paris");
+        assert_eq!(paris["properties"]["count"], 2);
+        let coordinates = paris["geometry"]["coordinates"].as_array().unwrap();
+        assert!((coordinates[0].as_f64().unwrap() - 2.3523).abs() < 1e-6, "longitude first");
+        assert!((coordinates[1].as_f64().unwrap() - 48.8567).abs() < 1e-6);
+
+        let london = &features[1];
+        assert!(london["properties"]["name"].is_null());
+        assert_eq!(london["properties"]["count"], 3);
+
+        let kml = export_clusters_kml(&clusters, &points, &HashMap::from([(1, "Fish & Chips".to_string())]));
+        assert!(kml.contains("<name>Cluster 0</name>"));
+        assert!(kml.contains("<name>Fish &amp; Chips</name>"));
+        assert!(kml.contains("<description>3 photos</description>"));
+        assert_eq!(kml.matches("<Placemark>").count(), 2);
+    }
 }
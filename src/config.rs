@@ -0,0 +1,339 @@
+//! `sift.toml` config file support.
+//!
+//! A config file holds options that would otherwise be repeated on every
+//! invocation. Top-level keys set global options, and each table sets the
+//! options of the command it is named after. Keys are the long option names,
+//! with `-` or `_`:
+//!
+//! ```toml
+//! max_retries = 5
+//!
+//! [organize]
+//! jobs = 8
+//! recursive = true
+//! template = "{year}/{month}/{location}"
+//! ext = ["cr2", "nef"]
+//! ```
+//!
+//! The file is read from `--config <path>`, or else from `./sift.toml` or
+//! `~/.config/sift/sift.toml`, whichever exists first. Options given on the
+//! command line always win over the file. A flag set to `true` in the file
+//! cannot be turned off from the command line.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use clap::error::ErrorKind;
+use clap::parser::ValueSource;
+use clap::{Arg, ArgMatches, Command, CommandFactory, FromArgMatches, Parser};
+use serde::Deserialize;
+use toml::Value;
+
+use crate::cli::Cli;
+
+/// Name of the config file looked for in each search directory.
+pub const CONFIG_FILE_NAME: &str = "sift.toml";
+
+/// A parsed config file: option values by key, and tables of them by command.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(transparent)]
+pub struct Config {
+    entries: BTreeMap<String, Value>,
+}
+
+impl Config {
+    /// Parses the TOML text of a config file.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        toml::from_str(text).map_err(|e| e.to_string())
+    }
+
+    /// Reads the config at `explicit`, or else the first of `search` that exists.
+    ///
+    /// Finding no config is not an error, but an `explicit` path that cannot be
+    /// read is, as is any file that is not valid TOML.
+    pub fn find(explicit: Option<&Path>, search: &[PathBuf]) -> Result<Option<Self>, String> {
+        let path = match explicit {
+            Some(path) => path,
+            None => match search.iter().find(|path| path.is_file()) {
+                Some(path) => path.as_path(),
+                None => return Ok(None),
+            },
+        };
+        let text = fs::read_to_string(path).map_err(|e| format!("cannot read config {:?}: {}", path, e))?;
+        Self::parse(&text)
+            .map(Some)
+            .map_err(|e| format!("invalid config {:?}: {}", path, e))
+    }
+
+    /// Global options: the top-level keys that are not command tables.
+    fn global_options(&self) -> impl Iterator<Item = (&String, &Value)> {
+        self.entries.iter().filter(|(_, value)| !value.is_table())
+    }
+
+    /// Command tables, by command name.
+    fn command_options(&self) -> impl Iterator<Item = (&String, &toml::Table)> {
+        self.entries
+            .iter()
+            .filter_map(|(name, value)| value.as_table().map(|table| (name, table)))
+    }
+}
+
+/// Where a config is looked for without `--config`, in order.
+pub fn search_paths() -> Vec<PathBuf> {
+    let mut paths = vec![PathBuf::from(CONFIG_FILE_NAME)];
+    if let Some(home) = env::var_os("HOME") {
+        paths.push(PathBuf::from(home).join(".config/sift").join(CONFIG_FILE_NAME));
+    }
+    paths
+}
+
+/// Parses `args` like [`Cli`] does, then fills in the options they leave unset
+/// from the config file (see the [module docs](self)).
+///
+/// Config values are passed to Clap as if they had been typed after the
+/// command line, so they are validated like any other argument.
+pub fn parse_with_config(args: Vec<OsString>, search: &[PathBuf]) -> Result<Cli, clap::Error> {
+    let command = Cli::command();
+    let matches = command.clone().try_get_matches_from(&args)?;
+    let explicit = matches.get_one::<PathBuf>("config").map(PathBuf::as_path);
+    let Some(config) = Config::find(explicit, search).map_err(config_error)? else {
+        return Cli::from_arg_matches(&matches);
+    };
+
+    let mut extra = Vec::new();
+    append_options(&command, &matches, "", config.global_options(), &mut extra)?;
+    for (name, options) in config.command_options() {
+        let Some(subcommand) = command.find_subcommand(name) else {
+            return Err(config_error(format!("unknown command [{}] in config", name)));
+        };
+        let active = matches.subcommand().filter(|(active, _)| active == name);
+        match active {
+            Some((_, sub_matches)) => {
+                append_options(subcommand, sub_matches, name, options.iter(), &mut extra)?;
+            }
+            // Still catch typos in the sections of other commands
+            None => {
+                if let Some(key) = options.keys().find(|key| option_arg(subcommand, key).is_none()) {
+                    return Err(unknown_option(key, name));
+                }
+            }
+        }
+    }
+
+    let mut args = args;
+    args.extend(extra);
+    Cli::try_parse_from(args)
+}
+
+/// Appends a `--long=value` argument for each of `options` that `matches`
+/// did not get from the command line, and that conflicts with no argument that
+/// did. `section` names the command table the options come from, or is empty
+/// for global options.
+fn append_options<'a>(
+    command: &Command,
+    matches: &ArgMatches,
+    section: &str,
+    options: impl Iterator<Item = (&'a String, &'a Value)>,
+    args: &mut Vec<OsString>,
+) -> Result<(), clap::Error> {
+    for (key, value) in options {
+        let arg = option_arg(command, key).ok_or_else(|| unknown_option(key, section))?;
+        if from_command_line(matches, arg) || conflicts_with_command_line(command, matches, arg) {
+            continue;
+        }
+        let flag = format!("--{}", arg.get_long().unwrap_or_default());
+        match value {
+            Value::Boolean(true) => args.push(flag.into()),
+            Value::Boolean(false) => {}
+            Value::Array(items) => {
+                for item in items {
+                    args.push(format!("{}={}", flag, scalar(key, item)?).into());
+                }
+            }
+            value => args.push(format!("{}={}", flag, scalar(key, value)?).into()),
+        }
+    }
+    Ok(())
+}
+
+fn from_command_line(matches: &ArgMatches, arg: &Arg) -> bool {
+    matches.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine)
+}
+
+/// Whether `arg` conflicts, in either direction, with an argument of `command`
+/// given on the command line, e.g. a config `verbose = true` with `-q`.
+fn conflicts_with_command_line(command: &Command, matches: &ArgMatches, arg: &Arg) -> bool {
+    let conflicts = command.get_arg_conflicts_with(arg);
+    command.get_arguments().filter(|other| from_command_line(matches, other)).any(|other| {
+        conflicts.contains(&other) || command.get_arg_conflicts_with(other).contains(&arg)
+    })
+}
+
+/// The long option of `command` that config `key` sets, if any.
+fn option_arg<'a>(command: &'a Command, key: &str) -> Option<&'a Arg> {
+    let id = key.replace('-', "_");
+    if id == "config" {
+        return None;
+    }
+    command
+        .get_arguments()
+        .find(|arg| arg.get_id().as_str() == id && arg.get_long().is_some() && !arg.is_hide_set())
+}
+
+/// Formats a single config value as a command-line value.
+fn scalar(key: &str, value: &Value) -> Result<String, clap::Error> {
+    match value {
+        Value::String(text) => Ok(text.clone()),
+        Value::Integer(number) => Ok(number.to_string()),
+        Value::Float(number) => Ok(number.to_string()),
+        Value::Datetime(date) => Ok(date.to_string()),
+        _ => Err(config_error(format!(
+            "config option {:?} must be a string, number, date, boolean or a list of them",
+            key
+        ))),
+    }
+}
+
+fn unknown_option(key: &str, section: &str) -> clap::Error {
+    if section.is_empty() {
+        config_error(format!("unknown global option {:?} in config", key))
+    } else {
+        config_error(format!("unknown option {:?} in [{}] of the config", key, section))
+    }
+}
+
+fn config_error(message: String) -> clap::Error {
+    Cli::command().error(ErrorKind::InvalidValue, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::Commands;
+    use std::io;
+    use tempfile::tempdir;
+
+    fn args(args: &[&str]) -> Vec<OsString> {
+        args.iter().map(OsString::from).collect()
+    }
+
+    const CONFIG: &str = r#"
+        max_retries = 7
+
+        [organize]
+        jobs = 8
+        recursive = true
+        fast_dedup = false
+        ext = ["cr2", "nef"]
+        min-size = "20KB"
+        since = 2024-01-01
+
+        [hash]
+        recursive = true
+    "#;
+
+    #[test]
+    fn test_command_line_beats_config() -> io::Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join(CONFIG_FILE_NAME);
+        fs::write(&path, CONFIG)?;
+        let search = [dir.path().join("missing.toml"), path];
+
+        let cli = parse_with_config(args(&["sift", "organize", "src", "dst"]), &search).unwrap();
+        assert_eq!(cli.max_retries, 7);
+        let Commands::Organize { jobs, recursive, fast_dedup, ext, min_size, since, .. } = cli.command else {
+            panic!("Expected Organize command");
+        };
+        assert_eq!(jobs, Some(8));
+        assert!(recursive);
+        assert!(!fast_dedup);
+        assert_eq!(ext, ["cr2", "nef"]);
+        assert_eq!(min_size, Some(20 * 1024));
+        assert_eq!(since, chrono::NaiveDate::from_ymd_opt(2024, 1, 1));
+
+        let cli = parse_with_config(
+            args(&["sift", "organize", "src", "dst", "--jobs", "2", "--ext", "dng", "--max-retries", "0"]),
+            &search,
+        )
+        .unwrap();
+        assert_eq!(cli.max_retries, 0);
+        let Commands::Organize { jobs, recursive, ext, .. } = cli.command else {
+            panic!("Expected Organize command");
+        };
+        assert_eq!(jobs, Some(2));
+        assert!(recursive);
+        assert_eq!(ext, ["dng"]);
+
+        // Config values that conflict with a command-line flag give way to it
+        fs::write(dir.path().join(CONFIG_FILE_NAME), format!("verbose = true\n{}", CONFIG))?;
+        let cli = parse_with_config(args(&["sift", "-q", "organize", "src", "dst", "--by-content"]), &search).unwrap();
+        assert!(cli.quiet);
+        assert!(!cli.verbose);
+        let Commands::Organize { by_content, ext, jobs, .. } = cli.command else {
+            panic!("Expected Organize command");
+        };
+        assert!(by_content);
+        assert!(ext.is_empty());
+        assert_eq!(jobs, Some(8));
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_config_is_a_no_op() -> io::Result<()> {
+        let dir = tempdir()?;
+        let search = [dir.path().join(CONFIG_FILE_NAME)];
+        let cli = parse_with_config(args(&["sift", "organize", "src", "dst"]), &search).unwrap();
+        assert_eq!(cli.max_retries, 3);
+        let Commands::Organize { jobs, recursive, ext, .. } = cli.command else {
+            panic!("Expected Organize command");
+        };
+        assert_eq!(jobs, None);
+        assert!(!recursive);
+        assert!(ext.is_empty());
+
+        // Unless it was asked for by name
+        let missing = dir.path().join("custom.toml");
+        let explicit = args(&["sift", "--config", missing.to_str().unwrap(), "organize", "src", "dst"]);
+        let Err(err) = parse_with_config(explicit, &search) else {
+            panic!("Expected a missing --config to fail");
+        };
+        assert!(err.to_string().contains("cannot read config"), "{}", err);
+        Ok(())
+    }
+
+    #[test]
+    fn test_explicit_config_and_bad_keys() -> io::Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("daily.toml");
+        fs::write(&path, "[organize]\njobs = 3\n")?;
+        let explicit = args(&["sift", "organize", "src", "dst", "--config", path.to_str().unwrap()]);
+        let Commands::Organize { jobs, .. } = parse_with_config(explicit, &[]).unwrap().command else {
+            panic!("Expected Organize command");
+        };
+        assert_eq!(jobs, Some(3));
+
+        for (text, expected) in [
+            ("[organize]\njobz = 3\n", "unknown option \"jobz\""),
+            ("[hash]\ndestination = \"x\"\n", "unknown option \"destination\""),
+            ("[organise]\njobs = 3\n", "unknown command [organise]"),
+            ("jobs = 3\n", "unknown global option \"jobs\""),
+            ("[organize]\njobs = 3\njobs = 4\n", "invalid config"),
+        ] {
+            fs::write(&path, text)?;
+            let explicit = args(&["sift", "--config", path.to_str().unwrap(), "organize", "src", "dst"]);
+            let Err(err) = parse_with_config(explicit, &[]) else {
+                panic!("Expected {:?} to be rejected", text);
+            };
+            assert!(err.to_string().contains(expected), "{}", err);
+        }
+
+        // Values are checked like command-line values
+        fs::write(&path, "[organize]\nmode = \"teleport\"\n")?;
+        let explicit = args(&["sift", "--config", path.to_str().unwrap(), "organize", "src", "dst"]);
+        assert!(parse_with_config(explicit, &[]).is_err());
+        Ok(())
+    }
+}
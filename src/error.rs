@@ -50,6 +50,31 @@ impl From<io::Error> for OrganizeError {
     }
 }
 
+impl From<bincode::Error> for OrganizeError {
+    fn from(err: bincode::Error) -> Self {
+        OrganizeError::IndexError(format!("invalid index data: {}", err))
+    }
+}
+
+impl From<serde_json::Error> for OrganizeError {
+    fn from(err: serde_json::Error) -> Self {
+        OrganizeError::Other(format!("JSON error: {}", err))
+    }
+}
+
+/// Lets `OrganizeError` flow into code that still returns `io::Result`.
+///
+/// I/O errors are unwrapped as-is; other variants become an `io::Error` carrying
+/// the `OrganizeError`, so it can be recovered with `get_ref().downcast_ref()`.
+impl From<OrganizeError> for io::Error {
+    fn from(err: OrganizeError) -> Self {
+        match err {
+            OrganizeError::IoError(e) => e,
+            err => io::Error::other(err),
+        }
+    }
+}
+
 /// Result type for operations that can fail with `OrganizeError`.
 pub type OrganizeResult<T> = Result<T, OrganizeError>;
 
@@ -84,4 +109,23 @@ mod tests {
             assert!(err.to_string().contains(expected));
         }
     }
+
+    #[test]
+    fn test_error_from_serde() {
+        let bad_index = bincode::deserialize::<Vec<String>>(&[0xFF]).unwrap_err();
+        assert!(matches!(OrganizeError::from(bad_index), OrganizeError::IndexError(_)));
+
+        let bad_json = serde_json::from_str::<serde_json::Value>("{").unwrap_err();
+        assert!(matches!(OrganizeError::from(bad_json), OrganizeError::Other(_)));
+    }
+
+    #[test]
+    fn test_error_into_io() {
+        let io_err: io::Error = OrganizeError::IoError(io::Error::from(io::ErrorKind::Interrupted)).into();
+        assert_eq!(io_err.kind(), io::ErrorKind::Interrupted);
+
+        let io_err: io::Error = OrganizeError::HashError("unreadable".to_string()).into();
+        let inner = io_err.get_ref().and_then(|e| e.downcast_ref::<OrganizeError>());
+        assert!(matches!(inner, Some(OrganizeError::HashError(_))));
+    }
 }
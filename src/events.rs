@@ -0,0 +1,119 @@
+//! Machine-readable progress events for `--json` mode.
+//!
+//! Each event is written as one JSON object per line (NDJSON), tagged by an
+//! `event` field, e.g.
+//!
+//! ```text
+//! {"event":"organized","src":"/in/IMG_1.jpg","dst":"/out/2024/01/02/IMG_1.jpg","hash":"af13…"}
+//! {"event":"skipped_duplicate","src":"/in/copy.jpg","duplicate_of":"/out/2024/01/02/IMG_1.jpg"}
+//! {"event":"summary","files_scanned":2,"files_organized":1,…}
+//! ```
+//!
+//! Human-readable logs stay on stderr and are suppressed while events are
+//! emitted, so stdout can be parsed line by line.
+//!
+//! Events go to a [`ProgressSink`]: the CLI uses an [`EventWriter`], while a
+//! program embedding Sift can pass a closure to [`crate::organize::organize`]
+//! and receive them without anything being printed.
+
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+use serde::Serialize;
+
+use crate::organize::OrganizeStats;
+
+/// One line of `--json` output.
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event<'a> {
+    /// A file was transferred into the destination
+    Organized { src: &'a Path, dst: &'a Path, hash: &'a str },
+    /// A dry run would transfer this file
+    WouldOrganize { src: &'a Path, dst: &'a Path },
+    /// A file's contents were already indexed (`duplicate_of` is its indexed path)
+    /// or appeared earlier in this run (`duplicate_of` is null)
+    SkippedDuplicate { src: &'a Path, duplicate_of: Option<&'a str> },
+    /// A file could not be analyzed, planned or transferred
+    Failed { src: &'a Path, error: String },
+    /// Final counters, emitted once at the end of the run
+    Summary(&'a OrganizeStats),
+}
+
+/// Receives the events of an organize run, in place of the log on stderr.
+///
+/// Implemented by [`EventWriter`] and by closures taking an [`Event`]. Events
+/// arrive from worker threads, hence `Send + Sync`.
+pub trait ProgressSink: Send + Sync {
+    /// Handles one event; an error aborts the run.
+    fn emit(&self, event: &Event<'_>) -> io::Result<()>;
+}
+
+impl<F> ProgressSink for F
+where
+    F: Fn(&Event<'_>) + Send + Sync,
+{
+    fn emit(&self, event: &Event<'_>) -> io::Result<()> {
+        self(event);
+        Ok(())
+    }
+}
+
+/// Writes events as newline-delimited JSON.
+///
+/// Each event is serialized first and written with a single `write_all` under a
+/// lock, so events emitted from worker threads never interleave.
+pub struct EventWriter {
+    out: Mutex<Box<dyn Write + Send>>,
+}
+
+impl EventWriter {
+    /// Emits events to `out`.
+    pub fn new<W: Write + Send + 'static>(out: W) -> Self {
+        EventWriter {
+            out: Mutex::new(Box::new(out)),
+        }
+    }
+
+    /// Emits events to standard output.
+    pub fn stdout() -> Self {
+        EventWriter::new(io::stdout())
+    }
+
+    /// Writes `event` as one line and flushes it.
+    pub fn emit(&self, event: &Event<'_>) -> io::Result<()> {
+        let mut line = serde_json::to_string(event)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        line.push('\n');
+        let mut out = self.out.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        out.write_all(line.as_bytes())?;
+        out.flush()
+    }
+}
+
+impl ProgressSink for EventWriter {
+    fn emit(&self, event: &Event<'_>) -> io::Result<()> {
+        EventWriter::emit(self, event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_format() -> serde_json::Result<()> {
+        let event = Event::SkippedDuplicate { src: Path::new("/in/a.jpg"), duplicate_of: None };
+        assert_eq!(
+            serde_json::to_string(&event)?,
+            r#"{"event":"skipped_duplicate","src":"/in/a.jpg","duplicate_of":null}"#
+        );
+
+        let stats = OrganizeStats { files_scanned: 2, ..OrganizeStats::default() };
+        let summary: serde_json::Value = serde_json::to_value(Event::Summary(&stats))?;
+        assert_eq!(summary["event"], "summary");
+        assert_eq!(summary["files_scanned"], 2);
+        Ok(())
+    }
+}
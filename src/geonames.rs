@@ -2,9 +2,13 @@
 //!
 //! This module provides an embedded database of major cities and locations
 //! worldwide, enabling offline reverse geocoding without external API calls.
-//! The database includes the top major cities with populations.
+//! The database includes the top major cities with populations; a full GeoNames
+//! cities file can be loaded instead for finer-grained place names.
 
 use crate::clustering::GeoNameEntry;
+use std::fs;
+use std::io::{self, BufRead};
+use std::path::Path;
 
 /// Returns an embedded list of major GeoNames entries for reverse geocoding.
 ///
@@ -55,10 +59,46 @@ pub fn load_geonames() -> Vec<GeoNameEntry> {
     ]
 }
 
+/// Loads GeoNames entries from a GeoNames cities dump.
+///
+/// Accepts the tab-separated `cities500.txt`, `cities1000.txt`, `cities5000.txt`
+/// or `cities15000.txt` files from <https://download.geonames.org/export/dump/>.
+/// Lines that cannot be parsed are skipped.
+///
+/// # Errors
+///
+/// Fails if the file cannot be read, or if it contains no usable entry at all
+/// (which usually means a wrong file was given).
+///
+/// # Examples
+///
+/// ```no_run
+/// # use sift::geonames;
+/// let locations = geonames::load_geonames_from_file("cities1000.txt")?;
+/// println!("Loaded {} places", locations.len());
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn load_geonames_from_file<P: AsRef<Path>>(path: P) -> io::Result<Vec<GeoNameEntry>> {
+    let reader = io::BufReader::new(fs::File::open(path.as_ref())?);
+    let mut entries = Vec::new();
+    for line in reader.lines() {
+        if let Some(entry) = parse_geonames_line(&line?) {
+            entries.push(entry);
+        }
+    }
+
+    if entries.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("no GeoNames entries found in {:?}", path.as_ref()),
+        ));
+    }
+    Ok(entries)
+}
+
 /// Parses a single line from the GeoNames cities1000.txt file format.
 ///
-/// This function can be used to load external GeoNames data files if you want
-/// to update the embedded database with more recent data or additional locations.
+/// Used by [`load_geonames_from_file`] to load external GeoNames data files.
 ///
 /// # Format
 ///
@@ -83,7 +123,6 @@ pub fn load_geonames() -> Vec<GeoNameEntry> {
 /// assert!(entry.is_some());
 /// assert_eq!(entry.unwrap().name, "Paris");
 /// ```
-#[allow(dead_code)]
 pub fn parse_geonames_line(line: &str) -> Option<GeoNameEntry> {
     let parts: Vec<&str> = line.split('\t').collect();
     if parts.len() < 6 {
@@ -135,6 +174,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(unused_comparisons, clippy::absurd_extreme_comparisons)]
     fn test_load_geonames_population_reasonable() {
         let locations = load_geonames();
         for location in locations {
@@ -209,4 +249,35 @@ mod tests {
             assert!(!location.name.is_empty());
         }
     }
+
+    #[test]
+    fn test_load_geonames_from_file() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("cities1000.txt");
+        fs::write(
+            &path,
+            "2988507\tParis\tParis\t\t48.85341\t2.3488\tP\tPPLC\tFR\t\t11\t75\t751\t75056\t2138551\t\t42\tEurope/Paris\t2024-01-01\n\
+             malformed row\n\
+             \n\
+             2996944\tLyon\tLyon\t\t45.74846\t4.84671\tP\tPPLA\tFR\t\t84\t69\t691\t69123\t522969\t\t173\tEurope/Paris\t2024-01-01\n",
+        )?;
+
+        let entries = load_geonames_from_file(&path)?;
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].name, "Lyon");
+        assert_eq!(entries[1].population, 522969);
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_geonames_from_file_errors() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        assert!(load_geonames_from_file(dir.path().join("missing.txt")).is_err());
+
+        let path = dir.path().join("not_geonames.txt");
+        fs::write(&path, "just some text\n")?;
+        let err = load_geonames_from_file(&path).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        Ok(())
+    }
 }
@@ -4,6 +4,9 @@
 //! optimized for large files with buffered I/O. It supports both individual file
 //! hashing and parallel batch processing.
 //!
+//! The `hash` command can also produce SHA-256 hashes ([`hash_file_with`]), to
+//! compare against checksums made by other tools.
+//!
 //! # Examples
 //!
 //! Hash a single file:
@@ -25,12 +28,24 @@
 //! ```
 
 use blake3;
+use clap::ValueEnum;
 use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
 use std::fs::File;
-use std::io::{self, Read};
-use std::path::Path;
+use std::io::{self, BufRead, Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::network_io;
 
 const BLOCK_SIZE: usize = 65536; // 64KB blocks for reading files
+const PARALLEL_BLOCK_SIZE: usize = 8 * 1_048_576; // 8MB blocks handed to update_rayon
+const STREAMING_BATCH: usize = 1024; // files hashed at a time by hash_files_streaming
+const ALGORITHM_HEADER: &str = "# algorithm: "; // first line of a checksum listing
+
+/// Files at least this large are hashed on all cores by [`hash_file_multithreaded`].
+pub const DEFAULT_PARALLEL_THRESHOLD: u64 = 16 * 1_048_576;
 
 /// Computes the Blake3 hash of a file using buffered I/O.
 ///
@@ -56,9 +71,246 @@ const BLOCK_SIZE: usize = 65536; // 64KB blocks for reading files
 /// ```
 pub fn hash_file<P: AsRef<Path>>(path: P) -> io::Result<blake3::Hash> {
     let file = File::open(path)?;
+    hash_reader(io::BufReader::with_capacity(BLOCK_SIZE * 4, file))
+}
+
+/// Like [`hash_file`], but retries transient network errors.
+///
+/// Opening the file and every block read go through `network_io`'s
+/// exponential backoff, so a blip halfway through a large file on an SMB/NFS
+/// share retries that one read instead of failing the whole hash. The file is
+/// still streamed in 64KB blocks rather than loaded into memory.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use sift::hash;
+/// let hash = hash::hash_file_resilient("/mnt/smb/photo.jpg")?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn hash_file_resilient<P: AsRef<Path>>(path: P) -> io::Result<blake3::Hash> {
+    let config = network_io::default_retry_config();
+    let file = network_io::retry_with(&config, || File::open(&path))?;
+    let reader = network_io::RetryingReader::new(file, config);
+    hash_reader(io::BufReader::with_capacity(BLOCK_SIZE * 4, reader))
+}
+
+/// Like [`hash_file`], but spreads a single large file across all cores.
+///
+/// Files of at least `parallel_threshold` bytes are read in 8MB blocks, each
+/// fed to `blake3::Hasher::update_rayon`. Smaller files take the sequential
+/// path, where spawning work on the thread pool costs more than it saves.
+/// Unlike [`hash_file_mmap`] this still streams the file, so it is safe to use
+/// on network shares.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use sift::hash;
+/// let hash = hash::hash_file_multithreaded("clip.mov", hash::DEFAULT_PARALLEL_THRESHOLD)?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn hash_file_multithreaded<P: AsRef<Path>>(
+    path: P,
+    parallel_threshold: u64,
+) -> io::Result<blake3::Hash> {
+    let mut file = File::open(path)?;
+    if file.metadata()?.len() < parallel_threshold {
+        return hash_reader(io::BufReader::with_capacity(BLOCK_SIZE * 4, file));
+    }
+
     let mut hasher = blake3::Hasher::new();
+    let mut buffer = vec![0; PARALLEL_BLOCK_SIZE];
+    loop {
+        let n = fill_buffer(&mut file, &mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update_rayon(&buffer[..n]);
+    }
 
-    let mut reader = io::BufReader::with_capacity(BLOCK_SIZE * 4, file);
+    Ok(hasher.finalize())
+}
+
+/// Reads until `buffer` is full or the reader is exhausted; returns the bytes read.
+fn fill_buffer<R: Read>(reader: &mut R, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match reader.read(&mut buffer[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+/// Computes the Blake3 hash of a file by memory-mapping it.
+///
+/// The mapped file is hashed with `blake3::Hasher::update_rayon`, which splits a
+/// single large file across all cores. On local SSDs this usually beats
+/// [`hash_file`]; on network shares every page fault is a round-trip, so keep
+/// using the buffered reader there.
+///
+/// Empty files are hashed without mapping, since mapping a zero-length file
+/// fails or is undefined on some platforms.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use sift::hash;
+/// let hash = hash::hash_file_mmap("/Volumes/SSD/video.mov")?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn hash_file_mmap<P: AsRef<Path>>(path: P) -> io::Result<blake3::Hash> {
+    let file = File::open(path)?;
+    if file.metadata()?.len() == 0 {
+        return Ok(hash_bytes(&[]));
+    }
+    // SAFETY: the map is read-only and dropped before returning. Another process
+    // truncating the file meanwhile could fault the read, as with any mmap use;
+    // that is accepted for an opt-in fast path on local libraries.
+    let map = unsafe { memmap2::Mmap::map(&file)? };
+    let mut hasher = blake3::Hasher::new();
+    hasher.update_rayon(&map);
+    Ok(hasher.finalize())
+}
+
+/// Hash algorithm of the `hash` command.
+///
+/// Everything else in Sift (the index, duplicate detection, `verify`) uses
+/// Blake3. SHA-256 is offered to match checksums kept by other tools.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum HashAlgorithm {
+    /// Blake3, SIMD-parallel and much faster
+    #[default]
+    Blake3,
+    /// SHA-256, as `sha256sum` computes it
+    Sha256,
+}
+
+impl fmt::Display for HashAlgorithm {
+    /// Writes the `--algorithm` name of the algorithm.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            HashAlgorithm::Blake3 => "blake3",
+            HashAlgorithm::Sha256 => "sha256",
+        })
+    }
+}
+
+/// Computes the hash of a file with `algorithm`, as lowercase hex.
+///
+/// Blake3 goes through [`hash_file`]; SHA-256 streams the file in the same
+/// 64KB blocks. Both produce 64 hex characters.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use sift::hash::{self, HashAlgorithm};
+/// let hex = hash::hash_file_with("photo.jpg", HashAlgorithm::Sha256)?;
+/// println!("{}  photo.jpg", hex);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn hash_file_with<P: AsRef<Path>>(path: P, algorithm: HashAlgorithm) -> io::Result<String> {
+    match algorithm {
+        HashAlgorithm::Blake3 => hash_file(path).map(|hash| hash.to_hex().to_string()),
+        HashAlgorithm::Sha256 => {
+            let file = File::open(path)?;
+            sha256_reader(io::BufReader::with_capacity(BLOCK_SIZE * 4, file))
+        }
+    }
+}
+
+/// Hashes everything `reader` yields with SHA-256, as lowercase hex.
+fn sha256_reader<R: Read>(mut reader: R) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; BLOCK_SIZE];
+
+    loop {
+        let n = reader.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+    }
+
+    Ok(hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+/// What a content hash was computed over.
+///
+/// * `Bytes` - The file's raw bytes ([`hash_file`] and friends)
+/// * `Pixels` - The decoded, orientation-normalized image ([`hash_pixels`])
+///
+/// Hashes of different kinds never compare equal in a meaningful way, so the
+/// index records the kind next to each hash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashKind {
+    #[default]
+    Bytes,
+    Pixels,
+}
+
+/// Computes the Blake3 hash of an image's decoded pixels.
+///
+/// The image is decoded, rotated or flipped upright according to its EXIF
+/// orientation, and converted to 8-bit RGBA before hashing its dimensions and
+/// pixel data. Two files therefore hash equal when they differ only in their
+/// metadata, such as a copy whose caption or GPS tags were edited. A copy that
+/// was re-encoded, resized or rotated by rewriting its pixels decodes to
+/// different pixels and does not match.
+///
+/// Only formats the `image` crate decodes here (JPEG and PNG) are supported.
+///
+/// # Errors
+///
+/// Fails with `InvalidData` if the file is not a decodable image.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use sift::hash;
+/// let original = hash::hash_pixels("IMG_0001.jpg")?;
+/// let retagged = hash::hash_pixels("IMG_0001 (edited).jpg")?;
+/// println!("same picture: {}", original == retagged);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn hash_pixels<P: AsRef<Path>>(path: P) -> io::Result<blake3::Hash> {
+    use image::{DynamicImage, ImageDecoder, ImageReader};
+
+    let invalid = |e: image::ImageError| io::Error::new(io::ErrorKind::InvalidData, e);
+    let mut decoder = ImageReader::open(path)?
+        .with_guessed_format()?
+        .into_decoder()
+        .map_err(invalid)?;
+    let orientation = decoder.orientation().map_err(invalid)?;
+    let mut image = DynamicImage::from_decoder(decoder).map_err(invalid)?;
+    image.apply_orientation(orientation);
+
+    let pixels = image.to_rgba8();
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(&pixels.width().to_le_bytes());
+    hasher.update(&pixels.height().to_le_bytes());
+    hasher.update(pixels.as_raw());
+    Ok(hasher.finalize())
+}
+
+/// Returns `true` if [`hash_pixels`] handles files with this path's extension.
+///
+/// Files it rejects always get byte hashes, even when pixel hashing is requested.
+pub fn supports_pixels<P: AsRef<Path>>(path: P) -> bool {
+    matches!(
+        image::ImageFormat::from_path(path),
+        Ok(image::ImageFormat::Jpeg | image::ImageFormat::Png)
+    )
+}
+
+/// Hashes everything `reader` yields, one block at a time.
+fn hash_reader<R: Read>(mut reader: R) -> io::Result<blake3::Hash> {
+    let mut hasher = blake3::Hasher::new();
     let mut buffer = vec![0; BLOCK_SIZE];
 
     loop {
@@ -98,7 +350,8 @@ pub fn hash_bytes(data: &[u8]) -> blake3::Hash {
 /// Computes Blake3 hashes for multiple files in parallel using Rayon.
 ///
 /// This function uses Rayon's data parallelism to hash multiple files
-/// concurrently. Files that cannot be read are silently skipped.
+/// concurrently. Files that cannot be read are silently skipped; use
+/// [`hash_files_parallel_detailed`] to find out which ones failed.
 ///
 /// # Arguments
 ///
@@ -106,7 +359,8 @@ pub fn hash_bytes(data: &[u8]) -> blake3::Hash {
 ///
 /// # Returns
 ///
-/// A vector of tuples containing (file_path, hash) for successfully hashed files
+/// A vector of tuples containing (file_path, hash) for successfully hashed files,
+/// in input order
 ///
 /// # Examples
 ///
@@ -117,16 +371,248 @@ pub fn hash_bytes(data: &[u8]) -> blake3::Hash {
 /// assert!(results.len() <= 2);
 /// ```
 pub fn hash_files_parallel<P: AsRef<Path>>(paths: Vec<P>) -> Vec<(String, blake3::Hash)> {
+    hash_files_parallel_detailed(paths)
+        .into_iter()
+        .filter_map(|(path, result)| {
+            let hash = result.ok()?; // Skip files that can't be read
+            Some((path.to_string_lossy().to_string(), hash))
+        })
+        .collect()
+}
+
+/// Computes Blake3 hashes for multiple files in parallel, keeping failures.
+///
+/// Returns one `(path, result)` pair per input path, in the same order as
+/// `paths`, so callers can report exactly which files could not be hashed and
+/// why.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use sift::hash;
+/// for (path, result) in hash::hash_files_parallel_detailed(vec!["a.jpg", "b.jpg"]) {
+///     match result {
+///         Ok(hash) => println!("{}: {}", path.display(), hash.to_hex()),
+///         Err(e) => eprintln!("{}: {}", path.display(), e),
+///     }
+/// }
+/// ```
+pub fn hash_files_parallel_detailed<P: AsRef<Path>>(
+    paths: Vec<P>,
+) -> Vec<(PathBuf, io::Result<blake3::Hash>)> {
+    hash_files_parallel_detailed_with(paths, |path| hash_file(path))
+}
+
+/// Like [`hash_files_parallel_detailed`], hashing each file with `hasher`
+/// (e.g. [`hash_file_mmap`], or [`hash_file_with`] for hex hashes of any
+/// algorithm).
+pub fn hash_files_parallel_detailed_with<P, F, H>(
+    paths: Vec<P>,
+    hasher: F,
+) -> Vec<(PathBuf, io::Result<H>)>
+where
+    P: AsRef<Path>,
+    F: Fn(&Path) -> io::Result<H> + Sync,
+    H: Send,
+{
     paths
         .into_iter()
-        .map(|p| p.as_ref().to_string_lossy().to_string())
+        .map(|p| p.as_ref().to_path_buf())
         .collect::<Vec<_>>()
         .into_par_iter()
-        .filter_map(|path| {
-            match hash_file(&path) {
-                Ok(hash) => Some((path, hash)),
-                Err(_) => None, // Skip files that can't be read
+        // Indexed parallel iterators collect in input order
+        .map(|path| {
+            let result = hasher(&path);
+            (path, result)
+        })
+        .collect()
+}
+
+/// Hashes the files `paths` yields in parallel, handing each result to
+/// `on_result` in the order of `paths`.
+///
+/// Unlike [`hash_files_parallel_detailed`], the paths are not collected first:
+/// they are taken from `paths` (e.g. a directory walk) 1024 at a time, and each
+/// batch is hashed on all cores before the next is taken. Hashing starts once
+/// the first batch is found, and memory stays bounded by the batch size however
+/// many files there are. `on_result` runs on the calling thread; its first
+/// error stops hashing and is returned.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use sift::hash;
+/// let files = walkdir::WalkDir::new("/photos")
+///     .sort_by_file_name()
+///     .into_iter()
+///     .filter_map(|entry| entry.ok())
+///     .filter(|entry| entry.file_type().is_file())
+///     .map(|entry| entry.into_path());
+/// hash::hash_files_streaming(files, |path| hash::hash_file(path), |path, result| {
+///     if let Ok(hash) = result {
+///         println!("{}: {}", path.display(), hash.to_hex());
+///     }
+///     Ok(())
+/// })?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn hash_files_streaming<I, F, R, H>(mut paths: I, hasher: F, mut on_result: R) -> io::Result<()>
+where
+    I: Iterator<Item = PathBuf>,
+    F: Fn(&Path) -> io::Result<H> + Sync,
+    R: FnMut(PathBuf, io::Result<H>) -> io::Result<()>,
+    H: Send,
+{
+    loop {
+        let batch: Vec<PathBuf> = paths.by_ref().take(STREAMING_BATCH).collect();
+        if batch.is_empty() {
+            return Ok(());
+        }
+        for (path, result) in hash_files_parallel_detailed_with(batch, &hasher) {
+            on_result(path, result)?;
+        }
+    }
+}
+
+/// One line of a checksum listing: `<hash>  <path>`, as written by `sha256sum`.
+///
+/// `hash` is lowercase hex.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checksum {
+    pub hash: String,
+    pub path: PathBuf,
+}
+
+/// A checksum listing, as read by [`read_checksums`].
+///
+/// Listings written by Sift start with a `# algorithm: <name>` line naming the
+/// [`HashAlgorithm`] (`sha256sum --check` skips it as a comment). `algorithm` is
+/// `None` for listings from other tools, which do not record it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChecksumListing {
+    pub algorithm: Option<HashAlgorithm>,
+    pub checksums: Vec<Checksum>,
+}
+
+/// How the `hash` command prints the path of each file.
+///
+/// * `Full` - As found (default)
+/// * `RelativeTo` - Relative to a base directory; paths outside it are printed in full
+/// * `FileName` - The file name alone, for flat reports
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum PathStyle {
+    #[default]
+    Full,
+    RelativeTo(PathBuf),
+    FileName,
+}
+
+impl PathStyle {
+    /// Returns `path` as it should be printed.
+    ///
+    /// Relative paths are compared with the base as absolute paths first, so
+    /// `photos/a.jpg` is under `./photos`. When that fails, both are compared
+    /// with symlinks resolved, so `/tmp/x` is still under a base given as its
+    /// target.
+    pub fn apply(&self, path: &Path) -> PathBuf {
+        match self {
+            PathStyle::Full => path.to_path_buf(),
+            PathStyle::FileName => path.file_name().map_or_else(|| path.to_path_buf(), PathBuf::from),
+            PathStyle::RelativeTo(base) => {
+                let lexical = || {
+                    let absolute = std::path::absolute(path).ok()?;
+                    Some(absolute.strip_prefix(std::path::absolute(base).ok()?).ok()?.to_path_buf())
+                };
+                let resolved = || {
+                    let canonical = path.canonicalize().ok()?;
+                    Some(canonical.strip_prefix(base.canonicalize().ok()?).ok()?.to_path_buf())
+                };
+                lexical().or_else(resolved).unwrap_or_else(|| path.to_path_buf())
+            }
+        }
+    }
+}
+
+/// Writes `checksums` as a listing: the `# algorithm` header, then one
+/// `<hash>  <path>` line each.
+pub fn write_checksums<W: Write>(mut writer: W, algorithm: HashAlgorithm, checksums: &[Checksum]) -> io::Result<()> {
+    write_checksum_header(&mut writer, algorithm)?;
+    for checksum in checksums {
+        write_checksum(&mut writer, checksum)?;
+    }
+    writer.flush()
+}
+
+/// Writes the `# algorithm: <name>` line that starts a listing.
+pub fn write_checksum_header<W: Write>(writer: &mut W, algorithm: HashAlgorithm) -> io::Result<()> {
+    writeln!(writer, "{}{}", ALGORITHM_HEADER, algorithm)
+}
+
+/// Writes one `<hash>  <path>` line of a listing.
+pub fn write_checksum<W: Write>(writer: &mut W, checksum: &Checksum) -> io::Result<()> {
+    writeln!(writer, "{}  {}", checksum.hash, checksum.path.display())
+}
+
+/// Reads a listing written by [`write_checksums`].
+///
+/// Blank lines and `#` comments are skipped, apart from the `# algorithm`
+/// header. The `sha256sum` binary-mode marker (`<hash> *<path>`) is accepted too.
+///
+/// # Errors
+///
+/// Fails with `InvalidData`, naming the line, if a line is not a hash, a space
+/// and a path, or if the header names an unknown algorithm.
+pub fn read_checksums<R: BufRead>(reader: R) -> io::Result<ChecksumListing> {
+    let mut algorithm = None;
+    let mut checksums = Vec::new();
+    for (number, line) in reader.lines().enumerate() {
+        let line = line?;
+        if let Some(name) = line.strip_prefix(ALGORITHM_HEADER) {
+            let parsed = HashAlgorithm::from_str(name.trim(), true).map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("line {}: unknown hash algorithm {:?}", number + 1, name.trim()),
+                )
+            })?;
+            algorithm = Some(parsed);
+            continue;
+        }
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let parsed = line.split_once(' ').and_then(|(hash, rest)| {
+            let path = rest.strip_prefix(' ').or_else(|| rest.strip_prefix('*'))?;
+            let valid = hash.len() == 64 && hash.bytes().all(|b| b.is_ascii_hexdigit()) && !path.is_empty();
+            valid.then(|| Checksum { hash: hash.to_lowercase(), path: PathBuf::from(path) })
+        });
+        match parsed {
+            Some(checksum) => checksums.push(checksum),
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("line {}: expected \"<hash>  <path>\", got {:?}", number + 1, line),
+                ));
             }
+        }
+    }
+    Ok(ChecksumListing { algorithm, checksums })
+}
+
+/// Re-hashes every file of `checksums` in parallel with `hasher`, which returns
+/// lowercase hex (e.g. [`hash_file_with`]).
+///
+/// Returns one result per checksum, in order: `Ok(true)` if the file still has
+/// its listed hash, `Ok(false)` if it changed, or the error if it could not be
+/// read.
+pub fn check_checksums<F>(checksums: &[Checksum], hasher: F) -> Vec<(&Checksum, io::Result<bool>)>
+where
+    F: Fn(&Path) -> io::Result<String> + Sync,
+{
+    checksums
+        .par_iter()
+        .map(|checksum| {
+            let result = hasher(&checksum.path).map(|hash| hash == checksum.hash);
+            (checksum, result)
         })
         .collect()
 }
@@ -134,6 +620,8 @@ pub fn hash_files_parallel<P: AsRef<Path>>(paths: Vec<P>) -> Vec<(String, blake3
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testutil::{self, FlakyReader};
+    use std::fs;
     use std::io::Write;
     use tempfile::NamedTempFile;
 
@@ -253,4 +741,272 @@ mod tests {
         assert_eq!(results.len(), 1, "Should skip nonexistent files");
         Ok(())
     }
+
+    #[test]
+    fn test_hash_file_resilient_matches_hash_file() -> io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        temp_file.write_all(&vec![7u8; 300_000])?;
+        temp_file.flush()?;
+
+        assert_eq!(hash_file_resilient(temp_file.path())?, hash_file(temp_file.path())?);
+        assert!(hash_file_resilient("/nonexistent/path/file.jpg").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_hash_reader_survives_mid_file_failure() -> io::Result<()> {
+        let data = vec![3u8; 200_000];
+        let flaky = FlakyReader::new(data.clone(), 100_000, io::ErrorKind::TimedOut);
+        let config = network_io::RetryConfig { initial_delay_ms: 1, ..Default::default() };
+
+        let hash = hash_reader(network_io::RetryingReader::new(flaky, config))?;
+        assert_eq!(hash, hash_bytes(&data));
+        Ok(())
+    }
+
+    #[test]
+    fn test_hash_files_parallel_detailed_keeps_order_and_errors() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let mut paths = Vec::new();
+        for i in 0..20 {
+            let path = dir.path().join(format!("{:02}.jpg", i));
+            fs::write(&path, format!("photo {}", i))?;
+            paths.push(path);
+        }
+        let missing = dir.path().join("missing.jpg");
+        paths.insert(7, missing.clone());
+
+        let results = hash_files_parallel_detailed(paths.clone());
+        let returned: Vec<PathBuf> = results.iter().map(|(path, _)| path.clone()).collect();
+        assert_eq!(returned, paths);
+
+        for (path, result) in &results {
+            if *path == missing {
+                assert_eq!(result.as_ref().unwrap_err().kind(), io::ErrorKind::NotFound);
+            } else {
+                assert_eq!(*result.as_ref().unwrap(), hash_file(path)?);
+            }
+        }
+
+        assert_eq!(hash_files_parallel(paths).len(), 20);
+        Ok(())
+    }
+
+    #[test]
+    fn test_hash_files_streaming_hashes_nested_tree() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let mut expected = Vec::new();
+        for (folder, count) in [("", 3), ("2023", 2), ("2023/01/15", 25), ("raw/deep/er", 4)] {
+            fs::create_dir_all(dir.path().join(folder))?;
+            for i in 0..count {
+                let path = dir.path().join(folder).join(format!("IMG_{:02}.jpg", i));
+                fs::write(&path, format!("{} {}", folder, i))?;
+                expected.push((path.clone(), hash_file(&path)?));
+            }
+        }
+        expected.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let files = walkdir::WalkDir::new(dir.path())
+            .sort_by_file_name()
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_file())
+            .map(|entry| entry.into_path());
+        let mut seen = Vec::new();
+        hash_files_streaming(files, |path| hash_file(path), |path, result| {
+            seen.push((path, result?));
+            Ok(())
+        })?;
+
+        // A sorted walk comes out in path order, without sorting the results
+        assert_eq!(seen.len(), 34);
+        assert_eq!(seen, expected);
+
+        // The first error from `on_result` stops the run
+        let mut calls = 0;
+        let files = expected.iter().map(|(path, _)| path.clone());
+        let stopped = hash_files_streaming(files, |path| hash_file(path), |_, _| {
+            calls += 1;
+            Err(io::Error::other("disk full"))
+        });
+        assert_eq!(stopped.unwrap_err().to_string(), "disk full");
+        assert_eq!(calls, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_checksums_round_trip() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let mut checksums = Vec::new();
+        for name in ["a.jpg", "b c.jpg", "d.jpg"] {
+            let path = dir.path().join(name);
+            fs::write(&path, name)?;
+            checksums.push(Checksum { hash: hash_file(&path)?.to_hex().to_string(), path });
+        }
+        let listing = dir.path().join("sums.txt");
+        write_checksums(File::create(&listing)?, HashAlgorithm::Blake3, &checksums)?;
+        assert!(fs::read_to_string(&listing)?.starts_with("# algorithm: blake3\n"));
+
+        let read = read_checksums(io::BufReader::new(File::open(&listing)?))?;
+        assert_eq!(read.algorithm, Some(HashAlgorithm::Blake3));
+        assert_eq!(read.checksums, checksums);
+        let read = read.checksums;
+        let results = check_checksums(&read, |path| hash_file_with(path, HashAlgorithm::Blake3));
+        assert!(results.iter().all(|(_, result)| matches!(result, Ok(true))));
+
+        // One file edited, one gone
+        fs::write(dir.path().join("a.jpg"), "edited")?;
+        fs::remove_file(dir.path().join("d.jpg"))?;
+        let results = check_checksums(&read, |path| hash_file_with(path, HashAlgorithm::Blake3));
+        assert!(matches!(results[0].1, Ok(false)));
+        assert!(matches!(results[1].1, Ok(true)));
+        assert_eq!(results[2].1.as_ref().unwrap_err().kind(), io::ErrorKind::NotFound);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sha256_test_vectors() -> io::Result<()> {
+        // FIPS 180-2 vectors, plus one longer than a read block
+        let million_a = "a".repeat(1_000_000);
+        for (input, expected) in [
+            ("", "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"),
+            ("abc", "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"),
+            (
+                "abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq",
+                "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1",
+            ),
+            (million_a.as_str(), "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0"),
+        ] {
+            let mut file = NamedTempFile::new()?;
+            file.write_all(input.as_bytes())?;
+            assert_eq!(hash_file_with(file.path(), HashAlgorithm::Sha256)?, expected);
+            assert_eq!(
+                hash_file_with(file.path(), HashAlgorithm::Blake3)?,
+                hash_bytes(input.as_bytes()).to_hex().as_str()
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_sha256_listing() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("abc.txt");
+        fs::write(&path, "abc")?;
+        let listing = format!(
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad  {}\n",
+            path.display()
+        );
+        let read = read_checksums(listing.as_bytes())?;
+        // Written by sha256sum: the algorithm is not recorded
+        assert_eq!(read.algorithm, None);
+        let read = read.checksums;
+        let sha256 = check_checksums(&read, |path| hash_file_with(path, HashAlgorithm::Sha256));
+        assert!(matches!(sha256[0].1, Ok(true)));
+        // Checked with the wrong algorithm, every file mismatches
+        let blake3 = check_checksums(&read, |path| hash_file_with(path, HashAlgorithm::Blake3));
+        assert!(matches!(blake3[0].1, Ok(false)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_path_styles() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let base = dir.path().join("photos");
+        let nested = base.join("2019/03/a.jpg");
+        fs::create_dir_all(nested.parent().unwrap())?;
+        fs::write(&nested, "a")?;
+        let outside = dir.path().join("other/b.jpg");
+
+        assert_eq!(PathStyle::Full.apply(&nested), nested);
+        assert_eq!(PathStyle::FileName.apply(&nested), PathBuf::from("a.jpg"));
+        assert_eq!(PathStyle::FileName.apply(&outside), PathBuf::from("b.jpg"));
+
+        let relative = PathStyle::RelativeTo(base.clone());
+        assert_eq!(relative.apply(&nested), PathBuf::from("2019/03/a.jpg"));
+        // Not under the base: printed in full
+        assert_eq!(relative.apply(&outside), outside);
+        // A base with a trailing `.` still matches
+        assert_eq!(PathStyle::RelativeTo(base.join(".")).apply(&nested), PathBuf::from("2019/03/a.jpg"));
+
+        // A base reached through a symlink matches files under its target
+        #[cfg(unix)]
+        {
+            let link = dir.path().join("link");
+            std::os::unix::fs::symlink(&base, &link)?;
+            assert_eq!(PathStyle::RelativeTo(link).apply(&nested), PathBuf::from("2019/03/a.jpg"));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_checksums_formats() {
+        let hash = hash_bytes(b"x").to_hex().to_string();
+        let text = format!(
+            "# algorithm: sha256\n{}  photo.jpg\n\n# a comment\n{} *raw/IMG 1.cr2\n",
+            hash.to_uppercase(),
+            hash
+        );
+        let read = read_checksums(text.as_bytes()).unwrap();
+        assert_eq!(read.algorithm, Some(HashAlgorithm::Sha256));
+        let read = read.checksums;
+        assert_eq!(read[0], Checksum { hash: hash.clone(), path: PathBuf::from("photo.jpg") });
+        assert_eq!(read[1].path, PathBuf::from("raw/IMG 1.cr2"));
+
+        for bad in ["photo.jpg", "abc  photo.jpg", &format!("{} ", hash), &format!("{}\tphoto.jpg", hash)] {
+            let err = read_checksums(format!("{}  ok.jpg\n{}\n", hash, bad).as_bytes()).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+            assert!(err.to_string().starts_with("line 2:"), "{}", err);
+        }
+        let err = read_checksums(format!("{}  ok.jpg\n# algorithm: md5\n", hash).as_bytes()).unwrap_err();
+        assert_eq!(err.to_string(), "line 2: unknown hash algorithm \"md5\"");
+    }
+
+    #[test]
+    fn test_hash_file_mmap_matches_buffered() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        // Empty, tiny, just over one block, and large enough to split across threads
+        for size in [0usize, 1, 65_537, 3 * 1_048_576 + 17] {
+            let path = dir.path().join(format!("{}.bin", size));
+            let data: Vec<u8> = (0..size).map(|i| (i % 253) as u8).collect();
+            fs::write(&path, &data)?;
+
+            assert_eq!(hash_file_mmap(&path)?, hash_file(&path)?, "size {}", size);
+        }
+        assert!(hash_file_mmap("/nonexistent/path/file.jpg").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_hash_file_multithreaded_matches_sequential() -> io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        let data: Vec<u8> = (0..32 * 1_048_576).map(|i| (i % 251) as u8).collect();
+        temp_file.write_all(&data)?;
+        temp_file.flush()?;
+
+        let expected = hash_bytes(&data);
+        assert_eq!(hash_file(temp_file.path())?, expected);
+        // Above the threshold (update_rayon) and below it (sequential fallback)
+        assert_eq!(hash_file_multithreaded(temp_file.path(), DEFAULT_PARALLEL_THRESHOLD)?, expected);
+        assert_eq!(hash_file_multithreaded(temp_file.path(), u64::MAX)?, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_hash_pixels_ignores_metadata() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let a = dir.path().join("a.jpg");
+        let b = dir.path().join("b.jpg");
+        fs::write(&a, testutil::encode_jpeg(None))?;
+        // One extra metadata byte, same picture
+        fs::write(&b, testutil::encode_jpeg(Some(b"x")))?;
+
+        assert_ne!(hash_file(&a)?, hash_file(&b)?);
+        assert_eq!(hash_pixels(&a)?, hash_pixels(&b)?);
+
+        let not_image = dir.path().join("c.jpg");
+        fs::write(&not_image, "not an image")?;
+        assert_eq!(hash_pixels(&not_image).unwrap_err().kind(), io::ErrorKind::InvalidData);
+        Ok(())
+    }
 }
@@ -4,6 +4,32 @@
 //! operations on network storage. The index maps file hashes to their metadata
 //! and is serialized using Bincode for compact binary storage.
 //!
+//! # On-disk format
+//!
+//! Since format v2 an index file starts with a fixed header:
+//!
+//! | Bytes  | Content                                   |
+//! |--------|-------------------------------------------|
+//! | 0..8   | Magic `SIFTIDX\0`                         |
+//! | 8..12  | Format version (`u32`, little-endian)     |
+//! | 12..44 | Blake3 checksum of the payload            |
+//! | 44..   | Bincode payload                           |
+//!
+//! Format v1 files are a bare Bincode payload without header. Older formats are
+//! upgraded in memory on load (see [`IndexFile`]) and written back in the current
+//! format on the next save; [`Index::migrate_file`] (`sift migrate <INDEX_FILE>`)
+//! rewrites them on disk right away.
+//!
+//! An index may have a Bloom filter sidecar, `<index>.bloom` (see
+//! [`bloom`](crate::bloom)), which [`Index::contains_hash`] and
+//! [`Index::get_entry`] consult before the map. It is created by
+//! [`Index::rebuild_bloom`] and kept up to date by every later save.
+//!
+//! An index can also be kept in a SQLite database instead (see
+//! [`sqlite_index`](crate::sqlite_index)); both implement [`IndexStore`], and
+//! [`Index::load_from_file`] reads either. Organize queries a SQLite index in
+//! place instead of loading it.
+//!
 //! # Examples
 //!
 //! Create and use an index:
@@ -19,23 +45,350 @@
 //! index.save_to_file("index.bin")?;
 //! # Ok::<(), std::io::Error>(())
 //! ```
+//!
+//! Share one loaded index between threads, or between successive operations,
+//! through an [`IndexHandle`]:
+//! ```no_run
+//! # use sift::index::IndexHandle;
+//! let index = IndexHandle::load("index.bin")?;
+//! std::thread::scope(|scope| {
+//!     for worker in 0..4 {
+//!         let index = index.clone();
+//!         scope.spawn(move || index.add_entry(format!("hash{}", worker), format!("/path{}", worker)));
+//!     }
+//! });
+//! index.save("index.bin")?;
+//! # Ok::<(), std::io::Error>(())
+//! ```
 
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use crate::bloom::{self, BloomFilter};
+use crate::error::OrganizeError;
+use crate::hash::HashKind;
+use crate::sqlite_index::{self, SqliteIndex};
+use clap::ValueEnum;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::fs;
-use std::io;
-use std::path::Path;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// Magic bytes opening every versioned index file.
+const INDEX_MAGIC: &[u8; 8] = b"SIFTIDX\0";
+
+/// Size of the header preceding the payload: magic, version and Blake3 checksum.
+const HEADER_LEN: usize = 8 + 4 + 32;
+
+/// How an index is stored on disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum IndexBackend {
+    /// One bincode file, loaded and rewritten whole ([`Index`])
+    #[default]
+    Bincode,
+    /// A SQLite database, written incrementally ([`SqliteIndex`])
+    Sqlite,
+}
+
+impl fmt::Display for IndexBackend {
+    /// Writes the `--index-format` name of the backend.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            IndexBackend::Bincode => "bincode",
+            IndexBackend::Sqlite => "sqlite",
+        })
+    }
+}
+
+impl IndexBackend {
+    /// Returns the backend of the index file at `path`, or `None` if there is no file.
+    pub fn detect(path: &Path) -> Option<IndexBackend> {
+        if !path.exists() {
+            None
+        } else if sqlite_index::is_sqlite_file(path) {
+            Some(IndexBackend::Sqlite)
+        } else {
+            Some(IndexBackend::Bincode)
+        }
+    }
+}
+
+/// Order in which [`Index::sorted_entries`] lists entries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum EntryOrder {
+    /// By content hash
+    #[default]
+    Hash,
+    /// By first recorded file path, then hash
+    Path,
+}
+
+/// Storage of index entries, whether held in memory or in a database.
+///
+/// Inserting an entry whose hash is already stored merges them as
+/// [`Index::insert`] does. Methods return `io::Result` so that stores backed by
+/// a database can report failures; the in-memory [`Index`] never fails.
+pub trait IndexStore {
+    /// Returns `true` if an entry with this hash is stored.
+    fn contains(&self, hash: &str) -> io::Result<bool>;
+    /// Stores an entry, merging it with any entry for the same hash.
+    fn insert(&mut self, entry: IndexEntry) -> io::Result<()>;
+    /// Returns the entry for a hash, if stored.
+    fn get(&self, hash: &str) -> io::Result<Option<IndexEntry>>;
+    /// Returns the entry recorded for a source path, if any.
+    fn get_by_source(&self, source_path: &str) -> io::Result<Option<IndexEntry>>;
+    /// Returns the distinct file sizes of the stored entries.
+    fn sizes(&self) -> io::Result<HashSet<u64>>;
+    /// Returns every stored entry, in no particular order.
+    fn iter(&self) -> io::Result<Box<dyn Iterator<Item = IndexEntry> + '_>>;
+    /// Returns the number of stored entries.
+    fn len(&self) -> io::Result<usize>;
+    /// Returns `true` if no entries are stored.
+    fn is_empty(&self) -> io::Result<bool> {
+        Ok(self.len()? == 0)
+    }
+}
+
+impl IndexStore for Index {
+    fn contains(&self, hash: &str) -> io::Result<bool> {
+        Ok(self.contains_hash(hash))
+    }
+
+    fn insert(&mut self, entry: IndexEntry) -> io::Result<()> {
+        Index::insert(self, entry);
+        Ok(())
+    }
+
+    fn get(&self, hash: &str) -> io::Result<Option<IndexEntry>> {
+        Ok(self.get_entry(hash).cloned())
+    }
+
+    fn get_by_source(&self, source_path: &str) -> io::Result<Option<IndexEntry>> {
+        Ok(Index::get_by_source(self, source_path).cloned())
+    }
+
+    fn sizes(&self) -> io::Result<HashSet<u64>> {
+        Ok(self.entries().map(|entry| entry.size).collect())
+    }
+
+    fn iter(&self) -> io::Result<Box<dyn Iterator<Item = IndexEntry> + '_>> {
+        Ok(Box::new(self.entries().cloned()))
+    }
+
+    fn len(&self) -> io::Result<usize> {
+        Ok(Index::len(self))
+    }
+}
+
+/// Default file name of the index, written at the destination root.
+pub const INDEX_FILE: &str = ".sift_index.bin";
+
+/// Version of the on-disk format written by [`Index::save_to_file`].
+///
+/// * v1 - Bare Bincode payload, entries hold `hash` and `file_path`
+/// * v2 - Adds the checksummed header
+/// * v3 - Entries also record the source path, size and mtime
+/// * v4 - Entries record every known path of their content (`file_paths`)
+/// * v5 - Entries record what their hash was computed over (`hash_kind`)
+pub const INDEX_FORMAT_VERSION: u32 = 5;
+
+/// Payload layout of v1 (headerless) and v2 index files.
+#[derive(Deserialize)]
+struct LegacyIndex {
+    entries: HashMap<String, LegacyIndexEntry>,
+}
+
+#[derive(Deserialize)]
+struct LegacyIndexEntry {
+    hash: String,
+    file_path: String,
+}
+
+impl From<LegacyIndexEntry> for IndexEntry {
+    fn from(legacy: LegacyIndexEntry) -> Self {
+        IndexEntry {
+            hash: legacy.hash,
+            file_paths: vec![legacy.file_path],
+            source_path: None,
+            size: 0,
+            mtime_unix: 0,
+            hash_kind: HashKind::Bytes,
+        }
+    }
+}
+
+/// Payload layout of v3 index files, with a single path per entry.
+#[derive(Deserialize)]
+struct V3Index {
+    entries: HashMap<String, V3IndexEntry>,
+}
+
+#[derive(Deserialize)]
+struct V3IndexEntry {
+    hash: String,
+    file_path: String,
+    source_path: Option<String>,
+    size: u64,
+    mtime_unix: i64,
+}
+
+impl From<V3IndexEntry> for IndexEntry {
+    fn from(v3: V3IndexEntry) -> Self {
+        IndexEntry {
+            hash: v3.hash,
+            file_paths: vec![v3.file_path],
+            source_path: v3.source_path,
+            size: v3.size,
+            mtime_unix: v3.mtime_unix,
+            hash_kind: HashKind::Bytes,
+        }
+    }
+}
+
+/// Payload layout of v4 index files, before hash kinds were recorded.
+#[derive(Deserialize)]
+struct V4Index {
+    entries: HashMap<String, V4IndexEntry>,
+}
+
+#[derive(Deserialize)]
+struct V4IndexEntry {
+    hash: String,
+    file_paths: Vec<String>,
+    source_path: Option<String>,
+    size: u64,
+    mtime_unix: i64,
+}
+
+impl From<V4IndexEntry> for IndexEntry {
+    fn from(v4: V4IndexEntry) -> Self {
+        IndexEntry {
+            hash: v4.hash,
+            file_paths: v4.file_paths,
+            source_path: v4.source_path,
+            size: v4.size,
+            mtime_unix: v4.mtime_unix,
+            hash_kind: HashKind::Bytes,
+        }
+    }
+}
+
+/// An index file as read from disk: its format version and its entries.
+///
+/// Decoding accepts every known format and upgrades the entries to the current
+/// [`IndexEntry`] schema: single paths become one-element `file_paths`, and
+/// fields older formats lacked get defaults (no source path, zero size and mtime,
+/// byte hashes). `version` still reports the format found on disk, so callers can
+/// tell whether the file needs rewriting.
+#[derive(Debug)]
+pub struct IndexFile {
+    pub version: u32,
+    pub entries: HashMap<String, IndexEntry>,
+}
+
+impl IndexFile {
+    /// Reads and decodes an index file.
+    pub fn read<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::decode(&fs::read(path)?)
+    }
+
+    /// Decodes raw index file contents, whatever their format version.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `InvalidData` if the file is truncated, its checksum does not
+    /// match, the payload cannot be decoded, or it was written by a newer Sift. The
+    /// inner error is always an [`OrganizeError::IndexError`].
+    pub fn decode(data: &[u8]) -> io::Result<Self> {
+        if data.len() < HEADER_LEN && data.starts_with(INDEX_MAGIC) {
+            return Err(index_error("corrupt index: the header is truncated".to_string()));
+        }
+        let version = detect_version(data);
+        let entries = match version {
+            1 => upgrade(deserialize_payload::<LegacyIndex>(data)?.entries),
+            2 => upgrade(deserialize_payload::<LegacyIndex>(verified_payload(data)?)?.entries),
+            3 => upgrade(deserialize_payload::<V3Index>(verified_payload(data)?)?.entries),
+            4 => upgrade(deserialize_payload::<V4Index>(verified_payload(data)?)?.entries),
+            INDEX_FORMAT_VERSION => deserialize_payload::<Index>(verified_payload(data)?)?.entries,
+            newer => {
+                return Err(index_error(format!(
+                    "index uses format v{}, newer than this build supports (v{}); \
+                     a newer version of Sift is needed to read it",
+                    newer, INDEX_FORMAT_VERSION
+                )));
+            }
+        };
+        Ok(IndexFile { version, entries })
+    }
+}
+
+impl From<IndexFile> for Index {
+    fn from(file: IndexFile) -> Self {
+        let mut index = Index::new();
+        for entry in file.entries.into_values() {
+            index.insert(entry);
+        }
+        index
+    }
+}
+
+fn upgrade<E: Into<IndexEntry>>(entries: HashMap<String, E>) -> HashMap<String, IndexEntry> {
+    entries
+        .into_iter()
+        .map(|(hash, entry)| (hash, entry.into()))
+        .collect()
+}
 
 /// Represents a single entry in the deduplication index.
 ///
 /// # Fields
 ///
 /// * `hash` - The Blake3 hash of the file contents
-/// * `file_path` - The path where the file was originally located
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// * `file_paths` - Every path this content is known to live at, first recorded first
+/// * `source_path` - The path the file was last organized from, if known
+/// * `size` - Size of the source file in bytes when it was hashed (0 if unknown)
+/// * `mtime_unix` - Modification time of the source file, in seconds since the Unix epoch
+/// * `hash_kind` - Whether `hash` covers the file's bytes or its decoded pixels
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct IndexEntry {
     pub hash: String,
-    pub file_path: String,
+    #[serde(alias = "file_path", deserialize_with = "deserialize_file_paths")]
+    pub file_paths: Vec<String>,
+    #[serde(default)]
+    pub source_path: Option<String>,
+    #[serde(default)]
+    pub size: u64,
+    #[serde(default)]
+    pub mtime_unix: i64,
+    #[serde(default)]
+    pub hash_kind: HashKind,
+}
+
+/// Reads `file_paths`, also accepting the single `file_path` string of JSON
+/// exports made before format v4. Bincode has no field names or types to go
+/// by, so it always reads a list.
+fn deserialize_file_paths<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum FilePaths {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    if !deserializer.is_human_readable() {
+        return Vec::deserialize(deserializer);
+    }
+    Ok(match FilePaths::deserialize(deserializer)? {
+        FilePaths::One(path) => vec![path],
+        FilePaths::Many(paths) => paths,
+    })
+}
+
+impl IndexEntry {
+    /// Returns the first path recorded for this content.
+    pub fn file_path(&self) -> &str {
+        self.file_paths.first().map(String::as_str).unwrap_or_default()
+    }
 }
 
 /// A persistent index for tracking processed files and enabling idempotent operations.
@@ -46,11 +399,18 @@ pub struct IndexEntry {
 ///
 /// # Thread Safety
 ///
-/// This struct is not thread-safe. For concurrent access, wrap it in `Arc<Mutex<>>`.
+/// Methods that modify the index take `&mut self`. To share an index between
+/// threads, for instance Rayon tasks, use an [`IndexHandle`].
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Index {
     /// Map from hash to file information
     entries: HashMap<String, IndexEntry>,
+    /// Map from source path to hash, rebuilt from `entries` after loading
+    #[serde(skip)]
+    by_source: HashMap<String, String>,
+    /// Filter ruling out absent hashes, kept in a sidecar file (None = no sidecar)
+    #[serde(skip)]
+    bloom: Option<BloomFilter>,
 }
 
 impl Index {
@@ -66,6 +426,8 @@ impl Index {
     pub fn new() -> Self {
         Index {
             entries: HashMap::new(),
+            by_source: HashMap::new(),
+            bloom: None,
         }
     }
 
@@ -89,25 +451,269 @@ impl Index {
     /// assert!(index.contains_hash("abc123"));
     /// ```
     pub fn contains_hash(&self, hash: &str) -> bool {
-        self.entries.contains_key(hash)
+        !self.bloom_rules_out(hash) && self.entries.contains_key(hash)
+    }
+
+    /// Returns `true` if the Bloom filter proves `hash` absent, without touching the map.
+    fn bloom_rules_out(&self, hash: &str) -> bool {
+        self.bloom.as_ref().is_some_and(|bloom| !bloom.might_contain(hash))
+    }
+
+    /// Builds the Bloom filter from the current entries.
+    ///
+    /// Once an index has a filter, saving it also writes the `<index>.bloom`
+    /// sidecar, and loading it picks the sidecar up again, so the filter only
+    /// needs building once. Entries inserted afterwards are added to it. The
+    /// filter is sized for twice the current entries; once the index outgrows
+    /// that, saves write a filter resized to fit.
+    pub fn rebuild_bloom(&mut self) {
+        self.bloom = Some(self.build_bloom());
+    }
+
+    fn build_bloom(&self) -> BloomFilter {
+        let mut bloom = BloomFilter::with_capacity(self.entries.len() * 2);
+        for hash in self.entries.keys() {
+            bloom.insert(hash);
+        }
+        bloom
+    }
+
+    /// Returns `true` if the index has a Bloom filter.
+    pub fn has_bloom(&self) -> bool {
+        self.bloom.is_some()
     }
 
     /// Adds an entry to the index.
     ///
-    /// If an entry with the same hash already exists, it will be overwritten.
+    /// If an entry with the same hash already exists, `file_path` is appended to
+    /// its paths (unless already recorded) and the rest of the entry is kept.
     ///
     /// # Arguments
     ///
     /// * `hash` - The Blake3 hash of the file
     /// * `file_path` - The path to the file
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sift::index::Index;
+    /// let mut index = Index::new();
+    /// index.add_entry("abc123".to_string(), "/a/img.jpg".to_string());
+    /// index.add_entry("abc123".to_string(), "/b/img.jpg".to_string());
+    /// assert_eq!(index.get_entry("abc123").unwrap().file_paths, ["/a/img.jpg", "/b/img.jpg"]);
+    /// ```
     pub fn add_entry(&mut self, hash: String, file_path: String) {
-        self.entries.insert(
-            hash.clone(),
-            IndexEntry {
+        match self.entries.get_mut(&hash) {
+            Some(entry) => {
+                if !entry.file_paths.contains(&file_path) {
+                    entry.file_paths.push(file_path);
+                }
+            }
+            None => self.insert(IndexEntry {
                 hash,
-                file_path,
-            },
-        );
+                file_paths: vec![file_path],
+                source_path: None,
+                size: 0,
+                mtime_unix: 0,
+                hash_kind: HashKind::Bytes,
+            }),
+        }
+    }
+
+    /// Adds many `(hash, file_path)` pairs, as [`Index::add_entry`] does for each.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sift::index::Index;
+    /// let mut index = Index::new();
+    /// index.add_entries_bulk([
+    ///     ("abc123".to_string(), "/a/img.jpg".to_string()),
+    ///     ("def456".to_string(), "/b/img.jpg".to_string()),
+    /// ]);
+    /// assert_eq!(index.len(), 2);
+    /// ```
+    pub fn add_entries_bulk(&mut self, entries: impl IntoIterator<Item = (String, String)>) {
+        let entries = entries.into_iter();
+        self.entries.reserve(entries.size_hint().0);
+        for (hash, file_path) in entries {
+            self.add_entry(hash, file_path);
+        }
+    }
+
+    /// Adds the entries of `other`, e.g. an index kept on another machine.
+    ///
+    /// Entries for new hashes are added as they are. For a hash both indexes
+    /// know, the paths of `other` are appended to the existing entry (unless
+    /// already recorded), whose other fields are kept.
+    ///
+    /// # Returns
+    ///
+    /// `(added, already_present)`: how many entries of `other` were new, and how
+    /// many had a hash already in this index.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sift::index::Index;
+    /// let mut laptop = Index::new();
+    /// laptop.add_entry("abc123".to_string(), "/laptop/img.jpg".to_string());
+    /// let mut desktop = Index::new();
+    /// desktop.add_entry("abc123".to_string(), "/desktop/img.jpg".to_string());
+    /// desktop.add_entry("def456".to_string(), "/desktop/other.jpg".to_string());
+    ///
+    /// assert_eq!(laptop.merge(desktop), (1, 1));
+    /// assert_eq!(laptop.get_entry("abc123").unwrap().file_paths, ["/laptop/img.jpg", "/desktop/img.jpg"]);
+    /// ```
+    pub fn merge(&mut self, other: Index) -> (usize, usize) {
+        let (mut added, mut already_present) = (0, 0);
+        for entry in other.entries.into_values() {
+            match self.entries.get_mut(&entry.hash) {
+                Some(existing) => {
+                    for path in entry.file_paths {
+                        if !existing.file_paths.contains(&path) {
+                            existing.file_paths.push(path);
+                        }
+                    }
+                    already_present += 1;
+                }
+                None => {
+                    self.insert(entry);
+                    added += 1;
+                }
+            }
+        }
+        (added, already_present)
+    }
+
+    /// Inserts a fully populated entry.
+    ///
+    /// If an entry with the same hash exists, the new paths are appended to it and
+    /// its source path, size and mtime are replaced by the new entry's.
+    pub fn insert(&mut self, mut entry: IndexEntry) {
+        if let Some(old) = self.entries.remove(&entry.hash) {
+            if let Some(old_source) = &old.source_path {
+                self.by_source.remove(old_source);
+            }
+            let mut file_paths = old.file_paths;
+            for path in entry.file_paths {
+                if !file_paths.contains(&path) {
+                    file_paths.push(path);
+                }
+            }
+            entry.file_paths = file_paths;
+        }
+        if let Some(source) = &entry.source_path {
+            self.by_source.insert(source.clone(), entry.hash.clone());
+        }
+        if let Some(bloom) = &mut self.bloom {
+            bloom.insert(&entry.hash);
+        }
+        self.entries.insert(entry.hash.clone(), entry);
+    }
+
+    /// Removes the entry for `hash`, returning it if it was present.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sift::index::Index;
+    /// let mut index = Index::new();
+    /// index.add_entry("abc123".to_string(), "/a/img.jpg".to_string());
+    /// assert!(index.remove("abc123").is_some());
+    /// assert!(index.remove("abc123").is_none());
+    /// ```
+    pub fn remove(&mut self, hash: &str) -> Option<IndexEntry> {
+        let entry = self.entries.remove(hash)?;
+        if let Some(source) = &entry.source_path {
+            self.by_source.remove(source);
+        }
+        Some(entry)
+    }
+
+    /// Drops file paths that no longer lead to a file, as `verify` reports
+    /// them missing: deleted, or replaced by a folder.
+    ///
+    /// Entries with several paths keep the ones still present; an entry left
+    /// with no path at all is removed.
+    ///
+    /// # Returns
+    ///
+    /// The number of entries removed
+    pub fn prune_missing(&mut self) -> usize {
+        let mut dead = Vec::new();
+        for (hash, entry) in self.entries.iter_mut() {
+            entry.file_paths.retain(|path| Path::new(path).is_file());
+            if entry.file_paths.is_empty() {
+                dead.push(hash.clone());
+            }
+        }
+        for hash in &dead {
+            self.remove(hash);
+        }
+        dead.len()
+    }
+
+    /// Returns the entries whose hash starts with `prefix`, sorted by hash.
+    ///
+    /// Like a git short hash, a prefix may match several entries; all are
+    /// returned. The comparison ignores case.
+    pub fn find_by_hash_prefix(&self, prefix: &str) -> Vec<&IndexEntry> {
+        let prefix = prefix.to_ascii_lowercase();
+        let mut matches: Vec<&IndexEntry> = self
+            .entries
+            .values()
+            .filter(|entry| entry.hash.starts_with(&prefix))
+            .collect();
+        matches.sort_by(|a, b| a.hash.cmp(&b.hash));
+        matches
+    }
+
+    /// Returns the entries with a recorded path containing `needle`, ignoring
+    /// case, sorted by hash.
+    pub fn find_by_path_substring(&self, needle: &str) -> Vec<&IndexEntry> {
+        let needle = needle.to_lowercase();
+        let mut matches: Vec<&IndexEntry> = self
+            .entries
+            .values()
+            .filter(|entry| entry.file_paths.iter().any(|path| path.to_lowercase().contains(&needle)))
+            .collect();
+        matches.sort_by(|a, b| a.hash.cmp(&b.hash));
+        matches
+    }
+
+    /// Returns the entry recorded for a source path, if any.
+    pub fn get_by_source(&self, source_path: &str) -> Option<&IndexEntry> {
+        self.by_source
+            .get(source_path)
+            .and_then(|hash| self.entries.get(hash))
+    }
+
+    /// Checks whether a source file is already indexed and unchanged since.
+    ///
+    /// A file counts as unchanged when an entry was recorded for the same source
+    /// path with the same size and modification time, which lets callers skip
+    /// re-hashing it. Entries without a recorded source never match.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sift::index::{Index, IndexEntry};
+    /// let mut index = Index::new();
+    /// index.insert(IndexEntry {
+    ///     hash: "abc123".to_string(),
+    ///     file_paths: vec!["/dest/2024/01/15/a.jpg".to_string()],
+    ///     source_path: Some("/src/a.jpg".to_string()),
+    ///     size: 1024,
+    ///     mtime_unix: 1_705_312_800,
+    ///     hash_kind: Default::default(),
+    /// });
+    /// assert!(index.is_unchanged("/src/a.jpg", 1024, 1_705_312_800));
+    /// assert!(!index.is_unchanged("/src/a.jpg", 2048, 1_705_312_800));
+    /// ```
+    pub fn is_unchanged(&self, path: &str, size: u64, mtime_unix: i64) -> bool {
+        self.get_by_source(path)
+            .is_some_and(|entry| entry.size == size && entry.mtime_unix == mtime_unix)
     }
 
     /// Retrieves an entry from the index by hash.
@@ -121,6 +727,9 @@ impl Index {
     /// * `Some(&IndexEntry)` if the hash exists
     /// * `None` if the hash is not in the index
     pub fn get_entry(&self, hash: &str) -> Option<&IndexEntry> {
+        if self.bloom_rules_out(hash) {
+            return None;
+        }
         self.entries.get(hash)
     }
 
@@ -153,14 +762,18 @@ impl Index {
     /// let mut index = Index::new();
     /// index.add_entry("hash1".to_string(), "/path1".to_string());
     /// for entry in index.entries() {
-    ///     println!("{}: {}", entry.hash, entry.file_path);
+    ///     println!("{}: {}", entry.hash, entry.file_paths.join(", "));
     /// }
     /// ```
     pub fn entries(&self) -> impl Iterator<Item = &IndexEntry> {
         self.entries.values()
     }
 
-    /// Loads an index from a binary file (Bincode format).
+    /// Loads an index from a binary file (Bincode format), or reads a SQLite
+    /// index into memory.
+    ///
+    /// Reading a SQLite index whole is meant for commands that need every entry
+    /// (listing, merging, converting); organize queries it in place instead.
     ///
     /// # Arguments
     ///
@@ -180,11 +793,128 @@ impl Index {
     /// # Ok::<(), std::io::Error>(())
     /// ```
     pub fn load_from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref();
+        if sqlite_index::is_sqlite_file(path) {
+            return SqliteIndex::open(path)?.to_index();
+        }
         let data = fs::read(path)?;
-        bincode::deserialize(&data)
+        let mut index = Index::from(IndexFile::decode(&data)?);
+
+        let bloom_path = bloom::sidecar_path(path);
+        if bloom_path.exists() {
+            let checksum: Option<&[u8; 32]> = if detect_version(&data) > 1 {
+                data[12..HEADER_LEN].try_into().ok()
+            } else {
+                None
+            };
+            match checksum.map(|checksum| BloomFilter::load(&bloom_path, checksum)) {
+                Some(Ok(bloom)) => index.bloom = Some(bloom),
+                // Stale or unreadable: rebuild it, and rewrite it on the next save
+                _ => index.rebuild_bloom(),
+            }
+        }
+        Ok(index)
+    }
+
+    /// Returns all entries in the given order.
+    ///
+    /// Unlike [`Index::entries`], the order is the same on every run, so a large
+    /// index can be paged through with `skip` and `take`.
+    pub fn sorted_entries(&self, order: EntryOrder) -> Vec<&IndexEntry> {
+        let mut entries: Vec<&IndexEntry> = self.entries.values().collect();
+        match order {
+            EntryOrder::Hash => entries.sort_by(|a, b| a.hash.cmp(&b.hash)),
+            EntryOrder::Path => entries.sort_by(|a, b| (a.file_path(), &a.hash).cmp(&(b.file_path(), &b.hash))),
+        }
+        entries
+    }
+
+    /// Writes all entries as a JSON array of `{hash, file_paths, ...}` objects, sorted by hash.
+    ///
+    /// Sorting keeps the output stable across runs, so two exports can be diffed.
+    pub fn write_json<W: Write>(&self, writer: W) -> io::Result<()> {
+        let entries = self.sorted_entries(EntryOrder::Hash);
+        serde_json::to_writer_pretty(writer, &entries)
             .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
     }
 
+    /// Exports the index to a human-readable JSON file.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use sift::index::Index;
+    /// let index = Index::load_from_file("index.bin")?;
+    /// index.export_json("index.json")?;
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn export_json<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(fs::File::create(path)?);
+        self.write_json(&mut writer)?;
+        writer.write_all(b"\n")?;
+        writer.flush()
+    }
+
+    /// Imports an index from a JSON file written by [`Index::export_json`].
+    ///
+    /// Entries are keyed by their `hash` field; if it appears more than once,
+    /// the entries are merged as by [`Index::insert`].
+    pub fn import_json<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let data = fs::read(path)?;
+        let entries: Vec<IndexEntry> = serde_json::from_slice(&data)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        let mut index = Index::new();
+        for entry in entries {
+            index.insert(entry);
+        }
+        Ok(index)
+    }
+
+    /// Upgrades an index file in place to [`INDEX_FORMAT_VERSION`].
+    ///
+    /// The on-disk version is detected from the header (headerless files are v1),
+    /// the entries are decoded with the matching layout, and the index is rewritten
+    /// atomically in the current format. An index already at the current version is
+    /// only verified, not rewritten.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok((from, to))` - The version found on disk and the version now on disk
+    /// * `Err(io::Error)` - If the file cannot be read, decoded or rewritten, or was
+    ///   written by a newer version of Sift
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use sift::index::Index;
+    /// let (from, to) = Index::migrate_file("/photos/.sift_index.bin")?;
+    /// println!("Migrated index v{} -> v{}", from, to);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn migrate_file<P: AsRef<Path>>(path: P) -> io::Result<(u32, u32)> {
+        let path = path.as_ref();
+        let file = IndexFile::read(path)?;
+        let version = file.version;
+        if version == INDEX_FORMAT_VERSION {
+            return Ok((version, version));
+        }
+
+        Index::from(file).save_to_file(path)?;
+        Ok((version, INDEX_FORMAT_VERSION))
+    }
+
+    /// Saves the index to `path` in the format of the index already there: a
+    /// SQLite index is rewritten in one transaction, anything else is saved as
+    /// by [`Index::save_to_file`].
+    pub fn save_keeping_format<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        match IndexBackend::detect(path) {
+            Some(IndexBackend::Sqlite) => SqliteIndex::open(path)?.replace_all(self),
+            _ => self.save_to_file(path),
+        }
+    }
+
     /// Saves the index to a binary file (Bincode format).
     ///
     /// # Arguments
@@ -206,42 +936,223 @@ impl Index {
     /// # Ok::<(), std::io::Error>(())
     /// ```
     pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
-        let data = bincode::serialize(self)
+        let payload = bincode::serialize(self)
             .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
-        fs::write(path, data)?;
+
+        let checksum = blake3::hash(&payload);
+        let mut data = Vec::with_capacity(HEADER_LEN + payload.len());
+        data.extend_from_slice(INDEX_MAGIC);
+        data.extend_from_slice(&INDEX_FORMAT_VERSION.to_le_bytes());
+        data.extend_from_slice(checksum.as_bytes());
+        data.extend_from_slice(&payload);
+
+        write_atomic(path.as_ref(), &data)?;
+        // Written after the index: if this fails, the old sidecar no longer
+        // matches the index and is ignored on load
+        if let Some(bloom) = &self.bloom {
+            let resized;
+            let bloom = if bloom.capacity() < self.entries.len() {
+                resized = self.build_bloom();
+                &resized
+            } else {
+                bloom
+            };
+            bloom.save(&bloom::sidecar_path(path.as_ref()), checksum.as_bytes())?;
+        }
         Ok(())
     }
 }
 
-impl Default for Index {
-    fn default() -> Self {
-        Self::new()
-    }
+/// A cloneable, thread-safe handle to a shared [`Index`].
+///
+/// Clones refer to the same index, so one loaded index can serve several
+/// operations in a process (say, an organize run then a verification) and
+/// worker threads can record entries directly instead of handing them back to
+/// a single thread. Lookups take a read lock and can run concurrently; inserts
+/// take the write lock briefly.
+///
+/// A poisoned lock is recovered, so one panicking thread does not take the
+/// index down with it.
+#[derive(Debug, Clone, Default)]
+pub struct IndexHandle {
+    inner: Arc<RwLock<Index>>,
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use tempfile::tempdir;
-
-    #[test]
-    fn test_index_creation() {
-        let index = Index::new();
-        assert!(index.is_empty());
-        assert_eq!(index.len(), 0);
+impl IndexHandle {
+    /// Wraps an index for sharing.
+    pub fn new(index: Index) -> Self {
+        IndexHandle { inner: Arc::new(RwLock::new(index)) }
     }
 
-    #[test]
-    fn test_add_single_entry() {
-        let mut index = Index::new();
-        let hash = "abc123".to_string();
-        let path = "/photos/img1.jpg".to_string();
-
-        index.add_entry(hash.clone(), path.clone());
+    /// Loads an index file, as [`Index::load_from_file`].
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Index::load_from_file(path).map(IndexHandle::new)
+    }
 
-        assert!(index.contains_hash(&hash));
-        assert_eq!(index.len(), 1);
-        assert_eq!(index.get_entry(&hash).unwrap().file_path, path);
+    /// Saves the index, as [`Index::save_to_file`]. Inserts wait until it is written.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.read().save_to_file(path)
+    }
+
+    /// Checks if a hash already exists in the index.
+    pub fn contains_hash(&self, hash: &str) -> bool {
+        self.read().contains_hash(hash)
+    }
+
+    /// Adds an entry to the index, as [`Index::add_entry`].
+    pub fn add_entry(&self, hash: String, file_path: String) {
+        self.write().add_entry(hash, file_path);
+    }
+
+    /// Adds many entries under a single lock, as [`Index::add_entries_bulk`].
+    ///
+    /// Cheaper than one [`IndexHandle::add_entry`] per entry when workers
+    /// insert in batches; lookups from other threads wait for the whole batch.
+    pub fn add_entries_bulk(&self, entries: impl IntoIterator<Item = (String, String)>) {
+        self.write().add_entries_bulk(entries);
+    }
+
+    /// Inserts a fully populated entry, as [`Index::insert`].
+    pub fn insert(&self, entry: IndexEntry) {
+        self.write().insert(entry);
+    }
+
+    /// Returns the number of entries in the index.
+    pub fn len(&self) -> usize {
+        self.read().len()
+    }
+
+    /// Returns `true` if the index has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.read().is_empty()
+    }
+
+    /// Locks the index for reading, for lookups beyond those of the handle.
+    ///
+    /// Inserts from other threads wait until the guard is dropped; inserting
+    /// through the handle from the thread holding it deadlocks.
+    pub fn read(&self) -> RwLockReadGuard<'_, Index> {
+        self.inner.read().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Locks the index for writing, for changes beyond those of the handle.
+    pub fn write(&self) -> RwLockWriteGuard<'_, Index> {
+        self.inner.write().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Returns the format version of raw index file contents (1 when there is no header).
+fn detect_version(data: &[u8]) -> u32 {
+    if data.len() >= HEADER_LEN && data.starts_with(INDEX_MAGIC) {
+        u32::from_le_bytes(data[8..12].try_into().unwrap())
+    } else {
+        1
+    }
+}
+
+/// Returns the payload of a headered index file after verifying its checksum.
+fn verified_payload(data: &[u8]) -> io::Result<&[u8]> {
+    let payload = &data[HEADER_LEN..];
+    if blake3::hash(payload).as_bytes() != &data[12..HEADER_LEN] {
+        return Err(index_error(
+            "corrupt index: checksum mismatch, the file is truncated or corrupted".to_string(),
+        ));
+    }
+    Ok(payload)
+}
+
+fn deserialize_payload<'a, T: Deserialize<'a>>(payload: &'a [u8]) -> io::Result<T> {
+    bincode::deserialize(payload)
+        .map_err(|e| index_error(format!("cannot decode index payload: {}", e)))
+}
+
+/// Wraps an [`OrganizeError::IndexError`] in an `InvalidData` I/O error.
+fn index_error(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, OrganizeError::IndexError(message))
+}
+
+/// Returns a file's modification time in whole seconds since the Unix epoch.
+///
+/// Times before the epoch are negative; platforms without mtime support yield 0.
+pub fn mtime_unix(metadata: &fs::Metadata) -> i64 {
+    match metadata.modified() {
+        Ok(time) => match time.duration_since(std::time::UNIX_EPOCH) {
+            Ok(after) => after.as_secs() as i64,
+            Err(before) => -(before.duration().as_secs() as i64),
+        },
+        Err(_) => 0,
+    }
+}
+
+/// Writes `data` to a sibling temporary file, then renames it over `path`,
+/// so readers never observe a partially written index (or Bloom sidecar).
+///
+/// The temporary file is synced before the rename, so a crash or power loss
+/// leaves either the old file or the complete new one, never a truncated file.
+/// `fs::rename` replaces an existing target on every platform (on Windows it
+/// uses `MoveFileExW` with `MOVEFILE_REPLACE_EXISTING`), so no separate delete,
+/// which would open a window without any index, is needed.
+pub fn write_atomic(path: &Path, data: &[u8]) -> io::Result<()> {
+    let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(".tmp");
+    let tmp_path = PathBuf::from(path).with_file_name(tmp_name);
+
+    let write = || -> io::Result<()> {
+        let mut file = fs::File::create(&tmp_path)?;
+        file.write_all(data)?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, path)
+    };
+    write().inspect_err(|_| {
+        let _ = fs::remove_file(&tmp_path);
+    })?;
+    sync_parent_dir(path);
+    Ok(())
+}
+
+/// Flushes the directory entry created by a rename to disk (best effort; Unix only,
+/// since directories cannot be opened for syncing on Windows).
+fn sync_parent_dir(path: &Path) {
+    #[cfg(unix)]
+    if let Some(parent) = path.parent() {
+        let parent = if parent.as_os_str().is_empty() { Path::new(".") } else { parent };
+        if let Ok(dir) = fs::File::open(parent) {
+            let _ = dir.sync_all();
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+}
+
+impl Default for Index {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_index_creation() {
+        let index = Index::new();
+        assert!(index.is_empty());
+        assert_eq!(index.len(), 0);
+    }
+
+    #[test]
+    fn test_add_single_entry() {
+        let mut index = Index::new();
+        let hash = "abc123".to_string();
+        let path = "/photos/img1.jpg".to_string();
+
+        index.add_entry(hash.clone(), path.clone());
+
+        assert!(index.contains_hash(&hash));
+        assert_eq!(index.len(), 1);
+        assert_eq!(index.get_entry(&hash).unwrap().file_path(), path);
     }
 
     #[test]
@@ -258,13 +1169,17 @@ mod tests {
     }
 
     #[test]
-    fn test_overwrite_entry() {
+    fn test_add_entry_appends_paths() {
         let mut index = Index::new();
-        index.add_entry("hash1".to_string(), "/old/path".to_string());
-        index.add_entry("hash1".to_string(), "/new/path".to_string());
+        index.add_entry("hash1".to_string(), "/first/path".to_string());
+        index.add_entry("hash1".to_string(), "/second/path".to_string());
+        index.add_entry("hash1".to_string(), "/first/path".to_string());
 
         assert_eq!(index.len(), 1);
-        assert_eq!(index.get_entry("hash1").unwrap().file_path, "/new/path");
+        assert!(index.contains_hash("hash1"));
+        let entry = index.get_entry("hash1").unwrap();
+        assert_eq!(entry.file_paths, ["/first/path", "/second/path"]);
+        assert_eq!(entry.file_path(), "/first/path");
     }
 
     #[test]
@@ -327,7 +1242,7 @@ mod tests {
 
         let loaded = Index::load_from_file(&index_path)?;
         let entry = loaded.get_entry("abc123def").unwrap();
-        assert_eq!(entry.file_path, "/very/long/path/to/file.jpg");
+        assert_eq!(entry.file_path(), "/very/long/path/to/file.jpg");
 
         Ok(())
     }
@@ -350,7 +1265,7 @@ mod tests {
         let loaded = Index::load_from_file(&index_path)?;
         assert_eq!(loaded.len(), 1000);
         assert!(loaded.contains_hash("hash_500"));
-        assert_eq!(loaded.get_entry("hash_999").unwrap().file_path, "/path/to/file_999.jpg");
+        assert_eq!(loaded.get_entry("hash_999").unwrap().file_path(), "/path/to/file_999.jpg");
 
         Ok(())
     }
@@ -367,4 +1282,658 @@ mod tests {
         let result = index.save_to_file("/nonexistent/directory/index.bin");
         assert!(result.is_err());
     }
+
+    /// Writes the headerless layout used before the format was versioned.
+    fn write_v1_index(path: &Path, entries: &[(&str, &str)]) -> io::Result<()> {
+        #[derive(Serialize)]
+        struct LegacyEntry<'a> {
+            hash: &'a str,
+            file_path: &'a str,
+        }
+        let map: HashMap<&str, LegacyEntry> = entries
+            .iter()
+            .map(|(hash, file_path)| (*hash, LegacyEntry { hash, file_path }))
+            .collect();
+        #[derive(Serialize)]
+        struct Legacy<'a> {
+            entries: HashMap<&'a str, LegacyEntry<'a>>,
+        }
+        let data = bincode::serialize(&Legacy { entries: map })
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(path, data)
+    }
+
+    #[test]
+    fn test_saved_index_has_header() -> io::Result<()> {
+        let dir = tempdir()?;
+        let index_path = dir.path().join("test.index");
+        Index::new().save_to_file(&index_path)?;
+
+        let data = fs::read(&index_path)?;
+        assert!(data.starts_with(INDEX_MAGIC));
+        assert_eq!(detect_version(&data), INDEX_FORMAT_VERSION);
+        assert!(!dir.path().join("test.index.tmp").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_v1_index_upgrades_in_memory() -> io::Result<()> {
+        let dir = tempdir()?;
+        let index_path = dir.path().join("old.index");
+        write_v1_index(&index_path, &[("hash1", "/file1"), ("hash2", "/file2")])?;
+
+        let file = IndexFile::read(&index_path)?;
+        assert_eq!(file.version, 1);
+
+        let index = Index::load_from_file(&index_path)?;
+        assert_eq!(index.len(), 2);
+        let entry = index.get_entry("hash1").unwrap();
+        assert_eq!(entry.file_paths, ["/file1"]);
+        assert_eq!((entry.size, entry.mtime_unix, entry.source_path.as_deref()), (0, 0, None));
+
+        // Loading does not rewrite the file
+        assert_eq!(detect_version(&fs::read(&index_path)?), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_migrate_v1_index() -> io::Result<()> {
+        let dir = tempdir()?;
+        let index_path = dir.path().join("old.index");
+        write_v1_index(&index_path, &[("hash1", "/file1"), ("hash2", "/file2")])?;
+
+        assert_eq!(Index::migrate_file(&index_path)?, (1, INDEX_FORMAT_VERSION));
+
+        let loaded = Index::load_from_file(&index_path)?;
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded.get_entry("hash2").unwrap().file_path(), "/file2");
+
+        // Migrating again is a no-op
+        assert_eq!(
+            Index::migrate_file(&index_path)?,
+            (INDEX_FORMAT_VERSION, INDEX_FORMAT_VERSION)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_detects_corruption() -> io::Result<()> {
+        let dir = tempdir()?;
+        let index_path = dir.path().join("test.index");
+        let mut index = Index::new();
+        index.add_entry("hash1".to_string(), "/file1".to_string());
+        index.save_to_file(&index_path)?;
+
+        let mut data = fs::read(&index_path)?;
+        let last = data.len() - 1;
+        data[last] ^= 0xFF;
+        fs::write(&index_path, data)?;
+
+        let err = Index::load_from_file(&index_path).unwrap_err();
+        assert!(err.to_string().contains("checksum mismatch"));
+        let inner = err.get_ref().and_then(|e| e.downcast_ref::<OrganizeError>());
+        assert!(matches!(inner, Some(OrganizeError::IndexError(msg)) if msg.starts_with("corrupt index")));
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_detects_truncation() -> io::Result<()> {
+        let dir = tempdir()?;
+        let index_path = dir.path().join("test.index");
+        let mut index = Index::new();
+        index.add_entry("hash1".to_string(), "/file1".to_string());
+        index.save_to_file(&index_path)?;
+        let data = fs::read(&index_path)?;
+
+        // Cut inside the payload, and inside the header itself
+        for len in [data.len() - 3, HEADER_LEN - 1, INDEX_MAGIC.len()] {
+            fs::write(&index_path, &data[..len])?;
+            let err = Index::load_from_file(&index_path).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+            assert!(err.to_string().starts_with("Index error: corrupt index"), "{}", err);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_save_leaves_no_temporary_file() -> io::Result<()> {
+        let dir = tempdir()?;
+        let index_path = dir.path().join("test.index");
+        let mut index = Index::new();
+        index.add_entry("hash1".to_string(), "/file1".to_string());
+        index.save_to_file(&index_path)?;
+        index.add_entry("hash2".to_string(), "/file2".to_string());
+        index.save_to_file(&index_path)?;
+
+        let loaded = Index::load_from_file(&index_path)?;
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded.get_entry("hash2").unwrap().file_path(), "/file2");
+        assert!(!dir.path().join("test.index.tmp").exists());
+        assert_eq!(fs::read_dir(dir.path())?.count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_save_replaces_leftover_temporary_file() -> io::Result<()> {
+        let dir = tempdir()?;
+        let index_path = dir.path().join("test.index");
+        let mut index = Index::new();
+        index.add_entry("hash1".to_string(), "/file1".to_string());
+        index.save_to_file(&index_path)?;
+
+        // A save interrupted before its rename leaves a partial temporary file
+        // and the previous index untouched.
+        fs::write(dir.path().join("test.index.tmp"), b"partial")?;
+        assert_eq!(Index::load_from_file(&index_path)?.len(), 1);
+
+        index.add_entry("hash2".to_string(), "/file2".to_string());
+        index.save_to_file(&index_path)?;
+        assert_eq!(Index::load_from_file(&index_path)?.len(), 2);
+        assert!(!dir.path().join("test.index.tmp").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_rejects_newer_version() -> io::Result<()> {
+        let dir = tempdir()?;
+        let index_path = dir.path().join("future.index");
+        Index::new().save_to_file(&index_path)?;
+
+        let mut data = fs::read(&index_path)?;
+        data[8..12].copy_from_slice(&(INDEX_FORMAT_VERSION + 1).to_le_bytes());
+        fs::write(&index_path, data)?;
+
+        let err = Index::load_from_file(&index_path).unwrap_err();
+        let inner = err.get_ref().and_then(|e| e.downcast_ref::<OrganizeError>());
+        assert!(matches!(inner, Some(OrganizeError::IndexError(_))));
+        assert!(err.to_string().contains("newer version of Sift"));
+        assert!(Index::migrate_file(&index_path).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_json_round_trip() -> io::Result<()> {
+        let dir = tempdir()?;
+        let json_path = dir.path().join("index.json");
+
+        let mut index = Index::new();
+        for i in 0..10 {
+            index.add_entry(format!("hash_{}", i), format!("/path/to/file_{}.jpg", i));
+        }
+        index.export_json(&json_path)?;
+
+        let imported = Index::import_json(&json_path)?;
+        assert_eq!(imported.len(), index.len());
+        for entry in index.entries() {
+            let other = imported.get_entry(&entry.hash).unwrap();
+            assert_eq!(other.file_paths, entry.file_paths);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_json_export_shape() -> io::Result<()> {
+        let mut index = Index::new();
+        index.add_entry("b".to_string(), "/file_b".to_string());
+        index.add_entry("a".to_string(), "/file_a".to_string());
+
+        let mut out = Vec::new();
+        index.write_json(&mut out)?;
+        let value: serde_json::Value = serde_json::from_slice(&out).unwrap();
+
+        assert_eq!(
+            value,
+            serde_json::json!([
+                {"hash": "a", "file_paths": ["/file_a"], "source_path": null, "size": 0, "mtime_unix": 0, "hash_kind": "bytes"},
+                {"hash": "b", "file_paths": ["/file_b"], "source_path": null, "size": 0, "mtime_unix": 0, "hash_kind": "bytes"},
+            ])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_json_before_v4() -> io::Result<()> {
+        let dir = tempdir()?;
+        let json_path = dir.path().join("index.json");
+        // Exported when an entry had a single `file_path` and no `hash_kind`
+        fs::write(
+            &json_path,
+            r#"[{"hash": "a", "file_path": "/file_a", "source_path": "/src/a", "size": 3, "mtime_unix": 9}]"#,
+        )?;
+
+        let index = Index::import_json(&json_path)?;
+        let entry = index.get_entry("a").unwrap();
+        assert_eq!(entry.file_paths, ["/file_a"]);
+        assert_eq!(entry.source_path.as_deref(), Some("/src/a"));
+        assert_eq!(entry.hash_kind, HashKind::Bytes);
+
+        // Exports of the current format still read back
+        let mut out = Vec::new();
+        index.write_json(&mut out)?;
+        fs::write(&json_path, out)?;
+        assert_eq!(Index::import_json(&json_path)?.get_entry("a"), Some(entry));
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_json_invalid() -> io::Result<()> {
+        let dir = tempdir()?;
+        let json_path = dir.path().join("index.json");
+        fs::write(&json_path, "{not json")?;
+
+        let err = Index::import_json(&json_path).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        Ok(())
+    }
+
+    fn sourced_entry(hash: &str, source: &str, size: u64, mtime_unix: i64) -> IndexEntry {
+        IndexEntry {
+            hash: hash.to_string(),
+            file_paths: vec![format!("/dest{}", source)],
+            source_path: Some(source.to_string()),
+            size,
+            mtime_unix,
+            hash_kind: HashKind::Bytes,
+        }
+    }
+
+    #[test]
+    fn test_is_unchanged() {
+        let mut index = Index::new();
+        index.insert(sourced_entry("hash1", "/src/a.jpg", 100, 1_700_000_000));
+        index.add_entry("hash2".to_string(), "/dest/b.jpg".to_string());
+
+        assert!(index.is_unchanged("/src/a.jpg", 100, 1_700_000_000));
+        assert!(!index.is_unchanged("/src/a.jpg", 101, 1_700_000_000));
+        assert!(!index.is_unchanged("/src/a.jpg", 100, 1_700_000_001));
+        assert!(!index.is_unchanged("/dest/b.jpg", 0, 0));
+        assert!(!index.is_unchanged("/src/unknown.jpg", 100, 1_700_000_000));
+    }
+
+    #[test]
+    fn test_insert_replaces_source_mapping() {
+        let mut index = Index::new();
+        index.insert(sourced_entry("hash1", "/src/a.jpg", 100, 1));
+        index.insert(sourced_entry("hash1", "/src/b.jpg", 100, 1));
+
+        assert!(index.get_by_source("/src/a.jpg").is_none());
+        let entry = index.get_by_source("/src/b.jpg").unwrap();
+        assert_eq!(entry.hash, "hash1");
+        assert_eq!(entry.file_paths, ["/dest/src/a.jpg", "/dest/src/b.jpg"]);
+    }
+
+    #[test]
+    fn test_source_lookup_survives_persistence() -> io::Result<()> {
+        let dir = tempdir()?;
+        let index_path = dir.path().join("test.index");
+
+        let mut index = Index::new();
+        index.insert(sourced_entry("hash1", "/src/a.jpg", 100, 1_700_000_000));
+        index.save_to_file(&index_path)?;
+
+        let loaded = Index::load_from_file(&index_path)?;
+        assert_eq!(loaded.get_entry("hash1"), index.get_entry("hash1"));
+        assert!(loaded.is_unchanged("/src/a.jpg", 100, 1_700_000_000));
+        Ok(())
+    }
+
+    #[test]
+    fn test_migrate_v2_index() -> io::Result<()> {
+        let dir = tempdir()?;
+        let index_path = dir.path().join("v2.index");
+        write_v2_index(&index_path, &[("hash1", "/file1")])?;
+
+        assert_eq!(IndexFile::read(&index_path)?.version, 2);
+        assert_eq!(Index::migrate_file(&index_path)?, (2, INDEX_FORMAT_VERSION));
+
+        let file = IndexFile::read(&index_path)?;
+        assert_eq!(file.version, INDEX_FORMAT_VERSION);
+        let entry = &file.entries["hash1"];
+        assert_eq!(entry.file_paths, ["/file1"]);
+        assert_eq!((entry.size, entry.mtime_unix), (0, 0));
+        Ok(())
+    }
+
+    /// Writes a headered index whose payload uses the pre-v3 entry layout.
+    fn write_v2_index(path: &Path, entries: &[(&str, &str)]) -> io::Result<()> {
+        write_v1_index(path, entries)?;
+        let payload = fs::read(path)?;
+        let mut data = INDEX_MAGIC.to_vec();
+        data.extend_from_slice(&2u32.to_le_bytes());
+        data.extend_from_slice(blake3::hash(&payload).as_bytes());
+        data.extend_from_slice(&payload);
+        fs::write(path, data)
+    }
+
+    #[test]
+    fn test_migrate_v3_index() -> io::Result<()> {
+        #[derive(Serialize)]
+        struct V3Entry<'a> {
+            hash: &'a str,
+            file_path: &'a str,
+            source_path: Option<&'a str>,
+            size: u64,
+            mtime_unix: i64,
+        }
+        #[derive(Serialize)]
+        struct V3<'a> {
+            entries: HashMap<&'a str, V3Entry<'a>>,
+        }
+        let entry = V3Entry {
+            hash: "hash1",
+            file_path: "/dest/a.jpg",
+            source_path: Some("/src/a.jpg"),
+            size: 42,
+            mtime_unix: 1_700_000_000,
+        };
+        let payload = bincode::serialize(&V3 { entries: HashMap::from([("hash1", entry)]) })
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let mut data = INDEX_MAGIC.to_vec();
+        data.extend_from_slice(&3u32.to_le_bytes());
+        data.extend_from_slice(blake3::hash(&payload).as_bytes());
+        data.extend_from_slice(&payload);
+
+        let dir = tempdir()?;
+        let index_path = dir.path().join("v3.index");
+        fs::write(&index_path, data)?;
+
+        assert_eq!(Index::migrate_file(&index_path)?, (3, INDEX_FORMAT_VERSION));
+        let loaded = Index::load_from_file(&index_path)?;
+        let entry = loaded.get_entry("hash1").unwrap();
+        assert_eq!(entry.file_paths, ["/dest/a.jpg"]);
+        assert!(loaded.is_unchanged("/src/a.jpg", 42, 1_700_000_000));
+        Ok(())
+    }
+
+    #[test]
+    fn test_migrate_v4_index() -> io::Result<()> {
+        #[derive(Serialize)]
+        struct V4Entry<'a> {
+            hash: &'a str,
+            file_paths: Vec<&'a str>,
+            source_path: Option<&'a str>,
+            size: u64,
+            mtime_unix: i64,
+        }
+        #[derive(Serialize)]
+        struct V4<'a> {
+            entries: HashMap<&'a str, V4Entry<'a>>,
+        }
+        let entry = V4Entry {
+            hash: "hash1",
+            file_paths: vec!["/dest/a.jpg", "/dest/b.jpg"],
+            source_path: Some("/src/a.jpg"),
+            size: 42,
+            mtime_unix: 1_700_000_000,
+        };
+        let payload = bincode::serialize(&V4 { entries: HashMap::from([("hash1", entry)]) })
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let mut data = INDEX_MAGIC.to_vec();
+        data.extend_from_slice(&4u32.to_le_bytes());
+        data.extend_from_slice(blake3::hash(&payload).as_bytes());
+        data.extend_from_slice(&payload);
+
+        let dir = tempdir()?;
+        let index_path = dir.path().join("v4.index");
+        fs::write(&index_path, data)?;
+
+        assert_eq!(Index::migrate_file(&index_path)?, (4, INDEX_FORMAT_VERSION));
+        let loaded = Index::load_from_file(&index_path)?;
+        let entry = loaded.get_entry("hash1").unwrap();
+        assert_eq!(entry.file_paths, ["/dest/a.jpg", "/dest/b.jpg"]);
+        assert_eq!(entry.hash_kind, HashKind::Bytes);
+        assert!(loaded.is_unchanged("/src/a.jpg", 42, 1_700_000_000));
+        Ok(())
+    }
+
+    #[test]
+    fn test_multiple_paths_survive_persistence() -> io::Result<()> {
+        let dir = tempdir()?;
+        let index_path = dir.path().join("test.index");
+
+        let mut index = Index::new();
+        index.add_entry("hash1".to_string(), "/library/a.jpg".to_string());
+        index.add_entry("hash1".to_string(), "/backup/a.jpg".to_string());
+        index.save_to_file(&index_path)?;
+
+        let loaded = Index::load_from_file(&index_path)?;
+        assert_eq!(loaded.get_entry("hash1").unwrap().file_paths, ["/library/a.jpg", "/backup/a.jpg"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_remove_present_and_absent() {
+        let mut index = Index::new();
+        index.insert(IndexEntry {
+            hash: "hash1".to_string(),
+            file_paths: vec!["/dest/a.jpg".to_string()],
+            source_path: Some("/src/a.jpg".to_string()),
+            size: 1,
+            mtime_unix: 2,
+            hash_kind: HashKind::Bytes,
+        });
+
+        let removed = index.remove("hash1").unwrap();
+        assert_eq!(removed.file_paths, ["/dest/a.jpg"]);
+        assert!(index.is_empty());
+        assert!(index.get_by_source("/src/a.jpg").is_none());
+
+        assert!(index.remove("hash1").is_none());
+        assert!(index.remove("never_added").is_none());
+    }
+
+    #[test]
+    fn test_prune_missing() -> io::Result<()> {
+        let dir = tempdir()?;
+        let kept = dir.path().join("kept.jpg");
+        fs::write(&kept, "kept")?;
+        let kept = kept.to_string_lossy().to_string();
+        let gone = dir.path().join("gone.jpg").to_string_lossy().to_string();
+
+        let mut index = Index::new();
+        index.add_entry("alive".to_string(), kept.clone());
+        index.add_entry("partial".to_string(), gone.clone());
+        index.add_entry("partial".to_string(), kept.clone());
+        index.add_entry("dead".to_string(), gone);
+
+        assert_eq!(index.prune_missing(), 1);
+        assert_eq!(index.len(), 2);
+        assert!(!index.contains_hash("dead"));
+        assert_eq!(index.get_entry("partial").unwrap().file_paths, [kept]);
+        assert_eq!(index.prune_missing(), 0);
+
+        // A folder now in the file's place counts as missing, as in verify
+        let replaced = dir.path().join("replaced.jpg");
+        fs::create_dir(&replaced)?;
+        index.add_entry("replaced".to_string(), replaced.to_string_lossy().to_string());
+        assert_eq!(index.prune_missing(), 1);
+        assert!(!index.contains_hash("replaced"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_index_handle_concurrent_inserts() -> io::Result<()> {
+        let index = IndexHandle::new(Index::new());
+        std::thread::scope(|scope| {
+            for worker in 0..8 {
+                let index = index.clone();
+                scope.spawn(move || {
+                    for i in 0..100 {
+                        let hash = format!("hash_{}_{}", worker, i);
+                        index.add_entry(hash.clone(), format!("/dest/{}/{}.jpg", worker, i));
+                        assert!(index.contains_hash(&hash));
+                    }
+                });
+            }
+        });
+        assert_eq!(index.len(), 800);
+        assert_eq!(index.read().get_entry("hash_7_99").unwrap().file_paths, ["/dest/7/99.jpg"]);
+
+        let dir = tempdir()?;
+        let path = dir.path().join("shared.index");
+        index.save(&path)?;
+        let reloaded = IndexHandle::load(&path)?;
+        assert_eq!(reloaded.len(), 800);
+        assert!((0..8).all(|worker| reloaded.contains_hash(&format!("hash_{}_0", worker))));
+        Ok(())
+    }
+
+    #[test]
+    fn test_sorted_entries_pages() {
+        let mut index = Index::new();
+        for (hash, path) in [("e5", "/b/1.jpg"), ("a1", "/e/5.jpg"), ("c3", "/a/2.jpg"), ("b2", "/d/4.jpg"), ("d4", "/c/3.jpg")] {
+            index.add_entry(hash.to_string(), path.to_string());
+        }
+
+        let page = |order| -> Vec<String> {
+            index.sorted_entries(order).into_iter().skip(2).take(2).map(|entry| entry.hash.clone()).collect()
+        };
+        assert_eq!(page(EntryOrder::Hash), ["c3", "d4"]);
+        assert_eq!(page(EntryOrder::Path), ["d4", "b2"]);
+
+        // Paging past the end yields nothing rather than wrapping
+        assert_eq!(index.sorted_entries(EntryOrder::Hash).into_iter().skip(5).count(), 0);
+    }
+
+    #[test]
+    fn test_find_entries() {
+        let mut index = Index::new();
+        index.add_entry("ab12ff".to_string(), "/photos/2023/Paris/IMG_1.jpg".to_string());
+        index.add_entry("ab34ee".to_string(), "/photos/2023/London/IMG_2.jpg".to_string());
+        index.add_entry("cd56dd".to_string(), "/photos/2024/Rome/img_3.jpg".to_string());
+        index.add_entry("cd56dd".to_string(), "/backup/paris-copy.jpg".to_string());
+
+        let hashes = |entries: Vec<&IndexEntry>| -> Vec<String> { entries.iter().map(|e| e.hash.clone()).collect() };
+
+        // An ambiguous prefix lists every match
+        assert_eq!(hashes(index.find_by_hash_prefix("ab")), ["ab12ff", "ab34ee"]);
+        assert_eq!(hashes(index.find_by_hash_prefix("AB3")), ["ab34ee"]);
+
+        // Any recorded path may match, whatever its case
+        assert_eq!(hashes(index.find_by_path_substring("paris")), ["ab12ff", "cd56dd"]);
+        assert_eq!(hashes(index.find_by_path_substring("IMG_3")), ["cd56dd"]);
+
+        assert!(index.find_by_hash_prefix("ff").is_empty());
+        assert!(index.find_by_path_substring("tokyo").is_empty());
+    }
+
+    #[test]
+    fn test_merge_indexes() -> io::Result<()> {
+        let entry = |hash: &str, path: &str, source: &str| IndexEntry {
+            hash: hash.to_string(),
+            file_paths: vec![path.to_string()],
+            source_path: Some(source.to_string()),
+            size: 10,
+            mtime_unix: 100,
+            hash_kind: HashKind::Bytes,
+        };
+        let mut laptop = Index::new();
+        laptop.insert(entry("shared", "/laptop/2023/a.jpg", "/sd/a.jpg"));
+        laptop.insert(entry("laptop_only", "/laptop/2023/b.jpg", "/sd/b.jpg"));
+        let mut desktop = Index::new();
+        desktop.insert(entry("shared", "/desktop/2023/a.jpg", "/phone/a.jpg"));
+        desktop.insert(entry("desktop_only", "/desktop/2023/c.jpg", "/phone/c.jpg"));
+
+        assert_eq!(laptop.merge(desktop), (1, 1));
+        assert_eq!(laptop.len(), 3);
+        let shared = laptop.get_entry("shared").unwrap();
+        assert_eq!(shared.file_paths, ["/laptop/2023/a.jpg", "/desktop/2023/a.jpg"]);
+        assert_eq!(shared.source_path.as_deref(), Some("/sd/a.jpg"), "existing fields are kept");
+        assert_eq!(laptop.get_by_source("/phone/c.jpg").unwrap().hash, "desktop_only");
+
+        // Merging the same index again changes nothing
+        let dir = tempdir()?;
+        let path = dir.path().join("merged.bin");
+        laptop.save_to_file(&path)?;
+        let again = Index::load_from_file(&path)?;
+        assert_eq!(laptop.merge(again), (0, 3));
+        assert_eq!(laptop.get_entry("shared").unwrap().file_paths.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_entries_bulk_from_threads() -> io::Result<()> {
+        let index = IndexHandle::new(Index::new());
+        std::thread::scope(|scope| {
+            for worker in 0..8 {
+                let index = index.clone();
+                scope.spawn(move || {
+                    for batch in 0..10 {
+                        index.add_entries_bulk((0..50).map(|i| {
+                            (format!("hash_{}_{}_{}", worker, batch, i), format!("/dest/{}/{}/{}.jpg", worker, batch, i))
+                        }));
+                    }
+                });
+            }
+        });
+        assert_eq!(index.len(), 8 * 10 * 50);
+        let index = index.read();
+        assert!((0..8).all(|worker| index.contains_hash(&format!("hash_{}_9_49", worker))));
+
+        // A bulk-built index saves and loads like any other
+        let dir = tempdir()?;
+        let path = dir.path().join("bulk.index");
+        index.save_to_file(&path)?;
+        let reloaded = Index::load_from_file(&path)?;
+        assert_eq!(reloaded.len(), 4000);
+        assert_eq!(reloaded.get_entry("hash_3_2_1").unwrap().file_paths, ["/dest/3/2/1.jpg"]);
+
+        // Repeated hashes merge their paths, as with add_entry
+        let mut index = Index::new();
+        index.add_entries_bulk([("h".to_string(), "/a.jpg".to_string()), ("h".to_string(), "/b.jpg".to_string())]);
+        assert_eq!(index.get_entry("h").unwrap().file_paths, ["/a.jpg", "/b.jpg"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_bloom_has_no_false_negatives() -> io::Result<()> {
+        let mut index = Index::new();
+        for i in 0..2000 {
+            index.add_entry(format!("hash{}", i), format!("/dest/{}.jpg", i));
+        }
+        index.rebuild_bloom();
+        // Entries inserted after the filter was built are added to it
+        for i in 2000..5000 {
+            index.add_entry(format!("hash{}", i), format!("/dest/{}.jpg", i));
+        }
+        assert!((0..5000).all(|i| index.contains_hash(&format!("hash{}", i))));
+        assert!((0..5000).all(|i| index.get_entry(&format!("hash{}", i)).is_some()));
+        assert!(!index.contains_hash("never_added"));
+
+        let dir = tempdir()?;
+        let path = dir.path().join("test.index");
+        index.save_to_file(&path)?;
+        assert!(dir.path().join("test.index.bloom").exists());
+
+        let loaded = Index::load_from_file(&path)?;
+        assert!(loaded.has_bloom());
+        assert!((0..5000).all(|i| loaded.contains_hash(&format!("hash{}", i))));
+        Ok(())
+    }
+
+    #[test]
+    fn test_stale_bloom_is_rebuilt() -> io::Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("test.index");
+        let mut index = Index::new();
+        index.add_entry("old".to_string(), "/dest/old.jpg".to_string());
+        index.rebuild_bloom();
+        index.save_to_file(&path)?;
+
+        // Another writer, unaware of the filter, adds an entry
+        let mut unaware = Index::new();
+        unaware.add_entry("old".to_string(), "/dest/old.jpg".to_string());
+        unaware.add_entry("new".to_string(), "/dest/new.jpg".to_string());
+        unaware.save_to_file(&path)?;
+
+        let loaded = Index::load_from_file(&path)?;
+        assert!(loaded.has_bloom());
+        assert!(loaded.contains_hash("new"));
+        assert!(loaded.contains_hash("old"));
+
+        // Without a sidecar, no filter is used
+        fs::remove_file(dir.path().join("test.index.bloom"))?;
+        assert!(!Index::load_from_file(&path)?.has_bloom());
+        Ok(())
+    }
 }
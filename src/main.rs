@@ -25,6 +25,7 @@
 //! - `geonames`: Embedded location database
 //! - `network_io`: Network-optimized I/O operations
 //! - `cli`: Command-line argument parsing
+//! - `config`: Default options from a `sift.toml` file
 //!
 //! # Examples
 //!
@@ -41,24 +42,61 @@
 //! # View index contents
 //! sift index my_index.bin --limit 20
 //!
+//! # Dump the whole index as JSON (for jq, diffing or hand-editing)
+//! sift index my_index.bin --format json
+//!
+//! # List files already in the library, with where the original lives
+//! sift duplicates /incoming --index /destination/organized/.sift_index.bin
+//!
+//! # Review the full plan before moving anything
+//! sift organize /source /dest --mode move --two-phase
+//!
+//! # Upgrade an index written by an older version
+//! sift migrate /destination/organized/.sift_index.bin
+//!
+//! # Combine the indexes of libraries organized on two machines
+//! sift index-merge laptop.bin desktop.bin -o merged.bin
+//!
+//! # Group photos into events (sessions separated by 12+ hours), no GPS needed
+//! sift cluster /photos --by-time --gap 12
+//!
 //! # Benchmark network performance
 //! sift benchmark /mnt/network/share --size-mb 500
 //! ```
 
+pub mod benchmark;
+pub mod bloom;
 pub mod error;
+pub mod events;
 pub mod hash;
 pub mod index;
+pub mod manifest;
 pub mod metadata;
 pub mod organization;
 pub mod clustering;
 pub mod geonames;
 pub mod network_io;
 pub mod cli;
+pub mod config;
 pub mod organize;
+pub mod template;
+pub mod stats;
+pub mod sqlite_index;
+
+#[cfg(test)]
+mod testutil;
 
+use std::collections::HashSet;
 use std::error::Error;
-use cli::{Cli, Commands};
-use organize::{OrganizeContext, Orchestrator};
+use std::io::Write;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+use chrono::NaiveDate;
+use cli::{Cli, Commands, IndexFormat};
+use rayon::prelude::*;
+use metadata::DateRange;
+use organize::{LogLevel, OrganizeContext, Orchestrator};
 
 fn main() -> Result<(), Box<dyn Error>> {
     let cli = Cli::parse_args();
@@ -67,90 +105,447 @@ fn main() -> Result<(), Box<dyn Error>> {
         eprintln!("Sift v0.1.0 - Photo organization utility");
     }
 
+    network_io::set_default_retry_config(network_io::RetryConfig {
+        max_retries: cli.max_retries,
+        initial_delay_ms: cli.retry_delay_ms,
+        ..network_io::RetryConfig::default()
+    });
+    if cli.quiet {
+        network_io::set_retry_reporter(Some(Box::new(|_| {})));
+    }
+
     match cli.command {
         Commands::Organize {
             source,
             destination,
             with_clustering,
             jobs,
+            max_open,
             index,
+            index_format,
+            manifest,
             dry_run,
+            mode,
+            no_preserve_times,
+            recursive,
+            link_back,
+            skip_existing_destination,
+            force,
+            fast_dedup,
+            pixel_dedup,
+            include_hidden,
+            follow_symlinks,
+            include_destination,
+            scan_dest,
+            min_rating,
+            require_rating,
+            since,
+            until,
+            include_undated,
+            min_size,
+            max_size,
+            geonames,
+            geo_prefer_population,
+            granularity,
+            by_camera,
+            template,
+            undated_dir,
+            two_phase,
+            yes,
+            checkpoint_every,
+            exit_code,
+            ext,
+            ext_only,
+            by_content,
         } => {
-            if dry_run {
+            if dry_run && !cli.json && !cli.quiet {
                 eprintln!("[DRY RUN] No files will be copied or modified");
             }
-            let ctx = OrganizeContext::new(source, destination, with_clustering, jobs, index);
+            let mut ctx = OrganizeContext::new(source, destination, with_clustering, jobs, index);
+            ctx.mode = mode;
+            ctx.preserve_times = !no_preserve_times;
+            ctx.index_backend = index_format;
+            ctx.dry_run = dry_run;
+            ctx.recursive = recursive;
+            ctx.link_back = link_back;
+            ctx.extensions = organize::resolve_extensions(&ext, ext_only);
+            ctx.by_content = by_content;
+            ctx.log_level = LogLevel::from_flags(cli.quiet, cli.verbose);
+            ctx.skip_existing_destination = skip_existing_destination && !force;
+            ctx.fast_dedup = fast_dedup;
+            ctx.pixel_dedup = pixel_dedup;
+            ctx.max_open = max_open.map(NonZeroUsize::get);
+            ctx.manifest_path = manifest;
+            ctx.exclude_destination = !include_destination;
+            ctx.scan_dest = scan_dest;
+            ctx.include_hidden = include_hidden;
+            ctx.follow_symlinks = follow_symlinks;
+            ctx.min_rating = min_rating;
+            ctx.require_rating = require_rating;
+            ctx.date_range = date_range(since, until, include_undated)?;
+            if let (Some(min), Some(max)) = (min_size, max_size)
+                && min > max {
+                    return Err(format!("--min-size {} is larger than --max-size {}", min, max).into());
+                }
+            ctx.min_size = min_size;
+            ctx.max_size = max_size;
+            ctx.geonames = geonames;
+            ctx.geo_prefer_population = geo_prefer_population;
+            ctx.granularity = granularity;
+            ctx.by_camera = by_camera;
+            ctx.template = template;
+            ctx.undated_dir = undated_dir;
+            ctx.checkpoint_every = checkpoint_every;
+            if !dry_run {
+                let cancel = ctx.cancel.clone();
+                ctrlc::set_handler(move || {
+                    // A second Ctrl-C aborts without waiting for the index to be saved
+                    if cancel.swap(true, Ordering::Relaxed) {
+                        std::process::exit(130);
+                    }
+                    eprintln!("\nInterrupted; finishing in-flight copies and saving the index (Ctrl-C again to abort)");
+                })?;
+            }
             let mut orchestrator = Orchestrator::new(ctx);
-            orchestrator.run()?;
+            if cli.json {
+                orchestrator = orchestrator.with_events(events::EventWriter::stdout());
+            }
+            let stats = if two_phase && !dry_run {
+                let plan = orchestrator.plan()?;
+                eprintln!("\n{}\n", plan);
+                if !yes && !confirm("Proceed?")? {
+                    eprintln!("Aborted; nothing was changed.");
+                    return Ok(());
+                }
+                orchestrator.execute(plan)?
+            } else {
+                orchestrator.run()?
+            };
+
+            // Exit 1 signals "there is work to do", mirroring `git diff --exit-code`
+            if exit_code && stats.files_organized > 0 {
+                std::process::exit(1);
+            }
         }
 
-        Commands::Hash { path, recursive } => {
-            if path.is_file() {
-                match hash::hash_file(&path) {
-                    Ok(h) => println!("{}: {}", path.display(), h.to_hex()),
-                    Err(e) => eprintln!("Error hashing {}: {}", path.display(), e),
+        Commands::Hash {
+            path,
+            recursive,
+            algorithm,
+            mmap,
+            parallel_threshold,
+            include_hidden,
+            follow_symlinks,
+            output,
+            check,
+            relative_to,
+            flatten,
+        } => {
+            // A listing names the algorithm it was written with, unless another tool wrote it
+            let listing = if check {
+                Some(hash::read_checksums(std::io::BufReader::new(std::fs::File::open(&path)?))?)
+            } else {
+                None
+            };
+            let recorded = listing.as_ref().and_then(|listing| listing.algorithm);
+            if let (Some(given), Some(recorded)) = (algorithm, recorded)
+                && given != recorded {
+                    return Err(format!("{} holds {} hashes, not {}", path.display(), recorded, given).into());
                 }
-            } else if path.is_dir() {
-                let mut files = Vec::new();
-                if recursive {
-                    for entry in walkdir::WalkDir::new(&path).into_iter().filter_map(|e| e.ok()) {
-                        if entry.file_type().is_file() {
-                            files.push(entry.path().to_path_buf());
+            let guessed = algorithm.is_none() && recorded.is_none();
+            let algorithm = algorithm.or(recorded).unwrap_or_default();
+            if algorithm != hash::HashAlgorithm::Blake3 && (mmap || parallel_threshold.is_some()) {
+                return Err(format!("--mmap and --parallel-threshold only apply to blake3, not {}", algorithm).into());
+            }
+            let hasher = |path: &Path| match (mmap, parallel_threshold) {
+                (true, _) => hash::hash_file_mmap(path).map(|h| h.to_hex().to_string()),
+                (false, Some(threshold)) => {
+                    hash::hash_file_multithreaded(path, threshold).map(|h| h.to_hex().to_string())
+                }
+                (false, None) => hash::hash_file_with(path, algorithm),
+            };
+            if let Some(listing) = listing {
+                let mut failed = 0;
+                let mut mismatched = 0;
+                for (checksum, result) in hash::check_checksums(&listing.checksums, hasher) {
+                    match result {
+                        Ok(true) => println!("{}: OK", checksum.path.display()),
+                        Ok(false) => {
+                            failed += 1;
+                            mismatched += 1;
+                            println!("{}: FAILED", checksum.path.display());
                         }
-                    }
-                } else {
-                    for entry in std::fs::read_dir(&path)? {
-                        let entry = entry?;
-                        if entry.path().is_file() {
-                            files.push(entry.path());
+                        Err(e) => {
+                            failed += 1;
+                            println!("{}: FAILED ({})", checksum.path.display(), e);
                         }
                     }
                 }
+                if failed > 0 {
+                    eprintln!("{} of {} {} hashes did not match", failed, listing.checksums.len(), algorithm);
+                    if guessed && mismatched == listing.checksums.len() {
+                        eprintln!("The listing does not record its algorithm; if another tool wrote it, pass --algorithm");
+                    }
+                    std::process::exit(1);
+                }
+                return Ok(());
+            }
 
-                let results = hash::hash_files_parallel(files);
-                for (file_path, h) in results {
-                    println!("{}: {}", file_path, h.to_hex());
+            // Files come in path order and are hashed a batch at a time, so output
+            // is sorted while memory stays bounded however large the tree
+            let files: Box<dyn Iterator<Item = PathBuf>> = if path.is_file() {
+                Box::new(std::iter::once(path.clone()))
+            } else if path.is_dir() && recursive {
+                let walk = organize::walk(&path, include_hidden, follow_symlinks)
+                    .filter_map(|entry| match entry {
+                        Ok(entry) => Some(entry),
+                        Err(err) => {
+                            if err.loop_ancestor().is_some() && !cli.quiet {
+                                eprintln!("Warning: skipping symlink loop at {:?}", err.path().unwrap_or(&path));
+                            }
+                            None
+                        }
+                    })
+                    .filter(|entry| entry.file_type().is_file())
+                    .map(walkdir::DirEntry::into_path);
+                Box::new(walk)
+            } else if path.is_dir() {
+                let mut files = Vec::new();
+                for entry in std::fs::read_dir(&path)? {
+                    let entry = entry?;
+                    if entry.path().is_file() && !organize::is_ignored(&entry.file_name(), include_hidden) {
+                        files.push(entry.path());
+                    }
                 }
+                files.sort();
+                Box::new(files.into_iter())
             } else {
                 eprintln!("Path not found: {}", path.display());
+                Box::new(std::iter::empty())
+            };
+
+            let style = match relative_to {
+                Some(base) => hash::PathStyle::RelativeTo(base),
+                None if flatten => hash::PathStyle::FileName,
+                None => hash::PathStyle::Full,
+            };
+            let mut listing = match &output {
+                Some(output) => {
+                    let mut writer = std::io::BufWriter::new(std::fs::File::create(output)?);
+                    hash::write_checksum_header(&mut writer, algorithm)?;
+                    Some(writer)
+                }
+                None => None,
+            };
+            let mut written = 0;
+            hash::hash_files_streaming(files, hasher, |file_path, result| {
+                let hash = match result {
+                    Ok(hash) => hash,
+                    Err(e) => {
+                        eprintln!("Error hashing {}: {}", file_path.display(), e);
+                        return Ok(());
+                    }
+                };
+                let checksum = hash::Checksum { hash, path: style.apply(&file_path) };
+                match listing.as_mut() {
+                    Some(writer) => hash::write_checksum(writer, &checksum)?,
+                    None => println!("{}: {}", checksum.path.display(), checksum.hash),
+                }
+                written += 1;
+                Ok(())
+            })?;
+            if let (Some(mut writer), Some(output)) = (listing, output) {
+                writer.flush()?;
+                if !cli.quiet {
+                    eprintln!("Wrote {} {} hashes to {}", written, algorithm, output.display());
+                }
             }
         }
 
-        Commands::Index { path, limit } => {
-            match index::Index::load_from_file(&path) {
-                Ok(idx) => {
-                    println!("Index loaded from {:?}: {} entries", path, idx.len());
-                    for (i, entry) in idx.entries().enumerate() {
-                        if i >= limit {
-                            break;
+        Commands::Index { path, limit, skip, sort, format, find } => {
+            let idx = match index::Index::load_from_file(&path) {
+                Ok(idx) => idx,
+                Err(e) => {
+                    eprintln!("Error loading index {:?}: {}", path, e);
+                    return Ok(());
+                }
+            };
+            match find {
+                Some(query) => {
+                    let mut matches = idx.find_by_hash_prefix(&query);
+                    let found: HashSet<&str> = matches.iter().map(|entry| entry.hash.as_str()).collect();
+                    for entry in idx.find_by_path_substring(&query) {
+                        if !found.contains(entry.hash.as_str()) {
+                            matches.push(entry);
                         }
-                        println!("{}: {}", entry.hash, entry.file_path);
+                    }
+                    if matches.is_empty() {
+                        println!("No entries match {:?}", query);
+                    }
+                    for entry in matches {
+                        println!("{}: {}", entry.hash, entry.file_paths.join(", "));
+                    }
+                }
+                None if format == IndexFormat::Json => {
+                    let stdout = std::io::stdout();
+                    let mut out = stdout.lock();
+                    idx.write_json(&mut out)?;
+                    writeln!(out)?;
+                }
+                None => {
+                    println!("Index loaded from {:?}: {} entries", path, idx.len());
+                    for entry in idx.sorted_entries(sort).into_iter().skip(skip).take(limit) {
+                        println!("{}: {}", entry.hash, entry.file_paths.join(", "));
                     }
                 }
-                Err(e) => eprintln!("Error loading index {:?}: {}", path, e),
             }
         }
 
-        Commands::Cluster { source, details } => {
-            eprintln!("Scanning for photos in {:?}...", source);
-            let photo_extensions = ["jpg", "jpeg", "png", "tiff", "raw", "heic"];
-            let points = Vec::new();
-            let mut paths = Vec::new();
-
-            for entry in walkdir::WalkDir::new(&source).into_iter().filter_map(|e| e.ok()) {
-                if entry.file_type().is_file() {
-                    let path = entry.path();
-                    if let Some(ext) = path.extension() {
-                        let ext_lower = ext.to_string_lossy().to_lowercase();
-                        if photo_extensions.contains(&ext_lower.as_str()) {
-                            // TODO: Actually extract GPS from EXIF
-                            // For now, this is a placeholder to show clustering works
-                            // if we had the coordinates.
-                            // In a real run, we'd use metadata::extract_gps(path)
-                            paths.push(path.to_path_buf());
-                        }
-                    }
+        Commands::Duplicates { dir, index } => {
+            let idx = index::Index::load_from_file(&index)?;
+            let stdout = std::io::stdout();
+            let mut out = stdout.lock();
+            let scan = organize::find_duplicates(&dir, &idx)?;
+            for (path, originals) in &scan.duplicates {
+                writeln!(out, "{}\t{}", path.display(), originals.join("\t"))?;
+            }
+            out.flush()?;
+            for (path, err) in &scan.unreadable {
+                eprintln!("Warning: could not read {:?}: {}", path, err);
+            }
+            if !scan.unreadable.is_empty() {
+                std::process::exit(1);
+            }
+        }
+        Commands::Verify { index, rehash, prune } => {
+            let mut idx = index::Index::load_from_file(&index)?;
+            let report = organize::verify_index(&idx, rehash);
+            for path in &report.missing {
+                println!("Missing: {}", path);
+            }
+            for path in &report.mismatched {
+                println!("Changed: {}", path);
+            }
+            println!("{}/{} indexed files clean", report.clean_count(), report.total);
+            if prune && !report.missing.is_empty() {
+                let removed = idx.prune_missing();
+                idx.save_keeping_format(&index)?;
+                println!("Pruned missing files; removed {} entries from {:?}", removed, index);
+            }
+            if !report.is_clean() {
+                std::process::exit(1);
+            }
+        }
+        Commands::Stats { dir, recursive } => {
+            let extensions = organize::resolve_extensions(&[], false);
+            print!("{}", stats::library_stats(&dir, recursive, &extensions)?);
+        }
+        Commands::Undo { manifest, destination, index } => {
+            let entries = manifest::read_manifest(&manifest)?;
+            let root = destination.unwrap_or_else(|| match manifest.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+                _ => PathBuf::from("."),
+            });
+            let report = manifest::undo(&entries, &root);
+            for (path, reason) in &report.skipped {
+                eprintln!("Warning: left {:?} in place: {}", path, reason);
+            }
+            println!(
+                "Removed {} files, moved {} back, removed {} empty folders",
+                report.removed, report.restored, report.folders_removed
+            );
+
+            // Undone files must not be skipped as duplicates by the next run
+            let index_path = index.unwrap_or_else(|| root.join(index::INDEX_FILE));
+            if index_path.exists() {
+                let mut idx = index::Index::load_from_file(&index_path)?;
+                let removed = idx.prune_missing();
+                idx.save_keeping_format(&index_path)?;
+                println!("Removed {} entries from {:?}", removed, index_path);
+            }
+
+            // Keep only what is left to undo, so running undo again does not
+            // report the files already undone as changed
+            let left: Vec<manifest::ManifestEntry> = entries
+                .into_iter()
+                .filter(|entry| entry.dst.as_ref().is_some_and(|dst| report.skipped.iter().any(|(path, _)| path == dst)))
+                .collect();
+            manifest::write_manifest(&manifest, &left)?;
+            if !report.skipped.is_empty() {
+                std::process::exit(1);
+            }
+        }
+        Commands::Migrate { index, bloom } => {
+            if index::IndexBackend::detect(&index) == Some(index::IndexBackend::Sqlite) {
+                println!("Index {:?} is a SQLite index; nothing to migrate", index);
+                if bloom && !cli.quiet {
+                    eprintln!("Warning: --bloom ignored: SQLite indexes look entries up in the database");
                 }
+                return Ok(());
+            }
+            let (from, to) = index::Index::migrate_file(&index)?;
+            if from == to {
+                println!("Index {:?} is already at format v{}", index, to);
+            } else {
+                println!("Migrated index {:?} from format v{} to v{}", index, from, to);
+            }
+            if bloom {
+                let mut idx = index::Index::load_from_file(&index)?;
+                idx.rebuild_bloom();
+                idx.save_to_file(&index)?;
+                println!("Bloom filter written to {:?}", bloom::sidecar_path(&index));
+            }
+        }
+        Commands::IndexMerge { first, second, output } => {
+            let mut merged = index::Index::load_from_file(&first)?;
+            let (added, already_present) = merged.merge(index::Index::load_from_file(&second)?);
+            merged.save_keeping_format(&output)?;
+            println!(
+                "Merged {:?} into {:?}: {} entries added, {} already present",
+                second, first, added, already_present
+            );
+            println!("{} entries written to {:?}", merged.len(), output);
+        }
+        Commands::Cluster {
+            source,
+            details,
+            ext,
+            ext_only,
+            min_gps_accuracy,
+            eps_km,
+            min_points,
+            geonames: geonames_file,
+            geo_prefer_population,
+            by_time,
+            gap,
+            export,
+            since,
+            until,
+            include_undated,
+        } => {
+            let range = date_range(since, until, include_undated)?;
+            if !cli.quiet {
+                eprintln!("Scanning for photos in {:?}...", source);
+            }
+            let photo_extensions = organize::resolve_extensions(&ext, ext_only);
+            if by_time {
+                print_time_clusters(&source, &photo_extensions, gap, details, range);
+                return Ok(());
+            }
+            let found = find_geotagged_photos(&source, &photo_extensions, range, min_gps_accuracy);
+            if found.rejected > 0 && !cli.quiet {
+                eprintln!("Ignored {} photos with implausible or imprecise GPS coordinates", found.rejected);
+            }
+            if found.duplicates > 0 && !cli.quiet {
+                eprintln!("Skipped {} duplicate photos", found.duplicates);
+            }
+
+            let mut points = Vec::with_capacity(found.photos.len());
+            let mut paths = Vec::with_capacity(found.photos.len());
+            for (path, fix) in found.photos {
+                // dbscan requires point ids to match their index
+                points.push(clustering::GeoPoint { id: points.len(), latitude: fix.latitude, longitude: fix.longitude });
+                paths.push(path);
             }
 
             if points.is_empty() {
@@ -158,23 +553,51 @@ fn main() -> Result<(), Box<dyn Error>> {
                 return Ok(());
             }
 
-            let clusters = clustering::dbscan(&points, 1.0, 3);
-            let geonames = geonames::load_geonames();
+            let clustering::DbscanResult { clusters, noise } =
+                clustering::dbscan_with_noise(&points, eps_km, min_points.get());
+            let geonames = match geonames_file {
+                Some(path) => geonames::load_geonames_from_file(&path)?,
+                None => geonames::load_geonames(),
+            };
 
             println!("Found {} clusters in {}", clusters.len(), source.display());
 
-            for (id, cluster_points) in clusters {
-                let first_point_id = cluster_points[0];
-                let first_point = &points[first_point_id];
-                let location_name = clustering::find_closest_location(first_point, &geonames)
+            let mut names = std::collections::HashMap::new();
+            for id in 0..clusters.len() {
+                let cluster_points = &clusters[&id];
+                let center = clustering::cluster_centroid(&points, cluster_points);
+                let location_name = clustering::name_location(&center, &geonames, geo_prefer_population)
                     .unwrap_or_else(|| "Unknown Location".to_string());
 
                 println!("Cluster {}: {} ({} photos)", id, location_name, cluster_points.len());
                 if details {
-                    for &p_id in &cluster_points {
+                    for &p_id in cluster_points {
                         println!("  - {:?}", paths[p_id]);
                     }
                 }
+                names.insert(id, location_name);
+            }
+
+            if !noise.is_empty() {
+                println!("Ungrouped: {} photos", noise.len());
+                if details {
+                    for &p_id in &noise {
+                        println!("  - {:?}", paths[p_id]);
+                    }
+                }
+            }
+
+            if let Some(export) = export {
+                let is_kml = export.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("kml"));
+                let document = if is_kml {
+                    clustering::export_clusters_kml(&clusters, &points, &names)
+                } else {
+                    clustering::export_clusters_geojson(&clusters, &points, &names)
+                };
+                std::fs::write(&export, document)?;
+                if !cli.quiet {
+                    eprintln!("Clusters written to {:?}", export);
+                }
             }
         }
 
@@ -182,43 +605,150 @@ fn main() -> Result<(), Box<dyn Error>> {
             path,
             size_mb,
             iterations,
+            uncached,
+            pattern,
+            write_chunk_kb,
+            fsync,
         } => {
-            use std::io::Write;
-            use std::time::Instant;
+            if !cli.quiet {
+                println!("Benchmarking performance on: {:?}", path);
+            }
+            if uncached && cfg!(not(unix)) && !cli.quiet {
+                eprintln!("--uncached is only supported on Unix; results may include cached reads");
+            }
+            let options = benchmark::BenchmarkOptions {
+                size_mb,
+                iterations,
+                pattern,
+                uncached,
+                write_chunk_kb: write_chunk_kb.get(),
+                fsync,
+            };
+            let report = benchmark::run(&path, &options, |line| println!("{}", line))?;
+            print!("\n{}", report);
+        }
+    }
 
-            println!("Benchmarking performance on: {:?}", path);
-            let test_file = path.join(".sift_benchmark.tmp");
-            let data = vec![0u8; size_mb * 1024 * 1024];
+    Ok(())
+}
 
-            print!("Creating {} MB test file... ", size_mb);
-            std::io::stdout().flush()?;
-            std::fs::write(&test_file, &data)?;
-            println!("Done.");
+/// Asks a yes/no question on stderr and reads the answer from stdin; anything but "y" is no.
+fn confirm(question: &str) -> std::io::Result<bool> {
+    eprint!("{} [y/N] ", question);
+    std::io::stderr().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
 
-            let mut total_duration = std::time::Duration::default();
+/// Builds the `--since` / `--until` range, rejecting one that ends before it starts.
+fn date_range(since: Option<NaiveDate>, until: Option<NaiveDate>, include_undated: bool) -> Result<DateRange, String> {
+    if let (Some(since), Some(until)) = (since, until)
+        && since > until {
+            return Err(format!("--since {} is after --until {}", since, until));
+        }
+    Ok(DateRange { since, until, include_undated })
+}
+
+/// Photos found by [`find_geotagged_photos`].
+///
+/// * `photos` - One path per distinct content, in path order, with its GPS position
+/// * `rejected` - Photos whose position is implausible or less accurate than required
+/// * `duplicates` - Further copies of a photo already in `photos`
+struct GeotaggedPhotos {
+    photos: Vec<(PathBuf, metadata::GpsFix)>,
+    rejected: usize,
+    duplicates: usize,
+}
+
+/// Finds the photos under `source` with a usable GPS position, for clustering.
+///
+/// Positions are read and the located photos hashed in parallel. Only the first
+/// copy of the same content is kept, so a photo imported twice counts once and
+/// copies cannot make up a cluster on their own. A photo that cannot be hashed
+/// is kept.
+fn find_geotagged_photos(
+    source: &Path,
+    extensions: &[String],
+    range: DateRange,
+    min_gps_accuracy: Option<f64>,
+) -> GeotaggedPhotos {
+    let mut candidates: Vec<PathBuf> = walkdir::WalkDir::new(source)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|entry| entry.file_type().is_file() && organize::has_photo_extension(entry.path(), extensions))
+        .map(walkdir::DirEntry::into_path)
+        .collect();
+    candidates.sort();
 
-            for i in 1..=iterations {
-                print!("Iteration {}/{}... ", i, iterations);
-                std::io::stdout().flush()?;
-                let start = Instant::now();
-                let _read_data = network_io::buffered_read_file(&test_file)?;
-                let duration = start.elapsed();
-                total_duration += duration;
-                println!("{:?}", duration);
+    let located: Vec<(PathBuf, metadata::GpsFix)> = candidates
+        .into_par_iter()
+        .filter(|path| range.is_unbounded() || range.admits(metadata::extract_date_with_fallback(path)))
+        .filter_map(|path| metadata::extract_gps(&path).map(|fix| (path, fix)))
+        .collect();
+    let (plausible, rejected): (Vec<_>, Vec<_>) = located.into_iter().partition(|(_, fix)| {
+        clustering::is_plausible_fix(fix.latitude, fix.longitude, fix.horizontal_error_m, min_gps_accuracy)
+    });
+
+    let hashes: Vec<Option<blake3::Hash>> =
+        plausible.par_iter().map(|(path, _)| hash::hash_file_resilient(path).ok()).collect();
+    let mut seen = std::collections::HashSet::new();
+    let mut photos = Vec::with_capacity(plausible.len());
+    let mut duplicates = 0;
+    for (photo, hash) in plausible.into_iter().zip(hashes) {
+        if let Some(hash) = hash
+            && !seen.insert(hash) {
+                duplicates += 1;
+                continue;
             }
+        photos.push(photo);
+    }
+    GeotaggedPhotos { photos, rejected: rejected.len(), duplicates }
+}
 
-            let avg_duration = total_duration / iterations as u32;
-            let throughput = (size_mb as f64) / avg_duration.as_secs_f64();
+/// Clusters the photos under `source` into events by capture time and prints them.
+fn print_time_clusters(
+    source: &std::path::Path,
+    extensions: &[String],
+    gap_hours: i64,
+    details: bool,
+    range: DateRange,
+) {
+    let mut dates = Vec::new();
+    let mut paths = Vec::new();
+    for entry in walkdir::WalkDir::new(source).into_iter().filter_map(|e| e.ok()) {
+        let path = entry.path();
+        if entry.file_type().is_file()
+            && organize::has_photo_extension(path, extensions)
+            && let Some(taken) = metadata::extract_capture_datetime(path)
+            && range.admits(Some(taken.date())) {
+                dates.push((paths.len(), taken));
+                paths.push(path.to_path_buf());
+            }
+    }
+
+    if dates.is_empty() {
+        println!("No photos found in {:?}", source);
+        return;
+    }
 
-            println!("\nBenchmark Results:");
-            println!("  Average Duration: {:?}", avg_duration);
-            println!("  Throughput: {:.2} MB/s", throughput);
+    let events = clustering::cluster_by_time(&dates, gap_hours);
+    println!("Found {} events in {}", events.len(), source.display());
 
-            if test_file.exists() {
-                std::fs::remove_file(test_file)?;
+    for id in 0..events.len() {
+        let members = &events[&id];
+        let start = dates[members[0]].1;
+        let end = dates[members[members.len() - 1]].1;
+        println!(
+            "Event {}: {} ({} photos)",
+            id,
+            clustering::event_folder_name(start, end),
+            members.len()
+        );
+        if details {
+            for &p_id in members {
+                println!("  - {:?}", paths[p_id]);
             }
         }
     }
-
-    Ok(())
 }
@@ -0,0 +1,314 @@
+//! Per-run manifest of what `organize` did with every file.
+//!
+//! Where the index is keyed by content hash and accumulates across runs, the
+//! manifest describes a single run: one entry per source file, saying where it
+//! went and how. It is a pretty-printed JSON array, written at the end of each
+//! run that transfers at least one file, e.g.
+//!
+//! ```text
+//! [
+//!   {
+//!     "src": "/in/IMG_1.jpg",
+//!     "dst": "/out/2024/01/02/IMG_1.jpg",
+//!     "hash": "af13…",
+//!     "hash_kind": "bytes",
+//!     "date": "2024-01-02",
+//!     "action": "copy"
+//!   },
+//!   {
+//!     "src": "/in/IMG_1 (1).jpg",
+//!     "dst": "/out/2024/01/02/IMG_1.jpg",
+//!     "hash": "af13…",
+//!     "hash_kind": "bytes",
+//!     "date": "2024-01-02",
+//!     "action": "skipped_duplicate"
+//!   }
+//! ]
+//! ```
+//!
+//! [`undo`] reverses a run from its manifest (`sift undo <MANIFEST>`).
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::hash::{self, HashKind};
+use crate::index;
+use crate::organization::{self, FileOp};
+
+/// Default file name of the run manifest, written at the destination root.
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// What a run did with one source file.
+///
+/// * `Copy`, `Move`, `Hardlink`, `Symlink` - The file was transferred this way
+/// * `AlreadyPresent` - Identical content already sat at `dst`; nothing was written
+/// * `SkippedDuplicate` - The content was already organized, at `dst`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ManifestAction {
+    Copy,
+    Move,
+    Hardlink,
+    Symlink,
+    AlreadyPresent,
+    SkippedDuplicate,
+}
+
+impl From<FileOp> for ManifestAction {
+    fn from(mode: FileOp) -> Self {
+        match mode {
+            FileOp::Copy => ManifestAction::Copy,
+            FileOp::Move => ManifestAction::Move,
+            FileOp::Hardlink => ManifestAction::Hardlink,
+            FileOp::Symlink => ManifestAction::Symlink,
+        }
+    }
+}
+
+/// One file's entry in the manifest.
+///
+/// # Fields
+///
+/// * `src` - Where the file was found
+/// * `dst` - Where it was placed, or for duplicates where its content already
+///   lives (None if that copy failed to organize in the same run)
+/// * `hash` - Blake3 hash of the file
+/// * `hash_kind` - Whether `hash` covers the file's bytes or, with `--pixel-dedup`, its pixels
+/// * `date` - Date of the file, used to pick its destination folder (None if unknown)
+/// * `action` - What was done with the file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub src: PathBuf,
+    pub dst: Option<PathBuf>,
+    pub hash: String,
+    #[serde(default)]
+    pub hash_kind: HashKind,
+    pub date: Option<NaiveDate>,
+    pub action: ManifestAction,
+}
+
+/// Writes `entries` to `path` as a JSON array, replacing any previous manifest.
+///
+/// The manifest is written with [`index::write_atomic`], so an interrupted write
+/// never leaves a truncated manifest behind.
+pub fn write_manifest(path: &Path, entries: &[ManifestEntry]) -> io::Result<()> {
+    index::write_atomic(path, &serde_json::to_vec_pretty(entries)?)
+}
+
+/// Reads a manifest written by [`write_manifest`].
+///
+/// # Errors
+///
+/// Fails with `InvalidData` if the file is not a manifest.
+pub fn read_manifest(path: &Path) -> io::Result<Vec<ManifestEntry>> {
+    let data = fs::read(path)?;
+    Ok(serde_json::from_slice(&data)?)
+}
+
+/// What [`undo`] did.
+///
+/// # Fields
+///
+/// * `removed` - Copied or linked files deleted from the destination
+/// * `restored` - Moved files put back at their source path
+/// * `skipped` - Destinations left alone, with the reason
+/// * `folders_removed` - Folders removed because undoing left them empty
+#[derive(Debug, Default)]
+pub struct UndoReport {
+    pub removed: usize,
+    pub restored: usize,
+    pub skipped: Vec<(PathBuf, String)>,
+    pub folders_removed: usize,
+}
+
+/// Reverses the transfers recorded in a manifest.
+///
+/// Copies, hard links and symlinks are deleted; moved files are moved back to
+/// their source path. Duplicates and already present files were never written,
+/// so they are left alone. Before touching a destination, it is re-hashed and
+/// compared with the recorded hash: files changed or removed since the run are
+/// skipped, as are moves whose source path has been taken again.
+///
+/// Folders left empty are removed, up to but excluding `root` (normally the
+/// destination the run organized into). Nothing outside `root` is removed.
+pub fn undo(entries: &[ManifestEntry], root: &Path) -> UndoReport {
+    let mut report = UndoReport::default();
+    // Latest transfers first, the reverse of the order they were made in
+    for entry in entries.iter().rev() {
+        let Some(dst) = &entry.dst else { continue };
+        if matches!(entry.action, ManifestAction::AlreadyPresent | ManifestAction::SkippedDuplicate) {
+            continue;
+        }
+        match undo_entry(entry, dst) {
+            Ok(()) => {
+                if entry.action == ManifestAction::Move {
+                    report.restored += 1;
+                } else {
+                    report.removed += 1;
+                }
+                report.folders_removed += remove_empty_parents(dst, root);
+            }
+            Err(reason) => report.skipped.push((dst.clone(), reason)),
+        }
+    }
+    report
+}
+
+/// Undoes one transfer, or explains why it was left alone.
+fn undo_entry(entry: &ManifestEntry, dst: &Path) -> Result<(), String> {
+    let actual = match entry.hash_kind {
+        HashKind::Bytes => hash::hash_file(dst),
+        HashKind::Pixels => hash::hash_pixels(dst),
+    };
+    match actual {
+        Ok(actual) if actual.to_hex().as_str() == entry.hash => {}
+        Ok(_) => return Err("changed since it was organized".to_string()),
+        Err(e) => return Err(format!("cannot be read: {}", e)),
+    }
+
+    if entry.action != ManifestAction::Move {
+        return fs::remove_file(dst).map_err(|e| format!("cannot be removed: {}", e));
+    }
+    if entry.src.exists() {
+        return Err(format!("source path {:?} is taken", entry.src));
+    }
+    if let Some(parent) = entry.src.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("cannot recreate {:?}: {}", parent, e))?;
+    }
+    organization::transfer_file(dst, &entry.src, FileOp::Move)
+        .map_err(|e| format!("cannot be moved back: {}", e))
+}
+
+/// Removes the empty folders containing `path`, innermost first, stopping at
+/// `root` or at the first folder that is not empty.
+fn remove_empty_parents(path: &Path, root: &Path) -> usize {
+    let mut removed = 0;
+    let mut dir = path.parent();
+    while let Some(folder) = dir {
+        if folder == root || !folder.starts_with(root) || fs::remove_dir(folder).is_err() {
+            break;
+        }
+        removed += 1;
+        dir = folder.parent();
+    }
+    removed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::organize::{OrganizeContext, Orchestrator};
+    use tempfile::TempDir;
+
+    /// Organizes `source` into `dest` with `mode` and returns the run's manifest.
+    fn organize(source: &Path, dest: &Path, mode: FileOp) -> io::Result<Vec<ManifestEntry>> {
+        let mut ctx = OrganizeContext::new(source.to_path_buf(), dest.to_path_buf(), false, None, None);
+        ctx.mode = mode;
+        Orchestrator::new(ctx.clone()).run()?;
+        read_manifest(&ctx.get_manifest_path())
+    }
+
+    /// Lists the entries directly inside `dir`, sorted.
+    fn list(dir: &Path) -> io::Result<Vec<String>> {
+        let mut names: Vec<String> = fs::read_dir(dir)?
+            .map(|entry| Ok(entry?.file_name().to_string_lossy().to_string()))
+            .collect::<io::Result<_>>()?;
+        names.sort();
+        Ok(names)
+    }
+
+    #[test]
+    fn test_manifest_round_trip() -> io::Result<()> {
+        let dir = TempDir::new()?;
+        let path = dir.path().join(MANIFEST_FILE);
+        let entries = vec![
+            ManifestEntry {
+                src: PathBuf::from("/in/a.jpg"),
+                dst: Some(PathBuf::from("/out/2024/01/02/a.jpg")),
+                hash: "af13".to_string(),
+                hash_kind: HashKind::Bytes,
+                date: NaiveDate::from_ymd_opt(2024, 1, 2),
+                action: FileOp::Move.into(),
+            },
+            ManifestEntry {
+                src: PathBuf::from("/in/b.jpg"),
+                dst: None,
+                hash: "af13".to_string(),
+                hash_kind: HashKind::Bytes,
+                date: None,
+                action: ManifestAction::SkippedDuplicate,
+            },
+        ];
+        write_manifest(&path, &entries)?;
+        assert_eq!(read_manifest(&path)?, entries);
+
+        let json: serde_json::Value = serde_json::from_slice(&fs::read(&path)?)?;
+        assert_eq!(json[0]["action"], "move");
+        assert_eq!(json[0]["date"], "2024-01-02");
+        assert_eq!(json[1]["action"], "skipped_duplicate");
+
+        fs::write(&path, "{}")?;
+        assert_eq!(read_manifest(&path).unwrap_err().kind(), io::ErrorKind::InvalidData);
+        Ok(())
+    }
+
+    #[test]
+    fn test_undo_copy_run() -> io::Result<()> {
+        let source = TempDir::new()?;
+        let dest = TempDir::new()?;
+        fs::write(source.path().join("IMG_20230101_a.jpg"), "a")?;
+        fs::write(source.path().join("IMG_20230101_b.jpg"), "a")?;
+        fs::write(source.path().join("IMG_20230102_c.jpg"), "c")?;
+        let entries = organize(source.path(), dest.path(), FileOp::Copy)?;
+
+        let report = undo(&entries, dest.path());
+        assert_eq!((report.removed, report.restored), (2, 0));
+        assert!(report.skipped.is_empty());
+        // 2023/01/01, 2023/01/02, 2023/01 and 2023
+        assert_eq!(report.folders_removed, 4);
+        assert_eq!(list(dest.path())?, [".sift_index.bin", MANIFEST_FILE]);
+        assert_eq!(list(source.path())?.len(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_undo_move_run() -> io::Result<()> {
+        let source = TempDir::new()?;
+        let dest = TempDir::new()?;
+        fs::write(source.path().join("IMG_20230101_a.jpg"), "a")?;
+        let entries = organize(source.path(), dest.path(), FileOp::Move)?;
+        assert!(!source.path().join("IMG_20230101_a.jpg").exists());
+
+        let report = undo(&entries, dest.path());
+        assert_eq!((report.removed, report.restored), (0, 1));
+        assert_eq!(fs::read_to_string(source.path().join("IMG_20230101_a.jpg"))?, "a");
+        assert_eq!(list(dest.path())?, [".sift_index.bin", MANIFEST_FILE]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_undo_skips_tampered_destination() -> io::Result<()> {
+        let source = TempDir::new()?;
+        let dest = TempDir::new()?;
+        fs::write(source.path().join("IMG_20230101_a.jpg"), "a")?;
+        fs::write(source.path().join("IMG_20230101_b.jpg"), "b")?;
+        let entries = organize(source.path(), dest.path(), FileOp::Move)?;
+
+        let tampered = dest.path().join("2023/01/01/IMG_20230101_b.jpg");
+        fs::write(&tampered, "edited")?;
+        let report = undo(&entries, dest.path());
+        assert_eq!(report.restored, 1);
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(report.skipped[0].0, tampered);
+
+        // The edited file stays put, and so does its folder
+        assert_eq!(fs::read_to_string(&tampered)?, "edited");
+        assert!(!source.path().join("IMG_20230101_b.jpg").exists());
+        assert_eq!(report.folders_removed, 0);
+        Ok(())
+    }
+}
@@ -1,7 +1,10 @@
 //! Photo metadata extraction from file attributes.
 //!
 //! This module provides functionality to extract temporal metadata from photos
-//! using file modification time. It also provides utilities for organizing files
+//! (EXIF) and videos (QuickTime/MP4 `mvhd`), falling back to the filename and
+//! file modification time, GPS coordinates from EXIF, and star ratings from XMP
+//! or EXIF. Dates and GPS coordinates missing from a photo are also read from
+//! its XMP sidecar. It also provides utilities for organizing files
 //! chronologically.
 //!
 //! # Examples
@@ -14,12 +17,34 @@
 //! # Ok::<(), std::io::Error>(())
 //! ```
 
-use chrono::{DateTime, Local, NaiveDate, Datelike};
-use exif::{In, Tag};
+use chrono::{DateTime, Datelike, FixedOffset, Local, NaiveDate, NaiveDateTime, NaiveTime};
+use clap::ValueEnum;
+use exif::{Context, Exif, In, Tag, Value};
+use std::fmt;
 use std::fs;
-use std::io;
+use std::io::{self, Read, Seek, SeekFrom};
 use std::path::Path;
 
+/// EXIF `Rating` (0x4746), written by Windows and most culling tools.
+const EXIF_RATING: Tag = Tag(Context::Tiff, 0x4746);
+
+/// How far into a file to look for an embedded XMP packet.
+///
+/// JPEG and most RAW formats store XMP in the leading metadata segments.
+const XMP_SCAN_LIMIT: u64 = 1024 * 1024;
+
+/// Extensions of QuickTime-family video files, dated from their `mvhd` atom.
+pub const VIDEO_EXTENSIONS: &[&str] = &["mp4", "mov", "m4v"];
+
+/// Extensions of HEIF image files, whose EXIF is stored as a container item.
+const HEIF_EXTENSIONS: &[&str] = &["heic", "heif"];
+
+/// Largest `meta` box or EXIF item read from a HEIF file.
+const HEIF_META_LIMIT: u64 = 4 * 1024 * 1024;
+
+/// Seconds between the QuickTime epoch (1904-01-01) and the Unix epoch.
+const QUICKTIME_EPOCH_OFFSET: i64 = 2_082_844_800;
+
 /// Metadata extracted from a photo file.
 ///
 /// # Fields
@@ -34,7 +59,8 @@ pub struct PhotoMetadata {
 
 /// Extracts the date taken from a photo file's EXIF data.
 ///
-/// Priority is given to the `DateTimeOriginal` tag.
+/// This is the date of [`extract_exif_datetime`]. HEIC/HEIF files whose
+/// container the `exif` crate rejects are searched for their `Exif` item.
 ///
 /// # Arguments
 ///
@@ -45,22 +71,177 @@ pub struct PhotoMetadata {
 /// * `Some(NaiveDate)` - The extracted date if found and valid
 /// * `None` - If EXIF data is missing or doesn't contain a valid date
 pub fn extract_exif_date<P: AsRef<Path>>(path: P) -> Option<NaiveDate> {
+    extract_exif_datetime(path).map(|taken| taken.date())
+}
+
+/// Extracts the capture time of a photo from its EXIF data.
+///
+/// Reads `DateTimeOriginal`, refined by `SubSecTimeOriginal` when present.
+/// This is the camera's clock time, even when it also recorded
+/// `OffsetTimeOriginal`: a photo taken on the evening of June 1st in Tokyo is
+/// filed under June 1st wherever it is organized. The offset is only applied
+/// to order photos, by [`extract_capture_datetime`].
+///
+/// Some cameras record incomplete timestamps such as `2019:03:00 00:00:00`.
+/// These are completed or rejected as described by [`DateFallback`];
+/// [`exif_date_fallback`] tells which was done.
+///
+/// # Returns
+///
+/// * `Some(NaiveDateTime)` - The capture time, with sub-second precision if recorded
+/// * `None` - If EXIF data is missing or `DateTimeOriginal` is absent or unusable
+pub fn extract_exif_datetime<P: AsRef<Path>>(path: P) -> Option<NaiveDateTime> {
+    read_exif_timestamp(path.as_ref())?.taken
+}
+
+/// What was done with an incomplete or invalid EXIF `DateTimeOriginal`.
+///
+/// * `FirstOfMonth` - The day was `00`: the photo is dated the first of the month
+/// * `Midnight` - The time was out of range (e.g. `25:00:00`): the date is kept, at midnight
+/// * `NextMethod` - The year or month was `0`, or the day does not exist in that
+///   month: the EXIF date is ignored and the next dating method (XMP sidecar,
+///   filename, modification time) is used
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateFallback {
+    FirstOfMonth,
+    Midnight,
+    NextMethod,
+}
+
+impl fmt::Display for DateFallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            DateFallback::FirstOfMonth => "dated the first of the month",
+            DateFallback::Midnight => "dated midnight of that day",
+            DateFallback::NextMethod => "dated by sidecar, filename or mtime instead",
+        })
+    }
+}
+
+/// Returns the raw EXIF `DateTimeOriginal` of a photo and the [`DateFallback`]
+/// [`extract_exif_datetime`] applied to it, or `None` if it was used as recorded
+/// (or there is none).
+pub fn exif_date_fallback<P: AsRef<Path>>(path: P) -> Option<(String, DateFallback)> {
+    let timestamp = read_exif_timestamp(path.as_ref())?;
+    Some((timestamp.recorded, timestamp.fallback?))
+}
+
+/// A `DateTimeOriginal` as recorded, and the capture time read from it.
+struct ExifTimestamp {
+    recorded: String,
+    taken: Option<NaiveDateTime>,
+    offset: Option<FixedOffset>,
+    fallback: Option<DateFallback>,
+}
+
+/// Reads `DateTimeOriginal` and its refinements (see [`extract_exif_datetime`]).
+///
+/// Returns `None` if the photo has no parseable `DateTimeOriginal` at all.
+fn read_exif_timestamp(path: &Path) -> Option<ExifTimestamp> {
+    let exif = read_exif(path)?;
+    let ascii = |tag| match &exif.get_field(tag, In::PRIMARY)?.value {
+        Value::Ascii(values) => values.first().map(Vec::as_slice),
+        _ => None,
+    };
+
+    let recorded = ascii(Tag::DateTimeOriginal)?;
+    let mut dt = parse_exif_datetime(recorded)?;
+    // A malformed refinement is ignored rather than discarding the whole time
+    if let Some(subsec) = ascii(Tag::SubSecTimeOriginal) {
+        let _ = dt.parse_subsec(subsec);
+    }
+    if let Some(offset) = ascii(Tag::OffsetTimeOriginal) {
+        let _ = dt.parse_offset(offset);
+    }
+
+    let (taken, fallback) = exif_components(&dt);
+    Some(ExifTimestamp {
+        recorded: String::from_utf8_lossy(recorded).into_owned(),
+        taken,
+        offset: dt.offset.and_then(|minutes| FixedOffset::east_opt(i32::from(minutes) * 60)),
+        fallback,
+    })
+}
+
+/// Parses an EXIF date and time.
+///
+/// The canonical `YYYY:MM:DD HH:MM:SS` goes through the `exif` crate. Other
+/// separators some cameras write (`2019-03-15`, `2019/03/15 10.20.30`) are read
+/// by splitting on non-digits: a four-digit year, then month and day, then
+/// optionally hours and minutes, and seconds. Component ranges are checked
+/// later, by [`exif_components`].
+fn parse_exif_datetime(text: &[u8]) -> Option<exif::DateTime> {
+    if let Ok(dt) = exif::DateTime::from_ascii(text) {
+        return Some(dt);
+    }
+
+    let text = std::str::from_utf8(text).ok()?.trim();
+    let parts: Vec<&str> = text.split(|c: char| !c.is_ascii_digit()).filter(|part| !part.is_empty()).collect();
+    let separators_ok = text
+        .chars()
+        .filter(|c| !c.is_ascii_digit())
+        .all(|c| matches!(c, ':' | '-' | '/' | '.' | ' ' | 'T'));
+    if !separators_ok || !matches!(parts.len(), 3 | 5 | 6) {
+        return None;
+    }
+    if parts[0].len() != 4 || parts[1..].iter().any(|part| part.len() > 2) {
+        return None;
+    }
+    let number = |index: usize| parts.get(index).map_or(Some(0), |part| part.parse().ok());
+    Some(exif::DateTime {
+        year: parts[0].parse().ok()?,
+        month: number(1)?,
+        day: number(2)?,
+        hour: number(3)?,
+        minute: number(4)?,
+        second: number(5)?,
+        nanosecond: None,
+        offset: None,
+    })
+}
+
+/// Validates each component of an EXIF date and time, completing what can be
+/// completed (see [`DateFallback`]).
+fn exif_components(dt: &exif::DateTime) -> (Option<NaiveDateTime>, Option<DateFallback>) {
+    if dt.year == 0 || !(1..=12).contains(&dt.month) {
+        return (None, Some(DateFallback::NextMethod));
+    }
+    let mut fallback = None;
+    let day = if dt.day == 0 {
+        fallback = Some(DateFallback::FirstOfMonth);
+        1
+    } else {
+        dt.day
+    };
+    let Some(date) = NaiveDate::from_ymd_opt(dt.year.into(), dt.month.into(), day.into()) else {
+        return (None, Some(DateFallback::NextMethod));
+    };
+    let time = NaiveTime::from_hms_nano_opt(
+        dt.hour.into(),
+        dt.minute.into(),
+        dt.second.into(),
+        dt.nanosecond.unwrap_or(0),
+    );
+    let time = time.unwrap_or_else(|| {
+        fallback.get_or_insert(DateFallback::Midnight);
+        NaiveTime::MIN
+    });
+    (Some(date.and_time(time)), fallback)
+}
+
+/// Reads the EXIF data of a photo.
+///
+/// Falls back to [`heif_exif_payload`] for `.heic`/`.heif` files, since
+/// `read_from_container` only accepts HEIF files declaring the `mif1` brand
+/// and caps the EXIF item at 64 KiB.
+fn read_exif(path: &Path) -> Option<Exif> {
     let file = fs::File::open(path).ok()?;
-    let mut reader = io::BufReader::new(file);
-    let exifreader = exif::Reader::new();
-    let exif = exifreader.read_from_container(&mut reader).ok()?;
-
-    if let Some(field) = exif.get_field(Tag::DateTimeOriginal, In::PRIMARY) {
-        let value = format!("{}", field.display_value());
-        // EXIF date format is usually "YYYY:MM:DD HH:MM:SS"
-        if value.len() >= 10 {
-            let year = value[0..4].parse::<i32>().ok()?;
-            let month = value[5..7].parse::<u32>().ok()?;
-            let day = value[8..10].parse::<u32>().ok()?;
-            return NaiveDate::from_ymd_opt(year, month, day);
-        }
+    let parsed = exif::Reader::new().read_from_container(&mut io::BufReader::new(file));
+    match parsed {
+        Ok(exif) => Some(exif),
+        Err(_) if is_heif(path) => exif::Reader::new().read_raw(heif_exif_payload(path)?).ok(),
+        Err(_) => None,
     }
-    None
 }
 
 /// Extracts the date taken from a photo file.
@@ -124,10 +305,58 @@ pub fn extract_date_safe<P: AsRef<Path>>(path: P) -> Option<NaiveDate> {
     extract_date(path).ok()
 }
 
+/// Inclusive range of capture dates to process (`--since` / `--until`).
+///
+/// Files without a date are outside any bounded range unless
+/// `include_undated` is set. The default range is unbounded and admits every file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DateRange {
+    /// First date to include
+    pub since: Option<NaiveDate>,
+    /// Last date to include
+    pub until: Option<NaiveDate>,
+    /// Admit files without a date while the range is bounded
+    pub include_undated: bool,
+}
+
+impl DateRange {
+    /// Returns `true` if neither bound is set, so no file needs dating to pass.
+    pub fn is_unbounded(&self) -> bool {
+        self.since.is_none() && self.until.is_none()
+    }
+
+    /// Returns `true` if a file with this date falls within the range.
+    pub fn admits(&self, date: Option<NaiveDate>) -> bool {
+        match date {
+            Some(date) => self.since.is_none_or(|since| date >= since) && self.until.is_none_or(|until| date <= until),
+            None => self.is_unbounded() || self.include_undated,
+        }
+    }
+}
+
+/// How finely photos are split into date folders.
+///
+/// # Variants
+///
+/// * `Year` - One folder per year (`2023`)
+/// * `Month` - One folder per month (`2023/10`)
+/// * `Day` - One folder per day (`2023/10/15`, default)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum Granularity {
+    /// `YYYY`
+    Year,
+    /// `YYYY/MM`
+    Month,
+    /// `YYYY/MM/DD`
+    #[default]
+    Day,
+}
+
 /// Builds a chronological folder path from a date.
 ///
 /// Creates a path string in the format `YYYY/MM/DD` suitable for organizing
-/// files into date-based directory structures.
+/// files into date-based directory structures. See
+/// [`build_chronological_path_with`] for coarser folders.
 ///
 /// # Arguments
 ///
@@ -147,12 +376,30 @@ pub fn extract_date_safe<P: AsRef<Path>>(path: P) -> Option<NaiveDate> {
 /// assert_eq!(path, "2023/10/15");
 /// ```
 pub fn build_chronological_path(date: NaiveDate) -> String {
-    format!(
-        "{}/{:02}/{:02}",
-        date.year(),
-        date.month(),
-        date.day()
-    )
+    build_chronological_path_with(date, Granularity::Day)
+}
+
+/// Builds a chronological folder path down to the given granularity.
+///
+/// # Examples
+///
+/// ```
+/// # use sift::metadata::{self, Granularity};
+/// # use chrono::NaiveDate;
+/// let date = NaiveDate::from_ymd_opt(2023, 10, 15).unwrap();
+/// assert_eq!(metadata::build_chronological_path_with(date, Granularity::Month), "2023/10");
+/// ```
+pub fn build_chronological_path_with(date: NaiveDate, granularity: Granularity) -> String {
+    match granularity {
+        Granularity::Year => format!("{}", date.year()),
+        Granularity::Month => format!("{}/{:02}", date.year(), date.month()),
+        Granularity::Day => format!(
+            "{}/{:02}/{:02}",
+            date.year(),
+            date.month(),
+            date.day()
+        ),
+    }
 }
 
 /// Extracts the date from a filename using YYYYMMDD pattern.
@@ -195,8 +442,9 @@ pub fn extract_date_from_filename(filename: &str) -> Option<NaiveDate> {
 ///
 /// Attempts to extract the date from a photo file using the following priority:
 /// 1. EXIF metadata (DateTimeOriginal)
-/// 2. Filename pattern (YYYYMMDD format)
-/// 3. File modification time (mtime)
+/// 2. XMP sidecar (`exif:DateTimeOriginal`), see [`extract_xmp_metadata`]
+/// 3. Filename pattern (YYYYMMDD format)
+/// 4. File modification time (mtime)
 ///
 /// This function provides a best-effort approach to finding the most accurate
 /// capture date for a photo file.
@@ -211,12 +459,390 @@ pub fn extract_date_from_filename(filename: &str) -> Option<NaiveDate> {
 /// * `None` - If the date cannot be extracted by any method
 pub fn extract_date_with_fallback<P: AsRef<Path>>(path: P) -> Option<NaiveDate> {
     let path_ref = path.as_ref();
+    if is_video(path_ref) {
+        return extract_video_date(path_ref);
+    }
 
-    // 1. Try EXIF
-    if let Some(date) = extract_exif_date(path_ref) {
-        return Some(date);
+    // 1. Try EXIF, then an XMP sidecar
+    if let Some(taken) = photo_datetime(path_ref) {
+        return Some(taken.date());
     }
 
+    fallback_date(path_ref)
+}
+
+/// Extracts the date a photo or video records about itself, without falling back
+/// to the file modification time.
+///
+/// Uses EXIF `DateTimeOriginal` (or an XMP sidecar's) for photos or the `mvhd`
+/// creation time for videos, then the filename pattern. Files for which this
+/// returns `None` are only dated by their mtime in
+/// [`extract_date_with_fallback`], which copying and syncing tools often reset.
+pub fn extract_recorded_date<P: AsRef<Path>>(path: P) -> Option<NaiveDate> {
+    let path_ref = path.as_ref();
+    let embedded = if is_video(path_ref) {
+        video_creation_time(path_ref).map(|created| created.and_utc().with_timezone(&Local).date_naive())
+    } else {
+        photo_datetime(path_ref).map(|taken| taken.date())
+    };
+    embedded.or_else(|| extract_date_from_filename(path_ref.file_name()?.to_str()?))
+}
+
+/// Extracts the capture date and time of a photo or video.
+///
+/// Uses [`extract_exif_datetime`] (or an XMP sidecar) for photos and the `mvhd`
+/// creation time for videos, falling back to the file modification time.
+/// Filenames only carry a date, so they are not consulted: a midnight timestamp
+/// would distort the gaps used by time-based clustering.
+///
+/// Unlike the dates photos are filed under, photo times that record an offset
+/// are converted to the local time zone, as video creation times are, so photos
+/// and videos shot in different zones sort in the order they were taken.
+pub fn extract_capture_datetime<P: AsRef<Path>>(path: P) -> Option<NaiveDateTime> {
+    let path_ref = path.as_ref();
+    let captured = if is_video(path_ref) {
+        video_creation_time(path_ref).map(|utc| utc.and_utc().with_timezone(&Local).naive_local())
+    } else {
+        photo_instant(path_ref)
+    };
+    captured.or_else(|| {
+        let modified = fs::metadata(path_ref).ok()?.modified().ok()?;
+        Some(DateTime::<Local>::from(modified).naive_local())
+    })
+}
+
+/// Capture time of a photo from its EXIF data, then from its XMP sidecar.
+fn photo_datetime(path: &Path) -> Option<NaiveDateTime> {
+    extract_exif_datetime(path).or_else(|| extract_xmp_metadata(path)?.taken)
+}
+
+/// Like [`photo_datetime`], but converted to the local time zone when the
+/// time records its offset.
+fn photo_instant(path: &Path) -> Option<NaiveDateTime> {
+    let (taken, offset) = match read_exif_timestamp(path).and_then(|exif| Some((exif.taken?, exif.offset))) {
+        Some(exif) => exif,
+        None => {
+            let xmp = extract_xmp_metadata(path)?;
+            (xmp.taken?, xmp.offset)
+        }
+    };
+    match offset {
+        Some(offset) => Some(taken.and_local_timezone(offset).single()?.with_timezone(&Local).naive_local()),
+        None => Some(taken),
+    }
+}
+
+/// Extracts the recording date of a QuickTime/MP4 video.
+///
+/// Uses the creation time of the movie header (`moov/mvhd`), then falls back to
+/// the filename pattern and the file modification time like
+/// [`extract_date_with_fallback`]. The creation time is stored in UTC and is
+/// converted to the local date.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use sift::metadata;
+/// if let Some(date) = metadata::extract_video_date("VID_0001.mp4") {
+///     println!("Recorded on: {}", date);
+/// }
+/// ```
+pub fn extract_video_date<P: AsRef<Path>>(path: P) -> Option<NaiveDate> {
+    let path_ref = path.as_ref();
+    if let Some(created) = video_creation_time(path_ref) {
+        return Some(created.and_utc().with_timezone(&Local).date_naive());
+    }
+    fallback_date(path_ref)
+}
+
+/// Returns `true` if the path has one of the [`VIDEO_EXTENSIONS`] (case-insensitive).
+pub fn is_video(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| VIDEO_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+/// Image container formats recognized by [`detect_image_kind`].
+///
+/// Most RAW formats (CR2, NEF, ARW, DNG) are TIFF-based and detected as `Tiff`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageKind {
+    /// JPEG, starting with an SOI marker
+    Jpeg,
+    /// PNG, starting with the PNG signature
+    Png,
+    /// HEIF/HEIC, an ISO media file with an image `ftyp` brand
+    Heic,
+    /// TIFF in either byte order, including TIFF-based RAW files
+    Tiff,
+}
+
+/// `ftyp` brands of HEIF images (still images and sequences).
+const HEIF_BRANDS: &[&[u8; 4]] = &[b"heic", b"heix", b"hevc", b"hevx", b"heim", b"heis", b"mif1", b"msf1"];
+
+/// Identifies an image by its leading bytes, whatever its extension.
+///
+/// Returns `None` for other content, and for files that cannot be read.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use sift::metadata::{self, ImageKind};
+/// if metadata::detect_image_kind("IMG_0001.jpg") != Some(ImageKind::Jpeg) {
+///     println!("not really a JPEG");
+/// }
+/// ```
+pub fn detect_image_kind<P: AsRef<Path>>(path: P) -> Option<ImageKind> {
+    let mut head = Vec::with_capacity(12);
+    fs::File::open(path).ok()?.take(12).read_to_end(&mut head).ok()?;
+    image_kind_of(&head)
+}
+
+/// Matches the magic bytes at the start of `head`.
+fn image_kind_of(head: &[u8]) -> Option<ImageKind> {
+    if head.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some(ImageKind::Jpeg)
+    } else if head.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some(ImageKind::Png)
+    } else if head.starts_with(b"II*\0") || head.starts_with(b"MM\0*") {
+        Some(ImageKind::Tiff)
+    } else if head.len() >= 12 && &head[4..8] == b"ftyp" && HEIF_BRANDS.iter().any(|brand| head[8..12] == brand[..]) {
+        Some(ImageKind::Heic)
+    } else {
+        None
+    }
+}
+
+/// Reads the `mvhd` creation time (UTC) of a QuickTime/MP4 file.
+///
+/// Top-level atoms are skipped by seeking, so a `moov` atom placed after a large
+/// `mdat` costs no extra reads. A zero (unset) creation time yields `None`.
+fn video_creation_time(path: &Path) -> Option<NaiveDateTime> {
+    let mut file = fs::File::open(path).ok()?;
+    let file_len = file.metadata().ok()?.len();
+    let (moov_start, moov_end) = find_atom(&mut file, 0, file_len, b"moov")?;
+    let (mvhd_start, _) = find_atom(&mut file, moov_start, moov_end, b"mvhd")?;
+
+    file.seek(SeekFrom::Start(mvhd_start)).ok()?;
+    let mut version = [0u8; 4];
+    file.read_exact(&mut version).ok()?;
+    let seconds = if version[0] == 1 {
+        let mut buf = [0u8; 8];
+        file.read_exact(&mut buf).ok()?;
+        u64::from_be_bytes(buf)
+    } else {
+        let mut buf = [0u8; 4];
+        file.read_exact(&mut buf).ok()?;
+        u32::from_be_bytes(buf) as u64
+    };
+    if seconds == 0 {
+        return None;
+    }
+
+    let unix = i64::try_from(seconds).ok()? - QUICKTIME_EPOCH_OFFSET;
+    DateTime::from_timestamp(unix, 0).map(|dt| dt.naive_utc())
+}
+
+/// Finds an atom of type `name` among the atoms between `start` and `end`.
+///
+/// Returns the byte range of its payload (after the header). Sizes come from the
+/// file and are untrusted: an atom smaller than its header or reaching past `end`
+/// ends the search.
+fn find_atom(file: &mut fs::File, start: u64, end: u64, name: &[u8; 4]) -> Option<(u64, u64)> {
+    let mut pos = start;
+    while pos.checked_add(8).is_some_and(|header_end| header_end <= end) {
+        file.seek(SeekFrom::Start(pos)).ok()?;
+        let mut header = [0u8; 8];
+        file.read_exact(&mut header).ok()?;
+        let mut size = u32::from_be_bytes(header[0..4].try_into().unwrap()) as u64;
+        let mut header_len = 8;
+        if size == 1 {
+            let mut large = [0u8; 8];
+            file.read_exact(&mut large).ok()?;
+            size = u64::from_be_bytes(large);
+            header_len = 16;
+        } else if size == 0 {
+            // Extends to the end of the enclosing atom
+            size = end - pos;
+        }
+        if size < header_len {
+            return None;
+        }
+        let atom_end = pos.checked_add(size).filter(|&atom_end| atom_end <= end)?;
+        if &header[4..8] == name {
+            return Some((pos + header_len, atom_end));
+        }
+        pos = atom_end;
+    }
+    None
+}
+
+/// Returns `true` if the path has one of the [`HEIF_EXTENSIONS`] (case-insensitive).
+fn is_heif(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| HEIF_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+/// Reads the TIFF-formatted EXIF block stored as the `Exif` item of a HEIF file.
+///
+/// The item is looked up by type in `meta/iinf`, located through `meta/iloc`
+/// (file offsets only, possibly split over several extents), and stripped of
+/// the header-offset prefix and `Exif\0\0` marker that precede the TIFF data.
+fn heif_exif_payload(path: &Path) -> Option<Vec<u8>> {
+    let mut file = fs::File::open(path).ok()?;
+    let file_len = file.metadata().ok()?.len();
+    let (meta_start, meta_end) = find_atom(&mut file, 0, file_len, b"meta")?;
+    if meta_end - meta_start > HEIF_META_LIMIT {
+        return None;
+    }
+    let mut meta = vec![0u8; (meta_end - meta_start) as usize];
+    file.seek(SeekFrom::Start(meta_start)).ok()?;
+    file.read_exact(&mut meta).ok()?;
+
+    // `meta` is a full box: version and flags precede its children
+    let children = meta.get(4..)?;
+    let item_id = heif_exif_item_id(find_box(children, b"iinf")?)?;
+    let extents = heif_item_extents(find_box(children, b"iloc")?, item_id)?;
+
+    let mut item = Vec::new();
+    for (offset, length) in extents {
+        if item.len() as u64 + length > HEIF_META_LIMIT {
+            return None;
+        }
+        file.seek(SeekFrom::Start(offset)).ok()?;
+        let start = item.len();
+        item.resize(start + length as usize, 0);
+        file.read_exact(&mut item[start..]).ok()?;
+    }
+
+    let tiff_offset = u32::from_be_bytes(item.get(0..4)?.try_into().ok()?) as usize;
+    item.get(4 + tiff_offset..).map(<[u8]>::to_vec)
+}
+
+/// Finds the ID of the item of type `Exif` in an `iinf` box payload.
+fn heif_exif_item_id(iinf: &[u8]) -> Option<u32> {
+    let mut reader = BoxReader::new(iinf);
+    let version = reader.uint(1)?;
+    reader.uint(3)?;
+    reader.uint(if version == 0 { 2 } else { 4 })?;
+    let mut entries = reader.rest();
+    while let Some((name, infe, rest)) = next_box(entries) {
+        entries = rest;
+        if name != b"infe" {
+            continue;
+        }
+        let mut reader = BoxReader::new(infe);
+        let version = reader.uint(1)?;
+        reader.uint(3)?;
+        // Versions 0 and 1 predate item types
+        if version < 2 {
+            continue;
+        }
+        let item_id = reader.uint(if version == 2 { 2 } else { 4 })?;
+        reader.uint(2)?;
+        if reader.bytes(4)? == b"Exif" {
+            return Some(item_id as u32);
+        }
+    }
+    None
+}
+
+/// Lists the `(offset, length)` extents of `item_id` from an `iloc` box payload.
+///
+/// Returns `None` for items stored inside the `idat` box or another item
+/// (construction methods 1 and 2).
+fn heif_item_extents(iloc: &[u8], item_id: u32) -> Option<Vec<(u64, u64)>> {
+    let mut reader = BoxReader::new(iloc);
+    let version = reader.uint(1)?;
+    reader.uint(3)?;
+    let sizes = reader.uint(1)?;
+    let (offset_size, length_size) = ((sizes >> 4) as usize, (sizes & 0xF) as usize);
+    let sizes = reader.uint(1)?;
+    let base_offset_size = (sizes >> 4) as usize;
+    let index_size = if version == 0 { 0 } else { (sizes & 0xF) as usize };
+    let id_size = if version < 2 { 2 } else { 4 };
+    let item_count = reader.uint(id_size)?;
+
+    for _ in 0..item_count {
+        let id = reader.uint(id_size)?;
+        let construction_method = if version == 0 { 0 } else { reader.uint(2)? & 0xF };
+        reader.uint(2)?;
+        let base_offset = reader.uint(base_offset_size)?;
+        let extent_count = reader.uint(2)?;
+        let mut extents = Vec::new();
+        for _ in 0..extent_count {
+            reader.uint(index_size)?;
+            let offset = reader.uint(offset_size)?;
+            let length = reader.uint(length_size)?;
+            extents.push((base_offset.checked_add(offset)?, length));
+        }
+        if id == item_id as u64 {
+            return (construction_method == 0).then_some(extents);
+        }
+    }
+    None
+}
+
+/// Finds the payload of the first box of type `name` in `data`.
+fn find_box<'a>(mut data: &'a [u8], name: &[u8; 4]) -> Option<&'a [u8]> {
+    while let Some((box_name, payload, rest)) = next_box(data) {
+        if box_name == name {
+            return Some(payload);
+        }
+        data = rest;
+    }
+    None
+}
+
+/// Splits the first box off `data`: its type, its payload and the boxes after it.
+fn next_box(data: &[u8]) -> Option<(&[u8], &[u8], &[u8])> {
+    let mut reader = BoxReader::new(data);
+    let size = reader.uint(4)?;
+    let name = reader.bytes(4)?;
+    let (size, header_len) = match size {
+        0 => (data.len() as u64, 8),
+        1 => (reader.uint(8)?, 16),
+        size => (size, 8),
+    };
+    if size < header_len || size > data.len() as u64 {
+        return None;
+    }
+    let (this, rest) = data.split_at(size as usize);
+    Some((name, &this[header_len as usize..], rest))
+}
+
+/// Reads big-endian fields from a box payload.
+struct BoxReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> BoxReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    fn bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        let (head, rest) = self.data.split_at_checked(len)?;
+        self.data = rest;
+        Some(head)
+    }
+
+    /// Reads an unsigned integer of `len` bytes (0 to 8); zero-sized fields read as 0.
+    fn uint(&mut self, len: usize) -> Option<u64> {
+        if len > 8 {
+            return None;
+        }
+        Some(self.bytes(len)?.iter().fold(0, |value, &byte| value << 8 | byte as u64))
+    }
+
+    fn rest(self) -> &'a [u8] {
+        self.data
+    }
+}
+
+/// Dates a file from its name, then from its modification time.
+fn fallback_date(path_ref: &Path) -> Option<NaiveDate> {
     // 2. Try to extract from filename
     if let Some(filename) = path_ref.file_name()
         && let Some(filename_str) = filename.to_str()
@@ -228,12 +854,338 @@ pub fn extract_date_with_fallback<P: AsRef<Path>>(path: P) -> Option<NaiveDate>
     extract_date_safe(path_ref)
 }
 
+/// A GPS position recorded in a photo's EXIF data.
+///
+/// # Fields
+///
+/// * `latitude` - Latitude in decimal degrees, negative for south
+/// * `longitude` - Longitude in decimal degrees, negative for west
+/// * `horizontal_error_m` - `GPSHPositioningError` in meters, when the camera recorded it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GpsFix {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub horizontal_error_m: Option<f64>,
+}
+
+/// Extracts the GPS position from a photo file's EXIF data, or from its XMP
+/// sidecar when the photo itself records none.
+///
+/// # Arguments
+///
+/// * `path` - Path to the photo file
+///
+/// # Returns
+///
+/// * `Some(GpsFix)` - If both latitude and longitude (with their N/S, E/W refs) are present
+/// * `None` - If neither the file nor its sidecar has a complete GPS position
+pub fn extract_gps<P: AsRef<Path>>(path: P) -> Option<GpsFix> {
+    let path = path.as_ref();
+    read_exif(path)
+        .and_then(|exif| exif_gps(&exif))
+        .or_else(|| extract_xmp_metadata(path)?.gps)
+}
+
+fn exif_gps(exif: &Exif) -> Option<GpsFix> {
+    let latitude = gps_coordinate(exif, Tag::GPSLatitude, Tag::GPSLatitudeRef, b'S')?;
+    let longitude = gps_coordinate(exif, Tag::GPSLongitude, Tag::GPSLongitudeRef, b'W')?;
+    let horizontal_error_m = exif
+        .get_field(Tag::GPSHPositioningError, In::PRIMARY)
+        .and_then(|field| match &field.value {
+            Value::Rational(v) if !v.is_empty() && v[0].denom != 0 => Some(v[0].to_f64()),
+            _ => None,
+        });
+
+    Some(GpsFix {
+        latitude,
+        longitude,
+        horizontal_error_m,
+    })
+}
+
+/// Reads a degrees/minutes/seconds GPS field as signed decimal degrees.
+fn gps_coordinate(exif: &Exif, tag: Tag, ref_tag: Tag, negative_ref: u8) -> Option<f64> {
+    let dms = match &exif.get_field(tag, In::PRIMARY)?.value {
+        Value::Rational(v) if v.len() >= 3 && v.iter().all(|r| r.denom != 0) => v.clone(),
+        _ => return None,
+    };
+    let degrees = dms[0].to_f64() + dms[1].to_f64() / 60.0 + dms[2].to_f64() / 3600.0;
+
+    match &exif.get_field(ref_tag, In::PRIMARY)?.value {
+        Value::Ascii(v) if v.first().and_then(|r| r.first()) == Some(&negative_ref) => {
+            Some(-degrees)
+        }
+        Value::Ascii(_) => Some(degrees),
+        _ => None,
+    }
+}
+
+/// Extracts the camera that took a photo from EXIF `Make` and `Model`.
+///
+/// Most cameras already repeat the make in the model ("Canon EOS R5"); when
+/// they don't, the two are joined ("SONY" + "ILCE-7M3" gives "SONY ILCE-7M3").
+/// Either tag alone is used as-is. Surrounding whitespace and NUL padding,
+/// which some cameras write, are trimmed.
+///
+/// # Returns
+///
+/// * `Some(String)` - The camera name
+/// * `None` - If the file has no EXIF data or neither tag is set
+pub fn extract_camera<P: AsRef<Path>>(path: P) -> Option<String> {
+    let exif = read_exif(path.as_ref())?;
+    let text = |tag| match &exif.get_field(tag, In::PRIMARY)?.value {
+        Value::Ascii(values) => {
+            let text = String::from_utf8_lossy(values.first()?);
+            let text = text.trim_matches(|c: char| c.is_whitespace() || c == '\0');
+            (!text.is_empty()).then(|| text.to_string())
+        }
+        _ => None,
+    };
+
+    match (text(Tag::Make), text(Tag::Model)) {
+        (Some(make), Some(model)) => {
+            let brand = make.split_whitespace().next().unwrap_or(&make).to_lowercase();
+            if model.to_lowercase().starts_with(&brand) {
+                Some(model)
+            } else {
+                Some(format!("{} {}", make, model))
+            }
+        }
+        (make, model) => model.or(make),
+    }
+}
+
+/// Extracts the star rating (0-5) a culling tool assigned to a photo.
+///
+/// Sources are tried in order: an XMP sidecar next to the file (see
+/// [`extract_xmp_metadata`]),
+/// an XMP packet embedded in the file (`xmp:Rating`), then the EXIF `Rating` tag.
+/// A "rejected" rating (-1) is reported as 0; ratings above 5 are clamped.
+///
+/// # Arguments
+///
+/// * `path` - Path to the photo file
+///
+/// # Returns
+///
+/// * `Some(u8)` - The rating, from 0 to 5
+/// * `None` - If the photo carries no rating
+pub fn extract_rating<P: AsRef<Path>>(path: P) -> Option<u8> {
+    let path = path.as_ref();
+    let rating = read_sidecar(path)
+        .and_then(|xmp| parse_xmp_rating(&xmp))
+        .or_else(|| embedded_xmp_rating(path))
+        .or_else(|| exif_rating(path))?;
+    Some(rating.clamp(0, 5) as u8)
+}
+
+fn embedded_xmp_rating(path: &Path) -> Option<i64> {
+    let mut head = Vec::new();
+    fs::File::open(path)
+        .ok()?
+        .take(XMP_SCAN_LIMIT)
+        .read_to_end(&mut head)
+        .ok()?;
+    parse_xmp_rating(&String::from_utf8_lossy(&head))
+}
+
+fn exif_rating(path: &Path) -> Option<i64> {
+    let exif = read_exif(path)?;
+    let field = exif.get_field(EXIF_RATING, In::PRIMARY)?;
+    field.value.get_uint(0).map(i64::from)
+}
+
+/// Finds `xmp:Rating` in an XMP packet.
+fn parse_xmp_rating(xmp: &str) -> Option<i64> {
+    let value = xmp_property(xmp, "xmp:Rating")?.trim();
+    let end = value
+        .char_indices()
+        .find(|&(i, c)| !(c.is_ascii_digit() || (i == 0 && c == '-')))
+        .map_or(value.len(), |(i, _)| i);
+    value[..end].parse().ok()
+}
+
+/// Date and GPS position recorded in an XMP sidecar.
+///
+/// # Fields
+///
+/// * `taken` - `exif:DateTimeOriginal`, as the camera's clock read it
+/// * `offset` - The UTC offset `taken` was recorded with, if any
+/// * `gps` - `exif:GPSLatitude` and `exif:GPSLongitude`, when both are present
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct XmpMetadata {
+    pub taken: Option<NaiveDateTime>,
+    pub offset: Option<FixedOffset>,
+    pub gps: Option<GpsFix>,
+}
+
+/// Reads the XMP sidecar of a photo, as written by Lightroom or darktable.
+///
+/// The sidecar is looked up as `<path>.xmp` (`IMG_0001.CR2.xmp`), then as
+/// `<stem>.xmp` (`IMG_0001.xmp`).
+///
+/// # Returns
+///
+/// * `Some(XmpMetadata)` - If a sidecar exists, even if it records neither date nor GPS
+/// * `None` - If the photo has no readable sidecar
+pub fn extract_xmp_metadata<P: AsRef<Path>>(path: P) -> Option<XmpMetadata> {
+    let xmp = read_sidecar(path.as_ref())?;
+    Some(parse_xmp_metadata(&xmp))
+}
+
+fn read_sidecar(path: &Path) -> Option<String> {
+    let mut appended = path.as_os_str().to_os_string();
+    appended.push(".xmp");
+    fs::read_to_string(appended)
+        .or_else(|_| fs::read_to_string(path.with_extension("xmp")))
+        .ok()
+}
+
+fn parse_xmp_metadata(xmp: &str) -> XmpMetadata {
+    let date = xmp_property(xmp, "exif:DateTimeOriginal").and_then(parse_xmp_date);
+    let (taken, offset) = (date.map(|(taken, _)| taken), date.and_then(|(_, offset)| offset));
+    let coordinate = |key| xmp_property(xmp, key).and_then(parse_xmp_coordinate);
+    let gps = match (coordinate("exif:GPSLatitude"), coordinate("exif:GPSLongitude")) {
+        (Some(latitude), Some(longitude)) => Some(GpsFix {
+            latitude,
+            longitude,
+            horizontal_error_m: xmp_property(xmp, "exif:GPSHPositioningError").and_then(parse_xmp_rational),
+        }),
+        _ => None,
+    };
+    XmpMetadata { taken, offset, gps }
+}
+
+/// Finds the value of property `key` in an XMP packet, written as an attribute
+/// (`key="value"`) or as an element (`<key>value</key>`).
+fn xmp_property<'a>(xmp: &'a str, key: &str) -> Option<&'a str> {
+    let mut rest = xmp;
+    while let Some(pos) = rest.find(key) {
+        rest = &rest[pos + key.len()..];
+        let value = rest.trim_start();
+        if let Some(attr) = value.strip_prefix('=') {
+            let attr = attr.trim_start();
+            let quote = attr.chars().next().filter(|c| matches!(c, '"' | '\''))?;
+            let attr = &attr[1..];
+            return attr.find(quote).map(|end| &attr[..end]);
+        } else if let Some(element) = value.strip_prefix('>') {
+            return element.find('<').map(|end| &element[..end]);
+        }
+    }
+    None
+}
+
+/// Parses an XMP date (ISO 8601: `2023-06-01T07:30:00.5+09:00`, with optional
+/// seconds, fraction and offset, or a bare date) into its local time and offset.
+fn parse_xmp_date(value: &str) -> Option<(NaiveDateTime, Option<FixedOffset>)> {
+    let value = value.trim();
+    let (local, offset) = if let Some(local) = value.strip_suffix('Z') {
+        (local, Some(0))
+    } else if value.len() > 16 && matches!(value.as_bytes()[value.len() - 6], b'+' | b'-') {
+        let (local, offset) = value.split_at(value.len() - 6);
+        let sign = if offset.starts_with('-') { -1 } else { 1 };
+        let hours: i32 = offset.get(1..3)?.parse().ok()?;
+        let minutes: i32 = offset.get(4..6)?.parse().ok()?;
+        (local, Some(sign * (hours * 3600 + minutes * 60)))
+    } else {
+        (value, None)
+    };
+
+    let taken = NaiveDateTime::parse_from_str(local, "%Y-%m-%dT%H:%M:%S%.f")
+        .or_else(|_| NaiveDateTime::parse_from_str(local, "%Y-%m-%dT%H:%M"))
+        .or_else(|_| NaiveDate::parse_from_str(local, "%Y-%m-%d").map(|date| date.and_time(NaiveTime::MIN)))
+        .ok()?;
+    match offset {
+        Some(seconds) => Some((taken, Some(FixedOffset::east_opt(seconds)?))),
+        None => Some((taken, None)),
+    }
+}
+
+/// Parses an XMP GPS coordinate (`DDD,MM,SSk` or `DDD,MM.mmk`, where `k` is
+/// N, S, E or W) as signed decimal degrees.
+fn parse_xmp_coordinate(value: &str) -> Option<f64> {
+    let value = value.trim();
+    let (number, sign) = match value.chars().last()? {
+        'N' | 'E' => (&value[..value.len() - 1], 1.0),
+        'S' | 'W' => (&value[..value.len() - 1], -1.0),
+        _ => return None,
+    };
+    let mut parts = number.split(',').map(|part| part.trim().parse::<f64>());
+    let degrees = parts.next()?.ok()?;
+    let minutes = parts.next()?.ok()?;
+    let seconds = parts.next().transpose().ok()?.unwrap_or(0.0);
+    if parts.next().is_some() {
+        return None;
+    }
+    Some(sign * (degrees + minutes / 60.0 + seconds / 3600.0))
+}
+
+/// Parses an XMP rational (`num/denom`).
+fn parse_xmp_rational(value: &str) -> Option<f64> {
+    let (num, denom) = value.trim().split_once('/')?;
+    let (num, denom): (f64, f64) = (num.parse().ok()?, denom.parse().ok()?);
+    (denom != 0.0).then(|| num / denom)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testutil;
     use std::io::Write;
     use tempfile::NamedTempFile;
 
+    #[test]
+    fn test_image_kind_from_magic_bytes() {
+        assert_eq!(image_kind_of(&[0xFF, 0xD8, 0xFF, 0xE1, 0, 0]), Some(ImageKind::Jpeg));
+        assert_eq!(image_kind_of(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"), Some(ImageKind::Png));
+        assert_eq!(image_kind_of(b"II*\0\x08\0\0\0"), Some(ImageKind::Tiff));
+        assert_eq!(image_kind_of(b"MM\0*\0\0\0\x08"), Some(ImageKind::Tiff));
+        assert_eq!(image_kind_of(b"\0\0\0\x18ftypheic\0\0\0\0"), Some(ImageKind::Heic));
+        assert_eq!(image_kind_of(b"\0\0\0\x18ftypmif1\0\0\0\0"), Some(ImageKind::Heic));
+        // MP4 video shares the container, not the brand
+        assert_eq!(image_kind_of(b"\0\0\0\x18ftypisom\0\0\0\0"), None);
+        assert_eq!(image_kind_of(b"%PDF-1.7"), None);
+        assert_eq!(image_kind_of(&[0xFF, 0xD8]), None);
+        assert_eq!(image_kind_of(b""), None);
+    }
+
+    #[test]
+    fn test_detect_image_kind_ignores_extension() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let jpeg = testutil::encode_jpeg(None);
+        let named = dir.path().join("photo.jpg");
+        let unnamed = dir.path().join("photo");
+        let misnamed = dir.path().join("scan.jpg");
+        fs::write(&named, &jpeg)?;
+        fs::write(&unnamed, &jpeg)?;
+        fs::write(&misnamed, b"%PDF-1.7 not a photo")?;
+
+        assert_eq!(detect_image_kind(&named), Some(ImageKind::Jpeg));
+        assert_eq!(detect_image_kind(&unnamed), Some(ImageKind::Jpeg));
+        assert_eq!(detect_image_kind(&misnamed), None);
+        assert_eq!(detect_image_kind(dir.path().join("missing.jpg")), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_date_range_bounds_are_inclusive() {
+        let date = |day| NaiveDate::from_ymd_opt(2023, 6, day);
+        let range = DateRange { since: date(10), until: date(20), include_undated: false };
+        assert!(!range.admits(date(9)));
+        assert!(range.admits(date(10)));
+        assert!(range.admits(date(20)));
+        assert!(!range.admits(date(21)));
+
+        // Undated files only pass a bounded range when asked for
+        assert!(!range.admits(None));
+        assert!(DateRange { include_undated: true, ..range }.admits(None));
+        assert!(DateRange::default().admits(None));
+
+        let open_ended = DateRange { since: date(10), ..DateRange::default() };
+        assert!(open_ended.admits(NaiveDate::from_ymd_opt(2030, 1, 1)));
+        assert!(!open_ended.admits(None));
+    }
+
     #[test]
     fn test_build_chronological_path() {
         let date = NaiveDate::from_ymd_opt(2023, 10, 15).unwrap();
@@ -241,6 +1193,15 @@ mod tests {
         assert_eq!(path, "2023/10/15");
     }
 
+    #[test]
+    fn test_build_chronological_path_granularities() {
+        let date = NaiveDate::from_ymd_opt(2023, 10, 5).unwrap();
+        assert_eq!(build_chronological_path_with(date, Granularity::Year), "2023");
+        assert_eq!(build_chronological_path_with(date, Granularity::Month), "2023/10");
+        assert_eq!(build_chronological_path_with(date, Granularity::Day), "2023/10/05");
+        assert_eq!(Granularity::default(), Granularity::Day);
+    }
+
     #[test]
     fn test_build_chronological_path_january() {
         let date = NaiveDate::from_ymd_opt(2024, 1, 5).unwrap();
@@ -399,4 +1360,472 @@ mod tests {
         assert_eq!(date.unwrap(), now);
         Ok(())
     }
+
+    #[test]
+    fn test_extract_recorded_date_ignores_mtime() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let undated = dir.path().join("photo.jpg");
+        fs::write(&undated, "no exif")?;
+        assert_eq!(extract_recorded_date(&undated), None);
+        assert!(extract_date_with_fallback(&undated).is_some());
+
+        let named = dir.path().join("IMG_20200101_0001.jpg");
+        fs::write(&named, "no exif")?;
+        assert_eq!(extract_recorded_date(&named), NaiveDate::from_ymd_opt(2020, 1, 1));
+        Ok(())
+    }
+
+    #[test]
+    fn test_extract_gps_from_jpeg() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("gps.jpg");
+        testutil::write_jpeg_with_gps(&path, -33.8688, 151.2093, Some(12.5))?;
+
+        let fix = extract_gps(&path).expect("GPS should be extracted");
+        assert!((fix.latitude - -33.8688).abs() < 1e-4);
+        assert!((fix.longitude - 151.2093).abs() < 1e-4);
+        assert_eq!(fix.horizontal_error_m, Some(12.5));
+        Ok(())
+    }
+
+    #[test]
+    fn test_extract_gps_missing() -> io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        temp_file.write_all(b"no exif here")?;
+
+        assert!(extract_gps(temp_file.path()).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_extract_camera() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let canon = dir.path().join("canon.jpg");
+        testutil::write_jpeg_with_exif(
+            &canon,
+            &[
+                testutil::ascii_field(Tag::Make, "Canon"),
+                testutil::ascii_field(Tag::Model, "Canon EOS R5 "),
+            ],
+        )?;
+        assert_eq!(extract_camera(&canon).as_deref(), Some("Canon EOS R5"));
+
+        let sony = dir.path().join("sony.jpg");
+        testutil::write_jpeg_with_exif(
+            &sony,
+            &[
+                testutil::ascii_field(Tag::Make, "SONY"),
+                testutil::ascii_field(Tag::Model, "ILCE-7M3"),
+            ],
+        )?;
+        assert_eq!(extract_camera(&sony).as_deref(), Some("SONY ILCE-7M3"));
+
+        let make_only = dir.path().join("make.jpg");
+        testutil::write_jpeg_with_exif(&make_only, &[testutil::ascii_field(Tag::Make, "GoPro")])?;
+        assert_eq!(extract_camera(&make_only).as_deref(), Some("GoPro"));
+
+        let plain = dir.path().join("plain.jpg");
+        fs::write(&plain, "no exif")?;
+        assert!(extract_camera(&plain).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_xmp_rating() {
+        assert_eq!(parse_xmp_rating(r#"<rdf:Description xmp:Rating="4"/>"#), Some(4));
+        assert_eq!(parse_xmp_rating("<xmp:Rating> 3 </xmp:Rating>"), Some(3));
+        assert_eq!(parse_xmp_rating("xmp:Rating='-1'"), Some(-1));
+        assert_eq!(parse_xmp_rating("<xmp:RatingPercent>75</xmp:RatingPercent>"), None);
+        assert_eq!(parse_xmp_rating("no rating"), None);
+    }
+
+    #[test]
+    fn test_extract_rating_from_embedded_xmp() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("rated.jpg");
+        testutil::write_jpeg_with_xmp(&path, r#"<rdf:Description xmp:Rating="5"/>"#)?;
+
+        assert_eq!(extract_rating(&path), Some(5));
+        Ok(())
+    }
+
+    #[test]
+    fn test_extract_rating_from_exif() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("rated.jpg");
+        testutil::write_jpeg_with_exif(
+            &path,
+            &[exif::Field { tag: EXIF_RATING, ifd_num: In::PRIMARY, value: Value::Short(vec![2]) }],
+        )?;
+
+        assert_eq!(extract_rating(&path), Some(2));
+        Ok(())
+    }
+
+    #[test]
+    fn test_extract_rating_prefers_sidecar() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("IMG_0001.cr2");
+        testutil::write_jpeg_with_xmp(&path, r#"xmp:Rating="1""#)?;
+        fs::write(dir.path().join("IMG_0001.xmp"), "<xmp:Rating>4</xmp:Rating>")?;
+
+        assert_eq!(extract_rating(&path), Some(4));
+        Ok(())
+    }
+
+    #[test]
+    fn test_extract_rating_missing_and_rejected() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let unrated = dir.path().join("unrated.jpg");
+        fs::write(&unrated, "plain bytes")?;
+        assert_eq!(extract_rating(&unrated), None);
+
+        let rejected = dir.path().join("rejected.jpg");
+        testutil::write_jpeg_with_xmp(&rejected, r#"xmp:Rating="-1""#)?;
+        assert_eq!(extract_rating(&rejected), Some(0));
+        Ok(())
+    }
+
+    /// A darktable-style sidecar recording a capture time and a GPS position.
+    const SIDECAR_XMP: &str = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+ <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+  <rdf:Description rdf:about=""
+    xmlns:exif="http://ns.adobe.com/exif/1.0/"
+    xmlns:xmp="http://ns.adobe.com/xap/1.0/"
+    exif:DateTimeOriginal="2019-03-10T16:20:05"
+    exif:GPSLatitude="48,51.396N"
+    exif:GPSLongitude="2,21,7.92E"
+    xmp:Rating="3"/>
+ </rdf:RDF>
+</x:xmpmeta>"#;
+
+    #[test]
+    fn test_parse_xmp_metadata() {
+        let xmp = parse_xmp_metadata(SIDECAR_XMP);
+        assert_eq!(xmp.taken, NaiveDate::from_ymd_opt(2019, 3, 10).unwrap().and_hms_opt(16, 20, 5));
+        let gps = xmp.gps.unwrap();
+        assert!((gps.latitude - 48.8566).abs() < 1e-6);
+        assert!((gps.longitude - 2.3522).abs() < 1e-6);
+        assert_eq!(gps.horizontal_error_m, None);
+
+        // Element form, southern/western coordinates, an offset and a positioning error
+        let xmp = parse_xmp_metadata(
+            "<exif:DateTimeOriginal>2020-01-31T23:30:00.5-03:00</exif:DateTimeOriginal>\
+             <exif:GPSLatitude>22,54.5S</exif:GPSLatitude>\
+             <exif:GPSLongitude>43,12.3W</exif:GPSLongitude>\
+             <exif:GPSHPositioningError>15/2</exif:GPSHPositioningError>",
+        );
+        let taken = NaiveDate::from_ymd_opt(2020, 1, 31).unwrap().and_hms_milli_opt(23, 30, 0, 500);
+        assert_eq!(xmp.taken, taken);
+        assert_eq!(xmp.offset, FixedOffset::west_opt(3 * 3600));
+        let gps = xmp.gps.unwrap();
+        assert!((gps.latitude + 22.908333).abs() < 1e-6);
+        assert!((gps.longitude + 43.205).abs() < 1e-6);
+        assert_eq!(gps.horizontal_error_m, Some(7.5));
+
+        // Date-only values, and a latitude without its longitude
+        let xmp = parse_xmp_metadata(r#"exif:DateTimeOriginal="2018-05-04" exif:GPSLatitude="10,0N""#);
+        assert_eq!(xmp.taken, NaiveDate::from_ymd_opt(2018, 5, 4).unwrap().and_hms_opt(0, 0, 0));
+        assert_eq!(xmp.gps, None);
+        assert_eq!(parse_xmp_metadata("<x:xmpmeta/>"), XmpMetadata::default());
+    }
+
+    #[test]
+    fn test_sidecar_dates_and_locates_raw() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        // darktable names sidecars after the full file name
+        let raw = dir.path().join("DSC_0001.NEF");
+        fs::write(&raw, "raw sensor data")?;
+        fs::write(dir.path().join("DSC_0001.NEF.xmp"), SIDECAR_XMP)?;
+        assert_eq!(extract_date_with_fallback(&raw), NaiveDate::from_ymd_opt(2019, 3, 10));
+        assert_eq!(extract_recorded_date(&raw), NaiveDate::from_ymd_opt(2019, 3, 10));
+        assert!(extract_gps(&raw).is_some());
+        assert_eq!(extract_rating(&raw), Some(3));
+
+        // Lightroom replaces the extension; the sidecar ranks below EXIF
+        let jpeg = dir.path().join("IMG_20200102_0001.jpg");
+        testutil::write_jpeg_with_gps(&jpeg, -33.8688, 151.2093, None)?;
+        fs::write(dir.path().join("IMG_20200102_0001.xmp"), SIDECAR_XMP)?;
+        assert_eq!(extract_date_with_fallback(&jpeg), NaiveDate::from_ymd_opt(2019, 3, 10));
+        assert!((extract_gps(&jpeg).unwrap().latitude + 33.8688).abs() < 1e-4);
+
+        let bare = dir.path().join("DSC_0002.NEF");
+        fs::write(&bare, "raw sensor data")?;
+        assert_eq!(extract_xmp_metadata(&bare), None);
+        assert_eq!(extract_gps(&bare), None);
+        Ok(())
+    }
+
+    /// 2021-06-15 12:00:00 UTC, in seconds since 1904-01-01.
+    const JUNE_15_2021_NOON: u64 = 1_623_758_400 + QUICKTIME_EPOCH_OFFSET as u64;
+
+    #[test]
+    fn test_video_creation_time() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let expected = NaiveDate::from_ymd_opt(2021, 6, 15).unwrap().and_hms_opt(12, 0, 0);
+
+        let v0 = dir.path().join("clip.mp4");
+        testutil::write_mp4_with_creation_time(&v0, JUNE_15_2021_NOON, false)?;
+        assert_eq!(video_creation_time(&v0), expected);
+
+        let v1 = dir.path().join("clip.MOV");
+        testutil::write_mp4_with_creation_time(&v1, JUNE_15_2021_NOON, true)?;
+        assert_eq!(video_creation_time(&v1), expected);
+        assert!(is_video(&v1));
+        Ok(())
+    }
+
+    #[test]
+    fn test_find_atom_rejects_malformed_sizes() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("crafted.mp4");
+        let atom = |size: u32, name: &[u8; 4], large: Option<u64>| {
+            let mut data = size.to_be_bytes().to_vec();
+            data.extend_from_slice(name);
+            if let Some(large) = large {
+                data.extend_from_slice(&large.to_be_bytes());
+            }
+            data
+        };
+        for crafted in [
+            // A 64-bit size that overflows the position
+            atom(1, b"free", Some(u64::MAX - 4)),
+            // Sizes smaller than the header itself
+            atom(1, b"free", Some(8)),
+            atom(4, b"free", None),
+            // Past the end of the file
+            atom(64, b"moov", None),
+        ] {
+            // After a valid atom, so the crafted one starts past offset 0
+            let mut data = atom(8, b"free", None);
+            data.extend_from_slice(&crafted);
+            data.extend_from_slice(&atom(16, b"moov", None));
+            data.resize(data.len() + 8, 0);
+            fs::write(&path, &data)?;
+            let mut file = fs::File::open(&path)?;
+            assert_eq!(find_atom(&mut file, 0, data.len() as u64, b"moov"), None);
+            assert_eq!(video_creation_time(&path), None);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_extract_video_date_fallbacks() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+
+        // An unset creation time falls back to the filename
+        let unset = dir.path().join("VID_20200102_0001.mp4");
+        testutil::write_mp4_with_creation_time(&unset, 0, false)?;
+        assert_eq!(extract_video_date(&unset), NaiveDate::from_ymd_opt(2020, 1, 2));
+
+        // Not an MP4 at all: falls back to mtime
+        let garbage = dir.path().join("clip.m4v");
+        fs::write(&garbage, "not a movie")?;
+        assert_eq!(video_creation_time(&garbage), None);
+        assert_eq!(extract_video_date(&garbage), extract_date_safe(&garbage));
+        Ok(())
+    }
+
+    #[test]
+    fn test_extract_capture_datetime() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let photo = dir.path().join("DSC_0001.jpg");
+        testutil::write_jpeg_with_exif(
+            &photo,
+            &[testutil::ascii_field(Tag::DateTimeOriginal, "2019:07:04 18:30:05")],
+        )?;
+        assert_eq!(
+            extract_capture_datetime(&photo),
+            NaiveDate::from_ymd_opt(2019, 7, 4).unwrap().and_hms_opt(18, 30, 5)
+        );
+
+        let plain = dir.path().join("IMG_20200102_0001.jpg");
+        fs::write(&plain, "no exif")?;
+        let mtime: DateTime<Local> = fs::metadata(&plain)?.modified()?.into();
+        assert_eq!(extract_capture_datetime(&plain), Some(mtime.naive_local()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_extract_exif_date_incomplete() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let photo = |name: &str, recorded: &str| -> io::Result<std::path::PathBuf> {
+            let path = dir.path().join(name);
+            testutil::write_jpeg_with_exif(&path, &[testutil::ascii_field(Tag::DateTimeOriginal, recorded)])?;
+            Ok(path)
+        };
+
+        let valid = photo("valid.jpg", "2019:03:15 10:20:30")?;
+        assert_eq!(extract_exif_date(&valid), NaiveDate::from_ymd_opt(2019, 3, 15));
+        assert_eq!(exif_date_fallback(&valid), None);
+
+        // Day zero: the first of the month, keeping the time
+        let day_zero = photo("day_zero.jpg", "2019:03:00 10:20:30")?;
+        assert_eq!(
+            extract_exif_datetime(&day_zero),
+            NaiveDate::from_ymd_opt(2019, 3, 1).unwrap().and_hms_opt(10, 20, 30)
+        );
+        assert_eq!(
+            exif_date_fallback(&day_zero),
+            Some(("2019:03:00 10:20:30".to_string(), DateFallback::FirstOfMonth))
+        );
+
+        // Month zero: no EXIF date, so the filename dates it
+        let month_zero = photo("IMG_20190302_0001.jpg", "2019:00:00 00:00:00")?;
+        assert_eq!(extract_exif_date(&month_zero), None);
+        assert_eq!(exif_date_fallback(&month_zero).map(|(_, fallback)| fallback), Some(DateFallback::NextMethod));
+        assert_eq!(extract_date_with_fallback(&month_zero), NaiveDate::from_ymd_opt(2019, 3, 2));
+
+        // A day the month does not have is not guessed at either
+        let april_31 = photo("april.jpg", "2019:04:31 12:00:00")?;
+        assert_eq!(extract_exif_date(&april_31), None);
+        assert_eq!(exif_date_fallback(&april_31).map(|(_, fallback)| fallback), Some(DateFallback::NextMethod));
+
+        // An impossible time keeps the date
+        let late = photo("late.jpg", "2019:03:15 25:61:00")?;
+        assert_eq!(
+            extract_exif_datetime(&late),
+            NaiveDate::from_ymd_opt(2019, 3, 15).unwrap().and_hms_opt(0, 0, 0)
+        );
+        assert_eq!(exif_date_fallback(&late).map(|(_, fallback)| fallback), Some(DateFallback::Midnight));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_exif_datetime_separators() {
+        let parsed = |text: &str| {
+            parse_exif_datetime(text.as_bytes())
+                .map(|dt| (dt.year, dt.month, dt.day, dt.hour, dt.minute, dt.second))
+        };
+        assert_eq!(parsed("2019:03:15 10:20:30"), Some((2019, 3, 15, 10, 20, 30)));
+        assert_eq!(parsed("2019-03-15 10:20:30"), Some((2019, 3, 15, 10, 20, 30)));
+        assert_eq!(parsed("2019/03/15 10:20:30"), Some((2019, 3, 15, 10, 20, 30)));
+        assert_eq!(parsed("2019.03.15 10.20.30"), Some((2019, 3, 15, 10, 20, 30)));
+        assert_eq!(parsed("2019-03-15T10:20:30"), Some((2019, 3, 15, 10, 20, 30)));
+        assert_eq!(parsed("2019/3/5 10:20"), Some((2019, 3, 5, 10, 20, 0)));
+        assert_eq!(parsed("2019-03-15"), Some((2019, 3, 15, 0, 0, 0)));
+
+        for malformed in [
+            "",
+            "                   ",
+            "15/03/2019 10:20:30",
+            "2019-03",
+            "2019-03-15 10",
+            "2019-003-15",
+            "2019-03-15 10:20:30+02:00",
+            "March 15, 2019",
+            "2019_03_15",
+        ] {
+            assert_eq!(parsed(malformed), None, "{:?}", malformed);
+        }
+    }
+
+    #[test]
+    fn test_extract_exif_date_with_dashes() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let photo = dir.path().join("IMG_0001.jpg");
+        testutil::write_jpeg_with_exif(&photo, &[testutil::ascii_field(Tag::DateTimeOriginal, "2019-03-15 10:20:30")])?;
+        assert_eq!(
+            extract_exif_datetime(&photo),
+            NaiveDate::from_ymd_opt(2019, 3, 15).unwrap().and_hms_opt(10, 20, 30)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_extract_exif_date_from_heic() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let photo = dir.path().join("IMG_0001.HEIC");
+        testutil::write_heic_with_exif(
+            &photo,
+            &[
+                testutil::ascii_field(Tag::DateTimeOriginal, "2021:08:14 07:45:30"),
+                testutil::ascii_field(Tag::Model, "iPhone 12"),
+            ],
+        )?;
+        // The fixture's brands are rejected by the `exif` crate itself
+        let file = fs::File::open(&photo)?;
+        assert!(exif::Reader::new().read_from_container(&mut io::BufReader::new(file)).is_err());
+
+        assert_eq!(extract_exif_date(&photo), NaiveDate::from_ymd_opt(2021, 8, 14));
+        assert_eq!(
+            extract_capture_datetime(&photo),
+            NaiveDate::from_ymd_opt(2021, 8, 14).unwrap().and_hms_opt(7, 45, 30)
+        );
+        assert_eq!(extract_camera(&photo).as_deref(), Some("iPhone 12"));
+
+        // A broken container falls through to the filename
+        let broken = dir.path().join("IMG_20200102_0001.heic");
+        let mut data = fs::read(&photo)?;
+        data.truncate(data.len() / 2);
+        fs::write(&broken, data)?;
+        assert_eq!(extract_exif_date(&broken), None);
+        assert_eq!(extract_date_with_fallback(&broken), NaiveDate::from_ymd_opt(2020, 1, 2));
+        Ok(())
+    }
+
+    #[test]
+    fn test_extract_exif_datetime_subsec_without_offset() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let photo = dir.path().join("DSC_0001.jpg");
+        testutil::write_jpeg_with_exif(
+            &photo,
+            &[
+                testutil::ascii_field(Tag::DateTimeOriginal, "2022:12:31 23:59:59"),
+                testutil::ascii_field(Tag::SubSecTimeOriginal, "25"),
+            ],
+        )?;
+        let expected = NaiveDate::from_ymd_opt(2022, 12, 31).unwrap().and_hms_milli_opt(23, 59, 59, 250);
+        assert_eq!(extract_exif_datetime(&photo), expected);
+        assert_eq!(extract_capture_datetime(&photo), expected);
+        assert_eq!(extract_exif_date(&photo), NaiveDate::from_ymd_opt(2022, 12, 31));
+
+        // A malformed sub-second value keeps the whole seconds
+        testutil::write_jpeg_with_exif(
+            &photo,
+            &[
+                testutil::ascii_field(Tag::DateTimeOriginal, "2022:12:31 23:59:59"),
+                testutil::ascii_field(Tag::SubSecTimeOriginal, "x"),
+            ],
+        )?;
+        assert_eq!(
+            extract_exif_datetime(&photo),
+            NaiveDate::from_ymd_opt(2022, 12, 31).unwrap().and_hms_opt(23, 59, 59)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_extract_exif_datetime_with_offset() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let tokyo = dir.path().join("tokyo.jpg");
+        let paris = dir.path().join("paris.jpg");
+        testutil::write_jpeg_with_exif(
+            &tokyo,
+            &[
+                testutil::ascii_field(Tag::DateTimeOriginal, "2023:06:02 07:30:00"),
+                testutil::ascii_field(Tag::OffsetTimeOriginal, "+09:00"),
+                testutil::ascii_field(Tag::SubSecTimeOriginal, "5"),
+            ],
+        )?;
+        testutil::write_jpeg_with_exif(
+            &paris,
+            &[
+                testutil::ascii_field(Tag::DateTimeOriginal, "2023:06:02 01:15:00"),
+                testutil::ascii_field(Tag::OffsetTimeOriginal, "+02:00"),
+            ],
+        )?;
+
+        // Filed by the camera's clock, whatever the local time zone
+        let tokyo_clock = NaiveDate::from_ymd_opt(2023, 6, 2).unwrap().and_hms_milli_opt(7, 30, 0, 500);
+        assert_eq!(extract_exif_datetime(&tokyo), tokyo_clock);
+        assert_eq!(extract_exif_date(&tokyo), NaiveDate::from_ymd_opt(2023, 6, 2));
+        assert_eq!(extract_exif_date(&paris), NaiveDate::from_ymd_opt(2023, 6, 2));
+
+        // Ordered by the offset: taken 45 minutes later, although its clock read an earlier time
+        let earlier = extract_capture_datetime(&tokyo).unwrap();
+        let later = extract_capture_datetime(&paris).unwrap();
+        assert_eq!(later - earlier, chrono::Duration::milliseconds(44 * 60_000 + 59_500));
+        Ok(())
+    }
 }
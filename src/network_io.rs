@@ -7,8 +7,11 @@
 //! # Features
 //!
 //! * 1MB buffered reads for optimal throughput on network shares
-//! * Exponential backoff retry mechanism for transient failures
-//! * Support for reading specific file chunks
+//! * Exponential backoff retry mechanism for transient failures, for reads, writes
+//!   and copies alike
+//! * Support for reading specific file chunks, or a whole file as parallel chunks
+//! * A counting semaphore ([`OpenLimit`]) capping how many files are open at once
+//! * Page-cache-bypassing reads for benchmarking (Unix only)
 //!
 //! # Examples
 //!
@@ -20,15 +23,79 @@
 //! # Ok::<(), std::io::Error>(())
 //! ```
 
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::{self, BufReader, Read, Seek};
 use std::path::Path;
+use std::sync::{Condvar, Mutex, OnceLock};
 use std::thread;
 use std::time::Duration;
 
+use rayon::prelude::*;
+
 const BUFFER_SIZE: usize = 1_048_576; // 1 MB buffer for network reads
 const MAX_RETRIES: usize = 3;
 const INITIAL_RETRY_DELAY_MS: u64 = 100;
+const BACKOFF_FACTOR: u32 = 2;
+
+/// How failed I/O operations are retried.
+///
+/// The default (3 retries starting at 100ms, doubling each time) suits most
+/// LAN shares; slow or flaky links may want more retries or a longer delay.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryConfig {
+    /// Number of retries after the first attempt; 0 disables retrying
+    pub max_retries: usize,
+    /// Delay before the first retry, in milliseconds
+    pub initial_delay_ms: u64,
+    /// Multiplier applied to the delay after each retry
+    pub backoff_factor: u32,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: MAX_RETRIES,
+            initial_delay_ms: INITIAL_RETRY_DELAY_MS,
+            backoff_factor: BACKOFF_FACTOR,
+        }
+    }
+}
+
+static DEFAULT_RETRY_CONFIG: OnceLock<RetryConfig> = OnceLock::new();
+
+/// Sets the retry configuration used by the functions without a `config`
+/// argument, such as [`copy_file_with_retries`].
+///
+/// Only the first call has an effect; `main` calls it once from the global
+/// `--max-retries`/`--retry-delay-ms` flags before any I/O happens.
+pub fn set_default_retry_config(config: RetryConfig) {
+    let _ = DEFAULT_RETRY_CONFIG.set(config);
+}
+
+/// Returns the process-wide retry configuration.
+pub fn default_retry_config() -> RetryConfig {
+    DEFAULT_RETRY_CONFIG.get().copied().unwrap_or_default()
+}
+
+/// Prints a retry notice, e.g. around a progress bar so it is not torn.
+pub type RetryReporter = Box<dyn Fn(&str) + Send + Sync>;
+
+static RETRY_REPORTER: Mutex<Option<RetryReporter>> = Mutex::new(None);
+
+/// Sets where retry notices go; `None` (the default) prints them to stderr.
+///
+/// Replaces any earlier reporter. The organize stages pass one that prints
+/// around their progress bar, and stays silent under `--quiet` or `--json`.
+pub fn set_retry_reporter(reporter: Option<RetryReporter>) {
+    *RETRY_REPORTER.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = reporter;
+}
+
+fn report_retry(message: &str) {
+    match &*RETRY_REPORTER.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) {
+        Some(reporter) => reporter(message),
+        None => eprintln!("{}", message),
+    }
+}
 
 /// Reads a file with optimized buffering for network shares (SMB/NFS).
 ///
@@ -62,8 +129,8 @@ pub fn buffered_read_file<P: AsRef<Path>>(path: P) -> io::Result<Vec<u8>> {
 
 /// Reads a file with automatic retry logic for transient network errors.
 ///
-/// Uses exponential backoff to retry failed read attempts. After MAX_RETRIES
-/// consecutive failures, the last error is returned to the caller.
+/// Uses exponential backoff to retry failed read attempts. Once the configured
+/// retries are exhausted, the last error is returned to the caller.
 ///
 /// # Arguments
 ///
@@ -81,6 +148,9 @@ pub fn buffered_read_file<P: AsRef<Path>>(path: P) -> io::Result<Vec<u8>> {
 /// * Third retry: 400ms delay
 /// * Gives up after 3 failures
 ///
+/// These are the defaults; `--max-retries` and `--retry-delay-ms` change them
+/// (see [`RetryConfig`]).
+///
 /// # Examples
 ///
 /// ```no_run
@@ -92,47 +162,244 @@ pub fn buffered_read_file<P: AsRef<Path>>(path: P) -> io::Result<Vec<u8>> {
 /// }
 /// ```
 pub fn read_file_with_retries<P: AsRef<Path>>(path: P) -> io::Result<Vec<u8>> {
-    read_with_exponential_backoff(|| buffered_read_file(&path))
+    read_file_with_config(path, &default_retry_config())
+}
+
+/// Reads a file through a 1MB buffer, retrying failures as `config` dictates.
+pub fn buffered_read_file_with_config<P: AsRef<Path>>(
+    path: P,
+    config: &RetryConfig,
+) -> io::Result<Vec<u8>> {
+    retry_with(config, || buffered_read_file(&path))
+}
+
+/// Like [`read_file_with_retries`], with an explicit retry configuration.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use sift::network_io::{self, RetryConfig};
+/// let config = RetryConfig { max_retries: 8, ..RetryConfig::default() };
+/// let data = network_io::read_file_with_config("/mnt/smb/photo.jpg", &config)?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn read_file_with_config<P: AsRef<Path>>(path: P, config: &RetryConfig) -> io::Result<Vec<u8>> {
+    buffered_read_file_with_config(path, config)
+}
+
+/// Writes `data` to `dest`, retrying transient failures with exponential backoff.
+///
+/// Each attempt rewrites the whole file, so a write interrupted halfway never
+/// leaves a truncated file behind once a later attempt succeeds.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use sift::network_io;
+/// network_io::write_file_with_retries("/mnt/smb/notes.txt", b"hello")?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn write_file_with_retries<P: AsRef<Path>>(dest: P, data: &[u8]) -> io::Result<()> {
+    retry(|| fs::write(&dest, data))
+}
+
+/// Copies `src` to `dest`, retrying transient failures with exponential backoff.
+///
+/// Used by organize for every copy, so a brief SMB/NFS hiccup costs a retry
+/// instead of failing the file.
+///
+/// # Returns
+///
+/// * `Ok(u64)` - The number of bytes copied
+/// * `Err(io::Error)` - The last error, once all attempts failed
+pub fn copy_file_with_retries<P: AsRef<Path>, Q: AsRef<Path>>(src: P, dest: Q) -> io::Result<u64> {
+    retry(|| fs::copy(&src, &dest))
+}
+
+/// Runs an I/O operation with the default retry configuration.
+///
+/// See [`retry_with`] for which errors are retried.
+pub fn retry<T, F>(operation: F) -> io::Result<T>
+where
+    F: FnMut() -> io::Result<T>,
+{
+    retry_with(&default_retry_config(), operation)
 }
 
-/// Generic retry function with exponential backoff for any I/O operation.
+/// Runs an I/O operation, retrying transient failures with exponential backoff.
 ///
-/// Implements exponential backoff retry logic for resilience against
-/// transient network failures.
-fn read_with_exponential_backoff<F>(mut operation: F) -> io::Result<Vec<u8>>
+/// Errors that retrying cannot fix (missing file, permission denied, invalid
+/// input, already exists) are returned immediately. Other errors are retried up
+/// to `config.max_retries` times, multiplying the delay by
+/// `config.backoff_factor` each time.
+pub fn retry_with<T, F>(config: &RetryConfig, mut operation: F) -> io::Result<T>
 where
-    F: FnMut() -> io::Result<Vec<u8>>,
+    F: FnMut() -> io::Result<T>,
 {
-    let mut last_error = None;
-    let mut delay_ms = INITIAL_RETRY_DELAY_MS;
+    let mut delay_ms = config.initial_delay_ms;
+    let mut attempt = 0;
 
-    for attempt in 0..=MAX_RETRIES {
+    loop {
         match operation() {
-            Ok(data) => {
+            Ok(value) => {
                 if attempt > 0 {
-                    eprintln!("Successfully read after {} retries", attempt);
+                    report_retry(&format!("Succeeded after {} retries", attempt));
                 }
-                return Ok(data);
+                return Ok(value);
             }
+            Err(e) if !is_transient(&e) || attempt >= config.max_retries => return Err(e),
             Err(e) => {
-                last_error = Some(e);
-
-                if attempt < MAX_RETRIES {
-                    eprintln!(
-                        "Read attempt {} failed, retrying in {}ms...",
-                        attempt + 1,
-                        delay_ms
-                    );
-                    thread::sleep(Duration::from_millis(delay_ms));
-                    delay_ms *= 2; // Exponential backoff
-                }
+                attempt += 1;
+                report_retry(&format!("Attempt {} failed ({}), retrying in {}ms...", attempt, e, delay_ms));
+                thread::sleep(Duration::from_millis(delay_ms));
+                delay_ms = delay_ms.saturating_mul(u64::from(config.backoff_factor));
             }
         }
     }
+}
+
+/// A reader that retries each failed `read` with exponential backoff.
+///
+/// Wraps a single open handle, so a transient error mid-stream retries just the
+/// read that failed instead of restarting from the beginning. Memory use stays
+/// at whatever buffer the caller reads into.
+pub struct RetryingReader<R> {
+    inner: R,
+    config: RetryConfig,
+}
+
+impl<R: Read> RetryingReader<R> {
+    /// Wraps `inner`, retrying its reads according to `config`.
+    pub fn new(inner: R, config: RetryConfig) -> Self {
+        Self { inner, config }
+    }
+}
+
+impl<R: Read> Read for RetryingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let inner = &mut self.inner;
+        retry_with(&self.config, || inner.read(buf))
+    }
+}
+
+/// Returns `false` for errors that will fail the same way on every attempt.
+fn is_transient(error: &io::Error) -> bool {
+    !matches!(
+        error.kind(),
+        io::ErrorKind::NotFound
+            | io::ErrorKind::PermissionDenied
+            | io::ErrorKind::AlreadyExists
+            | io::ErrorKind::InvalidInput
+    )
+}
+
+/// Alignment required by `O_DIRECT` for buffers, offsets and transfer sizes.
+///
+/// 4 KiB covers the logical block size of virtually every disk and network filesystem.
+#[cfg(unix)]
+const DIRECT_IO_ALIGNMENT: usize = 4096;
+
+/// Reads a file while bypassing the OS page cache, so repeated reads hit the storage.
+///
+/// Intended for benchmarking: without it, every iteration after the first is served
+/// from RAM and reports memory bandwidth rather than network throughput.
+///
+/// # Platform Support
+///
+/// * **Linux**: opens the file with `O_DIRECT`. Direct I/O requires the buffer, file
+///   offset and read size to be aligned to the device block size; reads are issued in
+///   aligned 1 MB blocks from an aligned buffer. Filesystems that reject `O_DIRECT`
+///   (e.g. tmpfs, some FUSE mounts) fall back to evicting the file from the page cache
+///   with `posix_fadvise(POSIX_FADV_DONTNEED)` before a normal read.
+/// * **macOS**: sets `F_NOCACHE` on the descriptor (no alignment requirements).
+/// * **Other Unix**: best-effort eviction with `posix_fadvise` where available.
+///
+/// Eviction only drops clean pages; it cannot flush caches kept by an SMB/NFS server.
+///
+/// # Arguments
+///
+/// * `path` - Path to the file to read
+///
+/// # Returns
+///
+/// * `Ok(Vec<u8>)` - The file contents
+/// * `Err(io::Error)` - If the file cannot be opened or read
+#[cfg(unix)]
+pub fn uncached_read_file<P: AsRef<Path>>(path: P) -> io::Result<Vec<u8>> {
+    let path = path.as_ref();
+
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+
+        match std::fs::OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_DIRECT)
+            .open(path)
+        {
+            Ok(file) => return read_direct(file),
+            // EINVAL: the filesystem does not support O_DIRECT
+            Err(e) if e.raw_os_error() == Some(libc::EINVAL) => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    let file = File::open(path)?;
+    drop_cached_pages(&file)?;
+    let mut reader = BufReader::with_capacity(BUFFER_SIZE, file);
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+    Ok(data)
+}
+
+/// Reads an `O_DIRECT` file descriptor using an aligned buffer.
+#[cfg(target_os = "linux")]
+fn read_direct(mut file: File) -> io::Result<Vec<u8>> {
+    // Over-allocate so an aligned window of BUFFER_SIZE bytes fits in the Vec
+    let mut raw = vec![0u8; BUFFER_SIZE + DIRECT_IO_ALIGNMENT];
+    let offset = raw.as_ptr().align_offset(DIRECT_IO_ALIGNMENT);
+    let buffer = &mut raw[offset..offset + BUFFER_SIZE];
+
+    let mut data = Vec::new();
+    loop {
+        let n = file.read(buffer)?;
+        if n == 0 {
+            break;
+        }
+        data.extend_from_slice(&buffer[..n]);
+        // A short read means EOF on an unaligned tail; the next offset would be unaligned
+        if n % DIRECT_IO_ALIGNMENT != 0 {
+            break;
+        }
+    }
+    Ok(data)
+}
+
+/// Asks the kernel to evict a file's pages from the page cache.
+#[cfg(unix)]
+fn drop_cached_pages(file: &File) -> io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+    let fd = file.as_raw_fd();
+
+    #[cfg(target_os = "macos")]
+    {
+        // SAFETY: fd is a valid, open descriptor owned by `file`
+        if unsafe { libc::fcntl(fd, libc::F_NOCACHE, 1) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+    }
 
-    Err(last_error.unwrap_or_else(|| {
-        io::Error::other("Unknown error after retries")
-    }))
+    #[cfg(any(target_os = "linux", target_os = "android", target_os = "freebsd"))]
+    {
+        // SAFETY: fd is a valid, open descriptor owned by `file`
+        let rc = unsafe { libc::posix_fadvise(fd, 0, 0, libc::POSIX_FADV_DONTNEED) };
+        if rc != 0 {
+            return Err(io::Error::from_raw_os_error(rc));
+        }
+    }
+
+    let _ = fd;
+    Ok(())
 }
 
 /// Reads a specific chunk (range) from a file.
@@ -168,17 +435,146 @@ pub fn read_file_chunk<P: AsRef<Path>>(
     let mut file = File::open(path)?;
     file.seek(std::io::SeekFrom::Start(offset))?;
 
-    let mut reader = BufReader::with_capacity(BUFFER_SIZE, file);
-    let mut buffer = vec![0; size];
-    let bytes_read = reader.read(&mut buffer)?;
-    buffer.truncate(bytes_read);
+    // A single `read` may stop short on network filesystems, so keep reading
+    // until `size` bytes or EOF.
+    let mut buffer = Vec::with_capacity(size);
+    file.take(size as u64).read_to_end(&mut buffer)?;
 
     Ok(buffer)
 }
 
+/// Reads a whole file as `chunk_size` ranges fetched concurrently.
+///
+/// On high-latency NFS/SMB links a single sequential reader spends most of its
+/// time waiting on round-trips; keeping `concurrency` reads in flight fills the
+/// pipe instead. Chunks are read on a dedicated Rayon pool and reassembled in
+/// file order. The last chunk may be shorter than `chunk_size`.
+///
+/// # Arguments
+///
+/// * `path` - Path to the file
+/// * `chunk_size` - Bytes per [`read_file_chunk`] call; must be non-zero
+/// * `concurrency` - Number of chunks read at once (0 means one per CPU)
+///
+/// # Returns
+///
+/// * `Ok(Vec<u8>)` - The file contents, identical to a sequential read
+/// * `Err(io::Error)` - If any chunk cannot be read, or `chunk_size` is 0
+///
+/// # Examples
+///
+/// ```no_run
+/// # use sift::network_io;
+/// let data = network_io::parallel_chunked_read("/mnt/nfs/video.mov", 4 * 1_048_576, 8)?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn parallel_chunked_read<P: AsRef<Path>>(
+    path: P,
+    chunk_size: usize,
+    concurrency: usize,
+) -> io::Result<Vec<u8>> {
+    if chunk_size == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "chunk_size must be greater than zero",
+        ));
+    }
+
+    let path = path.as_ref();
+    let len = fs::metadata(path)?.len();
+    let offsets: Vec<u64> = (0..len).step_by(chunk_size).collect();
+
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(concurrency)
+        .build()
+        .map_err(io::Error::other)?;
+    let chunks = pool.install(|| {
+        offsets
+            .par_iter()
+            .map(|&offset| read_file_chunk(path, offset, chunk_size))
+            .collect::<io::Result<Vec<_>>>()
+    })?;
+
+    let mut data = Vec::with_capacity(len as usize);
+    for chunk in chunks {
+        data.extend_from_slice(&chunk);
+    }
+    Ok(data)
+}
+
+/// Counting semaphore bounding how many files are open at the same time.
+///
+/// Thread pools size CPU parallelism; on an SMB share every worker also holds a
+/// connection, and too many at once stall the share. Wrapping each open-and-read
+/// section in [`OpenLimit::acquire`] caps I/O concurrency independently of the
+/// number of threads. An unbounded limit hands out permits without waiting.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use sift::network_io::{self, OpenLimit};
+/// let limit = OpenLimit::new(Some(2));
+/// let data = {
+///     let _permit = limit.acquire();
+///     network_io::read_file_with_retries("/mnt/smb/photo.jpg")?
+/// };
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct OpenLimit {
+    max: Option<usize>,
+    open: Mutex<usize>,
+    released: Condvar,
+}
+
+impl OpenLimit {
+    /// Allows up to `max` permits at once (None = unbounded). A limit of 0 is
+    /// treated as 1, so acquiring can never deadlock.
+    pub fn new(max: Option<usize>) -> Self {
+        OpenLimit {
+            max: max.map(|max| max.max(1)),
+            open: Mutex::new(0),
+            released: Condvar::new(),
+        }
+    }
+
+    /// Blocks until a permit is free and returns it; the permit is given back
+    /// when dropped.
+    pub fn acquire(&self) -> OpenPermit<'_> {
+        if let Some(max) = self.max {
+            let mut open = self.open.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            while *open >= max {
+                open = self
+                    .released
+                    .wait(open)
+                    .unwrap_or_else(|poisoned| poisoned.into_inner());
+            }
+            *open += 1;
+        }
+        OpenPermit { limit: self }
+    }
+}
+
+/// A permit from [`OpenLimit::acquire`], released on drop.
+#[derive(Debug)]
+pub struct OpenPermit<'a> {
+    limit: &'a OpenLimit,
+}
+
+impl Drop for OpenPermit<'_> {
+    fn drop(&mut self) {
+        if self.limit.max.is_some() {
+            let mut open = self.limit.open.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            *open -= 1;
+            self.limit.released.notify_one();
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testutil::FlakyReader;
     use std::io::Write;
     use tempfile::NamedTempFile;
 
@@ -304,4 +700,154 @@ mod tests {
         let result = read_file_chunk("/nonexistent/path/file.jpg", 0, 100);
         assert!(result.is_err());
     }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_uncached_read_file_aligned() -> io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        // Three aligned blocks plus a distinct pattern so misordered reads are caught
+        let test_data: Vec<u8> = (0..DIRECT_IO_ALIGNMENT * 3).map(|i| (i % 251) as u8).collect();
+        temp_file.write_all(&test_data)?;
+        temp_file.flush()?;
+
+        let data = uncached_read_file(temp_file.path())?;
+        assert_eq!(data, test_data);
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_uncached_read_file_nonexistent() {
+        let result = uncached_read_file("/nonexistent/path/file.jpg");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_retry_recovers_after_transient_failures() {
+        let mut calls = 0;
+        let result = retry(|| {
+            calls += 1;
+            if calls <= 2 {
+                Err(io::Error::new(io::ErrorKind::ConnectionReset, "smb hiccup"))
+            } else {
+                Ok(calls)
+            }
+        });
+
+        assert_eq!(result.unwrap(), 3);
+        assert_eq!(calls, 3);
+    }
+
+    #[test]
+    fn test_retry_gives_up_on_permanent_errors() {
+        let mut calls = 0;
+        let result: io::Result<()> = retry(|| {
+            calls += 1;
+            Err(io::Error::new(io::ErrorKind::NotFound, "gone"))
+        });
+
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::NotFound);
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn test_copy_and_write_with_retries() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let src = dir.path().join("src.jpg");
+        let dest = dir.path().join("dest.jpg");
+
+        write_file_with_retries(&src, b"photo bytes")?;
+        assert_eq!(copy_file_with_retries(&src, &dest)?, 11);
+        assert_eq!(fs::read(&dest)?, b"photo bytes");
+
+        assert!(copy_file_with_retries(dir.path().join("missing.jpg"), &dest).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_zero_retries_fails_immediately() {
+        let config = RetryConfig {
+            max_retries: 0,
+            initial_delay_ms: 60_000,
+            backoff_factor: 2,
+        };
+        let mut calls = 0;
+        let start = std::time::Instant::now();
+        let result: io::Result<()> = retry_with(&config, || {
+            calls += 1;
+            Err(io::Error::new(io::ErrorKind::TimedOut, "share offline"))
+        });
+
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::TimedOut);
+        assert_eq!(calls, 1);
+        assert!(start.elapsed() < Duration::from_secs(1), "Must not sleep");
+    }
+
+    #[test]
+    fn test_read_file_with_config() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("photo.jpg");
+        fs::write(&path, b"pixels")?;
+
+        let config = RetryConfig { max_retries: 1, ..RetryConfig::default() };
+        assert_eq!(read_file_with_config(&path, &config)?, b"pixels");
+        Ok(())
+    }
+
+    #[test]
+    fn test_parallel_chunked_read_matches_plain_read() -> io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        // 5MB plus a tail, so the last chunk is short
+        let data: Vec<u8> = (0..5 * 1_048_576 + 1234).map(|i| (i % 251) as u8).collect();
+        temp_file.write_all(&data)?;
+
+        let chunked = parallel_chunked_read(temp_file.path(), 1_048_576, 4)?;
+        assert_eq!(chunked, fs::read(temp_file.path())?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parallel_chunked_read_small_file() -> io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        temp_file.write_all(b"tiny")?;
+
+        assert_eq!(parallel_chunked_read(temp_file.path(), 1_048_576, 4)?, b"tiny");
+        assert!(parallel_chunked_read(temp_file.path(), 0, 4).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_retrying_reader_resumes_after_failure() -> io::Result<()> {
+        let data: Vec<u8> = (0..10_000).map(|i| (i % 256) as u8).collect();
+        let flaky = FlakyReader::new(data.clone(), 4096, io::ErrorKind::ConnectionReset);
+        let config = RetryConfig { initial_delay_ms: 1, ..RetryConfig::default() };
+
+        let mut out = Vec::new();
+        RetryingReader::new(flaky, config).read_to_end(&mut out)?;
+        assert_eq!(out, data);
+        Ok(())
+    }
+
+    #[test]
+    fn test_open_limit_bounds_concurrency() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let limit = OpenLimit::new(Some(2));
+        let open = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+        (0..32).into_par_iter().for_each(|_| {
+            let _permit = limit.acquire();
+            let now = open.fetch_add(1, Ordering::SeqCst) + 1;
+            peak.fetch_max(now, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(2));
+            open.fetch_sub(1, Ordering::SeqCst);
+        });
+        assert!(peak.into_inner() <= 2);
+
+        // Unbounded permits never block, even when many are held at once
+        let unbounded = OpenLimit::new(None);
+        let permits: Vec<OpenPermit> = (0..100).map(|_| unbounded.acquire()).collect();
+        assert_eq!(permits.len(), 100);
+    }
 }
@@ -8,49 +8,246 @@
 //!
 //! Organize a photo by date:
 //! ```no_run
-//! # use sift::organization;
+//! # use sift::organization::{self, FileOp};
 //! # use chrono::NaiveDate;
 //! let date = NaiveDate::from_ymd_opt(2023, 10, 15).unwrap();
 //! let dest = organization::organize_by_date(
 //!     "source.jpg",
 //!     "/photos",
-//!     date
+//!     date,
+//!     FileOp::Copy,
 //! )?;
 //! println!("Organized to: {:?}", dest);
 //! # Ok::<(), std::io::Error>(())
 //! ```
 
-use chrono::{NaiveDate, Datelike};
+use chrono::NaiveDate;
+use clap::ValueEnum;
+use filetime::FileTime;
+use std::collections::HashSet;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::io;
 
+use crate::hash;
+use crate::metadata::{self, Granularity};
+use crate::network_io;
+
+/// How a file is transferred from the source into the organized destination.
+///
+/// # Variants
+///
+/// * `Copy` - Copy the file, leaving the source untouched (default)
+/// * `Move` - Rename the file, falling back to copy-then-remove across filesystems
+/// * `Hardlink` - Create a hard link (source and destination must share a filesystem)
+/// * `Symlink` - Create a symbolic link pointing back to the source file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum FileOp {
+    /// Copy the file (preserves the source)
+    #[default]
+    Copy,
+    /// Move the file (rename, or copy + delete across filesystems)
+    Move,
+    /// Hard link the file into the destination
+    Hardlink,
+    /// Symlink the destination back to the source file
+    Symlink,
+}
+
+/// Transfers a single file to `dest_file` using the given operation.
+///
+/// # Arguments
+///
+/// * `source` - Path to the source file
+/// * `dest_file` - Full path of the file to create in the destination
+/// * `mode` - The transfer operation to perform
+///
+/// # Returns
+///
+/// * `Ok(())` - If the file was transferred
+/// * `Err(io::Error)` - If the operation fails
+pub fn transfer_file(source: &Path, dest_file: &Path, mode: FileOp) -> io::Result<()> {
+    match mode {
+        FileOp::Copy => network_io::copy_file_with_retries(source, dest_file).map(|_| ()),
+        FileOp::Move => move_file(source, dest_file),
+        FileOp::Hardlink => fs::hard_link(source, dest_file),
+        FileOp::Symlink => {
+            // Link to an absolute path so the link resolves from the destination folder
+            let target = fs::canonicalize(source)?;
+            symlink_file(&target, dest_file)
+        }
+    }
+}
+
+/// Moves a file, falling back to copy-then-remove when `rename` crosses devices.
+///
+/// `fs::rename` fails with `EXDEV` when source and destination live on different
+/// filesystems (e.g. local disk to an SMB mount). In that case the file is copied
+/// and the source removed only once the copy succeeded. Unlike a rename, the
+/// copy gets fresh timestamps; see [`set_times`] to restore them.
+fn move_file(source: &Path, dest_file: &Path) -> io::Result<()> {
+    match fs::rename(source, dest_file) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            network_io::copy_file_with_retries(source, dest_file)?;
+            fs::remove_file(source)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Gives `dest_file` the access and modification times recorded in `stat`, the
+/// metadata of its source read before the transfer (a move removes the source).
+///
+/// Creation times are left as they are: most platforms offer no way to set them.
+pub fn set_times(dest_file: &Path, stat: &fs::Metadata) -> io::Result<()> {
+    filetime::set_file_times(
+        dest_file,
+        FileTime::from_last_access_time(stat),
+        FileTime::from_last_modification_time(stat),
+    )
+}
+
+/// Where a file should land once destination collisions are taken into account.
+///
+/// # Variants
+///
+/// * `Vacant(PathBuf)` - A free path the file can be transferred to
+/// * `AlreadyPresent(PathBuf)` - A byte-identical copy already exists at this path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Placement {
+    /// The file can be transferred to this (currently unused) path
+    Vacant(PathBuf),
+    /// Identical content already exists at this path; no transfer is needed
+    AlreadyPresent(PathBuf),
+}
+
+/// Resolves filename collisions for a file about to be placed at `desired`.
+///
+/// If `desired` is free it is used as-is. If it is taken by a file with the same
+/// Blake3 hash, the existing file is reported as already present. Otherwise a numeric
+/// suffix is appended to the file stem (`IMG_0001_1.jpg`, `IMG_0001_2.jpg`, ...) until
+/// a free or identical path is found.
+///
+/// This function only reads from the filesystem, so it is safe to use for dry runs.
+///
+/// # Arguments
+///
+/// * `source` - Path to the file being organized
+/// * `desired` - The destination path the file would normally take
+///
+/// # Returns
+///
+/// * `Ok(Placement)` - The chosen destination
+/// * `Err(io::Error)` - If an existing file or the source cannot be hashed
+pub fn resolve_collision(source: &Path, desired: &Path) -> io::Result<Placement> {
+    resolve_collision_with(source, desired, &HashSet::new())
+}
+
+/// Like [`resolve_collision`], but also treats the paths in `reserved` as taken.
+///
+/// Used when planning a batch, so two files bound for the same name are given
+/// distinct destinations before either has been written.
+pub fn resolve_collision_with(
+    source: &Path,
+    desired: &Path,
+    reserved: &HashSet<PathBuf>,
+) -> io::Result<Placement> {
+    let mut source_hash = None;
+    let mut candidate = desired.to_path_buf();
+    let mut suffix = 0;
+
+    loop {
+        // symlink_metadata also catches dangling symlinks that would make the transfer fail
+        if fs::symlink_metadata(&candidate).is_ok() {
+            if source_hash.is_none() {
+                source_hash = Some(hash::hash_file(source)?);
+            }
+            if candidate.is_file() && Some(hash::hash_file(&candidate)?) == source_hash {
+                return Ok(Placement::AlreadyPresent(candidate));
+            }
+        } else if !reserved.contains(&candidate) {
+            return Ok(Placement::Vacant(candidate));
+        }
+
+        suffix += 1;
+        candidate = suffixed_path(desired, suffix);
+    }
+}
+
+/// Builds `<stem>_<n>.<ext>` next to `path`.
+fn suffixed_path(path: &Path, n: usize) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let file_name = match path.extension() {
+        Some(ext) => format!("{}_{}.{}", stem, n, ext.to_string_lossy()),
+        None => format!("{}_{}", stem, n),
+    };
+    path.with_file_name(file_name)
+}
+
+/// Places a file at `dest_file`, resolving collisions and creating parent folders.
+///
+/// Returns the path the file actually ended up at (which may carry a numeric suffix,
+/// or be a pre-existing identical file that was left untouched).
+fn place_file(source: &Path, dest_file: &Path, mode: FileOp) -> io::Result<PathBuf> {
+    if let Some(dest_dir) = dest_file.parent() {
+        fs::create_dir_all(dest_dir)?;
+    }
+
+    match resolve_collision(source, dest_file)? {
+        Placement::AlreadyPresent(existing) => Ok(existing),
+        Placement::Vacant(target) => {
+            transfer_file(source, &target, mode)?;
+            Ok(target)
+        }
+    }
+}
+
+#[cfg(unix)]
+fn symlink_file(target: &Path, link: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(windows)]
+fn symlink_file(target: &Path, link: &Path) -> io::Result<()> {
+    std::os::windows::fs::symlink_file(target, link)
+}
+
 /// Organizes a file into a chronological folder structure (YYYY/MM/DD).
 ///
-/// Creates the necessary directory structure and copies the file to the destination.
+/// Creates the necessary directory structure and transfers the file to the destination.
 /// The file is placed in a subfolder hierarchy based on its capture date.
 ///
+/// If a file with the same name already exists there, a byte-identical file is left
+/// in place and no transfer happens; different content gets a numeric suffix
+/// (see [`resolve_collision`]).
+///
 /// # Arguments
 ///
 /// * `source_file` - Path to the source file
 /// * `dest_root` - Root destination directory
 /// * `date` - The date to use for folder organization
+/// * `mode` - How the file is transferred (copy, move, hardlink, symlink)
 ///
 /// # Returns
 ///
-/// * `Ok(PathBuf)` - Path to the copied file in the destination
+/// * `Ok(PathBuf)` - Final path of the file in the destination
 /// * `Err(io::Error)` - If the operation fails
 ///
 /// # Examples
 ///
 /// ```no_run
-/// # use sift::organization;
+/// # use sift::organization::{self, FileOp};
 /// # use chrono::NaiveDate;
 /// let date = NaiveDate::from_ymd_opt(2023, 10, 15).unwrap();
 /// let result = organization::organize_by_date(
 ///     "photo.jpg",
 ///     "/organized_photos",
-///     date
+///     date,
+///     FileOp::Copy,
 /// )?;
 /// assert!(result.exists());
 /// # Ok::<(), std::io::Error>(())
@@ -59,33 +256,58 @@ pub fn organize_by_date<P: AsRef<Path>>(
     source_file: P,
     dest_root: P,
     date: NaiveDate,
+    mode: FileOp,
 ) -> io::Result<PathBuf> {
     let source = source_file.as_ref();
-    let root = dest_root.as_ref();
-
-    // Build destination path
-    let chrono_path = format!(
-        "{}/{:02}/{:02}",
-        date.year(),
-        date.month(),
-        date.day()
-    );
-    let dest_dir = root.join(&chrono_path);
-
-    // Create folder structure
-    fs::create_dir_all(&dest_dir)?;
-
-    // Copy or move file
-    let file_name = source
-        .file_name()
-        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Invalid file name"))?;
+    let dest_file = date_destination(source, dest_root.as_ref(), date, Granularity::Day)?;
+
+    place_file(source, &dest_file, mode)
+}
 
-    let dest_file = dest_dir.join(file_name);
+/// Computes where `organize_by_date` would place a file, without touching the filesystem.
+///
+/// Used by dry runs to report planned destinations, and by organize with any
+/// folder granularity.
+///
+/// # Arguments
+///
+/// * `source_file` - Path to the source file
+/// * `dest_root` - Root destination directory
+/// * `date` - The date to use for folder organization
+/// * `granularity` - How many date levels to nest (`YYYY`, `YYYY/MM` or `YYYY/MM/DD`)
+///
+/// # Returns
+///
+/// * `Ok(PathBuf)` - The destination path (e.g. `dest_root/YYYY/MM/DD/<file name>`)
+/// * `Err(io::Error)` - If the source path has no file name
+///
+/// # Examples
+///
+/// ```
+/// # use sift::organization;
+/// # use sift::metadata::Granularity;
+/// # use chrono::NaiveDate;
+/// let date = NaiveDate::from_ymd_opt(2023, 10, 15).unwrap();
+/// let dest = organization::date_destination("photo.jpg", "/photos", date, Granularity::Day)?;
+/// assert!(dest.ends_with("2023/10/15/photo.jpg"));
+/// let dest = organization::date_destination("photo.jpg", "/photos", date, Granularity::Month)?;
+/// assert!(dest.ends_with("2023/10/photo.jpg"));
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn date_destination<P: AsRef<Path>>(
+    source_file: P,
+    dest_root: P,
+    date: NaiveDate,
+    granularity: Granularity,
+) -> io::Result<PathBuf> {
+    let chrono_path = metadata::build_chronological_path_with(date, granularity);
 
-    // Copy file (not move, to preserve source)
-    fs::copy(source, &dest_file)?;
+    let file_name = source_file
+        .as_ref()
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Invalid file name"))?;
 
-    Ok(dest_file)
+    Ok(dest_root.as_ref().join(chrono_path).join(file_name))
 }
 
 /// Organizes a file into a chronological folder structure with geographic location.
@@ -93,6 +315,7 @@ pub fn organize_by_date<P: AsRef<Path>>(
 /// Creates a directory structure combining both chronological organization
 /// (YYYY/MM/DD) and geographic clustering (by location name).
 /// This is useful for organizing clustered photos geographically.
+/// Name collisions are handled as in [`organize_by_date`].
 ///
 /// # Arguments
 ///
@@ -100,23 +323,25 @@ pub fn organize_by_date<P: AsRef<Path>>(
 /// * `dest_root` - Root destination directory
 /// * `date` - The date to use for folder organization
 /// * `location` - The location name (e.g., "Paris", "New York")
+/// * `mode` - How the file is transferred (copy, move, hardlink, symlink)
 ///
 /// # Returns
 ///
-/// * `Ok(PathBuf)` - Path to the copied file in the destination
+/// * `Ok(PathBuf)` - Path to the organized file in the destination
 /// * `Err(io::Error)` - If the operation fails
 ///
 /// # Examples
 ///
 /// ```no_run
-/// # use sift::organization;
+/// # use sift::organization::{self, FileOp};
 /// # use chrono::NaiveDate;
 /// let date = NaiveDate::from_ymd_opt(2023, 10, 15).unwrap();
 /// let result = organization::organize_by_date_and_location(
 ///     "photo.jpg",
 ///     "/organized_photos",
 ///     date,
-///     "Paris"
+///     "Paris",
+///     FileOp::Copy,
 /// )?;
 /// // File will be at: /organized_photos/2023/10/15/Paris/photo.jpg
 /// # Ok::<(), std::io::Error>(())
@@ -126,32 +351,138 @@ pub fn organize_by_date_and_location<P: AsRef<Path>>(
     dest_root: P,
     date: NaiveDate,
     location: &str,
+    mode: FileOp,
 ) -> io::Result<PathBuf> {
-    let source = source_file.as_ref();
-    let root = dest_root.as_ref();
-
-    // Build destination path with location subfolder
-    let chrono_path = format!(
-        "{}/{:02}/{:02}/{}",
-        date.year(),
-        date.month(),
-        date.day(),
-        location
-    );
-    let dest_dir = root.join(&chrono_path);
-
-    // Create folder structure
-    fs::create_dir_all(&dest_dir)?;
-
-    // Copy file
-    let file_name = source
-        .file_name()
-        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Invalid file name"))?;
+    let destination = date_location_destination(&source_file, &dest_root, date, location, Granularity::Day)?;
+    place_file(source_file.as_ref(), &destination, mode)
+}
 
-    let dest_file = dest_dir.join(file_name);
-    fs::copy(source, &dest_file)?;
+/// Computes where `organize_by_date_and_location` would place a file, without
+/// touching the filesystem.
+///
+/// `location` goes through [`sanitize_path_segment`], so a place name always
+/// maps to exactly one folder.
+///
+/// # Examples
+///
+/// ```
+/// # use sift::organization;
+/// # use sift::metadata::Granularity;
+/// # use chrono::NaiveDate;
+/// let date = NaiveDate::from_ymd_opt(2023, 10, 15).unwrap();
+/// let dest = organization::date_location_destination("photo.jpg", "/photos", date, "Paris", Granularity::Day)?;
+/// assert!(dest.ends_with("2023/10/15/Paris/photo.jpg"));
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn date_location_destination<P: AsRef<Path>>(
+    source_file: P,
+    dest_root: P,
+    date: NaiveDate,
+    location: &str,
+    granularity: Granularity,
+) -> io::Result<PathBuf> {
+    let dated = date_destination(source_file, dest_root, date, granularity)?;
+    let folder = sanitize_path_segment(location);
+    let file_name = dated.file_name().unwrap_or_default().to_os_string();
+    Ok(dated.with_file_name(folder).join(file_name))
+}
 
-    Ok(dest_file)
+/// Organizes a file into `Camera/YYYY/MM/DD`.
+///
+/// Photos from different cameras are kept apart, which helps when several
+/// people's cards are imported into one library. Name collisions are handled
+/// as in [`organize_by_date`].
+///
+/// # Arguments
+///
+/// * `source_file` - Path to the source file
+/// * `dest_root` - Root destination directory
+/// * `date` - The date to use for folder organization
+/// * `camera` - Camera name, e.g. from [`metadata::extract_camera`]
+/// * `mode` - How the file is transferred (copy, move, hardlink, symlink)
+///
+/// # Examples
+///
+/// ```no_run
+/// # use sift::organization::{self, FileOp};
+/// # use chrono::NaiveDate;
+/// let date = NaiveDate::from_ymd_opt(2023, 10, 15).unwrap();
+/// let result = organization::organize_by_camera_and_date(
+///     "photo.jpg",
+///     "/organized_photos",
+///     date,
+///     "Canon EOS R5",
+///     FileOp::Copy,
+/// )?;
+/// // File will be at: /organized_photos/Canon EOS R5/2023/10/15/photo.jpg
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn organize_by_camera_and_date<P: AsRef<Path>>(
+    source_file: P,
+    dest_root: P,
+    date: NaiveDate,
+    camera: &str,
+    mode: FileOp,
+) -> io::Result<PathBuf> {
+    let destination = camera_date_destination(&source_file, &dest_root, date, camera, Granularity::Day)?;
+    place_file(source_file.as_ref(), &destination, mode)
+}
+
+/// Computes where `organize_by_camera_and_date` would place a file, without
+/// touching the filesystem.
+///
+/// # Examples
+///
+/// ```
+/// # use sift::organization;
+/// # use sift::metadata::Granularity;
+/// # use chrono::NaiveDate;
+/// let date = NaiveDate::from_ymd_opt(2023, 10, 15).unwrap();
+/// let dest = organization::camera_date_destination("a.jpg", "/photos", date, "X100V", Granularity::Day)?;
+/// assert!(dest.ends_with("X100V/2023/10/15/a.jpg"));
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn camera_date_destination<P: AsRef<Path>>(
+    source_file: P,
+    dest_root: P,
+    date: NaiveDate,
+    camera: &str,
+    granularity: Granularity,
+) -> io::Result<PathBuf> {
+    let camera_root = dest_root.as_ref().join(sanitize_path_segment(camera));
+    date_destination(source_file.as_ref(), camera_root.as_path(), date, granularity)
+}
+
+/// Makes a user- or metadata-derived name (place, camera) safe to use as one
+/// folder name.
+///
+/// Characters that are path separators or invalid on Windows and SMB shares
+/// (`/ \\ : * ? " < > |` and control characters) become `_`, and trailing dots
+/// and spaces, which Windows silently drops, are trimmed. Anything else,
+/// including non-ASCII letters, is kept. A name with nothing left (or `.`/`..`)
+/// becomes `_`, so the result is always exactly one non-empty path segment.
+///
+/// # Examples
+///
+/// ```
+/// # use sift::organization::sanitize_path_segment;
+/// assert_eq!(sanitize_path_segment("AC/DC: Live"), "AC_DC_ Live");
+/// assert_eq!(sanitize_path_segment("São Paulo"), "São Paulo");
+/// assert_eq!(sanitize_path_segment("  "), "_");
+/// ```
+pub fn sanitize_path_segment(name: &str) -> String {
+    let replaced: String = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    match replaced.trim_start().trim_end_matches(['.', ' ']) {
+        "" => "_".to_string(),
+        segment => segment.to_string(),
+    }
 }
 
 #[cfg(test)]
@@ -170,7 +501,7 @@ mod tests {
         source_file.flush()?;
 
         let date = NaiveDate::from_ymd_opt(2023, 10, 15).unwrap();
-        let result = organize_by_date(source_file.path(), dest_dir.path(), date)?;
+        let result = organize_by_date(source_file.path(), dest_dir.path(), date, FileOp::Copy)?;
 
         assert!(result.exists());
         assert!(result.to_string_lossy().contains("2023/10/15"));
@@ -187,7 +518,7 @@ mod tests {
         source_file.flush()?;
 
         let date = NaiveDate::from_ymd_opt(2024, 12, 25).unwrap();
-        let result = organize_by_date(source_file.path(), dest_dir.path(), date)?;
+        let result = organize_by_date(source_file.path(), dest_dir.path(), date, FileOp::Copy)?;
 
         // Check that all parent directories were created
         assert!(result.parent().unwrap().exists());
@@ -207,7 +538,7 @@ mod tests {
         source_file.flush()?;
 
         let date = NaiveDate::from_ymd_opt(2023, 6, 20).unwrap();
-        let result = organize_by_date(source_file.path(), dest_dir.path(), date)?;
+        let result = organize_by_date(source_file.path(), dest_dir.path(), date, FileOp::Copy)?;
 
         let copied_content = fs::read(&result)?;
         assert_eq!(copied_content, test_content);
@@ -230,6 +561,7 @@ mod tests {
             dest_dir.path(),
             date,
             "Paris",
+            FileOp::Copy,
         )?;
 
         assert!(result.exists());
@@ -256,6 +588,7 @@ mod tests {
                 dest_dir.path(),
                 date,
                 location,
+                FileOp::Copy,
             )?;
 
             assert!(result.to_string_lossy().contains(location));
@@ -274,7 +607,7 @@ mod tests {
         source_file.flush()?;
 
         let date = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
-        let result = organize_by_date(source_file.path(), dest_dir.path(), date)?;
+        let result = organize_by_date(source_file.path(), dest_dir.path(), date, FileOp::Copy)?;
 
         assert!(result.to_string_lossy().contains("2024/01/01"));
 
@@ -293,7 +626,7 @@ mod tests {
         let source_filename = source_file.path().file_name().unwrap().to_str().unwrap();
 
         let date = NaiveDate::from_ymd_opt(2023, 7, 4).unwrap();
-        let result = organize_by_date(source_file.path(), dest_dir.path(), date)?;
+        let result = organize_by_date(source_file.path(), dest_dir.path(), date, FileOp::Copy)?;
 
         let dest_filename = result.file_name().unwrap().to_str().unwrap();
         assert_eq!(source_filename, dest_filename);
@@ -319,6 +652,7 @@ mod tests {
                 dest_dir.path(),
                 date,
                 name,
+                FileOp::Copy,
             )?;
 
             assert!(result.to_string_lossy().contains(name));
@@ -326,4 +660,222 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_organize_by_date_move_removes_source() -> io::Result<()> {
+        let source_dir = tempdir()?;
+        let dest_dir = tempdir()?;
+
+        let source = source_dir.path().join("moved.jpg");
+        fs::write(&source, b"Move me")?;
+
+        let date = NaiveDate::from_ymd_opt(2023, 3, 9).unwrap();
+        let result = organize_by_date(source.as_path(), dest_dir.path(), date, FileOp::Move)?;
+
+        assert!(!source.exists(), "Source should be gone after a move");
+        assert_eq!(fs::read(&result)?, b"Move me");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_organize_by_date_hardlink() -> io::Result<()> {
+        let source_dir = tempdir()?;
+        let dest_dir = tempdir()?;
+
+        let source = source_dir.path().join("linked.jpg");
+        fs::write(&source, b"Link me")?;
+
+        let date = NaiveDate::from_ymd_opt(2023, 3, 9).unwrap();
+        let result = organize_by_date(source.as_path(), dest_dir.path(), date, FileOp::Hardlink)?;
+
+        assert!(source.exists(), "Source should be kept for a hard link");
+        assert_eq!(fs::read(&result)?, b"Link me");
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_organize_by_date_symlink() -> io::Result<()> {
+        let source_dir = tempdir()?;
+        let dest_dir = tempdir()?;
+
+        let source = source_dir.path().join("symlinked.jpg");
+        fs::write(&source, b"Point at me")?;
+
+        let date = NaiveDate::from_ymd_opt(2023, 3, 9).unwrap();
+        let result = organize_by_date(source.as_path(), dest_dir.path(), date, FileOp::Symlink)?;
+
+        assert!(fs::symlink_metadata(&result)?.file_type().is_symlink());
+        assert_eq!(fs::read_link(&result)?, fs::canonicalize(&source)?);
+        assert_eq!(fs::read(&result)?, b"Point at me");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_file_op_default_is_copy() {
+        assert_eq!(FileOp::default(), FileOp::Copy);
+    }
+
+    #[test]
+    fn test_date_destination_does_not_write() -> io::Result<()> {
+        let dest_dir = tempdir()?;
+
+        let date = NaiveDate::from_ymd_opt(2022, 11, 5).unwrap();
+        let planned = date_destination(Path::new("/src/IMG_0001.jpg"), dest_dir.path(), date, Granularity::Day)?;
+
+        assert_eq!(planned, dest_dir.path().join("2022/11/05/IMG_0001.jpg"));
+        assert!(!dest_dir.path().join("2022").exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_date_destination_granularity() -> io::Result<()> {
+        let date = NaiveDate::from_ymd_opt(2023, 10, 15).unwrap();
+        let source = Path::new("/src/IMG_0001.jpg");
+        let root = Path::new("/photos");
+
+        assert_eq!(date_destination(source, root, date, Granularity::Year)?, root.join("2023/IMG_0001.jpg"));
+        assert_eq!(date_destination(source, root, date, Granularity::Month)?, root.join("2023/10/IMG_0001.jpg"));
+        assert_eq!(date_destination(source, root, date, Granularity::Day)?, root.join("2023/10/15/IMG_0001.jpg"));
+        assert_eq!(
+            date_location_destination(source, root, date, "Paris", Granularity::Month)?,
+            root.join("2023/10/Paris/IMG_0001.jpg")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_organize_by_camera_and_date() -> io::Result<()> {
+        let source_dir = tempdir()?;
+        let dest_dir = tempdir()?;
+        let source = source_dir.path().join("DSC_0001.jpg");
+        crate::testutil::write_jpeg_with_exif(
+            &source,
+            &[crate::testutil::ascii_field(exif::Tag::Model, "NIKON Z 6_2")],
+        )?;
+
+        let camera = metadata::extract_camera(&source).unwrap();
+        let date = NaiveDate::from_ymd_opt(2023, 4, 9).unwrap();
+        let placed = organize_by_camera_and_date(&source, &dest_dir.path().to_path_buf(), date, &camera, FileOp::Copy)?;
+        assert_eq!(placed, dest_dir.path().join("NIKON Z 6_2/2023/04/09/DSC_0001.jpg"));
+        assert!(placed.exists());
+
+        let sanitized = camera_date_destination(&source, &dest_dir.path().to_path_buf(), date, "A/B", Granularity::Year)?;
+        assert_eq!(sanitized, dest_dir.path().join("A_B/2023/DSC_0001.jpg"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_sanitize_path_segment() {
+        assert_eq!(sanitize_path_segment("Canon EOS 5D Mark IV"), "Canon EOS 5D Mark IV");
+        assert_eq!(sanitize_path_segment("a/b\\c:d*e?f\"g<h>i|j"), "a_b_c_d_e_f_g_h_i_j");
+        assert_eq!(sanitize_path_segment("tab\there"), "tab_here");
+        assert_eq!(sanitize_path_segment("  Paris. "), "Paris");
+    }
+
+    #[test]
+    fn test_sanitize_path_segment_empty_after_sanitizing() {
+        assert_eq!(sanitize_path_segment(""), "_");
+        assert_eq!(sanitize_path_segment("   "), "_");
+        assert_eq!(sanitize_path_segment("."), "_");
+        assert_eq!(sanitize_path_segment(".."), "_");
+        assert_eq!(sanitize_path_segment("/"), "_");
+    }
+
+    #[test]
+    fn test_sanitize_path_segment_preserves_unicode() {
+        assert_eq!(sanitize_path_segment("São Paulo"), "São Paulo");
+        assert_eq!(sanitize_path_segment("東京"), "東京");
+        assert_eq!(sanitize_path_segment("Zürich / Genève"), "Zürich _ Genève");
+    }
+
+    #[test]
+    fn test_organize_by_date_plain_new_file() -> io::Result<()> {
+        let source_dir = tempdir()?;
+        let dest_dir = tempdir()?;
+
+        let source = source_dir.path().join("IMG_0001.jpg");
+        fs::write(&source, b"camera A")?;
+
+        let date = NaiveDate::from_ymd_opt(2023, 8, 1).unwrap();
+        let result = organize_by_date(source.as_path(), dest_dir.path(), date, FileOp::Copy)?;
+
+        assert_eq!(result, dest_dir.path().join("2023/08/01/IMG_0001.jpg"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_organize_by_date_collision_gets_suffix() -> io::Result<()> {
+        let dest_dir = tempdir()?;
+        let date = NaiveDate::from_ymd_opt(2023, 8, 1).unwrap();
+
+        let camera_a = tempdir()?;
+        let first = camera_a.path().join("IMG_0001.jpg");
+        fs::write(&first, b"camera A")?;
+
+        let camera_b = tempdir()?;
+        let second = camera_b.path().join("IMG_0001.jpg");
+        fs::write(&second, b"camera B")?;
+
+        let camera_c = tempdir()?;
+        let third = camera_c.path().join("IMG_0001.jpg");
+        fs::write(&third, b"camera C")?;
+
+        let first_dest = organize_by_date(first.as_path(), dest_dir.path(), date, FileOp::Copy)?;
+        let second_dest = organize_by_date(second.as_path(), dest_dir.path(), date, FileOp::Copy)?;
+        let third_dest = organize_by_date(third.as_path(), dest_dir.path(), date, FileOp::Copy)?;
+
+        let day = dest_dir.path().join("2023/08/01");
+        assert_eq!(first_dest, day.join("IMG_0001.jpg"));
+        assert_eq!(second_dest, day.join("IMG_0001_1.jpg"));
+        assert_eq!(third_dest, day.join("IMG_0001_2.jpg"));
+        assert_eq!(fs::read(&first_dest)?, b"camera A", "First file must not be overwritten");
+        assert_eq!(fs::read(&second_dest)?, b"camera B");
+        assert_eq!(fs::read(&third_dest)?, b"camera C");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_organize_by_date_identical_is_skipped() -> io::Result<()> {
+        let dest_dir = tempdir()?;
+        let date = NaiveDate::from_ymd_opt(2023, 8, 1).unwrap();
+
+        let original_dir = tempdir()?;
+        let original = original_dir.path().join("IMG_0001.jpg");
+        fs::write(&original, b"same bytes")?;
+
+        let copy_dir = tempdir()?;
+        let copy = copy_dir.path().join("IMG_0001.jpg");
+        fs::write(&copy, b"same bytes")?;
+
+        let first_dest = organize_by_date(original.as_path(), dest_dir.path(), date, FileOp::Copy)?;
+        let second_dest = organize_by_date(copy.as_path(), dest_dir.path(), date, FileOp::Copy)?;
+
+        assert_eq!(first_dest, second_dest, "Identical file should resolve to the existing copy");
+        let files_in_day = fs::read_dir(dest_dir.path().join("2023/08/01"))?.count();
+        assert_eq!(files_in_day, 1, "No suffixed duplicate should be created");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_resolve_collision_without_extension() -> io::Result<()> {
+        let dir = tempdir()?;
+        let existing = dir.path().join("README");
+        fs::write(&existing, b"old")?;
+
+        let source_dir = tempdir()?;
+        let source = source_dir.path().join("README");
+        fs::write(&source, b"new")?;
+
+        let placement = resolve_collision(&source, &existing)?;
+        assert_eq!(placement, Placement::Vacant(dir.path().join("README_1")));
+
+        Ok(())
+    }
 }
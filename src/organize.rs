@@ -3,16 +3,60 @@
 //! This module handles the high-level coordination of the photo organization pipeline,
 //! including index loading, file discovery, analysis, clustering, and file operations.
 
-use std::fs;
-use std::io;
-use std::path::PathBuf;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::ffi::OsStr;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use chrono::NaiveDate;
+use indicatif::{ProgressBar, ProgressStyle};
 use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
 
-use crate::hash;
-use crate::index::Index;
-use crate::metadata;
-use crate::organization;
+use crate::clustering::{self, GeoPoint};
+use crate::error::{OrganizeError, OrganizeResult};
+use crate::events::{Event, ProgressSink};
+use crate::geonames;
+use crate::hash::{self, HashKind};
+use crate::index::{self, Index, IndexBackend, IndexEntry, IndexHandle, IndexStore};
+use crate::manifest::{self, MANIFEST_FILE, ManifestAction, ManifestEntry};
+use crate::metadata::{self, DateRange, Granularity};
+use crate::network_io::{self, OpenLimit};
+use crate::sqlite_index::SqliteIndex;
+use crate::organization::{self, FileOp, Placement};
+use crate::template::{PathTemplate, TemplateError, Token, UNKNOWN_CAMERA};
+
+/// How much an organize run reports while it runs.
+///
+/// Each level reports everything the one before it does:
+///
+/// * `Quiet` - The final summary and errors only (`--quiet`)
+/// * `Normal` - Also stage messages, warnings and progress bars (default)
+/// * `Verbose` - Also a line per file processed (`--verbose`)
+///
+/// With an event sink, `Quiet` passes on only `failed` and `summary` events.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum LogLevel {
+    Quiet,
+    #[default]
+    Normal,
+    Verbose,
+}
+
+impl LogLevel {
+    /// Returns the level set by the `--quiet` and `--verbose` flags.
+    pub fn from_flags(quiet: bool, verbose: bool) -> Self {
+        match (quiet, verbose) {
+            (true, _) => LogLevel::Quiet,
+            (false, true) => LogLevel::Verbose,
+            (false, false) => LogLevel::Normal,
+        }
+    }
+}
 
 /// Context for an organize operation.
 ///
@@ -27,6 +71,52 @@ use crate::organization;
 /// * `with_clustering` - Whether to enable geographic clustering (optional)
 /// * `jobs` - Number of parallel workers (None = auto-detect CPU count)
 /// * `index_path` - Path to load/save index file (None = use default `.sift_index.bin`)
+/// * `mode` - How files are transferred into the destination (defaults to copy)
+/// * `preserve_times` - Give copies the access and modification times of their source
+///   (default), rather than the time of the copy
+/// * `dry_run` - Plan destinations without touching the filesystem or the index
+/// * `recursive` - Scan nested folders of the source, not just its top level
+/// * `link_back` - Append a destination → source line to `.sift_manifest.jsonl` per organized file
+/// * `extensions` - Lowercase file extensions treated as photos (see [`supported_extensions`])
+/// * `by_content` - Pick photos by their magic bytes instead of `extensions` (see
+///   [`scan_photos`])
+/// * `log_level` - What is reported while running: only the summary and errors, stage
+///   messages and progress bars as well (default), or every file as well
+/// * `skip_existing_destination` - Skip, without hashing, files whose destination already
+///   exists with the same size
+/// * `exclude_destination` - Ignore files under the destination when it is nested in the source
+/// * `scan_dest` - Before planning, hash photos already in the destination that the index
+///   does not know and add them to it, so their content is skipped as a duplicate
+/// * `min_rating` - Skip photos rated below this many stars (unrated photos pass)
+/// * `require_rating` - Also skip photos that carry no rating at all
+/// * `date_range` - Skip files taken outside `--since` / `--until`
+/// * `min_size` - Skip files smaller than this many bytes (None = no lower bound)
+/// * `max_size` - Skip files larger than this many bytes (None = no upper bound)
+/// * `geonames` - GeoNames cities file used to name `{location}` places (None = embedded city list)
+/// * `geo_prefer_population` - Name clusters after the largest nearby place rather than the nearest
+/// * `granularity` - Date folder depth: year, month or day (default)
+/// * `by_camera` - Nest the date folders under one folder per camera
+/// * `template` - Destination folder layout (None = `YYYY/MM/DD` cut to `granularity`, under a
+///   camera folder with `by_camera`)
+/// * `checkpoint_every` - Save the index after every this many transfers (0 = only at the end)
+/// * `cancel` - Set to stop starting new transfers; the index is saved before returning
+/// * `include_hidden` - Also scan dotfiles and dot-folders (Sift's own files are always skipped)
+/// * `follow_symlinks` - Walk into symlinked folders when scanning recursively
+/// * `undated_dir` - Folder for files without a date, relative to the destination
+///   (default `Unsorted`)
+/// * `fast_dedup` - Only fully hash files that share a size and a partial hash with another
+///   file during analysis (see [`duplicate_candidates`])
+/// * `pixel_dedup` - Compare images by their decoded, upright pixels rather than their bytes
+///   (see [`hash::hash_pixels`]); files that cannot be decoded fall back to byte hashes
+/// * `max_open` - Most files read or transferred at once, whatever the number of
+///   workers (None = one per worker)
+/// * `manifest_path` - Where to write the run manifest (None = `manifest.json`
+///   at the destination root; see [`manifest`](crate::manifest))
+/// * `index_backend` - How the index is stored: one bincode file rewritten on each
+///   save (default), or a SQLite database only the new entries are written to
+/// * `index` - An index already loaded by the caller, used instead of reading
+///   `index_path` and updated in place (None = load it from `index_path`). The
+///   index is still saved to `index_path`.
 ///
 /// # Examples
 ///
@@ -53,6 +143,70 @@ pub struct OrganizeContext {
     pub jobs: Option<usize>,
     /// Path to load/save index file (None = use default)
     pub index_path: Option<PathBuf>,
+    /// How files are transferred into the destination
+    pub mode: FileOp,
+    /// Whether copied files keep the timestamps of their source
+    pub preserve_times: bool,
+    /// Report planned destinations without copying files or saving the index
+    pub dry_run: bool,
+    /// Whether to scan subdirectories of the source
+    pub recursive: bool,
+    /// Whether to write the `.sift_manifest.jsonl` audit trail at the destination root
+    pub link_back: bool,
+    /// Lowercase extensions (without the dot) picked up by the scan
+    pub extensions: Vec<String>,
+    /// Whether the scan sniffs file contents instead of matching extensions
+    pub by_content: bool,
+    /// How much is reported while running
+    pub log_level: LogLevel,
+    /// Whether to trust an existing same-size destination file instead of hashing
+    pub skip_existing_destination: bool,
+    /// Whether to leave out files already inside a destination nested in the source
+    pub exclude_destination: bool,
+    /// Whether unindexed photos already in the destination are hashed into the index
+    pub scan_dest: bool,
+    /// Minimum star rating (0-5) a photo needs to be organized
+    pub min_rating: Option<u8>,
+    /// Whether unrated photos are skipped
+    pub require_rating: bool,
+    /// Capture dates to organize; files outside it are skipped
+    pub date_range: DateRange,
+    /// Smallest file size organized, in bytes
+    pub min_size: Option<u64>,
+    /// Largest file size organized, in bytes
+    pub max_size: Option<u64>,
+    /// GeoNames cities file for reverse geocoding (None = embedded list)
+    pub geonames: Option<PathBuf>,
+    /// Whether location names favor larger places over nearer ones
+    pub geo_prefer_population: bool,
+    /// How deep the built-in date layout nests (ignored with a template)
+    pub granularity: Granularity,
+    /// Whether the built-in layout starts with a camera folder (ignored with a template)
+    pub by_camera: bool,
+    /// Folder layout under the destination (None = built-in date layout)
+    pub template: Option<PathTemplate>,
+    /// Number of transfers between index checkpoints (0 = save only at the end)
+    pub checkpoint_every: usize,
+    /// Cancellation flag, typically set from a Ctrl-C handler
+    pub cancel: Arc<AtomicBool>,
+    /// Whether files proven unique by size and partial hash are hashed after transfer
+    pub fast_dedup: bool,
+    /// Where files without a date go, relative to the destination
+    pub undated_dir: PathBuf,
+    /// Whether hidden files and folders are scanned
+    pub include_hidden: bool,
+    /// Whether recursive scans walk into symlinked folders
+    pub follow_symlinks: bool,
+    /// Whether images are deduplicated by pixel content instead of file bytes
+    pub pixel_dedup: bool,
+    /// Cap on concurrently open files (None = bounded only by `jobs`)
+    pub max_open: Option<usize>,
+    /// Path to write the run manifest to (None = use default)
+    pub manifest_path: Option<PathBuf>,
+    /// Storage format of the index file
+    pub index_backend: IndexBackend,
+    /// Index shared with the caller (None = load from `index_path`)
+    pub index: Option<IndexHandle>,
 }
 
 impl OrganizeContext {
@@ -69,6 +223,7 @@ impl OrganizeContext {
     /// # Returns
     ///
     /// A new OrganizeContext instance configured with the given parameters.
+    /// Files are copied by default; set `mode` to move or link them instead.
     pub fn new(
         source: PathBuf,
         destination: PathBuf,
@@ -82,6 +237,38 @@ impl OrganizeContext {
             with_clustering,
             jobs,
             index_path,
+            mode: FileOp::default(),
+            preserve_times: true,
+            dry_run: false,
+            recursive: false,
+            link_back: false,
+            extensions: resolve_extensions(&[], false),
+            by_content: false,
+            log_level: LogLevel::default(),
+            skip_existing_destination: false,
+            exclude_destination: true,
+            scan_dest: false,
+            min_rating: None,
+            require_rating: false,
+            date_range: DateRange::default(),
+            min_size: None,
+            max_size: None,
+            geonames: None,
+            geo_prefer_population: false,
+            granularity: Granularity::default(),
+            by_camera: false,
+            template: None,
+            checkpoint_every: DEFAULT_CHECKPOINT_EVERY,
+            cancel: Arc::new(AtomicBool::new(false)),
+            fast_dedup: false,
+            undated_dir: PathBuf::from(DEFAULT_UNDATED_DIR),
+            include_hidden: false,
+            follow_symlinks: false,
+            pixel_dedup: false,
+            max_open: None,
+            manifest_path: None,
+            index_backend: IndexBackend::default(),
+            index: None,
         }
     }
 
@@ -95,9 +282,91 @@ impl OrganizeContext {
     /// The path to the index file to use for this organization operation.
     pub fn get_index_path(&self) -> PathBuf {
         self.index_path.clone().unwrap_or_else(|| {
-            self.destination.join(".sift_index.bin")
+            self.destination.join(index::INDEX_FILE)
         })
     }
+
+    /// Returns the folder the built-in date layout starts from: the destination,
+    /// or the camera's folder inside it with `by_camera`.
+    fn layout_root(&self, camera: Option<&str>) -> PathBuf {
+        if self.by_camera {
+            let camera = camera.unwrap_or(UNKNOWN_CAMERA);
+            self.destination.join(organization::sanitize_path_segment(camera))
+        } else {
+            self.destination.clone()
+        }
+    }
+
+    /// Gets the path to the run manifest: `manifest_path`, or by default
+    /// `{destination}/manifest.json`.
+    pub fn get_manifest_path(&self) -> PathBuf {
+        self.manifest_path
+            .clone()
+            .unwrap_or_else(|| self.destination.join(MANIFEST_FILE))
+    }
+
+    /// Gets the path to the link-back manifest: `{destination}/.sift_manifest.jsonl`.
+    pub fn get_link_back_path(&self) -> PathBuf {
+        self.destination.join(LINK_BACK_FILE)
+    }
+}
+
+/// Default folder, under the destination, for files without a date.
+pub const DEFAULT_UNDATED_DIR: &str = "Unsorted";
+
+/// Default number of transfers between index checkpoints.
+pub const DEFAULT_CHECKPOINT_EVERY: usize = 500;
+
+/// File name of the append-only link-back manifest written at the destination root.
+pub const LINK_BACK_FILE: &str = ".sift_manifest.jsonl";
+
+/// One line of the link-back manifest, mapping an organized file back to its source.
+///
+/// The manifest is newline-delimited JSON, independent of the bincode index format,
+/// so it stays readable (and greppable) for auditing or un-organizing.
+///
+/// # Fields
+///
+/// * `destination` - Where the file was placed
+/// * `source` - Where the file came from
+/// * `hash` - Blake3 hash of the file contents
+/// * `date` - Date used to pick the destination folder
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LinkBackEntry {
+    pub destination: String,
+    pub source: String,
+    pub hash: String,
+    pub date: Option<NaiveDate>,
+}
+
+/// Appends link-back entries to the manifest, one complete line per write.
+struct LinkBackWriter {
+    file: File,
+}
+
+impl LinkBackWriter {
+    /// Opens (or creates) the manifest in append mode.
+    fn open(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(LinkBackWriter { file })
+    }
+
+    /// Appends a single entry.
+    ///
+    /// The serialized line is written with one `write_all` on an append-mode handle,
+    /// so concurrent readers never observe a partially-interleaved record.
+    fn append(&mut self, entry: &LinkBackEntry) -> io::Result<()> {
+        let mut line = serde_json::to_string(entry)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        line.push('\n');
+        self.file.write_all(line.as_bytes())?;
+        self.file.flush()
+    }
+
+    /// Flushes the manifest to stable storage.
+    fn finish(self) -> io::Result<()> {
+        self.file.sync_data()
+    }
 }
 
 /// Represents a file record after analysis.
@@ -109,9 +378,15 @@ impl OrganizeContext {
 /// # Fields
 ///
 /// * `path` - Original path to the file
-/// * `hash` - Blake3 hash of the file contents (hex string)
+/// * `hash` - Blake3 hash of the file contents (hex string); empty until the file is
+///   transferred when `fast_dedup` proved it unique
 /// * `date` - Extracted date from file metadata (for chronological organization)
 /// * `location` - GPS coordinates (latitude, longitude) if available (for clustering)
+/// * `place` - Name of the place the photo was taken at, once clusters are named
+/// * `camera` - Camera from EXIF, read only for `by_camera` or a `{camera}` template
+/// * `size` - File size in bytes at analysis time
+/// * `mtime_unix` - Modification time at analysis time, in seconds since the Unix epoch
+/// * `hash_kind` - Whether `hash` covers the file's bytes or its decoded pixels
 #[derive(Debug, Clone)]
 pub struct FileRecord {
     /// Original file path
@@ -122,6 +397,16 @@ pub struct FileRecord {
     pub date: Option<NaiveDate>,
     /// GPS coordinates if available (lat, lon)
     pub location: Option<(f64, f64)>,
+    /// Place name from reverse geocoding the photo's cluster
+    pub place: Option<String>,
+    /// Camera model, if requested and recorded
+    pub camera: Option<String>,
+    /// File size in bytes
+    pub size: u64,
+    /// Modification time (Unix seconds)
+    pub mtime_unix: i64,
+    /// What `hash` was computed over
+    pub hash_kind: HashKind,
 }
 
 /// Statistics for an organize operation.
@@ -134,23 +419,84 @@ pub struct FileRecord {
 ///
 /// * `files_scanned` - Total unique files discovered in source
 /// * `files_analyzed` - Files successfully hashed and analyzed
+/// * `files_hashed` - Files whose contents were hashed
 /// * `files_skipped_duplicates` - Files skipped because already in index
+/// * `files_skipped_existing` - Files skipped because their destination already exists
+/// * `files_skipped_in_destination` - Files skipped because they already live in the destination
+/// * `files_skipped_rating` - Files skipped by the star rating filter
+/// * `files_skipped_date` - Files skipped for being taken outside the date range
 /// * `files_organized` - Files successfully copied to destination
+/// * `files_undated` - Organized files that had no date and went to the undated folder
 /// * `files_failed` - Files that encountered errors during organization
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, Serialize)]
 pub struct OrganizeStats {
     /// Total files discovered
     pub files_scanned: usize,
     /// Files successfully hashed and analyzed
     pub files_analyzed: usize,
+    /// Files read and hashed
+    pub files_hashed: usize,
     /// Files skipped as duplicates
     pub files_skipped_duplicates: usize,
+    /// Files skipped by the size-only destination check
+    pub files_skipped_existing: usize,
+    /// Files found inside the destination tree during the scan
+    pub files_skipped_in_destination: usize,
+    /// Files below the minimum rating, or unrated when a rating is required
+    pub files_skipped_rating: usize,
+    /// Files outside `--since` / `--until`, or undated while a range is given
+    pub files_skipped_date: usize,
+    /// Files smaller than `--min-size` or larger than `--max-size`
+    pub files_skipped_size: usize,
+    /// Folders and entries the scan could not read
+    pub files_skipped_errors: usize,
     /// Files successfully organized
     pub files_organized: usize,
+    /// Organized files placed in the undated folder (also counted as organized)
+    pub files_undated: usize,
     /// Files that failed
     pub files_failed: usize,
 }
 
+/// Outcome of an [`organize`] run.
+///
+/// # Fields
+///
+/// * `stats` - Counters of the run, as in the summary
+/// * `errors` - One message per file that failed
+#[derive(Debug, Clone, Default)]
+pub struct OrganizeReport {
+    pub stats: OrganizeStats,
+    pub errors: Vec<String>,
+}
+
+/// Runs the organize pipeline without printing anything.
+///
+/// Entry point for programs embedding Sift: events go to `sink` instead of
+/// stderr (see [`Orchestrator::with_events`]), and failed files are returned in
+/// the report rather than listed in a summary.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use std::path::PathBuf;
+/// # use sift::events::Event;
+/// # use sift::organize::{self, OrganizeContext};
+/// let ctx = OrganizeContext::new(PathBuf::from("/source"), PathBuf::from("/dest"), false, None, None);
+/// let report = organize::organize(ctx, |event: &Event<'_>| {
+///     if let Event::Organized { dst, .. } = event {
+///         println!("{}", dst.display());
+///     }
+/// })?;
+/// println!("{} organized, {} failed", report.stats.files_organized, report.errors.len());
+/// # Ok::<(), sift::error::OrganizeError>(())
+/// ```
+pub fn organize<S: ProgressSink + 'static>(context: OrganizeContext, sink: S) -> OrganizeResult<OrganizeReport> {
+    let mut orchestrator = Orchestrator::new(context).with_events(sink);
+    let stats = orchestrator.run()?;
+    Ok(OrganizeReport { stats, errors: orchestrator.errors })
+}
+
 /// Main orchestrator for photo organization.
 ///
 /// Coordinates all stages of the photo organization pipeline:
@@ -167,6 +513,8 @@ pub struct Orchestrator {
     context: OrganizeContext,
     stats: OrganizeStats,
     errors: Vec<String>,
+    events: Option<Box<dyn ProgressSink>>,
+    open_limit: OpenLimit,
 }
 
 impl Orchestrator {
@@ -197,96 +545,498 @@ impl Orchestrator {
     /// ```
     pub fn new(context: OrganizeContext) -> Self {
         Orchestrator {
+            open_limit: OpenLimit::new(context.max_open),
             context,
             stats: OrganizeStats::default(),
             errors: Vec::new(),
+            events: None,
         }
     }
 
+    /// Reports progress as events to `events` (e.g. an
+    /// [`EventWriter`](crate::events::EventWriter) for `--json`) instead of
+    /// logging to stderr; progress bars are hidden too.
+    ///
+    /// See [`crate::events`] for the format.
+    pub fn with_events<S: ProgressSink + 'static>(mut self, events: S) -> Self {
+        self.events = Some(Box::new(events));
+        self
+    }
+
+    /// Messages for the files that failed so far, or lost their timestamps, as
+    /// listed in the summary.
+    pub fn errors(&self) -> &[String] {
+        &self.errors
+    }
+
     /// Runs the complete organize pipeline.
     ///
+    /// Equivalent to [`Orchestrator::plan`] followed by [`Orchestrator::execute`];
+    /// in dry-run mode the plan is only reported and nothing is executed.
+    pub fn run(&mut self) -> OrganizeResult<OrganizeStats> {
+        let plan = self.plan()?;
+
+        if !self.context.dry_run {
+            return self.execute(plan);
+        }
+
+        for action in plan.actions.iter().filter(|action| !action.already_present) {
+            self.emit(&Event::WouldOrganize { src: &action.record.path, dst: &action.destination })?;
+            self.log(format!(
+                "[DRY RUN] Would organize {:?} -> {:?}",
+                action.record.path, action.destination
+            ));
+        }
+        // Counted as "would be organized"; nothing is recorded in the index
+        self.stats.files_organized += plan.transfer_count();
+        self.stats.files_skipped_existing += plan.actions.len() - plan.transfer_count();
+        self.stats.files_undated += plan.undated_count();
+        self.log("[DRY RUN] Index not saved".to_string());
+        self.print_summary();
+        Ok(self.stats.clone())
+    }
+
+    /// Analyzes the source and decides where every file goes, without writing anything.
+    ///
     /// Stages:
-    /// 1. Load index from destination
-    /// 2. Scan source directory for photo files
+    /// 1. Load index from destination (and, with `scan_dest`, add the photos
+    ///    already in the destination to it)
+    /// 2. Scan source directory for photo files (and apply the scan filters)
     /// 3. Analyze files: hash and extract metadata
-    /// 4. Deduplicate against index
-    /// 5. Optionally cluster by location
-    /// 6. Organize into destination folder structure
-    /// 7. Save updated index
-    pub fn run(&mut self) -> io::Result<OrganizeStats> {
-        eprintln!("Starting photo organization...");
-        eprintln!("Source: {:?}", self.context.source);
-        eprintln!("Destination: {:?}", self.context.destination);
+    /// 4. Deduplicate against the index and within the batch
+    /// 5. Choose each destination, resolving collisions with files on disk and
+    ///    with other planned files
+    ///
+    /// Files that cannot be planned (e.g. no date) are counted as failed; an
+    /// unreadable index fails the whole plan with [`OrganizeError::IndexError`].
+    pub fn plan(&mut self) -> OrganizeResult<Plan> {
+        self.log("Starting photo organization...".to_string());
+        self.log(format!("Source: {:?}", self.context.source));
+        self.log(format!("Destination: {:?}", self.context.destination));
 
         // Stage 1: Load index
-        eprintln!("Loading index...");
-        let mut index = self.load_index()?;
-        eprintln!("Index loaded: {} entries", index.len());
+        let (index, stored) = self.load_index()?;
+        let stored = stored.map(Mutex::new);
+        let index_path = self.context.get_index_path();
+        if self.context.log_level == LogLevel::Verbose {
+            let view = IndexView { index: &index.read(), stored: stored.as_ref() };
+            let entries = view.len().map_err(|e| index_failure("read", &index_path, e))?;
+            self.log(format!("Index loaded: {} entries", entries));
+        }
+        let seeded = if self.context.scan_dest {
+            self.seed_from_destination(&index, stored.as_ref())?
+        } else {
+            Vec::new()
+        };
 
         // Stage 2: Scan source
-        eprintln!("Scanning source directory...");
         let files = self.scan_source()?;
         self.stats.files_scanned = files.len();
-        eprintln!("Found {} files", files.len());
+        self.log(format!("Found {} files", files.len()));
+
+        let files = if self.context.exclude_destination {
+            self.exclude_destination_files(files)?
+        } else {
+            files
+        };
 
         if files.is_empty() {
-            eprintln!("No files to process");
-            return Ok(self.stats.clone());
+            self.log("No files to process".to_string());
         }
 
+        let files = if self.context.min_size.is_some() || self.context.max_size.is_some() {
+            self.filter_by_size(files)
+        } else {
+            files
+        };
+
+        // Stage 2b: Cheap idempotence check, before any hashing
+        let files = if self.context.skip_existing_destination {
+            self.skip_existing_destinations(files)
+        } else {
+            files
+        };
+
+        let files = if self.context.min_rating.is_some() || self.context.require_rating {
+            self.filter_by_rating(files)
+        } else {
+            files
+        };
+
+        // Dates read by the date filter, so analysis doesn't read them again
+        let (files, dates) = if self.context.date_range.is_unbounded() {
+            (files, HashMap::new())
+        } else {
+            self.filter_by_date(files)
+        };
+
         // Stage 3: Analyze files
-        eprintln!("Analyzing files...");
-        let records = self.analyze_files(&files)?;
+        let progress = self.progress_bar(files.len(), "Analyzing");
+        let pool = self.thread_pool()?;
+        let records = pool.install(|| {
+            let index = IndexView { index: &index.read(), stored: stored.as_ref() };
+            self.analyze_files(&files, &index, &dates, &progress)
+        })?;
+        progress.finish_and_clear();
         self.stats.files_analyzed = records.len();
-        eprintln!("Analyzed {} files", records.len());
 
-        // Stage 4: Deduplicate
-        eprintln!("Deduplicating...");
-        let unique_records: Vec<_> = records
-            .into_iter()
-            .filter(|record| {
-                if index.contains_hash(&record.hash) {
-                    eprintln!("Skipping duplicate: {:?}", record.path);
+        // Stage 3b: Name the places photos were taken at
+        let mut records = records;
+        if self.names_places() {
+            let places = self.name_places(&records)?;
+            for (record, place) in records.iter_mut().zip(places) {
+                record.place = place;
+            }
+        }
+
+        // Stages 4 and 5: Deduplicate and choose destinations
+        let progress = self.progress_bar(records.len(), "Planning");
+        let mut plan = Plan {
+            actions: Vec::new(),
+            new_folders: BTreeSet::new(),
+            mode: self.context.mode,
+            index,
+            duplicates: Vec::new(),
+            seeded,
+        };
+        let mut planned_hashes: HashSet<String> = HashSet::new();
+        let mut reserved: HashSet<PathBuf> = HashSet::new();
+
+        for record in records {
+            progress.inc(1);
+            // Files `fast_dedup` proved unique have no hash yet and need no check
+            if !record.hash.is_empty() {
+                let existing = IndexView { index: &plan.index.read(), stored: stored.as_ref() }
+                    .get(&record.hash)
+                    .map_err(|e| index_failure("read", &index_path, e))?
+                    .map(|entry| entry.file_path().to_string());
+                if let Some(existing) = existing {
+                    self.emit(&Event::SkippedDuplicate {
+                        src: &record.path,
+                        duplicate_of: Some(&existing),
+                    })?;
+                    self.trace(
+                        &progress,
+                        format!(
+                            "Skipping duplicate {:?} (already present as {:?})",
+                            record.path, existing
+                        ),
+                    );
                     self.stats.files_skipped_duplicates += 1;
-                    false
-                } else {
-                    true
+                    plan.duplicates.push(ManifestEntry {
+                        src: record.path,
+                        dst: Some(PathBuf::from(existing)),
+                        hash: record.hash,
+                        hash_kind: record.hash_kind,
+                        date: record.date,
+                        action: ManifestAction::SkippedDuplicate,
+                    });
+                    continue;
                 }
-            })
-            .collect();
-
-        eprintln!(
-            "After dedup: {} unique files",
-            unique_records.len()
-        );
+                if !planned_hashes.insert(record.hash.clone()) {
+                    self.emit(&Event::SkippedDuplicate { src: &record.path, duplicate_of: None })?;
+                    self.trace(&progress, format!("Skipping duplicate {:?} (same content earlier in this run)", record.path));
+                    self.stats.files_skipped_duplicates += 1;
+                    // Its destination is only known once the earlier copy is organized
+                    plan.duplicates.push(ManifestEntry {
+                        src: record.path,
+                        dst: None,
+                        hash: record.hash,
+                        hash_kind: record.hash_kind,
+                        date: record.date,
+                        action: ManifestAction::SkippedDuplicate,
+                    });
+                    continue;
+                }
+            }
 
-        // Stage 5: Organize files
-        eprintln!("Organizing files...");
-        for record in unique_records {
-            match self.organize_file(&record) {
-                Ok(_) => {
-                    self.stats.files_organized += 1;
-                    // Add to index
-                    index.add_entry(record.hash, record.path.to_string_lossy().to_string());
+            match self.plan_file(record, &reserved) {
+                Ok(action) => {
+                    if !action.already_present {
+                        reserved.insert(action.destination.clone());
+                        plan.add_new_folders(&action.destination, &self.context.destination);
+                    }
+                    plan.actions.push(action);
                 }
-                Err(e) => {
-                    let err_msg = format!("Failed to organize {:?}: {}", record.path, e);
-                    eprintln!("{}", err_msg);
+                Err((path, e)) => {
+                    self.emit(&Event::Failed { src: &path, error: e.to_string() })?;
+                    let err_msg = format!("Failed to organize {:?}: {}", path, e);
+                    self.trace(&progress, err_msg.clone());
                     self.errors.push(err_msg);
                     self.stats.files_failed += 1;
                 }
             }
         }
+        progress.finish_and_clear();
+
+        self.trace(&progress, format!("Planned {} files", plan.actions.len()));
+        Ok(plan)
+    }
+
+    /// Carries out a plan produced by [`Orchestrator::plan`] and saves the index.
+    ///
+    /// Every planned transfer lands exactly at its planned destination. If that path
+    /// was taken in the meantime the file fails instead of overwriting it.
+    ///
+    /// Failing to save the index is an [`OrganizeError::IndexError`]; a cancelled run
+    /// returns an [`OrganizeError::IoError`] of kind `Interrupted` once the index is saved.
+    pub fn execute(&mut self, plan: Plan) -> OrganizeResult<OrganizeStats> {
+        let Plan { actions, index, duplicates, seeded, .. } = plan;
+        let mut manifest: Vec<ManifestEntry> = Vec::with_capacity(actions.len() + duplicates.len());
+
+        let progress = self.progress_bar(actions.len(), "Organizing");
+        let mut link_back = if self.context.link_back {
+            Some(LinkBackWriter::open(&self.context.get_link_back_path())?)
+        } else {
+            None
+        };
 
-        // Stage 6: Save index
-        eprintln!("Saving index...");
         let index_path = self.context.get_index_path();
-        index.save_to_file(&index_path)?;
-        eprintln!("Index saved to {:?}", index_path);
+        // Entries not yet written to a SQLite index
+        let mut unsaved: Vec<IndexEntry> = match self.context.index_backend {
+            IndexBackend::Bincode => Vec::new(),
+            IndexBackend::Sqlite => seeded,
+        };
+        let batch_size = match self.context.checkpoint_every {
+            0 => usize::MAX,
+            n => n,
+        };
+        let pool = self.thread_pool()?;
+        let mut actions = actions.into_iter();
+
+        // Transfers run in parallel, one batch at a time. Workers record each
+        // transfer in the shared index as it completes; the link-back file is only
+        // written between batches, from this thread. Saving the index after each
+        // batch lets an interrupted run resume where it stopped, since anything
+        // already indexed is skipped as a duplicate.
+        loop {
+            let batch: Vec<PlannedAction> = actions.by_ref().take(batch_size).collect();
+            if batch.is_empty() {
+                break;
+            }
+            // Each finished transfer carries the error that kept its timestamps from being set, if any
+            let results: Vec<(PlannedAction, Option<OrganizeResult<Option<io::Error>>>)> = pool.install(|| {
+                batch
+                    .into_par_iter()
+                    .map(|action| {
+                        // Once cancelled, let in-flight transfers finish but start no more
+                        if self.cancelled() {
+                            return (action, None);
+                        }
+                        let mut action = action;
+                        let permit = self.open_limit.acquire();
+                        let result = self.realize(&action).and_then(|times_error| {
+                            if action.record.hash.is_empty() {
+                                // Read the transferred copy rather than the (possibly remote) source
+                                let hash = hash::hash_file(&action.destination).map_err(|e| {
+                                    OrganizeError::HashError(format!("{:?}: {}", action.destination, e))
+                                })?;
+                                action.record.hash = hash.to_hex().to_string();
+                            }
+                            index.insert(action.index_entry());
+                            Ok(times_error)
+                        });
+                        drop(permit);
+                        progress.inc(1);
+                        (action, Some(result))
+                    })
+                    .collect()
+            });
+
+            for (action, result) in results {
+                match result {
+                    None => {}
+                    Some(Ok(times_error)) => {
+                        if self.context.index_backend == IndexBackend::Sqlite {
+                            unsaved.push(action.index_entry());
+                        }
+                        if let Some(e) = times_error {
+                            let err_msg = format!("Could not keep the timestamps of {:?}: {}", action.destination, e);
+                            self.trace(&progress, err_msg.clone());
+                            self.errors.push(err_msg);
+                        }
+                        let record = action.record;
+                        if action.already_present {
+                            // Identical content was already there: recorded, not transferred
+                            self.trace(&progress, format!("Already at {:?}: {:?}", action.destination, record.path));
+                            self.stats.files_skipped_existing += 1;
+                        } else {
+                            self.trace(&progress, format!("Organized {:?} -> {:?}", record.path, action.destination));
+                            self.stats.files_organized += 1;
+                            self.emit(&Event::Organized {
+                                src: &record.path,
+                                dst: &action.destination,
+                                hash: &record.hash,
+                            })?;
+                        }
+                        if action.undated {
+                            self.stats.files_undated += 1;
+                        }
+                        manifest.push(ManifestEntry {
+                            src: record.path.clone(),
+                            dst: Some(action.destination.clone()),
+                            hash: record.hash.clone(),
+                            hash_kind: record.hash_kind,
+                            date: record.date,
+                            action: if action.already_present {
+                                ManifestAction::AlreadyPresent
+                            } else {
+                                self.context.mode.into()
+                            },
+                        });
+                        if let Some(writer) = link_back.as_mut() {
+                            writer.append(&LinkBackEntry {
+                                destination: action.destination.to_string_lossy().to_string(),
+                                source: record.path.to_string_lossy().to_string(),
+                                hash: record.hash,
+                                date: record.date,
+                            })?;
+                        }
+                    }
+                    Some(Err(e)) => {
+                        self.emit(&Event::Failed { src: &action.record.path, error: e.to_string() })?;
+                        let err_msg = format!("Failed to organize {:?}: {}", action.record.path, e);
+                        self.trace(&progress, err_msg.clone());
+                        self.errors.push(err_msg);
+                        self.stats.files_failed += 1;
+                    }
+                }
+            }
+
+            if self.cancelled() {
+                break;
+            }
+            if actions.len() > 0 {
+                self.save_index(&index, &mut unsaved)?;
+            }
+        }
+        progress.finish_and_clear();
+
+        if let Some(writer) = link_back {
+            writer.finish()?;
+        }
+        self.save_index(&index, &mut unsaved)?;
+        self.trace(&progress, format!("Index saved to {:?}", index_path));
+        self.write_manifest(manifest, duplicates)?;
+
+        if self.cancelled() {
+            self.print_summary();
+            return Err(OrganizeError::IoError(io::Error::new(
+                io::ErrorKind::Interrupted,
+                format!(
+                    "interrupted after organizing {} files; progress is saved, re-run to continue",
+                    self.stats.files_organized
+                ),
+            )));
+        }
+
+        self.print_summary();
+        Ok(self.stats.clone())
+    }
+
+    /// Writes the run manifest: organized files, then skipped duplicates.
+    ///
+    /// Duplicates of files organized earlier in the same run get that file's
+    /// destination; if it failed, their `dst` stays empty. A run that transferred
+    /// nothing leaves the default manifest alone, so the last run that did can
+    /// still be undone.
+    fn write_manifest(
+        &self,
+        mut entries: Vec<ManifestEntry>,
+        duplicates: Vec<ManifestEntry>,
+    ) -> OrganizeResult<()> {
+        let path = self.context.get_manifest_path();
+        if self.context.manifest_path.is_none()
+            && entries.iter().all(|entry| entry.action == ManifestAction::AlreadyPresent)
+        {
+            self.log(format!("Nothing transferred; keeping the previous manifest {:?}", path));
+            return Ok(());
+        }
+
+        let organized: HashMap<&str, &Path> = entries
+            .iter()
+            .filter_map(|entry| Some((entry.hash.as_str(), entry.dst.as_deref()?)))
+            .collect();
+        let duplicates: Vec<ManifestEntry> = duplicates
+            .into_iter()
+            .map(|mut entry| {
+                if entry.dst.is_none() {
+                    entry.dst = organized.get(entry.hash.as_str()).map(|dst| dst.to_path_buf());
+                }
+                entry
+            })
+            .collect();
+        entries.extend(duplicates);
+
+        manifest::write_manifest(&path, &entries).map_err(|e| {
+            OrganizeError::IoError(io::Error::new(e.kind(), format!("cannot write manifest {:?}: {}", path, e)))
+        })
+    }
+
+    /// Returns `true` if photos need place names, for a `{location}` template.
+    fn names_places(&self) -> bool {
+        self.uses_token(Token::Location)
+    }
+
+    /// Returns `true` if the path template references `token`.
+    fn uses_token(&self, token: Token) -> bool {
+        self.context.template.as_ref().is_some_and(|template| template.uses(token))
+    }
+
+    /// Returns `true` once the run has been asked to stop.
+    fn cancelled(&self) -> bool {
+        self.context.cancel.load(Ordering::Relaxed)
+    }
+
+    /// Builds the thread pool for parallel stages, sized by `jobs` (default: one per CPU).
+    ///
+    /// Hashing and transfers run inside this pool rather than Rayon's global one, so
+    /// `--jobs` also caps the number of concurrent connections to a network share.
+    fn thread_pool(&self) -> OrganizeResult<rayon::ThreadPool> {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(self.context.jobs.unwrap_or(0))
+            .build()
+            .map_err(|e| OrganizeError::Other(format!("cannot start worker threads: {}", e)))
+    }
 
+    /// Prints the end-of-run counters and any errors.
+    fn print_summary(&self) {
+        if let Some(events) = &self.events {
+            // Best effort: the run itself already succeeded or failed on its own
+            let _ = events.emit(&Event::Summary(&self.stats));
+            return;
+        }
         eprintln!("\nOrganization complete!");
-        eprintln!("Files organized: {}", self.stats.files_organized);
+        if self.context.dry_run {
+            eprintln!("Files that would be organized: {}", self.stats.files_organized);
+        } else {
+            eprintln!("Files organized: {}", self.stats.files_organized);
+        }
+        if self.stats.files_undated > 0 {
+            eprintln!(
+                "Without a date (in {:?}): {}",
+                self.context.undated_dir, self.stats.files_undated
+            );
+        }
         eprintln!("Duplicates skipped: {}", self.stats.files_skipped_duplicates);
+        if self.context.skip_existing_destination {
+            eprintln!("Already at destination: {}", self.stats.files_skipped_existing);
+        }
+        if self.stats.files_skipped_rating > 0 {
+            eprintln!("Filtered out by rating: {}", self.stats.files_skipped_rating);
+        }
+        if self.stats.files_skipped_date > 0 {
+            eprintln!("Outside date range: {}", self.stats.files_skipped_date);
+        }
+        if self.stats.files_skipped_size > 0 {
+            eprintln!("Outside size range: {}", self.stats.files_skipped_size);
+        }
+        if self.stats.files_skipped_errors > 0 {
+            eprintln!("Unreadable (skipped): {}", self.stats.files_skipped_errors);
+        }
+        if self.stats.files_skipped_in_destination > 0 {
+            eprintln!("Inside destination (ignored): {}", self.stats.files_skipped_in_destination);
+        }
         eprintln!("Failed: {}", self.stats.files_failed);
 
         if !self.errors.is_empty() {
@@ -295,313 +1045,3202 @@ impl Orchestrator {
                 eprintln!("  - {}", err);
             }
         }
-
-        Ok(self.stats.clone())
     }
 
-    /// Loads the index from the destination directory.
-    fn load_index(&self) -> io::Result<Index> {
+    /// Returns the index shared through the context, or loads it from the
+    /// destination directory.
+    ///
+    /// A SQLite index is not loaded: it is opened and returned alongside an
+    /// empty index, which collects the entries of this run until they are saved.
+    ///
+    /// An existing index stored with another backend than `index_backend` is an
+    /// error, rather than being silently converted by the next save.
+    fn load_index(&self) -> OrganizeResult<(IndexHandle, Option<SqliteIndex>)> {
+        if let Some(index) = &self.context.index {
+            return Ok((index.clone(), None));
+        }
         let index_path = self.context.get_index_path();
-        if index_path.exists() {
-            Index::load_from_file(&index_path)
-        } else {
-            Ok(Index::new())
+        match IndexBackend::detect(&index_path) {
+            None => Ok((IndexHandle::default(), None)),
+            Some(found) if found != self.context.index_backend => Err(OrganizeError::IndexError(format!(
+                "{:?} is a {} index; pass --index-format {} to use it",
+                index_path, found, found
+            ))),
+            Some(IndexBackend::Sqlite) => SqliteIndex::open(&index_path)
+                .map(|stored| (IndexHandle::default(), Some(stored)))
+                .map_err(|e| index_failure("load", &index_path, e)),
+            Some(IndexBackend::Bincode) => IndexHandle::load(&index_path)
+                .map(|index| (index, None))
+                .map_err(|e| index_failure("load", &index_path, e)),
+        }
+    }
+
+    /// Hashes the photos in the destination tree that `index` does not list yet
+    /// and adds them to it, so content organized by another tool (or by hand) is
+    /// recognized as a duplicate. Returns the added entries.
+    ///
+    /// The destination is always scanned recursively, without hidden files or
+    /// symlinked folders. Files that cannot be read are left out.
+    fn seed_from_destination(
+        &mut self,
+        index: &IndexHandle,
+        stored: Option<&Mutex<SqliteIndex>>,
+    ) -> OrganizeResult<Vec<IndexEntry>> {
+        let destination = self.context.destination.clone();
+        if !destination.is_dir() {
+            return Ok(Vec::new());
+        }
+        let known = IndexView { index: &index.read(), stored }
+            .file_paths()
+            .map_err(|e| index_failure("read", &self.context.get_index_path(), e))?;
+        // A source inside the destination holds what is about to be organized, not the library
+        let nested_source = nested_destination(&destination, &self.context.source)?;
+        let options = ScanOptions { recursive: true, by_content: self.context.by_content, ..Default::default() };
+        let scan = scan_photos(&destination, &self.context.extensions, options)?;
+        let files: Vec<PathBuf> = self
+            .skip_unreadable(scan)
+            .into_iter()
+            .filter(|path| nested_source.as_ref().is_none_or(|source| !path.starts_with(source)))
+            .filter(|path| !known.contains(path.to_string_lossy().as_ref()))
+            .collect();
+
+        let progress = self.progress_bar(files.len(), "Indexing destination");
+        let pool = self.thread_pool()?;
+        let entries: Vec<IndexEntry> = pool.install(|| {
+            files
+                .par_iter()
+                .filter_map(|path| {
+                    progress.inc(1);
+                    let _permit = self.open_limit.acquire();
+                    existing_entry(path, self.context.pixel_dedup)
+                })
+                .collect()
+        });
+        progress.finish_and_clear();
+
+        for entry in &entries {
+            index.insert(entry.clone());
         }
+        self.log(format!("Seeded index with {} files already in the destination", entries.len()));
+        Ok(entries)
+    }
+
+    /// Saves the index, reporting failure as an [`OrganizeError::IndexError`].
+    ///
+    /// A bincode index is rewritten whole; a SQLite index only receives the
+    /// `unsaved` entries, which are drained.
+    fn save_index(&self, index: &IndexHandle, unsaved: &mut Vec<IndexEntry>) -> OrganizeResult<()> {
+        let path = self.context.get_index_path();
+        let saved = match self.context.index_backend {
+            IndexBackend::Bincode => index.save(&path),
+            IndexBackend::Sqlite => SqliteIndex::open(&path).and_then(|mut store| store.insert_all(unsaved.drain(..))),
+        };
+        saved.map_err(|e| index_failure("save", &path, e))
     }
 
     /// Scans the source directory for photo files.
     ///
+    /// Only the top level of the source is scanned unless `recursive` is set in the
+    /// context, in which case all nested folders (e.g. `DCIM/100CANON/`) are walked.
+    /// Results are sorted by path so runs process files in a stable order.
+    ///
     /// # Symlink Behavior
     ///
     /// The scanner follows symbolic links when encountered. If a symlink points to:
     /// - **A file**: The file is checked for photo extensions and included if matched
-    /// - **A directory**: The directory contents are NOT recursively traversed (non-recursive scan)
-    ///
-    /// This behavior allows organizing photos from symlinked files while preventing
-    /// infinite loops from circular symlink references. For recursive scanning including
-    /// symlinked directories, use a dedicated recursive walker (planned for future).
-    ///
-    /// # Note on Recursion
-    ///
-    /// The current implementation only scans the immediate source directory (non-recursive).
-    /// To organize photos from nested directories, the source path should point to a
-    /// directory containing all photos, or use a glob pattern in future versions.
-    fn scan_source(&self) -> io::Result<Vec<PathBuf>> {
-        let mut files = Vec::new();
-        let photo_extensions = ["jpg", "jpeg", "png", "tiff", "raw", "heic"];
-
-        for entry in fs::read_dir(&self.context.source)? {
-            let entry = entry?;
-            let path = entry.path();
-
-            // Follow symlinks: is_file() returns true for symlinks pointing to files
-            if path.is_file()
-                && let Some(ext) = path.extension() {
-                    let ext_lower = ext.to_string_lossy().to_lowercase();
-                    if photo_extensions.contains(&ext_lower.as_str()) {
-                        files.push(path);
-                    }
-                }
+    /// - **A directory**: The directory contents are only traversed when recursive
+    ///   with `follow_symlinks`; a link back to one of its own parent folders is
+    ///   skipped with a warning, so circular links cannot loop forever
+    ///
+    /// Folders and entries that cannot be read are skipped with a warning and
+    /// counted in `files_skipped_errors`; an unreadable source fails the scan.
+    fn scan_source(&mut self) -> io::Result<Vec<PathBuf>> {
+        let options = ScanOptions {
+            recursive: self.context.recursive,
+            include_hidden: self.context.include_hidden,
+            follow_symlinks: self.context.follow_symlinks,
+            by_content: self.context.by_content,
+        };
+        let scan = scan_photos(&self.context.source, &self.context.extensions, options)?;
+        Ok(self.skip_unreadable(scan))
+    }
+
+    /// Warns about the entries a scan could not read and counts them as
+    /// skipped, returning the files it found. Symlink loops are only warned about.
+    fn skip_unreadable(&mut self, scan: PhotoScan) -> Vec<PathBuf> {
+        for path in &scan.symlink_loops {
+            self.log(format!("Warning: skipping symlink loop at {:?}", path));
+        }
+        for (path, err) in &scan.unreadable {
+            self.log(format!("Warning: skipping unreadable {:?}: {}", path, err));
+        }
+        self.stats.files_skipped_errors += scan.unreadable.len();
+        scan.files
+    }
+
+    /// Prints a status message to stderr, unless quiet or emitting JSON events.
+    fn log(&self, message: String) {
+        if self.context.log_level > LogLevel::Quiet && self.events.is_none() {
+            eprintln!("{}", message);
+        }
+    }
+
+    /// Writes a JSON event, if events were requested. When quiet, only failures
+    /// and the summary are passed on.
+    fn emit(&self, event: &Event<'_>) -> io::Result<()> {
+        match &self.events {
+            Some(_) if self.context.log_level == LogLevel::Quiet
+                && !matches!(event, Event::Failed { .. } | Event::Summary(_)) => Ok(()),
+            Some(events) => events.emit(event),
+            None => Ok(()),
+        }
+    }
+
+    /// Reports a file that failed analysis: as an event, or on stderr.
+    fn report_failure(&self, progress: &ProgressBar, path: &Path, message: String) -> io::Result<()> {
+        match &self.events {
+            Some(events) => events.emit(&Event::Failed { src: path, error: message }),
+            None => {
+                progress.suspend(|| eprintln!("{}", message));
+                Ok(())
+            }
+        }
+    }
+
+    /// Creates a stage progress bar, hidden when quiet or emitting JSON events.
+    ///
+    /// Network retry notices are printed around the bar until the next stage,
+    /// and dropped whenever the bar is hidden.
+    fn progress_bar(&self, len: usize, stage: &'static str) -> ProgressBar {
+        if self.context.log_level == LogLevel::Quiet || self.events.is_some() {
+            network_io::set_retry_reporter(Some(Box::new(|_| {})));
+            return ProgressBar::hidden();
         }
+        let progress = progress_bar(len, stage);
+        let around = progress.clone();
+        network_io::set_retry_reporter(Some(Box::new(move |message| around.suspend(|| eprintln!("{}", message)))));
+        progress
+    }
 
-        Ok(files)
+    /// Prints a per-file message when verbose, without tearing the progress bar.
+    fn trace(&self, progress: &ProgressBar, message: String) {
+        if self.context.log_level == LogLevel::Verbose && self.events.is_none() {
+            progress.suspend(|| eprintln!("{}", message));
+        }
     }
 
     /// Analyzes files: computes hashes and extracts metadata.
-    fn analyze_files(&self, files: &[PathBuf]) -> io::Result<Vec<FileRecord>> {
-        let records: Vec<FileRecord> = files
+    ///
+    /// Files the index already knows under the same source path, size and mtime
+    /// are not re-read: their recorded hash is reused, so deduplication drops them.
+    ///
+    /// `progress` is advanced once per file from the worker threads; its
+    /// counter is atomic, so the shared reference needs no extra locking.
+    ///
+    /// Files with an entry in `dates` take their date from it rather than
+    /// reading it again.
+    ///
+    /// Files that cannot be read ([`OrganizeError::FileAccess`]) or hashed
+    /// ([`OrganizeError::HashError`]) are reported and counted as failed.
+    fn analyze_files(
+        &mut self,
+        files: &[PathBuf],
+        index: &IndexView,
+        dates: &HashMap<PathBuf, Option<NaiveDate>>,
+        progress: &ProgressBar,
+    ) -> OrganizeResult<Vec<FileRecord>> {
+        // Pixel hashes can't be narrowed down by size, since equal pictures differ in size
+        let candidates = if self.context.fast_dedup && !self.context.pixel_dedup {
+            index
+                .sizes()
+                .map_err(|e| index_failure("read", &self.context.get_index_path(), e))?
+                .map(|indexed_sizes| duplicate_candidates(files, &indexed_sizes, &self.open_limit))
+        } else {
+            None
+        };
+        let hashed = AtomicUsize::new(0);
+        let results: Vec<(&PathBuf, OrganizeResult<FileRecord>)> = files
             .par_iter()
-            .filter_map(|path| {
-                match hash::hash_file(path) {
-                    Ok(blake3_hash) => {
-                        let hash_str = blake3_hash.to_hex().to_string();
-                        let date = metadata::extract_date_with_fallback(path);
-
-                        Some(FileRecord {
-                            path: path.clone(),
-                            hash: hash_str,
-                            date,
-                            location: None, // TODO: Extract from EXIF GPS
-                        })
-                    }
-                    Err(e) => {
-                        eprintln!("Failed to hash {:?}: {}", path, e);
-                        None
-                    }
-                }
+            .map(|path| {
+                let date = dates.get(path).copied();
+                (path, self.analyze_file(path, index, date, candidates.as_ref(), &hashed, progress))
             })
             .collect();
 
+        let mut records = Vec::with_capacity(results.len());
+        for (path, result) in results {
+            match result {
+                Ok(record) => records.push(record),
+                Err(e) => {
+                    let err_msg = format!("Failed to analyze {:?}: {}", path, e);
+                    self.report_failure(progress, path, err_msg.clone())?;
+                    self.errors.push(err_msg);
+                    self.stats.files_failed += 1;
+                }
+            }
+        }
+
+        self.stats.files_hashed += hashed.into_inner();
         Ok(records)
     }
 
-    /// Organizes a single file to its destination.
-    fn organize_file(&self, record: &FileRecord) -> io::Result<PathBuf> {
-        let date = record.date.ok_or_else(|| {
-            io::Error::new(
-                io::ErrorKind::InvalidData,
-                "Cannot organize file without date",
-            )
-        })?;
+    /// Hashes one file and extracts the metadata its destination depends on.
+    ///
+    /// The date is read from the file unless `date` already holds it. With
+    /// `fast_dedup`, files missing from `candidates` are left unhashed. With
+    /// `pixel_dedup`, decodable images get a pixel hash and other files a byte hash.
+    fn analyze_file(
+        &self,
+        path: &Path,
+        index: &IndexView,
+        date: Option<Option<NaiveDate>>,
+        candidates: Option<&HashSet<PathBuf>>,
+        hashed: &AtomicUsize,
+        progress: &ProgressBar,
+    ) -> OrganizeResult<FileRecord> {
+        progress.inc(1);
+        let stat = fs::metadata(path).map_err(|e| OrganizeError::FileAccess(e.to_string()))?;
+        let size = stat.len();
+        let mtime_unix = index::mtime_unix(&stat);
 
-        organization::organize_by_date(&record.path, &self.context.destination, date)
-    }
-}
+        let source = path.to_string_lossy();
+        let wanted_kind = if self.context.pixel_dedup && hash::supports_pixels(path) {
+            HashKind::Pixels
+        } else {
+            HashKind::Bytes
+        };
+        let indexed = index
+            .get_by_source(&source)
+            .map_err(|e| index_failure("read", &self.context.get_index_path(), e))?;
+        if let Some(entry) = indexed
+            && entry.size == size
+            && entry.mtime_unix == mtime_unix
+            && entry.hash_kind == wanted_kind {
+                return Ok(FileRecord {
+                    path: path.to_path_buf(),
+                    hash: entry.hash,
+                    date: None,
+                    location: None,
+                    place: None,
+                    camera: None,
+                    size,
+                    mtime_unix,
+                    hash_kind: entry.hash_kind,
+                });
+            }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::fs;
-    use tempfile::TempDir;
-    use chrono::Datelike;
+        // Hashing and metadata extraction below all open the file
+        let _permit = self.open_limit.acquire();
 
-    #[test]
-    fn test_organize_context_creation() {
-        let ctx = OrganizeContext::new(
-            PathBuf::from("/source"),
-            PathBuf::from("/dest"),
-            false,
-            Some(4),
-            None,
-        );
+        // Images that fail to decode fall back to a byte hash
+        let pixel_hash = if wanted_kind == HashKind::Pixels {
+            hash::hash_pixels(path).ok()
+        } else {
+            None
+        };
+        let (hash, hash_kind) = if let Some(hash) = pixel_hash {
+            hashed.fetch_add(1, Ordering::Relaxed);
+            (hash.to_hex().to_string(), HashKind::Pixels)
+        } else if candidates.is_none_or(|candidates| candidates.contains(path)) {
+            hashed.fetch_add(1, Ordering::Relaxed);
+            let hash = hash::hash_file_resilient(path)
+                .map_err(|e| OrganizeError::HashError(e.to_string()))?;
+            (hash.to_hex().to_string(), HashKind::Bytes)
+        } else {
+            (String::new(), HashKind::Bytes)
+        };
 
-        assert_eq!(ctx.source, PathBuf::from("/source"));
-        assert_eq!(ctx.destination, PathBuf::from("/dest"));
-        assert!(!ctx.with_clustering);
-        assert_eq!(ctx.jobs, Some(4));
-    }
+        let date = date.unwrap_or_else(|| metadata::extract_date_with_fallback(path));
+        // Only re-read under --verbose, where the fallback is reported
+        if self.context.log_level == LogLevel::Verbose
+            && let Some((recorded, fallback)) = metadata::exif_date_fallback(path) {
+                self.trace(progress, format!("Incomplete EXIF date {:?} in {:?}: {}", recorded, path, fallback));
+            }
+        let location = if self.names_places() {
+            metadata::extract_gps(path)
+                .filter(|fix| clustering::is_plausible_fix(fix.latitude, fix.longitude, None, None))
+                .map(|fix| (fix.latitude, fix.longitude))
+        } else {
+            None
+        };
 
-    #[test]
-    fn test_organize_context_default_index_path() {
-        let ctx = OrganizeContext::new(
+        let camera = if self.context.by_camera || self.uses_token(Token::Camera) {
+            metadata::extract_camera(path)
+        } else {
+            None
+        };
+
+        Ok(FileRecord {
+            path: path.to_path_buf(),
+            hash,
+            date,
+            location,
+            place: None,
+            camera,
+            size,
+            mtime_unix,
+            hash_kind,
+        })
+    }
+
+    /// Drops scanned files that live inside the destination tree.
+    ///
+    /// When the destination is nested in the source (e.g. `~/Photos/Organized` while
+    /// organizing `~/Photos --recursive`), the scan would otherwise pick up every
+    /// already-organized photo again on each run.
+    fn exclude_destination_files(&mut self, files: Vec<PathBuf>) -> io::Result<Vec<PathBuf>> {
+        let Some(nested) = nested_destination(&self.context.source, &self.context.destination)?
+        else {
+            return Ok(files);
+        };
+
+        let scanned = files.len();
+        let pending: Vec<PathBuf> = files
+            .into_iter()
+            .filter(|path| !path.starts_with(&nested))
+            .collect();
+
+        self.stats.files_skipped_in_destination += scanned - pending.len();
+        Ok(pending)
+    }
+
+    /// Drops photos that fail the `min_rating` / `require_rating` filter.
+    fn filter_by_rating(&mut self, files: Vec<PathBuf>) -> Vec<PathBuf> {
+        let scanned = files.len();
+        let min_rating = self.context.min_rating.unwrap_or(0);
+        let require_rating = self.context.require_rating;
+        let pending: Vec<PathBuf> = files
+            .into_par_iter()
+            .filter(|path| match metadata::extract_rating(path) {
+                Some(rating) => rating >= min_rating,
+                None => !require_rating,
+            })
+            .collect();
+
+        self.stats.files_skipped_rating += scanned - pending.len();
+        pending
+    }
+
+    /// Drops files outside the `min_size` / `max_size` bounds, which are inclusive.
+    ///
+    /// Files whose size cannot be read are kept, so that analysis reports them.
+    fn filter_by_size(&mut self, files: Vec<PathBuf>) -> Vec<PathBuf> {
+        let scanned = files.len();
+        let min = self.context.min_size.unwrap_or(0);
+        let max = self.context.max_size.unwrap_or(u64::MAX);
+        let pending: Vec<PathBuf> = files
+            .into_par_iter()
+            .filter(|path| fs::metadata(path).map_or(true, |stat| (min..=max).contains(&stat.len())))
+            .collect();
+
+        self.stats.files_skipped_size += scanned - pending.len();
+        pending
+    }
+
+    /// Drops files taken outside the `date_range`, dated as they would be organized.
+    ///
+    /// Returns the remaining files along with the date read for each.
+    fn filter_by_date(&mut self, files: Vec<PathBuf>) -> (Vec<PathBuf>, HashMap<PathBuf, Option<NaiveDate>>) {
+        let scanned = files.len();
+        let range = self.context.date_range;
+        let pending: Vec<(PathBuf, Option<NaiveDate>)> = files
+            .into_par_iter()
+            .map(|path| {
+                let date = metadata::extract_date_with_fallback(&path);
+                (path, date)
+            })
+            .filter(|(_, date)| range.admits(*date))
+            .collect();
+
+        self.stats.files_skipped_date += scanned - pending.len();
+        let files = pending.iter().map(|(path, _)| path.clone()).collect();
+        (files, pending.into_iter().collect())
+    }
+
+    /// Drops files whose date-derived destination already exists with the same size.
+    ///
+    /// This is a size-only comparison: a different photo with the same name, date
+    /// and size would wrongly be taken as organized. It is meant for append-only
+    /// sources with unique filenames, where it avoids re-reading every file.
+    fn skip_existing_destinations(&mut self, files: Vec<PathBuf>) -> Vec<PathBuf> {
+        let scanned = files.len();
+        let context = &self.context;
+        let pending: Vec<PathBuf> = files
+            .into_par_iter()
+            .filter(|path| !destination_matches(path, context))
+            .collect();
+
+        self.stats.files_skipped_existing += scanned - pending.len();
+        pending
+    }
+
+    /// Clusters the records' GPS positions and names each cluster after the closest
+    /// known place.
+    ///
+    /// Returns one entry per record: the place name, or `None` for records without
+    /// GPS data or outside any cluster.
+    fn name_places(&self, records: &[FileRecord]) -> io::Result<Vec<Option<String>>> {
+        let locations = match &self.context.geonames {
+            Some(path) => geonames::load_geonames_from_file(path)?,
+            None => geonames::load_geonames(),
+        };
+
+        // dbscan requires point ids to match their index
+        let mut points = Vec::new();
+        let mut owners = Vec::new();
+        for (index, record) in records.iter().enumerate() {
+            if let Some((latitude, longitude)) = record.location {
+                points.push(GeoPoint { id: points.len(), latitude, longitude });
+                owners.push(index);
+            }
+        }
+
+        // A place needs a photo with three others within 1km, as before dbscan
+        // counted the core point itself towards `min_points`
+        let mut places = vec![None; records.len()];
+        for members in clustering::dbscan(&points, 1.0, 4).into_values() {
+            let name = clustering::name_location(
+                &clustering::cluster_centroid(&points, &members),
+                &locations,
+                self.context.geo_prefer_population,
+            );
+            for member in members {
+                places[owners[member]] = name.clone();
+            }
+        }
+        Ok(places)
+    }
+
+    /// Chooses the destination of one file, reading but never writing the filesystem.
+    ///
+    /// The folder comes from the path template when one is set, and from the date
+    /// otherwise. Files the layout needs a date for but that have none go to the
+    /// `undated_dir` folder instead. On failure the source path is returned with
+    /// the error, for reporting: failing to hash a file already at the destination
+    /// is a [`OrganizeError::HashError`].
+    fn plan_file(
+        &self,
+        record: FileRecord,
+        reserved: &HashSet<PathBuf>,
+    ) -> Result<PlannedAction, (PathBuf, OrganizeError)> {
+        let planned = (|| {
+            let file_name = record.path.file_name().ok_or_else(|| {
+                OrganizeError::OrganizationError("Invalid file name".to_string())
+            })?;
+            let undated = self.context.destination.join(&self.context.undated_dir).join(file_name);
+            let desired = if let Some(template) = &self.context.template {
+                match template.render(&record) {
+                    Ok(folder) => Some(self.context.destination.join(folder).join(file_name)),
+                    Err(TemplateError::MissingDate) => None,
+                    Err(e) => return Err(OrganizeError::Other(e.to_string())),
+                }
+            } else if let Some(date) = record.date {
+                let root = self.context.layout_root(record.camera.as_deref());
+                let destination = organization::date_destination(
+                    record.path.as_path(),
+                    root.as_path(),
+                    date,
+                    self.context.granularity,
+                );
+                Some(destination.map_err(|e| OrganizeError::OrganizationError(e.to_string()))?)
+            } else {
+                None
+            };
+            let is_undated = desired.is_none();
+            let desired = desired.unwrap_or(undated);
+            // Collisions are resolved by comparing hashes with the file already there
+            let placement = organization::resolve_collision_with(&record.path, &desired, reserved)
+                .map_err(|e| OrganizeError::HashError(e.to_string()))?;
+            Ok((desired, placement, is_undated))
+        })();
+
+        match planned {
+            Ok((desired, placement, undated)) => {
+                let (destination, already_present) = match placement {
+                    Placement::Vacant(path) => (path, false),
+                    Placement::AlreadyPresent(path) => (path, true),
+                };
+                Ok(PlannedAction {
+                    renamed: destination != desired,
+                    destination,
+                    already_present,
+                    undated,
+                    record,
+                })
+            }
+            Err(e) => Err((record.path, e)),
+        }
+    }
+
+    /// Performs the filesystem side of one planned action.
+    ///
+    /// With `preserve_times`, a copy, or a move that had to copy across
+    /// filesystems, then gets its source's timestamps. That is best effort: a
+    /// destination refusing them (e.g. some network shares) still holds a complete
+    /// copy, so the transfer counts as done and the error is returned in `Ok`.
+    ///
+    /// Any failure, including the destination having been taken since planning,
+    /// is an [`OrganizeError::OrganizationError`].
+    fn realize(&self, action: &PlannedAction) -> OrganizeResult<Option<io::Error>> {
+        if action.already_present {
+            return Ok(None);
+        }
+        let keeps_times = matches!(self.context.mode, FileOp::Copy | FileOp::Move) && self.context.preserve_times;
+        let transfer = || {
+            if let Some(dest_dir) = action.destination.parent() {
+                fs::create_dir_all(dest_dir)?;
+            }
+            if fs::symlink_metadata(&action.destination).is_ok() {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!("{:?} was created after planning", action.destination),
+                ));
+            }
+            // Read before a move removes the source
+            let source_stat = if keeps_times { Some(fs::metadata(&action.record.path)?) } else { None };
+            organization::transfer_file(&action.record.path, &action.destination, self.context.mode)?;
+            Ok(source_stat.and_then(|stat| organization::set_times(&action.destination, &stat).err()))
+        };
+        transfer().map_err(|e| OrganizeError::OrganizationError(e.to_string()))
+    }
+}
+
+/// The index as planning consults it: the entries held in memory and, with the
+/// SQLite backend, the database behind them, queried in place rather than loaded.
+struct IndexView<'a> {
+    index: &'a Index,
+    stored: Option<&'a Mutex<SqliteIndex>>,
+}
+
+impl IndexView<'_> {
+    fn stored(&self) -> Option<MutexGuard<'_, SqliteIndex>> {
+        self.stored.map(|stored| stored.lock().unwrap_or_else(|poisoned| poisoned.into_inner()))
+    }
+
+    fn len(&self) -> io::Result<usize> {
+        let stored = match self.stored() {
+            Some(stored) => stored.len()?,
+            None => 0,
+        };
+        Ok(self.index.len() + stored)
+    }
+
+    /// Returns the entry for a hash, looking in memory first.
+    fn get(&self, hash: &str) -> io::Result<Option<IndexEntry>> {
+        if let Some(entry) = self.index.get_entry(hash) {
+            return Ok(Some(entry.clone()));
+        }
+        self.stored().map_or(Ok(None), |stored| stored.get(hash))
+    }
+
+    /// Returns the entry recorded for a source path, looking in memory first.
+    fn get_by_source(&self, source_path: &str) -> io::Result<Option<IndexEntry>> {
+        if let Some(entry) = self.index.get_by_source(source_path) {
+            return Ok(Some(entry.clone()));
+        }
+        self.stored().map_or(Ok(None), |stored| stored.get_by_source(source_path))
+    }
+
+    /// Returns the sizes of the indexed files, or `None` if one cannot be known.
+    ///
+    /// Entries from indexes that predate recorded sizes hold 0, so their size is
+    /// read from the first of their files still on disk. An entry none of whose
+    /// files remain could match a file of any size.
+    fn sizes(&self) -> io::Result<Option<HashSet<u64>>> {
+        let mut sizes = IndexStore::sizes(self.index)?;
+        if let Some(stored) = self.stored() {
+            sizes.extend(stored.sizes()?);
+        }
+        if !sizes.remove(&0) {
+            return Ok(Some(sizes));
+        }
+
+        let mut unsized_paths: Vec<Vec<String>> = self
+            .index
+            .entries()
+            .filter(|entry| entry.size == 0)
+            .map(|entry| entry.file_paths.clone())
+            .collect();
+        if let Some(stored) = self.stored() {
+            unsized_paths.extend(stored.iter()?.filter(|entry| entry.size == 0).map(|entry| entry.file_paths));
+        }
+        for paths in unsized_paths {
+            match paths.iter().find_map(|path| fs::metadata(path).ok().filter(|stat| stat.is_file())) {
+                Some(stat) => {
+                    sizes.insert(stat.len());
+                }
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(sizes))
+    }
+
+    /// Returns every file path recorded in the index.
+    fn file_paths(&self) -> io::Result<HashSet<String>> {
+        let mut paths: HashSet<String> =
+            self.index.entries().flat_map(|entry| entry.file_paths.iter().cloned()).collect();
+        if let Some(stored) = self.stored() {
+            paths.extend(stored.iter()?.flat_map(|entry| entry.file_paths));
+        }
+        Ok(paths)
+    }
+}
+
+/// Converts an index load or save failure into an [`OrganizeError::IndexError`],
+/// keeping the message of an index error the I/O error already wraps.
+fn index_failure(action: &str, path: &Path, err: io::Error) -> OrganizeError {
+    let detail = match err.get_ref().and_then(|inner| inner.downcast_ref::<OrganizeError>()) {
+        Some(OrganizeError::IndexError(message)) => message.clone(),
+        _ => err.to_string(),
+    };
+    OrganizeError::IndexError(format!("cannot {} {:?}: {}", action, path, detail))
+}
+
+/// Bytes read from the start of same-size files to tell them apart cheaply.
+const PARTIAL_HASH_BYTES: usize = 65536;
+
+/// Returns the files that may duplicate another file and so need a full hash.
+///
+/// Files are grouped by size first: a file no other scanned or indexed file
+/// matches in size cannot be a duplicate. Same-size scanned files are then
+/// compared on a Blake3 hash of their first 64KB, and only files agreeing on it
+/// are candidates. The index keeps only full hashes, so every file sharing a size
+/// with an indexed file is a candidate (see [`IndexView::sizes`] for indexes that
+/// predate recorded sizes). Files that cannot be read are returned too, so that
+/// analysis reports the error.
+///
+/// Partial reads hold a permit from `open_limit`.
+fn duplicate_candidates(files: &[PathBuf], indexed_sizes: &HashSet<u64>, open_limit: &OpenLimit) -> HashSet<PathBuf> {
+    let sizes: Vec<(&PathBuf, io::Result<u64>)> = files
+        .par_iter()
+        .map(|path| (path, fs::metadata(path).map(|stat| stat.len())))
+        .collect();
+
+    let mut candidates = HashSet::new();
+    let mut by_size: HashMap<u64, Vec<&PathBuf>> = HashMap::new();
+    for (path, size) in sizes {
+        match size {
+            Ok(size) if indexed_sizes.contains(&size) => {
+                candidates.insert(path.clone());
+            }
+            Ok(size) => by_size.entry(size).or_default().push(path),
+            Err(_) => {
+                candidates.insert(path.clone());
+            }
+        }
+    }
+
+    for group in by_size.into_values().filter(|group| group.len() > 1) {
+        let partials: Vec<(&PathBuf, Option<blake3::Hash>)> = group
+            .into_par_iter()
+            .map(|path| {
+                let _permit = open_limit.acquire();
+                let partial = network_io::read_file_chunk(path, 0, PARTIAL_HASH_BYTES)
+                    .ok()
+                    .map(|chunk| hash::hash_bytes(&chunk));
+                (path, partial)
+            })
+            .collect();
+
+        let mut counts: HashMap<blake3::Hash, usize> = HashMap::new();
+        for (_, partial) in &partials {
+            if let Some(partial) = partial {
+                *counts.entry(*partial).or_default() += 1;
+            }
+        }
+        for (path, partial) in partials {
+            if partial.is_none_or(|partial| counts[&partial] > 1) {
+                candidates.insert(path.clone());
+            }
+        }
+    }
+
+    candidates
+}
+
+/// Index entry for a file found in the destination, hashed as analysis would
+/// hash it; `None` if it cannot be read.
+fn existing_entry(path: &Path, pixel_dedup: bool) -> Option<IndexEntry> {
+    let stat = fs::metadata(path).ok()?;
+    let pixel_hash = if pixel_dedup && hash::supports_pixels(path) {
+        hash::hash_pixels(path).ok()
+    } else {
+        None
+    };
+    let (hash, hash_kind) = match pixel_hash {
+        Some(hash) => (hash, HashKind::Pixels),
+        None => (hash::hash_file_resilient(path).ok()?, HashKind::Bytes),
+    };
+    Some(IndexEntry {
+        hash: hash.to_hex().to_string(),
+        file_paths: vec![path.to_string_lossy().to_string()],
+        source_path: None,
+        size: stat.len(),
+        mtime_unix: index::mtime_unix(&stat),
+        hash_kind,
+    })
+}
+
+/// One file's entry in a [`Plan`].
+///
+/// # Fields
+///
+/// * `record` - The analyzed source file
+/// * `destination` - Where the file will be placed
+/// * `already_present` - Identical content already sits at `destination`; nothing is transferred
+/// * `renamed` - A numeric suffix was added to avoid a collision
+/// * `undated` - The file has no date and goes to the undated folder
+#[derive(Debug, Clone)]
+pub struct PlannedAction {
+    pub record: FileRecord,
+    pub destination: PathBuf,
+    pub already_present: bool,
+    pub renamed: bool,
+    pub undated: bool,
+}
+
+impl PlannedAction {
+    /// The index entry recording where the file landed, once transferred.
+    fn index_entry(&self) -> IndexEntry {
+        IndexEntry {
+            hash: self.record.hash.clone(),
+            file_paths: vec![self.destination.to_string_lossy().to_string()],
+            source_path: Some(self.record.path.to_string_lossy().to_string()),
+            size: self.record.size,
+            mtime_unix: self.record.mtime_unix,
+            hash_kind: self.record.hash_kind,
+        }
+    }
+}
+
+/// The complete set of changes an organize run will make, computed up front.
+///
+/// Produced by [`Orchestrator::plan`] without touching the destination, and carried
+/// out by [`Orchestrator::execute`]. Its `Display` output is the summary shown
+/// before asking for confirmation.
+#[derive(Debug)]
+pub struct Plan {
+    /// Files to organize, in processing order
+    pub actions: Vec<PlannedAction>,
+    /// Folders that will be created under the destination
+    pub new_folders: BTreeSet<PathBuf>,
+    mode: FileOp,
+    index: IndexHandle,
+    /// Manifest entries for skipped duplicates, in processing order
+    duplicates: Vec<ManifestEntry>,
+    /// Index entries added by `scan_dest`, saved along with the transfers
+    seeded: Vec<IndexEntry>,
+}
+
+impl Plan {
+    /// Returns the number of files that will actually be transferred.
+    pub fn transfer_count(&self) -> usize {
+        self.actions.iter().filter(|a| !a.already_present).count()
+    }
+
+    /// Returns the number of files that will be renamed to avoid a collision.
+    pub fn collision_count(&self) -> usize {
+        self.actions.iter().filter(|a| a.renamed).count()
+    }
+
+    /// Returns the number of files without a date, bound for the undated folder.
+    pub fn undated_count(&self) -> usize {
+        self.actions.iter().filter(|a| a.undated).count()
+    }
+
+    /// Returns the bytes the destination needs for the planned transfers.
+    ///
+    /// Links take no space; moves are counted in full, as they copy across filesystems.
+    pub fn bytes_needed(&self) -> u64 {
+        match self.mode {
+            FileOp::Hardlink | FileOp::Symlink => 0,
+            FileOp::Copy | FileOp::Move => self
+                .actions
+                .iter()
+                .filter(|a| !a.already_present)
+                .map(|a| a.record.size)
+                .sum(),
+        }
+    }
+
+    /// Records every missing ancestor folder of `destination` below `root`.
+    fn add_new_folders(&mut self, destination: &Path, root: &Path) {
+        let mut dir = destination.parent();
+        while let Some(current) = dir {
+            if current == root || !current.starts_with(root) || current.exists() {
+                break;
+            }
+            if !self.new_folders.insert(current.to_path_buf()) {
+                break;
+            }
+            dir = current.parent();
+        }
+    }
+}
+
+impl fmt::Display for Plan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Plan:")?;
+        writeln!(f, "  Files to {:<12} {}", format!("{:?}:", self.mode).to_lowercase(), self.transfer_count())?;
+        writeln!(f, "  Already in place:     {}", self.actions.len() - self.transfer_count())?;
+        writeln!(f, "  New folders:          {}", self.new_folders.len())?;
+        writeln!(f, "  Renamed (collisions): {}", self.collision_count())?;
+        writeln!(f, "  Undated:              {}", self.undated_count())?;
+        write!(f, "  Space needed:         {}", format_bytes(self.bytes_needed()))
+    }
+}
+
+/// Formats a byte count with a binary unit, e.g. `1.5 GiB`.
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+/// Returns the destination spelled as a path under `source`, if it is nested inside it.
+///
+/// Both paths are canonicalized for the comparison, but the result is rebuilt from
+/// `source` as given so it can be prefix-matched against scanned paths directly.
+/// A destination that does not exist yet cannot contain anything and yields `None`.
+///
+/// With the arguments swapped, spells a source nested in the destination.
+fn nested_destination(source: &Path, destination: &Path) -> io::Result<Option<PathBuf>> {
+    let Ok(destination) = destination.canonicalize() else {
+        return Ok(None);
+    };
+    let source_canonical = source.canonicalize()?;
+    Ok(destination
+        .strip_prefix(&source_canonical)
+        .ok()
+        .map(|relative| source.join(relative)))
+}
+
+/// Result of [`find_duplicates`].
+///
+/// # Fields
+///
+/// * `duplicates` - `(file, originals)` pairs sorted by file path, where `originals` are all the paths the index
+///   recorded for that hash
+/// * `unreadable` - Files that could not be hashed, sorted by path, with the error
+#[derive(Debug, Default)]
+pub struct DuplicateScan {
+    pub duplicates: Vec<(PathBuf, Vec<String>)>,
+    pub unreadable: Vec<(PathBuf, io::Error)>,
+}
+
+/// Finds files under `dir` whose contents are already recorded in `index`.
+///
+/// Every regular file is hashed (in parallel), whatever its extension. Files
+/// that cannot be read are returned in [`DuplicateScan::unreadable`] rather
+/// than failing the whole scan.
+pub fn find_duplicates(dir: &Path, index: &Index) -> io::Result<DuplicateScan> {
+    let mut files = Vec::new();
+    for entry in WalkDir::new(dir) {
+        let entry = entry?;
+        if entry.file_type().is_file() {
+            files.push(entry.into_path());
+        }
+    }
+
+    let mut scan = DuplicateScan::default();
+    for (path, result) in hash::hash_files_parallel_detailed(files) {
+        match result {
+            Ok(hash) => {
+                if let Some(existing) = index.get_entry(&hash.to_hex()) {
+                    scan.duplicates.push((path, existing.file_paths.clone()));
+                }
+            }
+            Err(e) => scan.unreadable.push((path, e)),
+        }
+    }
+    scan.duplicates.sort();
+    scan.unreadable.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(scan)
+}
+
+/// Outcome of checking an index against the files it points to.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct VerifyReport {
+    /// Number of indexed file paths checked
+    pub total: usize,
+    /// Indexed paths that no longer exist
+    pub missing: Vec<String>,
+    /// Indexed paths whose contents no longer match the stored hash (only with rehash)
+    pub mismatched: Vec<String>,
+}
+
+impl VerifyReport {
+    /// Number of paths that passed every check.
+    pub fn clean_count(&self) -> usize {
+        self.total - self.missing.len() - self.mismatched.len()
+    }
+
+    /// Returns `true` if no problem was found.
+    pub fn is_clean(&self) -> bool {
+        self.missing.is_empty() && self.mismatched.is_empty()
+    }
+}
+
+/// Checks that every path recorded in `index` still exists and, with `rehash`,
+/// still hashes to the recorded value.
+///
+/// Catches drift from files deleted or edited outside of Sift. Paths are checked
+/// in parallel; both problem lists come back sorted.
+pub fn verify_index(index: &Index, rehash: bool) -> VerifyReport {
+    let paths: Vec<(&IndexEntry, &str)> = index
+        .entries()
+        .flat_map(|entry| entry.file_paths.iter().map(move |path| (entry, path.as_str())))
+        .collect();
+
+    let problems: Vec<(String, bool)> = paths
+        .par_iter()
+        .filter_map(|&(entry, path)| {
+            if !Path::new(path).is_file() {
+                return Some((path.to_string(), true));
+            }
+            if rehash {
+                let actual = match entry.hash_kind {
+                    HashKind::Bytes => hash::hash_file(path),
+                    HashKind::Pixels => hash::hash_pixels(path),
+                };
+                // A file that exists but can't be read counts as changed
+                let matches = actual
+                    .map(|actual| actual.to_hex().as_str() == entry.hash)
+                    .unwrap_or(false);
+                if !matches {
+                    return Some((path.to_string(), false));
+                }
+            }
+            None
+        })
+        .collect();
+
+    let mut report = VerifyReport {
+        total: paths.len(),
+        ..VerifyReport::default()
+    };
+    for (path, missing) in problems {
+        if missing {
+            report.missing.push(path);
+        } else {
+            report.mismatched.push(path);
+        }
+    }
+    report.missing.sort();
+    report.mismatched.sort();
+    report
+}
+
+/// Returns `true` if the file's date destination exists with the same size.
+///
+/// The destination follows the context's template, granularity and camera
+/// folders. Templates using `{location}` never match, since places are only
+/// known after clustering.
+fn destination_matches(path: &Path, context: &OrganizeContext) -> bool {
+    let Some(date) = metadata::extract_date_with_fallback(path) else {
+        return false;
+    };
+    let wants_camera = context.by_camera
+        || context.template.as_ref().is_some_and(|t| t.uses(Token::Camera));
+    let record = FileRecord {
+        path: path.to_path_buf(),
+        hash: String::new(),
+        date: Some(date),
+        location: None,
+        place: None,
+        camera: wants_camera.then(|| metadata::extract_camera(path)).flatten(),
+        size: 0,
+        mtime_unix: 0,
+        hash_kind: HashKind::Bytes,
+    };
+    let dest = match &context.template {
+        Some(template) if template.uses(Token::Location) => return false,
+        Some(template) => match (template.render(&record), path.file_name()) {
+            (Ok(folder), Some(name)) => context.destination.join(folder).join(name),
+            _ => return false,
+        },
+        None => {
+            let root = context.layout_root(record.camera.as_deref());
+            match organization::date_destination(path, root.as_path(), date, context.granularity) {
+                Ok(dest) => dest,
+                Err(_) => return false,
+            }
+        }
+    };
+    match (fs::metadata(path), fs::metadata(&dest)) {
+        (Ok(source), Ok(existing)) => existing.is_file() && existing.len() == source.len(),
+        _ => false,
+    }
+}
+
+/// Creates a `{pos}/{len}` progress bar with an ETA, drawn on stderr.
+///
+/// The bar hides itself when stderr is not a terminal, so piped output stays clean.
+fn progress_bar(len: usize, stage: &'static str) -> ProgressBar {
+    let style = ProgressStyle::with_template("{msg:>10} [{bar:40}] {pos}/{len} ETA {eta}")
+        .expect("progress template is valid")
+        .progress_chars("=> ");
+    ProgressBar::new(len as u64).with_style(style).with_message(stage)
+}
+
+/// File extensions recognized as photos (and phone videos) when no `--ext` option is given.
+pub fn supported_extensions() -> &'static [&'static str] {
+    &["jpg", "jpeg", "png", "tiff", "raw", "heic", "mp4", "mov", "m4v"]
+}
+
+/// Builds the extension list used for scanning.
+///
+/// `extra` extensions are appended to [`supported_extensions`], or replace them
+/// entirely when `only` is set. Entries are lowercased, stripped of a leading dot
+/// and deduplicated, so `".CR2"` and `"cr2"` are the same extension.
+pub fn resolve_extensions(extra: &[String], only: bool) -> Vec<String> {
+    let defaults: &[&str] = if only { &[] } else { supported_extensions() };
+    let mut extensions: Vec<String> = Vec::new();
+
+    for ext in defaults.iter().copied().chain(extra.iter().map(String::as_str)) {
+        let ext = ext.trim().trim_start_matches('.').to_lowercase();
+        if !ext.is_empty() && !extensions.contains(&ext) {
+            extensions.push(ext);
+        }
+    }
+    extensions
+}
+
+/// Returns `true` if the extension of `path` is in `extensions` (case-insensitive).
+pub fn has_photo_extension(path: &Path, extensions: &[String]) -> bool {
+    path.extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .is_some_and(|ext| extensions.contains(&ext))
+}
+
+/// How [`scan_photos`] walks a folder.
+///
+/// # Fields
+///
+/// * `recursive` - Walk subfolders too, not just the top level
+/// * `include_hidden` - Also list hidden files and folders (see [`is_ignored`])
+/// * `follow_symlinks` - Walk into symlinked folders (see [`walk`])
+/// * `by_content` - Pick photos by their leading bytes rather than their extension
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ScanOptions {
+    pub recursive: bool,
+    pub include_hidden: bool,
+    pub follow_symlinks: bool,
+    pub by_content: bool,
+}
+
+/// Lists the files under `dir` with one of `extensions`, sorted by path.
+///
+/// With `by_content`, files are picked by their leading bytes instead (see
+/// [`metadata::detect_image_kind`]) and `extensions` is ignored: a JPEG without
+/// an extension is listed, a PDF named `.jpg` is not. Videos are not detected.
+///
+/// A file reachable under several paths (through a symlinked folder, or with
+/// another letter case on a case-insensitive filesystem) is listed once, under
+/// the first of its paths (see [`dedupe_physical`]).
+///
+/// Only the top level is listed unless `recursive` is set. Symlinks to files
+/// are followed; symlinks to directories are only walked into with
+/// `follow_symlinks` (see [`walk`]). Hidden files and folders are left out
+/// unless `include_hidden` is set (see [`is_ignored`]).
+///
+/// Entries that cannot be read, such as a subfolder without read permission,
+/// are skipped and returned in [`PhotoScan::unreadable`] rather than failing
+/// the scan, and symlink loops in [`PhotoScan::symlink_loops`]. Only a `dir`
+/// that cannot be read itself is an error.
+pub fn scan_photos(dir: &Path, extensions: &[String], options: ScanOptions) -> io::Result<PhotoScan> {
+    let ScanOptions { recursive, include_hidden, follow_symlinks, by_content } = options;
+    let mut files = Vec::new();
+    let mut unreadable = Vec::new();
+    let mut symlink_loops = Vec::new();
+
+    if recursive {
+        for entry in walk(dir, include_hidden, follow_symlinks) {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(err) if err.depth() == 0 => return Err(err.into()),
+                Err(err) if err.loop_ancestor().is_some() => {
+                    symlink_loops.push(err.path().unwrap_or(dir).to_path_buf());
+                    continue;
+                }
+                Err(err) => {
+                    let path = err.path().unwrap_or(dir).to_path_buf();
+                    unreadable.push((path, err.into()));
+                    continue;
+                }
+            };
+            let path = entry.into_path();
+            if is_photo_file(&path, extensions, by_content) {
+                files.push(path);
+            }
+        }
+    } else {
+        for entry in fs::read_dir(dir)? {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(err) => {
+                    unreadable.push((dir.to_path_buf(), err));
+                    continue;
+                }
+            };
+            if !is_ignored(&entry.file_name(), include_hidden) && is_photo_file(&entry.path(), extensions, by_content) {
+                files.push(entry.path());
+            }
+        }
+    }
+
+    files.sort();
+    Ok(PhotoScan { files: dedupe_physical(files), unreadable, symlink_loops })
+}
+
+/// Result of [`scan_photos`].
+///
+/// # Fields
+///
+/// * `files` - Photo files found, sorted by path
+/// * `unreadable` - Entries skipped because they could not be read, with the error
+/// * `symlink_loops` - Symlinks not walked into because they lead back to one of their parent folders
+#[derive(Debug, Default)]
+pub struct PhotoScan {
+    pub files: Vec<PathBuf>,
+    pub unreadable: Vec<(PathBuf, io::Error)>,
+    pub symlink_loops: Vec<PathBuf>,
+}
+
+/// Drops paths that resolve to the same file as an earlier path, keeping order.
+///
+/// Paths are compared by [`fs::canonicalize`], which resolves symlinks, `.` and
+/// `..`, and on case-insensitive filesystems the case the file is stored with.
+/// The paths themselves are kept as given, so a symlinked file is still
+/// organized from the link. A path that cannot be resolved is kept.
+fn dedupe_physical(files: Vec<PathBuf>) -> Vec<PathBuf> {
+    let mut seen = HashSet::new();
+    files
+        .into_iter()
+        .filter(|path| seen.insert(fs::canonicalize(path).unwrap_or_else(|_| path.clone())))
+        .collect()
+}
+
+/// Walks `dir` recursively, leaving out ignored entries (see [`is_ignored`]).
+///
+/// Entries of each directory are visited in file name order, so files come out
+/// in path order. With `follow_symlinks`, symlinked directories are walked into. A link to
+/// one of its own ancestors would repeat the walk forever; it is not walked into
+/// but yielded as an error whose `loop_ancestor` is set, for the caller to report.
+pub fn walk(dir: &Path, include_hidden: bool, follow_symlinks: bool) -> impl Iterator<Item = walkdir::Result<walkdir::DirEntry>> {
+    WalkDir::new(dir)
+        .follow_links(follow_symlinks)
+        .sort_by_file_name()
+        .into_iter()
+        // `dir` itself (depth 0) may well be hidden, e.g. a temporary directory
+        .filter_entry(move |entry| entry.depth() == 0 || !is_ignored(entry.file_name(), include_hidden))
+}
+
+/// Returns `true` if a directory entry named `name` should not be scanned.
+///
+/// Sift's own files (`.sift_index.bin`, its `.tmp` copy while saving,
+/// `.sift_manifest.jsonl`, `.sift_benchmark.tmp`) are always ignored, so a
+/// destination nested in the source never gets its index organized. Other
+/// dotfiles and dot-folders are ignored unless `include_hidden` is set.
+pub fn is_ignored(name: &OsStr, include_hidden: bool) -> bool {
+    let name = name.to_string_lossy();
+    name.starts_with(SIFT_ARTIFACT_PREFIX) || (!include_hidden && name.starts_with('.'))
+}
+
+/// Prefix of every file Sift writes next to the photos it organizes.
+const SIFT_ARTIFACT_PREFIX: &str = ".sift_";
+
+/// Returns `true` if `path` is a file (following symlinks) with a photo extension,
+/// or with `by_content`, a file starting like an image.
+fn is_photo_file(path: &Path, extensions: &[String], by_content: bool) -> bool {
+    // Follow symlinks: is_file() returns true for symlinks pointing to files
+    if !path.is_file() {
+        return false;
+    }
+    if by_content {
+        metadata::detect_image_kind(path).is_some()
+    } else {
+        has_photo_extension(path, extensions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::IndexStore;
+    use crate::testutil;
+    use exif::Tag;
+    use std::fs;
+    use tempfile::TempDir;
+    use chrono::Datelike;
+
+    #[test]
+    fn test_organize_context_creation() {
+        let ctx = OrganizeContext::new(
+            PathBuf::from("/source"),
+            PathBuf::from("/dest"),
+            false,
+            Some(4),
+            None,
+        );
+
+        assert_eq!(ctx.source, PathBuf::from("/source"));
+        assert_eq!(ctx.destination, PathBuf::from("/dest"));
+        assert!(!ctx.with_clustering);
+        assert_eq!(ctx.jobs, Some(4));
+        assert_eq!(ctx.mode, FileOp::Copy);
+    }
+
+    #[test]
+    fn test_organize_context_default_index_path() {
+        let ctx = OrganizeContext::new(
+            PathBuf::from("/source"),
+            PathBuf::from("/dest"),
+            false,
+            None,
+            None,
+        );
+
+        let index_path = ctx.get_index_path();
+        assert!(index_path.ends_with(".sift_index.bin"));
+    }
+
+    #[test]
+    fn test_organize_context_custom_index_path() {
+        let custom_path = PathBuf::from("/custom/index.bin");
+        let ctx = OrganizeContext::new(
+            PathBuf::from("/source"),
+            PathBuf::from("/dest"),
+            false,
+            None,
+            Some(custom_path.clone()),
+        );
+
+        let index_path = ctx.get_index_path();
+        assert_eq!(index_path, custom_path);
+    }
+
+    #[test]
+    fn test_stats_default() {
+        let stats = OrganizeStats::default();
+        assert_eq!(stats.files_scanned, 0);
+        assert_eq!(stats.files_analyzed, 0);
+        assert_eq!(stats.files_organized, 0);
+    }
+
+    #[test]
+    fn test_file_record_creation() {
+        let record = FileRecord {
+            path: PathBuf::from("/source/photo.jpg"),
+            hash: "abc123def456".to_string(),
+            date: None,
+            location: None,
+            place: None,
+            camera: None,
+            size: 0,
+            mtime_unix: 0,
+            hash_kind: HashKind::Bytes,
+        };
+
+        assert_eq!(record.path, PathBuf::from("/source/photo.jpg"));
+        assert_eq!(record.hash, "abc123def456");
+        assert!(record.date.is_none());
+        assert!(record.location.is_none());
+    }
+
+    #[test]
+    fn test_file_record_with_date() {
+        use chrono::NaiveDate;
+
+        let date = NaiveDate::from_ymd_opt(2024, 2, 11);
+        let record = FileRecord {
+            path: PathBuf::from("/source/photo.jpg"),
+            hash: "abc123".to_string(),
+            date,
+            location: None,
+            place: None,
+            camera: None,
+            size: 0,
+            mtime_unix: 0,
+            hash_kind: HashKind::Bytes,
+        };
+
+        assert!(record.date.is_some());
+        assert_eq!(record.date.unwrap().year(), 2024);
+    }
+
+    #[test]
+    fn test_file_record_with_location() {
+        let record = FileRecord {
+            path: PathBuf::from("/source/photo.jpg"),
+            hash: "abc123".to_string(),
+            date: None,
+            location: Some((37.7749, -122.4194)), // San Francisco
+            place: None,
+            camera: None,
+            size: 0,
+            mtime_unix: 0,
+            hash_kind: HashKind::Bytes,
+        };
+
+        assert!(record.location.is_some());
+        let (lat, lon) = record.location.unwrap();
+        assert_eq!(lat, 37.7749);
+        assert_eq!(lon, -122.4194);
+    }
+
+    #[test]
+    fn test_scan_source_empty_directory() -> io::Result<()> {
+        let temp = TempDir::new()?;
+        let dest = TempDir::new()?;
+
+        let ctx = OrganizeContext::new(
+            temp.path().to_path_buf(),
+            dest.path().to_path_buf(),
+            false,
+            None,
+            None,
+        );
+
+        let mut orchestrator = Orchestrator::new(ctx);
+        let files = orchestrator.scan_source()?;
+
+        assert_eq!(files.len(), 0);
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_scan_follow_symlinks_survives_loops() -> io::Result<()> {
+        use std::os::unix::fs::symlink;
+
+        let source = TempDir::new()?;
+        let elsewhere = TempDir::new()?;
+        fs::create_dir(source.path().join("album"))?;
+        fs::write(source.path().join("album/IMG_1.jpg"), "1")?;
+        fs::write(elsewhere.path().join("IMG_2.jpg"), "2")?;
+        // A link back to the source root, and one to a folder outside it
+        symlink(source.path(), source.path().join("album/loop"))?;
+        symlink(elsewhere.path(), source.path().join("linked"))?;
+
+        let extensions = resolve_extensions(&[], false);
+        let walking = ScanOptions { recursive: true, ..Default::default() };
+        let following = ScanOptions { follow_symlinks: true, ..walking };
+        let files = scan_photos(source.path(), &extensions, walking)?.files;
+        assert_eq!(files, [source.path().join("album/IMG_1.jpg")]);
+
+        let scan = scan_photos(source.path(), &extensions, following)?;
+        assert_eq!(scan.files, [source.path().join("album/IMG_1.jpg"), source.path().join("linked/IMG_2.jpg")]);
+        // Reported to the caller, not counted as unreadable
+        assert_eq!(scan.symlink_loops, [source.path().join("album/loop")]);
+        assert!(scan.unreadable.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_unreadable_folder_does_not_stop_the_run() -> io::Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let source = TempDir::new()?;
+        let dest = TempDir::new()?;
+        fs::write(source.path().join("IMG_20230101_a.jpg"), "a")?;
+        let locked = source.path().join("locked");
+        fs::create_dir(&locked)?;
+        fs::write(locked.join("IMG_20230102_b.jpg"), "b")?;
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o000))?;
+        if fs::read_dir(&locked).is_ok() {
+            // Permissions don't apply to root
+            fs::set_permissions(&locked, fs::Permissions::from_mode(0o755))?;
+            return Ok(());
+        }
+
+        let mut ctx = OrganizeContext::new(source.path().to_path_buf(), dest.path().to_path_buf(), false, None, None);
+        ctx.recursive = true;
+        let result = Orchestrator::new(ctx).run();
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o755))?;
+        let stats = result?;
+        assert_eq!(stats.files_organized, 1);
+        assert_eq!(stats.files_skipped_errors, 1);
+        assert!(dest.path().join("2023/01/01/IMG_20230101_a.jpg").is_file());
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_scan_skips_dangling_symlink() -> io::Result<()> {
+        let source = TempDir::new()?;
+        fs::write(source.path().join("IMG_1.jpg"), "1")?;
+        std::os::unix::fs::symlink(source.path().join("gone"), source.path().join("IMG_2.jpg"))?;
+
+        // Following links means reading the missing target
+        let extensions = resolve_extensions(&[], false);
+        let following = ScanOptions { recursive: true, follow_symlinks: true, ..Default::default() };
+        let scan = scan_photos(source.path(), &extensions, following)?;
+        assert_eq!(scan.files, [source.path().join("IMG_1.jpg")]);
+        assert_eq!(scan.unreadable.len(), 1);
+        assert_eq!(scan.unreadable[0].0, source.path().join("IMG_2.jpg"));
+
+        // The folder being scanned must exist, though
+        assert!(scan_photos(&source.path().join("gone"), &extensions, following).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_dedupe_physical_paths() -> io::Result<()> {
+        let dir = TempDir::new()?;
+        fs::create_dir(dir.path().join("album"))?;
+        let photo = dir.path().join("album/IMG_1.jpg");
+        fs::write(&photo, "1")?;
+        let other = dir.path().join("album/IMG_2.jpg");
+        fs::write(&other, "2")?;
+
+        let files = vec![
+            photo.clone(),
+            dir.path().join("album/./IMG_1.jpg"),
+            dir.path().join("album/../album/IMG_1.jpg"),
+            other.clone(),
+            dir.path().join("missing.jpg"),
+        ];
+        assert_eq!(dedupe_physical(files), [photo, other, dir.path().join("missing.jpg")]);
+        Ok(())
+    }
+
+    #[cfg(any(target_os = "macos", windows))]
+    #[test]
+    fn test_dedupe_physical_paths_ignores_case() -> io::Result<()> {
+        let dir = TempDir::new()?;
+        let photo = dir.path().join("Photo.JPG");
+        fs::write(&photo, "1")?;
+        let probe = dir.path().join("photo.jpg");
+        if fs::metadata(&probe).is_err() {
+            // This volume is case-sensitive after all
+            return Ok(());
+        }
+        assert_eq!(dedupe_physical(vec![photo.clone(), probe]), [photo]);
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_scan_lists_a_linked_folder_once() -> io::Result<()> {
+        use std::os::unix::fs::symlink;
+
+        let source = TempDir::new()?;
+        fs::create_dir(source.path().join("album"))?;
+        fs::write(source.path().join("album/IMG_1.jpg"), "1")?;
+        symlink(source.path().join("album"), source.path().join("shortcut"))?;
+
+        let extensions = resolve_extensions(&[], false);
+        let following = ScanOptions { recursive: true, follow_symlinks: true, ..Default::default() };
+        let files = scan_photos(source.path(), &extensions, following)?.files;
+        assert_eq!(files, [source.path().join("album/IMG_1.jpg")]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_scan_source_with_photos() -> io::Result<()> {
+        let temp = TempDir::new()?;
+        let dest = TempDir::new()?;
+
+        // Create test photo files
+        fs::write(temp.path().join("photo1.jpg"), "test")?;
+        fs::write(temp.path().join("photo2.jpeg"), "test")?;
+        fs::write(temp.path().join("photo3.png"), "test")?;
+        fs::write(temp.path().join("document.txt"), "test")?; // Should be ignored
+
+        let ctx = OrganizeContext::new(
+            temp.path().to_path_buf(),
+            dest.path().to_path_buf(),
+            false,
+            None,
+            None,
+        );
+
+        let mut orchestrator = Orchestrator::new(ctx);
+        let files = orchestrator.scan_source()?;
+
+        assert_eq!(files.len(), 3, "Should find 3 photo files (not txt)");
+        Ok(())
+    }
+
+    #[test]
+    fn test_orchestrator_new() {
+        let ctx = OrganizeContext::new(
+            PathBuf::from("/source"),
+            PathBuf::from("/dest"),
+            false,
+            None,
+            None,
+        );
+
+        let orchestrator = Orchestrator::new(ctx.clone());
+
+        assert_eq!(orchestrator.stats.files_scanned, 0);
+        assert_eq!(orchestrator.stats.files_analyzed, 0);
+        assert_eq!(orchestrator.errors.len(), 0);
+    }
+
+    #[test]
+    fn test_organize_context_clone() {
+        let ctx = OrganizeContext::new(
             PathBuf::from("/source"),
             PathBuf::from("/dest"),
+            true,
+            Some(8),
+            Some(PathBuf::from("/custom/index.bin")),
+        );
+
+        let cloned = ctx.clone();
+
+        assert_eq!(ctx.source, cloned.source);
+        assert_eq!(ctx.destination, cloned.destination);
+        assert_eq!(ctx.with_clustering, cloned.with_clustering);
+        assert_eq!(ctx.jobs, cloned.jobs);
+        assert_eq!(ctx.index_path, cloned.index_path);
+    }
+
+    #[test]
+    #[allow(clippy::field_reassign_with_default)]
+    fn test_stats_with_values() {
+        let mut stats = OrganizeStats::default();
+        stats.files_scanned = 100;
+        stats.files_analyzed = 95;
+        stats.files_skipped_duplicates = 5;
+        stats.files_organized = 90;
+        stats.files_failed = 0;
+
+        assert_eq!(stats.files_scanned, 100);
+        assert_eq!(stats.files_organized, 90);
+        assert_eq!(stats.files_skipped_duplicates, 5);
+    }
+
+    #[test]
+    fn test_stats_clone() {
+        let stats = OrganizeStats {
+            files_scanned: 50,
+            files_analyzed: 48,
+            files_skipped_duplicates: 2,
+            files_organized: 46,
+            files_failed: 2,
+            ..Default::default()
+        };
+
+        let cloned = stats.clone();
+        assert_eq!(stats.files_scanned, cloned.files_scanned);
+        assert_eq!(stats.files_organized, cloned.files_organized);
+    }
+
+    #[test]
+    fn test_dry_run_writes_nothing() -> io::Result<()> {
+        let source = TempDir::new()?;
+        let dest = TempDir::new()?;
+        fs::write(source.path().join("IMG_20230704_001.jpg"), "dry run data")?;
+
+        let mut ctx = OrganizeContext::new(
+            source.path().to_path_buf(),
+            dest.path().to_path_buf(),
+            false,
+            None,
+            None,
+        );
+        ctx.dry_run = true;
+
+        let stats = Orchestrator::new(ctx.clone()).run()?;
+
+        assert_eq!(stats.files_organized, 1, "File should be reported as pending");
+        assert!(!dest.path().join("2023").exists(), "Dry run must not create folders");
+        assert!(!ctx.get_index_path().exists(), "Dry run must not save the index");
+        Ok(())
+    }
+
+    #[test]
+    fn test_files_already_in_place_are_not_counted_as_organized() -> io::Result<()> {
+        let source = TempDir::new()?;
+        let dest = TempDir::new()?;
+        fs::write(source.path().join("IMG_20230704_001.jpg"), "already there")?;
+        let ctx = OrganizeContext::new(source.path().to_path_buf(), dest.path().to_path_buf(), false, None, None);
+        assert_eq!(Orchestrator::new(ctx.clone()).run()?.files_organized, 1);
+
+        // Without the index, the copy is only found at its destination
+        fs::remove_file(ctx.get_index_path())?;
+        let mut dry = ctx.clone();
+        dry.dry_run = true;
+        let stats = Orchestrator::new(dry).run()?;
+        assert_eq!((stats.files_organized, stats.files_skipped_existing), (0, 1));
+
+        let stats = Orchestrator::new(ctx.clone()).run()?;
+        assert_eq!((stats.files_organized, stats.files_skipped_existing), (0, 1));
+        assert!(ctx.get_index_path().exists(), "The file is recorded in the rebuilt index");
+        Ok(())
+    }
+
+    #[test]
+    fn test_scan_source_flat_ignores_nested() -> io::Result<()> {
+        let temp = TempDir::new()?;
+        let dest = TempDir::new()?;
+
+        fs::write(temp.path().join("top.jpg"), "test")?;
+        fs::create_dir_all(temp.path().join("DCIM/100CANON"))?;
+        fs::write(temp.path().join("DCIM/100CANON/nested.jpg"), "test")?;
+
+        let ctx = OrganizeContext::new(
+            temp.path().to_path_buf(),
+            dest.path().to_path_buf(),
+            false,
+            None,
+            None,
+        );
+
+        let files = Orchestrator::new(ctx).scan_source()?;
+        assert_eq!(files, vec![temp.path().join("top.jpg")]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_scan_source_recursive_nested_tree() -> io::Result<()> {
+        let temp = TempDir::new()?;
+        let dest = TempDir::new()?;
+
+        fs::write(temp.path().join("top.jpg"), "test")?;
+        fs::create_dir_all(temp.path().join("DCIM/100CANON"))?;
+        fs::write(temp.path().join("DCIM/level1.png"), "test")?;
+        fs::write(temp.path().join("DCIM/100CANON/level2.JPG"), "test")?;
+        fs::write(temp.path().join("DCIM/100CANON/notes.txt"), "test")?; // Should be ignored
+
+        let mut ctx = OrganizeContext::new(
+            temp.path().to_path_buf(),
+            dest.path().to_path_buf(),
+            false,
+            None,
+            None,
+        );
+        ctx.recursive = true;
+
+        let files = Orchestrator::new(ctx).scan_source()?;
+        assert_eq!(
+            files,
+            vec![
+                temp.path().join("DCIM/100CANON/level2.JPG"),
+                temp.path().join("DCIM/level1.png"),
+                temp.path().join("top.jpg"),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_link_back_manifest_lines_in_order() -> io::Result<()> {
+        let source = TempDir::new()?;
+        let dest = TempDir::new()?;
+
+        let names = ["IMG_20230101_a.jpg", "IMG_20230102_b.jpg", "IMG_20230103_c.jpg"];
+        for name in &names {
+            fs::write(source.path().join(name), format!("content of {}", name))?;
+        }
+
+        let mut ctx = OrganizeContext::new(
+            source.path().to_path_buf(),
+            dest.path().to_path_buf(),
+            false,
+            None,
+            None,
+        );
+        ctx.link_back = true;
+        Orchestrator::new(ctx.clone()).run()?;
+
+        let manifest = fs::read_to_string(ctx.get_link_back_path())?;
+        let entries: Vec<LinkBackEntry> = manifest
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        assert_eq!(entries.len(), names.len(), "One line per organized file");
+        for (i, (entry, name)) in entries.iter().zip(names.iter()).enumerate() {
+            let day = (i + 1) as u32;
+            let source_path = source.path().join(name);
+            assert_eq!(entry.source, source_path.to_string_lossy());
+            assert!(entry.destination.ends_with(&format!("2023/01/{:02}/{}", day, name)));
+            assert_eq!(entry.hash, hash::hash_file(&source_path)?.to_hex().to_string());
+            assert_eq!(entry.date, NaiveDate::from_ymd_opt(2023, 1, day));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_link_back_disabled_by_default() -> io::Result<()> {
+        let source = TempDir::new()?;
+        let dest = TempDir::new()?;
+        fs::write(source.path().join("IMG_20230101_a.jpg"), "content")?;
+
+        let ctx = OrganizeContext::new(
+            source.path().to_path_buf(),
+            dest.path().to_path_buf(),
+            false,
+            None,
+            None,
+        );
+        Orchestrator::new(ctx.clone()).run()?;
+
+        assert!(!ctx.get_link_back_path().exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_run_manifest_lists_every_file() -> io::Result<()> {
+        let source = TempDir::new()?;
+        let dest = TempDir::new()?;
+        fs::write(source.path().join("IMG_20230101_a.jpg"), "first")?;
+        fs::write(source.path().join("IMG_20230101_b.jpg"), "first")?;
+        fs::write(source.path().join("IMG_20230102_c.jpg"), "second")?;
+
+        let mut ctx = OrganizeContext::new(
+            source.path().to_path_buf(),
+            dest.path().to_path_buf(),
+            false,
+            None,
+            None,
+        );
+        ctx.mode = FileOp::Move;
+        Orchestrator::new(ctx.clone()).run()?;
+
+        let hash_of = |data: &str| hash::hash_bytes(data.as_bytes()).to_hex().to_string();
+        let entry = |src: &str, dst: &str, data: &str, day: u32, action| ManifestEntry {
+            src: source.path().join(src),
+            dst: Some(dest.path().join(dst)),
+            hash: hash_of(data),
+            hash_kind: HashKind::Bytes,
+            date: NaiveDate::from_ymd_opt(2023, 1, day),
+            action,
+        };
+        assert_eq!(
+            manifest::read_manifest(&ctx.get_manifest_path())?,
+            [
+                entry("IMG_20230101_a.jpg", "2023/01/01/IMG_20230101_a.jpg", "first", 1, ManifestAction::Move),
+                entry("IMG_20230102_c.jpg", "2023/01/02/IMG_20230102_c.jpg", "second", 2, ManifestAction::Move),
+                // The in-run duplicate points at the copy organized before it
+                entry("IMG_20230101_b.jpg", "2023/01/01/IMG_20230101_a.jpg", "first", 1, ManifestAction::SkippedDuplicate),
+            ]
+        );
+
+        // A run that transfers nothing keeps the default manifest of the last run that did
+        let first = fs::read(ctx.get_manifest_path())?;
+        Orchestrator::new(ctx.clone()).run()?;
+        assert_eq!(fs::read(ctx.get_manifest_path())?, first);
+
+        // A later run writes its own manifest; already indexed content points at its
+        // copy (the skipped duplicate was left in the source)
+        fs::write(source.path().join("IMG_20230103_d.jpg"), "second")?;
+        let custom = dest.path().join("run2.json");
+        ctx.manifest_path = Some(custom.clone());
+        Orchestrator::new(ctx.clone()).run()?;
+        assert_eq!(
+            manifest::read_manifest(&custom)?,
+            [
+                entry("IMG_20230101_b.jpg", "2023/01/01/IMG_20230101_a.jpg", "first", 1, ManifestAction::SkippedDuplicate),
+                entry("IMG_20230103_d.jpg", "2023/01/02/IMG_20230102_c.jpg", "second", 3, ManifestAction::SkippedDuplicate),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_resolve_extensions_appends_to_defaults() {
+        let extensions = resolve_extensions(&["CR2".to_string(), ".dng".to_string()], false);
+
+        assert_eq!(extensions.len(), supported_extensions().len() + 2);
+        assert!(extensions.contains(&"jpg".to_string()));
+        assert!(extensions.contains(&"cr2".to_string()));
+        assert!(extensions.contains(&"dng".to_string()));
+    }
+
+    #[test]
+    fn test_resolve_extensions_override_and_dedup() {
+        let extra = vec!["nef".to_string(), "NEF".to_string(), "".to_string()];
+        assert_eq!(resolve_extensions(&extra, true), vec!["nef".to_string()]);
+
+        let defaults = resolve_extensions(&["JPG".to_string()], false);
+        assert_eq!(defaults.len(), supported_extensions().len());
+    }
+
+    #[test]
+    fn test_has_photo_extension_case_insensitive() {
+        let extensions = resolve_extensions(&["cr2".to_string()], false);
+
+        assert!(has_photo_extension(Path::new("IMG_0001.CR2"), &extensions));
+        assert!(has_photo_extension(Path::new("IMG_0001.Jpg"), &extensions));
+        assert!(!has_photo_extension(Path::new("IMG_0001.nef"), &extensions));
+        assert!(!has_photo_extension(Path::new("README"), &extensions));
+    }
+
+    #[test]
+    fn test_scan_source_custom_extensions() -> io::Result<()> {
+        let source = TempDir::new()?;
+        fs::write(source.path().join("a.jpg"), "jpeg")?;
+        fs::write(source.path().join("b.CR2"), "raw")?;
+        fs::write(source.path().join("c.dng"), "raw")?;
+
+        let mut ctx = OrganizeContext::new(
+            source.path().to_path_buf(),
+            PathBuf::from("/dest"),
+            false,
+            None,
+            None,
+        );
+        assert_eq!(Orchestrator::new(ctx.clone()).scan_source()?.len(), 1);
+
+        ctx.extensions = resolve_extensions(&["cr2".to_string(), "dng".to_string()], true);
+        let files = Orchestrator::new(ctx).scan_source()?;
+        assert_eq!(files, vec![source.path().join("b.CR2"), source.path().join("c.dng")]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_scan_source_by_content() -> io::Result<()> {
+        let source = TempDir::new()?;
+        let jpeg = testutil::encode_jpeg(None);
+        fs::write(source.path().join("IMG_0001.jpg"), &jpeg)?;
+        fs::write(source.path().join("IMG_0002"), &jpeg)?;
+        fs::write(source.path().join("IMG_0003.bin"), &jpeg)?;
+        fs::write(source.path().join("invoice.jpg"), "%PDF-1.7")?;
+        testutil::write_heic_with_exif(&source.path().join("IMG_0004.dat"), &[testutil::ascii_field(Tag::Model, "Phone")])?;
+
+        let mut ctx = OrganizeContext::new(
+            source.path().to_path_buf(),
+            PathBuf::from("/dest"),
+            false,
+            None,
+            None,
+        );
+        let files = Orchestrator::new(ctx.clone()).scan_source()?;
+        assert_eq!(files, vec![source.path().join("IMG_0001.jpg"), source.path().join("invoice.jpg")]);
+
+        ctx.by_content = true;
+        let files = Orchestrator::new(ctx).scan_source()?;
+        assert_eq!(
+            files,
+            vec![
+                source.path().join("IMG_0001.jpg"),
+                source.path().join("IMG_0002"),
+                source.path().join("IMG_0003.bin"),
+                source.path().join("IMG_0004.dat"),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_scan_skips_hidden_files_and_sift_artifacts() -> io::Result<()> {
+        let source = TempDir::new()?;
+        fs::write(source.path().join("IMG_0001.jpg"), "photo")?;
+        fs::write(source.path().join(".hidden.jpg"), "hidden")?;
+        fs::write(source.path().join(".sift_index.bin"), "index")?;
+        fs::create_dir(source.path().join(".thumbnails"))?;
+        fs::write(source.path().join(".thumbnails").join("IMG_0001.jpg"), "thumb")?;
+
+        let mut ctx = OrganizeContext::new(
+            source.path().to_path_buf(),
+            PathBuf::from("/dest"),
+            false,
+            None,
+            None,
+        );
+        // Even when `bin` counts as a photo extension, the index is never picked up
+        ctx.extensions = resolve_extensions(&["bin".to_string()], false);
+        ctx.recursive = true;
+        let files = Orchestrator::new(ctx.clone()).scan_source()?;
+        assert_eq!(files, vec![source.path().join("IMG_0001.jpg")]);
+
+        ctx.include_hidden = true;
+        let files = Orchestrator::new(ctx.clone()).scan_source()?;
+        assert_eq!(
+            files,
+            vec![
+                source.path().join(".hidden.jpg"),
+                source.path().join(".thumbnails").join("IMG_0001.jpg"),
+                source.path().join("IMG_0001.jpg"),
+            ]
+        );
+
+        ctx.recursive = false;
+        ctx.include_hidden = false;
+        let files = Orchestrator::new(ctx).scan_source()?;
+        assert_eq!(files, vec![source.path().join("IMG_0001.jpg")]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_analyze_advances_progress() -> io::Result<()> {
+        let source = TempDir::new()?;
+        for i in 0..5 {
+            fs::write(source.path().join(format!("photo{}.jpg", i)), format!("data {}", i))?;
+        }
+
+        let ctx = OrganizeContext::new(
+            source.path().to_path_buf(),
+            PathBuf::from("/dest"),
+            false,
+            None,
+            None,
+        );
+        let mut orchestrator = Orchestrator::new(ctx);
+        let files = orchestrator.scan_source()?;
+        let progress = ProgressBar::hidden();
+        progress.set_length(files.len() as u64);
+
+        let records = orchestrator.analyze_files(&files, &IndexView { index: &Index::new(), stored: None }, &HashMap::new(), &progress)?;
+        assert_eq!(records.len(), 5);
+        assert_eq!(progress.position(), 5);
+        Ok(())
+    }
+
+    #[test]
+    fn test_skip_existing_destination_second_run() -> io::Result<()> {
+        let source = TempDir::new()?;
+        let dest = TempDir::new()?;
+        fs::write(source.path().join("IMG_20230101_a.jpg"), "photo a")?;
+        fs::write(source.path().join("IMG_20230102_b.jpg"), "photo b")?;
+
+        let mut ctx = OrganizeContext::new(
+            source.path().to_path_buf(),
+            dest.path().to_path_buf(),
+            false,
+            None,
+            None,
+        );
+        ctx.skip_existing_destination = true;
+
+        let first = Orchestrator::new(ctx.clone()).run()?;
+        assert_eq!(first.files_hashed, 2);
+        assert_eq!(first.files_organized, 2);
+
+        let copied = dest.path().join("2023/01/01/IMG_20230101_a.jpg");
+        let mtime_before = fs::metadata(&copied)?.modified()?;
+
+        let second = Orchestrator::new(ctx).run()?;
+        assert_eq!(second.files_hashed, 0, "Second run must not hash anything");
+        assert_eq!(second.files_skipped_existing, 2);
+        assert_eq!(second.files_organized, 0);
+        assert_eq!(fs::metadata(&copied)?.modified()?, mtime_before);
+        Ok(())
+    }
+
+    #[test]
+    fn test_skip_existing_destination_size_mismatch() -> io::Result<()> {
+        let source = TempDir::new()?;
+        let dest = TempDir::new()?;
+        fs::write(source.path().join("IMG_20230101_a.jpg"), "new, longer contents")?;
+        fs::create_dir_all(dest.path().join("2023/01/01"))?;
+        fs::write(dest.path().join("2023/01/01/IMG_20230101_a.jpg"), "old")?;
+
+        let mut ctx = OrganizeContext::new(
+            source.path().to_path_buf(),
+            dest.path().to_path_buf(),
+            false,
+            None,
+            None,
+        );
+        ctx.skip_existing_destination = true;
+
+        let stats = Orchestrator::new(ctx).run()?;
+        assert_eq!(stats.files_skipped_existing, 0);
+        assert_eq!(stats.files_hashed, 1);
+        assert!(dest.path().join("2023/01/01/IMG_20230101_a_1.jpg").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_unchanged_sources_are_not_rehashed() -> io::Result<()> {
+        let source = TempDir::new()?;
+        let dest = TempDir::new()?;
+        fs::write(source.path().join("IMG_20230101_a.jpg"), "photo a")?;
+        fs::write(source.path().join("IMG_20230102_b.jpg"), "photo b")?;
+
+        let ctx = OrganizeContext::new(
+            source.path().to_path_buf(),
+            dest.path().to_path_buf(),
+            false,
+            None,
+            None,
+        );
+        let first = Orchestrator::new(ctx.clone()).run()?;
+        assert_eq!(first.files_hashed, 2);
+
+        let index = Index::load_from_file(ctx.get_index_path())?;
+        let source_a = source.path().join("IMG_20230101_a.jpg");
+        let entry = index.get_by_source(&source_a.to_string_lossy()).unwrap();
+        assert_eq!(entry.size, 7);
+        assert_eq!(entry.mtime_unix, index::mtime_unix(&fs::metadata(&source_a)?));
+
+        // Touching one file's contents (and size) forces a re-hash of that file only
+        fs::write(source.path().join("IMG_20230102_b.jpg"), "photo b, edited")?;
+        let second = Orchestrator::new(ctx).run()?;
+        assert_eq!(second.files_hashed, 1);
+        assert_eq!(second.files_skipped_duplicates, 1);
+        assert_eq!(second.files_organized, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_fast_dedup_tells_same_size_files_apart() -> io::Result<()> {
+        let source = TempDir::new()?;
+        let dest = TempDir::new()?;
+        // Same size and same first 64KB, differing only in the last byte
+        let mut a = vec![0u8; 70_000];
+        let mut b = a.clone();
+        a[69_999] = 1;
+        b[69_999] = 2;
+        fs::write(source.path().join("IMG_20230101_a.jpg"), &a)?;
+        fs::write(source.path().join("IMG_20230101_b.jpg"), &b)?;
+        fs::write(source.path().join("IMG_20230101_c.jpg"), &a)?;
+        fs::write(source.path().join("IMG_20230102_d.jpg"), "unique size")?;
+
+        let mut ctx = OrganizeContext::new(
+            source.path().to_path_buf(),
+            dest.path().to_path_buf(),
+            false,
+            None,
+            None,
+        );
+        ctx.fast_dedup = true;
+        let stats = Orchestrator::new(ctx.clone()).run()?;
+        assert_eq!(stats.files_hashed, 3);
+        assert_eq!(stats.files_organized, 3);
+        assert_eq!(stats.files_skipped_duplicates, 1);
+
+        // The file skipped during analysis is still indexed under its full hash
+        let index = Index::load_from_file(ctx.get_index_path())?;
+        for name in ["IMG_20230101_a.jpg", "IMG_20230101_b.jpg", "IMG_20230102_d.jpg"] {
+            let source_path = source.path().join(name);
+            let entry = index.get_by_source(&source_path.to_string_lossy()).unwrap();
+            assert_eq!(entry.hash, hash::hash_file(&source_path)?.to_hex().to_string());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_pixel_dedup_ignores_metadata_changes() -> io::Result<()> {
+        let source = TempDir::new()?;
+        let dest = TempDir::new()?;
+        fs::write(source.path().join("IMG_20230101_a.jpg"), testutil::encode_jpeg(None))?;
+        fs::write(source.path().join("IMG_20230101_b.jpg"), testutil::encode_jpeg(Some(b"x")))?;
+        fs::write(source.path().join("IMG_20230101_c.jpg"), "not decodable")?;
+
+        let ctx = OrganizeContext::new(
+            source.path().to_path_buf(),
+            dest.path().to_path_buf(),
+            false,
+            None,
+            None,
+        );
+        // Byte hashes tell the two JPEGs apart
+        let byte_dest = TempDir::new()?;
+        let mut byte_ctx = ctx.clone();
+        byte_ctx.destination = byte_dest.path().to_path_buf();
+        let stats = Orchestrator::new(byte_ctx).run()?;
+        assert_eq!((stats.files_organized, stats.files_skipped_duplicates), (3, 0));
+
+        let mut ctx = ctx;
+        ctx.pixel_dedup = true;
+        let stats = Orchestrator::new(ctx.clone()).run()?;
+        assert_eq!((stats.files_organized, stats.files_skipped_duplicates), (2, 1));
+
+        // The undecodable file falls back to a byte hash
+        let index = Index::load_from_file(ctx.get_index_path())?;
+        let mut kinds: Vec<HashKind> = index.entries().map(|entry| entry.hash_kind).collect();
+        kinds.sort_by_key(|kind| *kind == HashKind::Pixels);
+        assert_eq!(kinds, [HashKind::Bytes, HashKind::Pixels]);
+        let undecodable = source.path().join("IMG_20230101_c.jpg");
+        let entry = index.get_by_source(&undecodable.to_string_lossy()).unwrap();
+        assert_eq!(entry.hash, hash::hash_file(&undecodable)?.to_hex().to_string());
+        Ok(())
+    }
+
+    #[test]
+    fn test_max_open_one_processes_every_file() -> io::Result<()> {
+        let source = TempDir::new()?;
+        let dest = TempDir::new()?;
+        for i in 0..12 {
+            fs::write(source.path().join(format!("IMG_202301{:02}_{}.jpg", i + 1, i)), format!("photo {}", i))?;
+        }
+        fs::write(source.path().join("IMG_20230201_copy.jpg"), "photo 0")?;
+
+        let mut ctx = OrganizeContext::new(
+            source.path().to_path_buf(),
+            dest.path().to_path_buf(),
+            false,
+            Some(4),
+            None,
+        );
+        ctx.max_open = Some(1);
+        let stats = Orchestrator::new(ctx.clone()).run()?;
+        assert_eq!(stats.files_organized, 12);
+        assert_eq!(stats.files_skipped_duplicates, 1);
+        assert_eq!(stats.files_failed, 0);
+        assert_eq!(Index::load_from_file(ctx.get_index_path())?.len(), 12);
+        Ok(())
+    }
+
+    #[test]
+    fn test_organize_reports_to_sink() -> io::Result<()> {
+        let source = TempDir::new()?;
+        let dest = TempDir::new()?;
+        fs::write(source.path().join("IMG_20230101_a.jpg"), "same")?;
+        fs::write(source.path().join("IMG_20230101_b.jpg"), "same")?;
+        fs::write(source.path().join("IMG_20230102_c.jpg"), "other")?;
+
+        let ctx = OrganizeContext::new(source.path().to_path_buf(), dest.path().to_path_buf(), false, None, None);
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = {
+            let seen = Arc::clone(&seen);
+            move |event: &Event<'_>| seen.lock().unwrap().push(serde_json::to_value(event).unwrap())
+        };
+        let report = organize(ctx, sink)?;
+
+        assert_eq!(report.stats.files_organized, 2);
+        assert_eq!(report.stats.files_skipped_duplicates, 1);
+        assert!(report.errors.is_empty());
+        let seen = seen.lock().unwrap();
+        let count = |kind: &str| seen.iter().filter(|event| event["event"] == kind).count();
+        assert_eq!((count("organized"), count("skipped_duplicate"), count("summary")), (2, 1, 1));
+        assert_eq!(seen.last().unwrap()["files_organized"], 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_quiet_reports_only_failures_and_summary() -> io::Result<()> {
+        let source = TempDir::new()?;
+        let dest = TempDir::new()?;
+        fs::write(source.path().join("IMG_20230101_a.jpg"), "same")?;
+        fs::write(source.path().join("IMG_20230101_b.jpg"), "same")?;
+        fs::write(source.path().join("IMG_20230102_c.jpg"), "other")?;
+        // Its folder is taken by a file, so the transfer fails
+        fs::create_dir(dest.path().join("2023"))?;
+        fs::write(dest.path().join("2023/01"), "not a folder")?;
+
+        let mut ctx = OrganizeContext::new(source.path().to_path_buf(), dest.path().to_path_buf(), false, None, None);
+        ctx.log_level = LogLevel::Quiet;
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = {
+            let seen = Arc::clone(&seen);
+            move |event: &Event<'_>| seen.lock().unwrap().push(serde_json::to_value(event).unwrap())
+        };
+        let report = organize(ctx, sink)?;
+
+        assert_eq!(report.stats.files_skipped_duplicates, 1);
+        assert_eq!(report.stats.files_failed, 2);
+        let seen = seen.lock().unwrap();
+        let kinds: Vec<&str> = seen.iter().map(|event| event["event"].as_str().unwrap()).collect();
+        assert_eq!(kinds, ["failed", "failed", "summary"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_log_level_from_flags() {
+        assert_eq!(LogLevel::from_flags(false, false), LogLevel::Normal);
+        assert_eq!(LogLevel::from_flags(true, false), LogLevel::Quiet);
+        assert_eq!(LogLevel::from_flags(false, true), LogLevel::Verbose);
+        assert!(LogLevel::Quiet < LogLevel::Normal && LogLevel::Normal < LogLevel::Verbose);
+    }
+
+    #[test]
+    fn test_json_events() -> io::Result<()> {
+        let source = TempDir::new()?;
+        let dest = TempDir::new()?;
+        let log = TempDir::new()?;
+        fs::write(source.path().join("IMG_20230101_a.jpg"), "same")?;
+        fs::write(source.path().join("IMG_20230101_b.jpg"), "same")?;
+
+        let ctx = OrganizeContext::new(
+            source.path().to_path_buf(),
+            dest.path().to_path_buf(),
+            false,
+            None,
+            None,
+        );
+        let events_path = log.path().join("events.jsonl");
+        let events = crate::events::EventWriter::new(File::create(&events_path)?);
+        Orchestrator::new(ctx).with_events(events).run()?;
+
+        let lines: Vec<serde_json::Value> = fs::read_to_string(&events_path)?
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let kinds: Vec<&str> = lines.iter().map(|line| line["event"].as_str().unwrap()).collect();
+        assert_eq!(kinds, ["skipped_duplicate", "organized", "summary"]);
+
+        assert_eq!(lines[0]["src"], source.path().join("IMG_20230101_b.jpg").to_string_lossy().as_ref());
+        assert!(lines[0]["duplicate_of"].is_null());
+        assert_eq!(
+            lines[1]["dst"],
+            dest.path().join("2023/01/01/IMG_20230101_a.jpg").to_string_lossy().as_ref()
+        );
+        let summary = &lines[2];
+        assert_eq!(summary["files_scanned"], 2);
+        assert_eq!(summary["files_organized"], 1);
+        assert_eq!(summary["files_skipped_duplicates"], 1);
+        assert_eq!(summary["files_failed"], 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_duplicate_candidates() -> io::Result<()> {
+        let source = TempDir::new()?;
+        let unique = source.path().join("unique.jpg");
+        let indexed_size = source.path().join("indexed_size.jpg");
+        let first = source.path().join("first.jpg");
+        let second = source.path().join("second.jpg");
+        fs::write(&unique, "12345")?;
+        fs::write(&indexed_size, "1234")?;
+        fs::write(&first, "abc")?;
+        fs::write(&second, "xyz")?;
+
+        let mut index = Index::new();
+        index.insert(IndexEntry {
+            hash: "h".to_string(),
+            file_paths: vec!["/dest/old.jpg".to_string()],
+            source_path: None,
+            size: 4,
+            mtime_unix: 0,
+            hash_kind: HashKind::Bytes,
+        });
+        let files = vec![unique, indexed_size.clone(), first, second];
+        // Same size but different first bytes: neither needs a full hash
+        assert_eq!(duplicate_candidates(&files, &index.sizes()?, &OpenLimit::new(None)), HashSet::from([indexed_size]));
+
+        // A size missing from an old entry is read from its file
+        let dest = TempDir::new()?;
+        let legacy = dest.path().join("legacy.jpg");
+        fs::write(&legacy, "54321")?;
+        index.insert(IndexEntry {
+            hash: "legacy".to_string(),
+            file_paths: vec![legacy.to_string_lossy().into_owned()],
+            source_path: None,
+            size: 0,
+            mtime_unix: 0,
+            hash_kind: HashKind::Bytes,
+        });
+        let indexed_sizes = IndexView { index: &index, stored: None }.sizes()?.unwrap();
+        assert_eq!(indexed_sizes, HashSet::from([4, 5]));
+        assert_eq!(
+            duplicate_candidates(&files, &indexed_sizes, &OpenLimit::new(None)),
+            HashSet::from([files[0].clone(), files[1].clone()])
+        );
+
+        // Unless the file is gone too: then every file is hashed
+        fs::remove_file(&legacy)?;
+        assert_eq!(IndexView { index: &index, stored: None }.sizes()?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_files_inside_nested_destination_are_skipped() -> io::Result<()> {
+        let source = TempDir::new()?;
+        let dest = source.path().join("Organized");
+        fs::create_dir_all(dest.join("2023/01/01"))?;
+        fs::write(dest.join("2023/01/01/IMG_20230101_old.jpg"), "organized earlier")?;
+        fs::write(source.path().join("IMG_20230102_new.jpg"), "new photo")?;
+
+        let mut ctx = OrganizeContext::new(
+            source.path().to_path_buf(),
+            dest.clone(),
+            false,
+            None,
+            None,
+        );
+        ctx.recursive = true;
+
+        let stats = Orchestrator::new(ctx.clone()).run()?;
+        assert_eq!(stats.files_scanned, 2);
+        assert_eq!(stats.files_skipped_in_destination, 1);
+        assert_eq!(stats.files_hashed, 1);
+        assert_eq!(stats.files_organized, 1);
+        assert!(dest.join("2023/01/02/IMG_20230102_new.jpg").exists());
+
+        // Toggled off, the organized photo is scanned (and found to be in place)
+        ctx.exclude_destination = false;
+        let stats = Orchestrator::new(ctx).run()?;
+        assert_eq!(stats.files_skipped_in_destination, 0);
+        assert_eq!(stats.files_scanned, 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_nested_destination() -> io::Result<()> {
+        let source = TempDir::new()?;
+        let inside = source.path().join("Organized");
+        fs::create_dir(&inside)?;
+        let outside = TempDir::new()?;
+
+        assert_eq!(nested_destination(source.path(), &inside)?, Some(inside.clone()));
+        assert_eq!(nested_destination(source.path(), outside.path())?, None);
+        assert_eq!(nested_destination(source.path(), &source.path().join("missing"))?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_date_range_filter() -> io::Result<()> {
+        let source = TempDir::new()?;
+        let dest = TempDir::new()?;
+        for name in ["IMG_20230531_a.jpg", "IMG_20230601_b.jpg", "IMG_20230630_c.jpg", "IMG_20230701_d.jpg"] {
+            fs::write(source.path().join(name), name)?;
+        }
+
+        let mut ctx = OrganizeContext::new(source.path().to_path_buf(), dest.path().to_path_buf(), false, None, None);
+        ctx.date_range = DateRange {
+            since: NaiveDate::from_ymd_opt(2023, 6, 1),
+            until: NaiveDate::from_ymd_opt(2023, 6, 30),
+            include_undated: false,
+        };
+        let stats = Orchestrator::new(ctx).run()?;
+        assert_eq!(stats.files_skipped_date, 2);
+        assert_eq!(stats.files_organized, 2, "Both boundary days are included");
+        assert_eq!(stats.files_failed, 0);
+        assert!(dest.path().join("2023/06/01/IMG_20230601_b.jpg").exists());
+        assert!(dest.path().join("2023/06/30/IMG_20230630_c.jpg").exists());
+        assert!(!dest.path().join("2023/05").exists());
+        assert!(!dest.path().join("2023/07").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_min_rating_filter() -> io::Result<()> {
+        let source = TempDir::new()?;
+        let dest = TempDir::new()?;
+        testutil::write_jpeg_with_xmp(&source.path().join("IMG_20230101_keep.jpg"), r#"xmp:Rating="4""#)?;
+        testutil::write_jpeg_with_xmp(&source.path().join("IMG_20230101_drop.jpg"), r#"xmp:Rating="2""#)?;
+        fs::write(source.path().join("IMG_20230101_unrated.jpg"), "no metadata")?;
+
+        let mut ctx = OrganizeContext::new(
+            source.path().to_path_buf(),
+            dest.path().to_path_buf(),
+            false,
+            None,
+            None,
+        );
+        ctx.min_rating = Some(3);
+        ctx.dry_run = true;
+
+        let stats = Orchestrator::new(ctx.clone()).run()?;
+        assert_eq!(stats.files_skipped_rating, 1);
+        assert_eq!(stats.files_organized, 2, "Rated 4 and unrated photos pass");
+
+        ctx.require_rating = true;
+        let stats = Orchestrator::new(ctx).run()?;
+        assert_eq!(stats.files_skipped_rating, 2);
+        assert_eq!(stats.files_organized, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_find_duplicates() -> io::Result<()> {
+        let dir = TempDir::new()?;
+        fs::create_dir(dir.path().join("nested"))?;
+        fs::write(dir.path().join("b_copy.jpg"), "known photo")?;
+        fs::write(dir.path().join("nested/a_copy.txt"), "known photo")?;
+        fs::write(dir.path().join("new.jpg"), "never seen")?;
+
+        let mut index = Index::new();
+        let known = hash::hash_bytes(b"known photo").to_hex().to_string();
+        index.add_entry(known, "/library/2023/01/01/original.jpg".to_string());
+
+        let scan = find_duplicates(dir.path(), &index)?;
+        assert!(scan.unreadable.is_empty());
+        assert_eq!(
+            scan.duplicates,
+            vec![
+                (dir.path().join("b_copy.jpg"), vec!["/library/2023/01/01/original.jpg".to_string()]),
+                (dir.path().join("nested/a_copy.txt"), vec!["/library/2023/01/01/original.jpg".to_string()]),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_find_duplicates_reports_unreadable_file() -> io::Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = TempDir::new()?;
+        fs::write(dir.path().join("copy.jpg"), "known photo")?;
+        let locked = dir.path().join("locked.jpg");
+        fs::write(&locked, "known photo")?;
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o000))?;
+        if fs::File::open(&locked).is_ok() {
+            // Permissions don't apply to root
+            return Ok(());
+        }
+
+        let mut index = Index::new();
+        index.add_entry(hash::hash_bytes(b"known photo").to_hex().to_string(), "/library/original.jpg".to_string());
+
+        let scan = find_duplicates(dir.path(), &index)?;
+        assert_eq!(scan.duplicates, vec![(dir.path().join("copy.jpg"), vec!["/library/original.jpg".to_string()])]);
+        assert_eq!(scan.unreadable.len(), 1);
+        assert_eq!(scan.unreadable[0].0, locked);
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_index_reports_missing_file() -> io::Result<()> {
+        let dir = TempDir::new()?;
+        let kept = dir.path().join("kept.jpg");
+        fs::write(&kept, "kept")?;
+
+        let mut index = Index::new();
+        index.add_entry(hash::hash_bytes(b"kept").to_hex().to_string(), kept.to_string_lossy().to_string());
+        let gone = dir.path().join("gone.jpg").to_string_lossy().to_string();
+        index.add_entry(hash::hash_bytes(b"gone").to_hex().to_string(), gone.clone());
+
+        let report = verify_index(&index, false);
+        assert_eq!(report.total, 2);
+        assert_eq!(report.missing, vec![gone]);
+        assert!(report.mismatched.is_empty());
+        assert_eq!(report.clean_count(), 1);
+        assert!(!report.is_clean());
+        Ok(())
+    }
+
+    #[test]
+    fn test_shared_index_is_updated_in_place() -> io::Result<()> {
+        let source = TempDir::new()?;
+        let dest = TempDir::new()?;
+        fs::write(source.path().join("IMG_20230101_a.jpg"), "a")?;
+        fs::write(source.path().join("IMG_20230102_b.jpg"), "b")?;
+
+        let shared = IndexHandle::default();
+        let mut ctx = OrganizeContext::new(source.path().to_path_buf(), dest.path().to_path_buf(), false, None, None);
+        ctx.index = Some(shared.clone());
+        Orchestrator::new(ctx).run()?;
+
+        // The caller sees the run's entries without reloading the saved index
+        assert_eq!(shared.len(), 2);
+        assert!(shared.contains_hash(hash::hash_bytes(b"a").to_hex().as_str()));
+        assert!(verify_index(&shared.read(), true).is_clean());
+        assert_eq!(Index::load_from_file(dest.path().join(".sift_index.bin"))?.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sqlite_index_backend() -> io::Result<()> {
+        let source = TempDir::new()?;
+        let dest = TempDir::new()?;
+        fs::write(source.path().join("IMG_20230101_a.jpg"), "a")?;
+        fs::write(source.path().join("IMG_20230102_b.jpg"), "b")?;
+
+        let context = || {
+            let mut ctx = OrganizeContext::new(source.path().to_path_buf(), dest.path().to_path_buf(), false, None, None);
+            ctx.index_backend = IndexBackend::Sqlite;
+            ctx
+        };
+        let stats = Orchestrator::new(context()).run()?;
+        assert_eq!(stats.files_organized, 2);
+        let index_path = dest.path().join(".sift_index.bin");
+        assert_eq!(IndexBackend::detect(&index_path), Some(IndexBackend::Sqlite));
+        assert_eq!(SqliteIndex::open(&index_path)?.len()?, 2);
+
+        // A second run finds the entries in the database, without rehashing
+        // unchanged sources, and adds only the new file
+        fs::write(source.path().join("IMG_20230103_c.jpg"), "c")?;
+        let stats = Orchestrator::new(context()).run()?;
+        assert_eq!(stats.files_organized, 1);
+        assert_eq!(stats.files_skipped_duplicates, 2);
+        assert_eq!(stats.files_hashed, 1);
+        assert_eq!(SqliteIndex::open(&index_path)?.len()?, 3);
+
+        // Planning queries the database in place rather than loading it
+        let plan = Orchestrator::new(context()).plan()?;
+        assert!(plan.actions.is_empty());
+        assert!(plan.index.is_empty());
+
+        // Opening it as a bincode index fails rather than overwriting it
+        let ctx = OrganizeContext::new(source.path().to_path_buf(), dest.path().to_path_buf(), false, None, None);
+        let err = Orchestrator::new(ctx).run().unwrap_err();
+        assert!(err.to_string().contains("--index-format sqlite"), "{}", err);
+        assert_eq!(SqliteIndex::open(&index_path)?.len()?, 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_size_filter_bounds_are_inclusive() -> io::Result<()> {
+        let source = TempDir::new()?;
+        let dest = TempDir::new()?;
+        for (day, len) in [(1, 1), (2, 2), (3, 3), (4, 4), (5, 5)] {
+            fs::write(source.path().join(format!("IMG_2023010{}.jpg", day)), "x".repeat(len))?;
+        }
+
+        let mut ctx = OrganizeContext::new(source.path().to_path_buf(), dest.path().to_path_buf(), false, None, None);
+        ctx.min_size = Some(2);
+        ctx.max_size = Some(4);
+        let stats = Orchestrator::new(ctx).run()?;
+        assert_eq!(stats.files_organized, 3);
+        assert_eq!(stats.files_skipped_size, 2);
+        assert!(dest.path().join("2023/01/02/IMG_20230102.jpg").is_file());
+        assert!(dest.path().join("2023/01/04/IMG_20230104.jpg").is_file());
+        assert!(!dest.path().join("2023/01/01").exists());
+        assert!(!dest.path().join("2023/01/05").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_copy_preserves_source_timestamps() -> io::Result<()> {
+        use filetime::FileTime;
+
+        let source = TempDir::new()?;
+        let dest = TempDir::new()?;
+        let old = FileTime::from_unix_time(1_000_000_000, 0);
+        for name in ["IMG_20230101_a.jpg", "IMG_20230102_b.jpg"] {
+            let path = source.path().join(name);
+            fs::write(&path, name)?;
+            filetime::set_file_mtime(&path, old)?;
+        }
+        let modified = |path: PathBuf| fs::metadata(path).map(|stat| FileTime::from_last_modification_time(&stat));
+
+        let ctx = OrganizeContext::new(source.path().to_path_buf(), dest.path().to_path_buf(), false, None, None);
+        Orchestrator::new(ctx).run()?;
+        assert_eq!(modified(dest.path().join("2023/01/01/IMG_20230101_a.jpg"))?, old);
+
+        // Opting out leaves copies with the time they were made
+        fs::remove_dir_all(dest.path().join("2023"))?;
+        fs::remove_file(dest.path().join(".sift_index.bin"))?;
+        let mut ctx = OrganizeContext::new(source.path().to_path_buf(), dest.path().to_path_buf(), false, None, None);
+        ctx.preserve_times = false;
+        Orchestrator::new(ctx).run()?;
+        assert!(modified(dest.path().join("2023/01/02/IMG_20230102_b.jpg"))? > old);
+        Ok(())
+    }
+
+    #[test]
+    fn test_scan_dest_skips_content_already_in_destination() -> io::Result<()> {
+        let source = TempDir::new()?;
+        let dest = TempDir::new()?;
+        fs::write(source.path().join("IMG_20230101_a.jpg"), "already there")?;
+        fs::write(source.path().join("IMG_20230102_b.jpg"), "new")?;
+        // Organized earlier by another tool, under another name
+        fs::create_dir_all(dest.path().join("Old/Holidays"))?;
+        fs::write(dest.path().join("Old/Holidays/beach.jpg"), "already there")?;
+
+        let mut ctx = OrganizeContext::new(source.path().to_path_buf(), dest.path().to_path_buf(), false, None, None);
+        ctx.scan_dest = true;
+        let stats = Orchestrator::new(ctx).run()?;
+        assert_eq!(stats.files_organized, 1);
+        assert_eq!(stats.files_skipped_duplicates, 1);
+        assert!(!dest.path().join("2023/01/01/IMG_20230101_a.jpg").exists());
+        assert!(dest.path().join("2023/01/02/IMG_20230102_b.jpg").is_file());
+
+        // The existing file is kept in the index, and not hashed again on the next scan
+        let index = Index::load_from_file(dest.path().join(".sift_index.bin"))?;
+        let seeded = index.get_entry(hash::hash_bytes(b"already there").to_hex().as_ref()).unwrap();
+        assert_eq!(seeded.file_paths, [dest.path().join("Old/Holidays/beach.jpg").to_string_lossy()]);
+        assert_eq!(seeded.source_path, None);
+        assert_eq!(index.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_scan_dest_ignores_source_nested_in_destination() -> io::Result<()> {
+        let dest = TempDir::new()?;
+        let inbox = dest.path().join("Inbox");
+        fs::create_dir_all(&inbox)?;
+        fs::write(inbox.join("IMG_20230101_a.jpg"), "a")?;
+        fs::write(inbox.join("IMG_20230102_b.jpg"), "b")?;
+        fs::create_dir_all(dest.path().join("2022/05/01"))?;
+        fs::write(dest.path().join("2022/05/01/old.jpg"), "old")?;
+
+        let mut ctx = OrganizeContext::new(inbox.clone(), dest.path().to_path_buf(), false, None, None);
+        ctx.scan_dest = true;
+        let stats = Orchestrator::new(ctx).run()?;
+        assert_eq!((stats.files_organized, stats.files_skipped_duplicates), (2, 0));
+        assert!(dest.path().join("2023/01/02/IMG_20230102_b.jpg").is_file());
+
+        // Only the library itself was seeded
+        let index = Index::load_from_file(dest.path().join(".sift_index.bin"))?;
+        assert!(index.get_entry(hash::hash_bytes(b"old").to_hex().as_ref()).is_some());
+        assert_eq!(index.len(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_index_rehash_detects_changed_content() -> io::Result<()> {
+        let dir = TempDir::new()?;
+        let photo = dir.path().join("photo.jpg");
+        fs::write(&photo, "original pixels")?;
+
+        let mut index = Index::new();
+        let path = photo.to_string_lossy().to_string();
+        index.add_entry(hash::hash_bytes(b"original pixels").to_hex().to_string(), path.clone());
+        assert!(verify_index(&index, true).is_clean());
+
+        fs::write(&photo, "edited pixels")?;
+        assert!(verify_index(&index, false).is_clean(), "Existence check alone can't see edits");
+        let report = verify_index(&index, true);
+        assert_eq!(report.mismatched, vec![path]);
+        assert_eq!(report.clean_count(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_interrupted_run_resumes_from_checkpoint() -> io::Result<()> {
+        let source = TempDir::new()?;
+        let dest = TempDir::new()?;
+        for day in 1..=5 {
+            fs::write(source.path().join(format!("IMG_2023010{}_0001.jpg", day)), format!("photo {}", day))?;
+        }
+        let context = || {
+            let mut ctx = OrganizeContext::new(
+                source.path().to_path_buf(),
+                dest.path().to_path_buf(),
+                false,
+                None,
+                None,
+            );
+            ctx.checkpoint_every = 2;
+            ctx
+        };
+
+        // Simulate a run killed right after its first checkpoint: only the first
+        // batch of transfers happened, and the index saved at that point survives
+        let mut orchestrator = Orchestrator::new(context());
+        let mut plan = orchestrator.plan()?;
+        plan.actions.truncate(2);
+        orchestrator.execute(plan)?;
+
+        let partial = Index::load_from_file(dest.path().join(".sift_index.bin"))?;
+        assert_eq!(partial.len(), 2);
+
+        let stats = Orchestrator::new(context()).run()?;
+        assert_eq!(stats.files_skipped_duplicates, 2, "Checkpointed files are not copied again");
+        assert_eq!(stats.files_organized, 3);
+        assert_eq!(Index::load_from_file(dest.path().join(".sift_index.bin"))?.len(), 5);
+        assert_eq!(files_under(dest.path()).len(), 7); // 5 photos + index + manifest
+        Ok(())
+    }
+
+    #[test]
+    fn test_cancelled_run_saves_index_and_stops() -> io::Result<()> {
+        let source = TempDir::new()?;
+        let dest = TempDir::new()?;
+        fs::write(source.path().join("IMG_20230101_0001.jpg"), "one")?;
+        fs::write(source.path().join("IMG_20230102_0001.jpg"), "two")?;
+
+        let ctx = OrganizeContext::new(
+            source.path().to_path_buf(),
+            dest.path().to_path_buf(),
             false,
             None,
             None,
         );
+        let cancel = ctx.cancel.clone();
+        let mut orchestrator = Orchestrator::new(ctx);
+        let plan = orchestrator.plan()?;
+        cancel.store(true, Ordering::Relaxed);
 
-        let index_path = ctx.get_index_path();
-        assert!(index_path.ends_with(".sift_index.bin"));
+        let err = orchestrator.execute(plan).unwrap_err();
+        assert!(matches!(err, OrganizeError::IoError(ref e) if e.kind() == io::ErrorKind::Interrupted));
+        assert_eq!(
+            files_under(dest.path()),
+            vec![PathBuf::from(".sift_index.bin")]
+        );
+        assert!(Index::load_from_file(dest.path().join(".sift_index.bin"))?.is_empty());
+        Ok(())
     }
 
     #[test]
-    fn test_organize_context_custom_index_path() {
-        let custom_path = PathBuf::from("/custom/index.bin");
-        let ctx = OrganizeContext::new(
-            PathBuf::from("/source"),
-            PathBuf::from("/dest"),
+    fn test_organize_with_template() -> io::Result<()> {
+        let source = TempDir::new()?;
+        let dest = TempDir::new()?;
+        fs::write(source.path().join("IMG_20230105_0001.jpg"), "one")?;
+        testutil::write_jpeg_with_exif(
+            &source.path().join("DSC_0002.jpg"),
+            &[
+                testutil::ascii_field(Tag::DateTimeOriginal, "2023:02:14 09:00:00"),
+                testutil::ascii_field(Tag::Model, "X100V"),
+            ],
+        )?;
+
+        let mut ctx = OrganizeContext::new(
+            source.path().to_path_buf(),
+            dest.path().to_path_buf(),
             false,
             None,
-            Some(custom_path.clone()),
+            None,
+        );
+        ctx.template = Some(PathTemplate::parse("{year}/{camera}/{year}-{month}").unwrap());
+        let stats = Orchestrator::new(ctx.clone()).run()?;
+        assert_eq!(stats.files_organized, 2);
+        assert_eq!(
+            files_under(dest.path()),
+            vec![
+                PathBuf::from(".sift_index.bin"),
+                PathBuf::from("2023/Unknown Camera/2023-01/IMG_20230105_0001.jpg"),
+                PathBuf::from("2023/X100V/2023-02/DSC_0002.jpg"),
+                PathBuf::from(MANIFEST_FILE),
+            ]
         );
 
-        let index_path = ctx.get_index_path();
-        assert_eq!(index_path, custom_path);
+        // --skip-existing-destination looks for files where the template put them
+        ctx.skip_existing_destination = true;
+        let rerun = Orchestrator::new(ctx).run()?;
+        assert_eq!(rerun.files_skipped_existing, 2);
+        Ok(())
     }
 
     #[test]
-    fn test_stats_default() {
-        let stats = OrganizeStats::default();
-        assert_eq!(stats.files_scanned, 0);
-        assert_eq!(stats.files_analyzed, 0);
-        assert_eq!(stats.files_organized, 0);
+    fn test_organize_by_month() -> io::Result<()> {
+        let source = TempDir::new()?;
+        let dest = TempDir::new()?;
+        fs::write(source.path().join("IMG_20231005_0001.jpg"), "one")?;
+        fs::write(source.path().join("IMG_20231028_0002.jpg"), "two")?;
+
+        let mut ctx = OrganizeContext::new(
+            source.path().to_path_buf(),
+            dest.path().to_path_buf(),
+            false,
+            None,
+            None,
+        );
+        ctx.granularity = Granularity::Month;
+        Orchestrator::new(ctx).run()?;
+        assert_eq!(
+            files_under(dest.path()),
+            vec![
+                PathBuf::from(".sift_index.bin"),
+                PathBuf::from("2023/10/IMG_20231005_0001.jpg"),
+                PathBuf::from("2023/10/IMG_20231028_0002.jpg"),
+                PathBuf::from(MANIFEST_FILE),
+            ]
+        );
+        Ok(())
     }
 
     #[test]
-    fn test_file_record_creation() {
-        let record = FileRecord {
-            path: PathBuf::from("/source/photo.jpg"),
-            hash: "abc123def456".to_string(),
-            date: None,
-            location: None,
-        };
+    fn test_organize_by_camera() -> io::Result<()> {
+        let source = TempDir::new()?;
+        let dest = TempDir::new()?;
+        testutil::write_jpeg_with_exif(
+            &source.path().join("DSC_0001.jpg"),
+            &[
+                testutil::ascii_field(Tag::DateTimeOriginal, "2023:06:01 12:00:00"),
+                testutil::ascii_field(Tag::Make, "FUJIFILM"),
+                testutil::ascii_field(Tag::Model, "X100V"),
+            ],
+        )?;
+        fs::write(source.path().join("IMG_20230602_0001.jpg"), "no exif")?;
 
-        assert_eq!(record.path, PathBuf::from("/source/photo.jpg"));
-        assert_eq!(record.hash, "abc123def456");
-        assert!(record.date.is_none());
-        assert!(record.location.is_none());
+        let mut ctx = OrganizeContext::new(
+            source.path().to_path_buf(),
+            dest.path().to_path_buf(),
+            false,
+            None,
+            None,
+        );
+        ctx.by_camera = true;
+        Orchestrator::new(ctx.clone()).run()?;
+        assert_eq!(
+            files_under(dest.path()),
+            vec![
+                PathBuf::from(".sift_index.bin"),
+                PathBuf::from("FUJIFILM X100V/2023/06/01/DSC_0001.jpg"),
+                PathBuf::from("Unknown Camera/2023/06/02/IMG_20230602_0001.jpg"),
+                PathBuf::from(MANIFEST_FILE),
+            ]
+        );
+
+        ctx.skip_existing_destination = true;
+        assert_eq!(Orchestrator::new(ctx).run()?.files_skipped_existing, 2);
+        Ok(())
     }
 
     #[test]
-    fn test_file_record_with_date() {
-        use chrono::NaiveDate;
+    fn test_location_template_without_gps() -> io::Result<()> {
+        let source = TempDir::new()?;
+        let dest = TempDir::new()?;
+        fs::write(source.path().join("IMG_20230105_0001.jpg"), "one")?;
 
-        let date = NaiveDate::from_ymd_opt(2024, 2, 11);
-        let record = FileRecord {
-            path: PathBuf::from("/source/photo.jpg"),
-            hash: "abc123".to_string(),
-            date,
-            location: None,
-        };
+        let mut ctx = OrganizeContext::new(
+            source.path().to_path_buf(),
+            dest.path().to_path_buf(),
+            false,
+            None,
+            None,
+        );
+        ctx.template = Some(PathTemplate::parse("{year}/{location}").unwrap());
+        Orchestrator::new(ctx).run()?;
+        assert!(dest.path().join("2023/Unknown Location/IMG_20230105_0001.jpg").exists());
+        Ok(())
+    }
 
-        assert!(record.date.is_some());
-        assert_eq!(record.date.unwrap().year(), 2024);
+    /// Lists every file under `dir`, relative to it, sorted.
+    fn files_under(dir: &Path) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = WalkDir::new(dir)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file())
+            .map(|e| e.path().strip_prefix(dir).unwrap().to_path_buf())
+            .collect();
+        files.sort();
+        files
     }
 
     #[test]
-    fn test_file_record_with_location() {
-        let record = FileRecord {
-            path: PathBuf::from("/source/photo.jpg"),
-            hash: "abc123".to_string(),
-            date: None,
-            location: Some((37.7749, -122.4194)), // San Francisco
-        };
+    fn test_plan_writes_nothing_and_execute_follows_it() -> io::Result<()> {
+        let source = TempDir::new()?;
+        let dest = TempDir::new()?;
+        fs::create_dir_all(source.path().join("card1"))?;
+        fs::create_dir_all(source.path().join("card2"))?;
+        fs::write(source.path().join("card1/IMG_20230101_0001.jpg"), "first")?;
+        fs::write(source.path().join("card2/IMG_20230101_0001.jpg"), "second")?;
+        fs::write(source.path().join("IMG_20230103_0002.jpg"), "third")?;
+        fs::write(source.path().join("IMG_20230103_0003.jpg"), "third")?;
 
-        assert!(record.location.is_some());
-        let (lat, lon) = record.location.unwrap();
-        assert_eq!(lat, 37.7749);
-        assert_eq!(lon, -122.4194);
+        let mut ctx = OrganizeContext::new(
+            source.path().to_path_buf(),
+            dest.path().to_path_buf(),
+            false,
+            None,
+            None,
+        );
+        ctx.recursive = true;
+        let mut orchestrator = Orchestrator::new(ctx);
+
+        let plan = orchestrator.plan()?;
+        assert!(files_under(dest.path()).is_empty(), "plan() must not write");
+        assert_eq!(fs::read_dir(dest.path())?.count(), 0);
+
+        // Same content twice is planned once; same name twice gets a suffix
+        assert_eq!(orchestrator.stats.files_skipped_duplicates, 1);
+        assert_eq!(plan.actions.len(), 3);
+        assert_eq!(plan.collision_count(), 1);
+        assert_eq!(plan.bytes_needed(), 5 + 6 + 5);
+        assert_eq!(
+            plan.new_folders,
+            BTreeSet::from([
+                dest.path().join("2023"),
+                dest.path().join("2023/01"),
+                dest.path().join("2023/01/01"),
+                dest.path().join("2023/01/03"),
+            ])
+        );
+
+        let mut planned: Vec<PathBuf> = plan
+            .actions
+            .iter()
+            .map(|a| a.destination.strip_prefix(dest.path()).unwrap().to_path_buf())
+            .collect();
+        planned.push(PathBuf::from(".sift_index.bin"));
+        planned.push(PathBuf::from(MANIFEST_FILE));
+        planned.sort();
+
+        let stats = orchestrator.execute(plan)?;
+        assert_eq!(stats.files_organized, 3);
+        assert_eq!(files_under(dest.path()), planned);
+        Ok(())
     }
 
     #[test]
-    fn test_scan_source_empty_directory() -> io::Result<()> {
-        let temp = TempDir::new()?;
+    fn test_execute_refuses_destination_taken_after_planning() -> io::Result<()> {
+        let source = TempDir::new()?;
         let dest = TempDir::new()?;
+        fs::write(source.path().join("IMG_20230101_0001.jpg"), "planned")?;
 
         let ctx = OrganizeContext::new(
-            temp.path().to_path_buf(),
+            source.path().to_path_buf(),
             dest.path().to_path_buf(),
             false,
             None,
             None,
         );
+        let mut orchestrator = Orchestrator::new(ctx);
+        let plan = orchestrator.plan()?;
 
-        let orchestrator = Orchestrator::new(ctx);
-        let files = orchestrator.scan_source()?;
+        let taken = dest.path().join("2023/01/01/IMG_20230101_0001.jpg");
+        fs::create_dir_all(taken.parent().unwrap())?;
+        fs::write(&taken, "someone else")?;
 
-        assert_eq!(files.len(), 0);
+        assert!(matches!(
+            orchestrator.realize(&plan.actions[0]),
+            Err(OrganizeError::OrganizationError(_))
+        ));
+        let stats = orchestrator.execute(plan)?;
+        assert_eq!(stats.files_failed, 1);
+        assert_eq!(fs::read_to_string(&taken)?, "someone else");
         Ok(())
     }
 
     #[test]
-    fn test_scan_source_with_photos() -> io::Result<()> {
-        let temp = TempDir::new()?;
+    fn test_failures_map_to_error_variants() -> io::Result<()> {
+        let source = TempDir::new()?;
         let dest = TempDir::new()?;
+        let ctx = OrganizeContext::new(
+            source.path().to_path_buf(),
+            dest.path().to_path_buf(),
+            false,
+            None,
+            None,
+        );
+        let orchestrator = Orchestrator::new(ctx.clone());
+        let progress = ProgressBar::hidden();
+        let hashed = AtomicUsize::new(0);
+        let index = IndexView { index: &Index::new(), stored: None };
 
-        // Create test photo files
-        fs::write(temp.path().join("photo1.jpg"), "test")?;
-        fs::write(temp.path().join("photo2.jpeg"), "test")?;
-        fs::write(temp.path().join("photo3.png"), "test")?;
-        fs::write(temp.path().join("document.txt"), "test")?; // Should be ignored
+        let missing = source.path().join("missing.jpg");
+        let result = orchestrator.analyze_file(&missing, &index, None, None, &hashed, &progress);
+        assert!(matches!(result, Err(OrganizeError::FileAccess(_))));
+
+        // A directory can be stat'ed but not read
+        let folder = source.path().join("folder.jpg");
+        fs::create_dir(&folder)?;
+        let result = orchestrator.analyze_file(&folder, &index, None, None, &hashed, &progress);
+        assert!(matches!(result, Err(OrganizeError::HashError(_))));
+
+        fs::write(ctx.get_index_path(), "not an index")?;
+        let result = Orchestrator::new(ctx).run();
+        assert!(matches!(result, Err(OrganizeError::IndexError(_))));
+        Ok(())
+    }
+
+    #[test]
+    fn test_undated_files_go_to_unsorted() -> io::Result<()> {
+        let source = TempDir::new()?;
+        let dest = TempDir::new()?;
+        let photo = source.path().join("scan.jpg");
+        fs::write(&photo, "no date anywhere")?;
 
         let ctx = OrganizeContext::new(
-            temp.path().to_path_buf(),
+            source.path().to_path_buf(),
             dest.path().to_path_buf(),
             false,
             None,
             None,
         );
+        let mut orchestrator = Orchestrator::new(ctx.clone());
+        // Every file has at least an mtime, so strip the date from the analyzed record
+        let mut record = orchestrator.analyze_file(
+            &photo,
+            &IndexView { index: &Index::new(), stored: None },
+            None,
+            None,
+            &AtomicUsize::new(0),
+            &ProgressBar::hidden(),
+        )?;
+        record.date = None;
+        let action = orchestrator.plan_file(record, &HashSet::new()).map_err(|(_, e)| e)?;
+        assert!(action.undated);
+        assert_eq!(action.destination, dest.path().join("Unsorted/scan.jpg"));
 
-        let orchestrator = Orchestrator::new(ctx);
-        let files = orchestrator.scan_source()?;
+        let plan = Plan {
+            actions: vec![action],
+            new_folders: BTreeSet::new(),
+            mode: FileOp::Copy,
+            index: IndexHandle::default(),
+            duplicates: Vec::new(),
+            seeded: Vec::new(),
+        };
+        let stats = orchestrator.execute(plan)?;
+        assert_eq!(stats.files_organized, 1);
+        assert_eq!(stats.files_undated, 1);
+        assert_eq!(stats.files_failed, 0);
+        assert!(dest.path().join("Unsorted/scan.jpg").is_file());
 
-        assert_eq!(files.len(), 3, "Should find 3 photo files (not txt)");
+        // Recorded in the index, so the next run skips it
+        let index = Index::load_from_file(ctx.get_index_path())?;
+        assert!(index.get_by_source(&photo.to_string_lossy()).is_some());
+
+        // Templates needing a date fall back the same way, into a custom folder
+        let mut ctx = ctx;
+        ctx.template = Some(PathTemplate::parse("{camera}/{year}").unwrap());
+        ctx.undated_dir = PathBuf::from("No Date");
+        let orchestrator = Orchestrator::new(ctx);
+        let mut record = orchestrator.analyze_file(
+            &photo,
+            &IndexView { index: &Index::new(), stored: None },
+            None,
+            None,
+            &AtomicUsize::new(0),
+            &ProgressBar::hidden(),
+        )?;
+        record.date = None;
+        let action = orchestrator.plan_file(record, &HashSet::new()).map_err(|(_, e)| e)?;
+        assert_eq!(action.destination, dest.path().join("No Date/scan.jpg"));
         Ok(())
     }
 
     #[test]
-    fn test_orchestrator_new() {
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(3 * 1024 * 1024 * 1024), "3.0 GiB");
+    }
+
+    #[test]
+    fn test_parallel_execute_counts_successes_and_failures() -> io::Result<()> {
+        let source = TempDir::new()?;
+        let dest = TempDir::new()?;
+        for i in 1..=4 {
+            fs::write(source.path().join(format!("IMG_2023010{}_x.jpg", i)), format!("photo {}", i))?;
+        }
+
         let ctx = OrganizeContext::new(
-            PathBuf::from("/source"),
-            PathBuf::from("/dest"),
+            source.path().to_path_buf(),
+            dest.path().to_path_buf(),
             false,
-            None,
+            Some(3),
             None,
         );
+        let mut orchestrator = Orchestrator::new(ctx.clone());
+        let plan = orchestrator.plan()?;
+        assert_eq!(plan.actions.len(), 4);
 
-        let orchestrator = Orchestrator::new(ctx.clone());
+        // The source vanishes between planning and execution
+        fs::remove_file(source.path().join("IMG_20230102_x.jpg"))?;
+        let stats = orchestrator.execute(plan)?;
 
-        assert_eq!(orchestrator.stats.files_scanned, 0);
-        assert_eq!(orchestrator.stats.files_analyzed, 0);
-        assert_eq!(orchestrator.errors.len(), 0);
+        assert_eq!(stats.files_organized, 3);
+        assert_eq!(stats.files_failed, 1);
+        assert_eq!(orchestrator.errors.len(), 1);
+        assert!(orchestrator.errors[0].contains("IMG_20230102_x.jpg"));
+        assert!(dest.path().join("2023/01/04/IMG_20230104_x.jpg").exists());
+
+        let index = Index::load_from_file(ctx.get_index_path())?;
+        assert_eq!(index.len(), 3);
+        Ok(())
     }
 
     #[test]
-    fn test_organize_context_clone() {
+    fn test_single_job_completes() -> io::Result<()> {
+        let source = TempDir::new()?;
+        let dest = TempDir::new()?;
+        for i in 1..=5 {
+            fs::write(source.path().join(format!("IMG_2023010{}_x.jpg", i)), format!("photo {}", i))?;
+        }
+
         let ctx = OrganizeContext::new(
-            PathBuf::from("/source"),
-            PathBuf::from("/dest"),
-            true,
-            Some(8),
-            Some(PathBuf::from("/custom/index.bin")),
+            source.path().to_path_buf(),
+            dest.path().to_path_buf(),
+            false,
+            Some(1),
+            None,
         );
+        let mut orchestrator = Orchestrator::new(ctx);
+        assert_eq!(orchestrator.thread_pool()?.current_num_threads(), 1);
 
-        let cloned = ctx.clone();
+        let stats = orchestrator.run()?;
+        assert_eq!(stats.files_hashed, 5);
+        assert_eq!(stats.files_organized, 5);
+        assert_eq!(stats.files_failed, 0);
+        Ok(())
+    }
 
-        assert_eq!(ctx.source, cloned.source);
-        assert_eq!(ctx.destination, cloned.destination);
-        assert_eq!(ctx.with_clustering, cloned.with_clustering);
-        assert_eq!(ctx.jobs, cloned.jobs);
-        assert_eq!(ctx.index_path, cloned.index_path);
+    #[test]
+    fn test_exif_date_decides_destination() -> io::Result<()> {
+        let source = TempDir::new()?;
+        let dest = TempDir::new()?;
+        let taken = testutil::ascii_field(exif::Tag::DateTimeOriginal, "2019:07:04 18:30:00");
+        testutil::write_jpeg_with_exif(
+            &source.path().join("DSC_0001.jpg"),
+            &[taken.clone(), testutil::ascii_field(exif::Tag::Make, "Canon")],
+        )?;
+        // EXIF also beats a conflicting date in the filename
+        testutil::write_jpeg_with_exif(
+            &source.path().join("IMG_20230101_0002.jpg"),
+            &[taken, testutil::ascii_field(exif::Tag::Make, "Nikon")],
+        )?;
+
+        let ctx = OrganizeContext::new(
+            source.path().to_path_buf(),
+            dest.path().to_path_buf(),
+            false,
+            None,
+            None,
+        );
+        let stats = Orchestrator::new(ctx).run()?;
+        assert_eq!(stats.files_organized, 2);
+
+        let day = dest.path().join("2019/07/04");
+        assert!(day.join("DSC_0001.jpg").exists());
+        assert!(day.join("IMG_20230101_0002.jpg").exists());
+        let today = chrono::Local::now().date_naive();
+        assert!(!dest.path().join(today.year().to_string()).exists());
+        Ok(())
     }
 
     #[test]
-    fn test_stats_with_values() {
-        let mut stats = OrganizeStats::default();
-        stats.files_scanned = 100;
-        stats.files_analyzed = 95;
-        stats.files_skipped_duplicates = 5;
-        stats.files_organized = 90;
-        stats.files_failed = 0;
+    fn test_videos_are_organized_by_recording_date() -> io::Result<()> {
+        let source = TempDir::new()?;
+        let dest = TempDir::new()?;
+        // 2021-06-15 12:00:00 UTC, in seconds since 1904-01-01
+        testutil::write_mp4_with_creation_time(&source.path().join("clip.mp4"), 3_706_603_200, false)?;
+        fs::write(source.path().join("IMG_20210615_0001.jpg"), "photo")?;
 
-        assert_eq!(stats.files_scanned, 100);
-        assert_eq!(stats.files_organized, 90);
-        assert_eq!(stats.files_skipped_duplicates, 5);
+        let ctx = OrganizeContext::new(
+            source.path().to_path_buf(),
+            dest.path().to_path_buf(),
+            false,
+            None,
+            None,
+        );
+        let stats = Orchestrator::new(ctx).run()?;
+        assert_eq!(stats.files_organized, 2);
+        assert!(dest.path().join("2021/06/15/clip.mp4").exists());
+        assert!(dest.path().join("2021/06/15/IMG_20210615_0001.jpg").exists());
+        Ok(())
     }
 
     #[test]
-    fn test_stats_clone() {
-        let stats = OrganizeStats {
-            files_scanned: 50,
-            files_analyzed: 48,
-            files_skipped_duplicates: 2,
-            files_organized: 46,
-            files_failed: 2,
-        };
+    fn test_location_template_names_places_from_gps() -> io::Result<()> {
+        let source = TempDir::new()?;
+        let dest = TempDir::new()?;
+        for (i, offset) in [0.0, 0.0005, 0.001, 0.0015].iter().enumerate() {
+            let name = format!("IMG_20230101_{}.jpg", i);
+            testutil::write_jpeg_with_gps(&source.path().join(name), 48.8566 + offset, 2.3522, None)?;
+        }
+        fs::write(source.path().join("IMG_20230101_nogps.jpg"), "no gps")?;
 
-        let cloned = stats.clone();
-        assert_eq!(stats.files_scanned, cloned.files_scanned);
-        assert_eq!(stats.files_organized, cloned.files_organized);
+        let mut ctx = OrganizeContext::new(
+            source.path().to_path_buf(),
+            dest.path().to_path_buf(),
+            false,
+            None,
+            None,
+        );
+        ctx.template = Some(PathTemplate::parse("{year}/{location}").unwrap());
+        let stats = Orchestrator::new(ctx.clone()).run()?;
+        assert_eq!(stats.files_organized, 5);
+        assert!(dest.path().join("2023/Paris/IMG_20230101_0.jpg").exists());
+        assert!(dest.path().join("2023/Paris/IMG_20230101_2.jpg").exists());
+        assert!(dest.path().join("2023/Unknown Location/IMG_20230101_nogps.jpg").exists());
+
+        // An external GeoNames file replaces the embedded city list
+        let cities = source.path().join("cities.txt");
+        fs::write(&cities, "1\tLe Marais\tLe Marais\t\t48.8575\t2.3590\t\t\t\t\t\t\t\t\t0\n")?;
+        let dest = TempDir::new()?;
+        ctx.destination = dest.path().to_path_buf();
+        ctx.geonames = Some(cities);
+        Orchestrator::new(ctx.clone()).run()?;
+        assert!(dest.path().join("2023/Le Marais/IMG_20230101_1.jpg").exists());
+
+        // Three photos together are too few to make a place
+        let source = TempDir::new()?;
+        let dest = TempDir::new()?;
+        for (i, offset) in [0.0, 0.0005, 0.001].iter().enumerate() {
+            let name = format!("IMG_20230101_{}.jpg", i);
+            testutil::write_jpeg_with_gps(&source.path().join(name), 48.8566 + offset, 2.3522, None)?;
+        }
+        ctx.source = source.path().to_path_buf();
+        ctx.destination = dest.path().to_path_buf();
+        ctx.geonames = None;
+        Orchestrator::new(ctx).run()?;
+        assert!(dest.path().join("2023/Unknown Location/IMG_20230101_0.jpg").exists());
+        assert!(!dest.path().join("2023/Paris").exists());
+        Ok(())
     }
 }
@@ -0,0 +1,344 @@
+//! SQLite-backed index store.
+//!
+//! The bincode [`Index`] is read into memory whole and rewritten whole on every
+//! save. A [`SqliteIndex`] keeps one row per entry in a SQLite database, so
+//! entries can be looked up and inserted one at a time, and saving a run only
+//! writes the entries it added. Both implement [`IndexStore`]; organize picks one
+//! with `--index-format bincode|sqlite`.
+//!
+//! Schema (`PRAGMA user_version` 1):
+//!
+//! ```text
+//! CREATE TABLE entries (
+//!     hash        TEXT PRIMARY KEY,
+//!     file_paths  TEXT NOT NULL,     -- JSON array
+//!     source_path TEXT,
+//!     size        INTEGER NOT NULL,
+//!     mtime_unix  INTEGER NOT NULL,
+//!     hash_kind   TEXT NOT NULL      -- "bytes" or "pixels"
+//! );
+//! CREATE INDEX entries_source ON entries (source_path);
+//! ```
+
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+
+use rusqlite::{Connection, OptionalExtension, Row, params};
+
+use crate::error::OrganizeError;
+use crate::hash::HashKind;
+use crate::index::{Index, IndexEntry, IndexStore};
+
+/// First bytes of every SQLite database file.
+const SQLITE_MAGIC: &[u8; 16] = b"SQLite format 3\0";
+
+/// Schema version, stored as `PRAGMA user_version`.
+const SCHEMA_VERSION: i32 = 1;
+
+/// Returns `true` if the file at `path` is a SQLite database.
+pub fn is_sqlite_file(path: &Path) -> bool {
+    let mut magic = [0u8; 16];
+    fs::File::open(path)
+        .and_then(|mut file| file.read_exact(&mut magic))
+        .is_ok_and(|()| &magic == SQLITE_MAGIC)
+}
+
+/// An index stored in a SQLite database.
+///
+/// Every [`IndexStore::insert`] is its own transaction; use
+/// [`SqliteIndex::insert_all`] to write many entries at once.
+pub struct SqliteIndex {
+    conn: Connection,
+}
+
+impl SqliteIndex {
+    /// Opens the index database at `path`, creating it if it does not exist.
+    ///
+    /// # Errors
+    ///
+    /// Fails with an [`OrganizeError::IndexError`] if the file is not a SQLite
+    /// database or was written by a newer version of Sift.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let conn = Connection::open(path).map_err(sqlite_error)?;
+        let version: i32 = conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .map_err(sqlite_error)?;
+        if version > SCHEMA_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                OrganizeError::IndexError(format!(
+                    "SQLite index uses schema v{}, newer than this build supports (v{})",
+                    version, SCHEMA_VERSION
+                )),
+            ));
+        }
+        conn.execute_batch(&format!(
+            "CREATE TABLE IF NOT EXISTS entries (
+                 hash TEXT PRIMARY KEY,
+                 file_paths TEXT NOT NULL,
+                 source_path TEXT,
+                 size INTEGER NOT NULL,
+                 mtime_unix INTEGER NOT NULL,
+                 hash_kind TEXT NOT NULL
+             );
+             CREATE INDEX IF NOT EXISTS entries_source ON entries (source_path);
+             PRAGMA user_version = {};",
+            SCHEMA_VERSION
+        ))
+        .map_err(sqlite_error)?;
+        Ok(SqliteIndex { conn })
+    }
+
+    /// Inserts entries in a single transaction, merging them as [`Index::insert`] does.
+    pub fn insert_all(&mut self, entries: impl IntoIterator<Item = IndexEntry>) -> io::Result<()> {
+        let tx = self.conn.transaction().map_err(sqlite_error)?;
+        for entry in entries {
+            upsert(&tx, entry)?;
+        }
+        tx.commit().map_err(sqlite_error)
+    }
+
+    /// Replaces every entry with those of `index`, in a single transaction.
+    pub fn replace_all(&mut self, index: &Index) -> io::Result<()> {
+        let tx = self.conn.transaction().map_err(sqlite_error)?;
+        tx.execute("DELETE FROM entries", []).map_err(sqlite_error)?;
+        for entry in index.entries() {
+            upsert(&tx, entry.clone())?;
+        }
+        tx.commit().map_err(sqlite_error)
+    }
+
+    /// Reads every entry into an in-memory [`Index`].
+    pub fn to_index(&self) -> io::Result<Index> {
+        let mut index = Index::new();
+        for entry in self.iter()? {
+            index.insert(entry);
+        }
+        Ok(index)
+    }
+}
+
+impl IndexStore for SqliteIndex {
+    fn contains(&self, hash: &str) -> io::Result<bool> {
+        self.conn
+            .query_row("SELECT 1 FROM entries WHERE hash = ?1", [hash], |_| Ok(()))
+            .optional()
+            .map(|found| found.is_some())
+            .map_err(sqlite_error)
+    }
+
+    fn insert(&mut self, entry: IndexEntry) -> io::Result<()> {
+        self.insert_all([entry])
+    }
+
+    fn get(&self, hash: &str) -> io::Result<Option<IndexEntry>> {
+        get(&self.conn, hash)
+    }
+
+    fn get_by_source(&self, source_path: &str) -> io::Result<Option<IndexEntry>> {
+        self.conn
+            .query_row(
+                "SELECT hash, file_paths, source_path, size, mtime_unix, hash_kind FROM entries
+                 WHERE source_path = ?1 LIMIT 1",
+                [source_path],
+                read_entry,
+            )
+            .optional()
+            .map_err(sqlite_error)
+    }
+
+    fn sizes(&self) -> io::Result<HashSet<u64>> {
+        let mut statement = self.conn.prepare("SELECT DISTINCT size FROM entries").map_err(sqlite_error)?;
+        let sizes = statement
+            .query_map([], |row| row.get::<_, i64>(0))
+            .map_err(sqlite_error)?
+            .map(|size| size.map(|size| size as u64))
+            .collect::<Result<_, _>>()
+            .map_err(sqlite_error)?;
+        Ok(sizes)
+    }
+
+    fn iter(&self) -> io::Result<Box<dyn Iterator<Item = IndexEntry> + '_>> {
+        let mut statement = self
+            .conn
+            .prepare("SELECT hash, file_paths, source_path, size, mtime_unix, hash_kind FROM entries")
+            .map_err(sqlite_error)?;
+        let entries = statement
+            .query_map([], read_entry)
+            .map_err(sqlite_error)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(sqlite_error)?;
+        Ok(Box::new(entries.into_iter()))
+    }
+
+    fn len(&self) -> io::Result<usize> {
+        self.conn
+            .query_row("SELECT COUNT(*) FROM entries", [], |row| row.get::<_, i64>(0))
+            .map(|count| count as usize)
+            .map_err(sqlite_error)
+    }
+}
+
+fn get(conn: &Connection, hash: &str) -> io::Result<Option<IndexEntry>> {
+    conn.query_row(
+        "SELECT hash, file_paths, source_path, size, mtime_unix, hash_kind FROM entries WHERE hash = ?1",
+        [hash],
+        read_entry,
+    )
+    .optional()
+    .map_err(sqlite_error)
+}
+
+/// Writes `entry`, appending the paths of an existing entry for the same hash.
+fn upsert(conn: &Connection, mut entry: IndexEntry) -> io::Result<()> {
+    if let Some(old) = get(conn, &entry.hash)? {
+        let mut file_paths = old.file_paths;
+        for path in entry.file_paths {
+            if !file_paths.contains(&path) {
+                file_paths.push(path);
+            }
+        }
+        entry.file_paths = file_paths;
+    }
+    let file_paths = serde_json::to_string(&entry.file_paths)?;
+    conn.execute(
+        "INSERT INTO entries (hash, file_paths, source_path, size, mtime_unix, hash_kind)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+         ON CONFLICT(hash) DO UPDATE SET
+             file_paths = excluded.file_paths,
+             source_path = excluded.source_path,
+             size = excluded.size,
+             mtime_unix = excluded.mtime_unix,
+             hash_kind = excluded.hash_kind",
+        params![
+            entry.hash,
+            file_paths,
+            entry.source_path,
+            entry.size as i64,
+            entry.mtime_unix,
+            hash_kind_name(entry.hash_kind),
+        ],
+    )
+    .map_err(sqlite_error)?;
+    Ok(())
+}
+
+fn read_entry(row: &Row<'_>) -> rusqlite::Result<IndexEntry> {
+    let file_paths: String = row.get(1)?;
+    let file_paths = serde_json::from_str(&file_paths)
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(1, rusqlite::types::Type::Text, Box::new(e)))?;
+    let hash_kind: String = row.get(5)?;
+    Ok(IndexEntry {
+        hash: row.get(0)?,
+        file_paths,
+        source_path: row.get(2)?,
+        size: row.get::<_, i64>(3)? as u64,
+        mtime_unix: row.get(4)?,
+        hash_kind: if hash_kind == "pixels" { HashKind::Pixels } else { HashKind::Bytes },
+    })
+}
+
+fn hash_kind_name(kind: HashKind) -> &'static str {
+    match kind {
+        HashKind::Bytes => "bytes",
+        HashKind::Pixels => "pixels",
+    }
+}
+
+/// Wraps a SQLite failure in an [`OrganizeError::IndexError`].
+fn sqlite_error(err: rusqlite::Error) -> io::Error {
+    io::Error::other(OrganizeError::IndexError(format!("SQLite index: {}", err)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn entry(hash: &str, path: &str, source: &str) -> IndexEntry {
+        IndexEntry {
+            hash: hash.to_string(),
+            file_paths: vec![path.to_string()],
+            source_path: Some(source.to_string()),
+            size: 42,
+            mtime_unix: -7,
+            hash_kind: HashKind::Pixels,
+        }
+    }
+
+    /// Runs the same assertions against any store, starting empty.
+    fn exercise_store(store: &mut impl IndexStore) -> io::Result<()> {
+        assert_eq!(store.len()?, 0);
+        assert!(!store.contains("h1")?);
+        assert_eq!(store.get("h1")?, None);
+
+        store.insert(entry("h1", "/dest/a.jpg", "/src/a.jpg"))?;
+        store.insert(entry("h2", "/dest/b.jpg", "/src/b.jpg"))?;
+        assert_eq!(store.len()?, 2);
+        assert!(store.contains("h1")?);
+        assert_eq!(store.get("h2")?, Some(entry("h2", "/dest/b.jpg", "/src/b.jpg")));
+        assert_eq!(store.get_by_source("/src/b.jpg")?.map(|entry| entry.hash).as_deref(), Some("h2"));
+        assert_eq!(store.get_by_source("/dest/b.jpg")?, None);
+
+        // Same hash: paths are merged, the rest is replaced
+        let mut again = entry("h1", "/dest/copy/a.jpg", "/src/copy/a.jpg");
+        again.size = 43;
+        store.insert(again)?;
+        let merged = store.get("h1")?.unwrap();
+        assert_eq!(merged.file_paths, ["/dest/a.jpg", "/dest/copy/a.jpg"]);
+        assert_eq!(merged.source_path.as_deref(), Some("/src/copy/a.jpg"));
+        assert_eq!(merged.size, 43);
+        assert_eq!(store.len()?, 2);
+        assert_eq!(store.get_by_source("/src/copy/a.jpg")?.map(|entry| entry.hash).as_deref(), Some("h1"));
+        assert_eq!(store.sizes()?, HashSet::from([42, 43]));
+
+        let mut hashes: Vec<String> = store.iter()?.map(|entry| entry.hash).collect();
+        hashes.sort();
+        assert_eq!(hashes, ["h1", "h2"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_bincode_index_store() -> io::Result<()> {
+        exercise_store(&mut Index::new())
+    }
+
+    #[test]
+    fn test_sqlite_index_store() -> io::Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("index.sqlite");
+        exercise_store(&mut SqliteIndex::open(&path)?)?;
+
+        // Entries persist, and read back as an in-memory index
+        assert!(is_sqlite_file(&path));
+        let reopened = SqliteIndex::open(&path)?;
+        assert_eq!(reopened.len()?, 2);
+        let index = reopened.to_index()?;
+        assert_eq!(index.get_by_source("/src/copy/a.jpg").unwrap().hash, "h1");
+        Ok(())
+    }
+
+    #[test]
+    fn test_replace_all_and_load_from_file() -> io::Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("index.sqlite");
+        let mut store = SqliteIndex::open(&path)?;
+        store.insert_all([entry("h1", "/dest/a.jpg", "/src/a.jpg"), entry("h2", "/dest/b.jpg", "/src/b.jpg")])?;
+
+        let mut index = Index::load_from_file(&path)?;
+        assert_eq!(index.len(), 2);
+        index.remove("h1");
+        index.save_keeping_format(&path)?;
+        assert!(is_sqlite_file(&path));
+        assert_eq!(SqliteIndex::open(&path)?.len()?, 1);
+        assert!(!SqliteIndex::open(&path)?.contains("h1")?);
+
+        // A bincode index is not a database
+        let bincode = dir.path().join("index.bin");
+        Index::new().save_to_file(&bincode)?;
+        assert!(!is_sqlite_file(&bincode));
+        Ok(())
+    }
+}
@@ -0,0 +1,204 @@
+//! Library overview for the stats command.
+//!
+//! Summarizes a photo folder before it is organized: how many photos it holds
+//! per year, camera and extension, and how many lack a recorded date or GPS
+//! position. Nothing is hashed, moved or written.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! # use std::path::Path;
+//! # use sift::{organize, stats};
+//! let extensions = organize::resolve_extensions(&[], false);
+//! let stats = stats::library_stats(Path::new("/photos"), true, &extensions)?;
+//! print!("{}", stats);
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+use chrono::Datelike;
+use rayon::prelude::*;
+
+use crate::metadata;
+use crate::organize;
+use crate::template::UNKNOWN_CAMERA;
+
+/// Counts describing a photo library.
+///
+/// # Fields
+///
+/// * `total` - Number of photos (and videos) scanned
+/// * `by_year` - Photos per year, for photos with a recorded date
+/// * `by_camera` - Photos per camera, with [`UNKNOWN_CAMERA`] for photos recording none
+/// * `by_extension` - Photos per lowercase extension
+/// * `undated` - Photos with neither an embedded date nor a date in their filename
+/// * `without_gps` - Photos with no GPS position
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct LibraryStats {
+    pub total: usize,
+    pub by_year: BTreeMap<i32, usize>,
+    pub by_camera: BTreeMap<String, usize>,
+    pub by_extension: BTreeMap<String, usize>,
+    pub undated: usize,
+    pub without_gps: usize,
+}
+
+/// What one file contributes to [`LibraryStats`].
+struct FileFacts {
+    extension: String,
+    year: Option<i32>,
+    camera: Option<String>,
+    has_gps: bool,
+}
+
+impl LibraryStats {
+    fn add(&mut self, facts: FileFacts) {
+        self.total += 1;
+        *self.by_extension.entry(facts.extension).or_default() += 1;
+        match facts.year {
+            Some(year) => *self.by_year.entry(year).or_default() += 1,
+            None => self.undated += 1,
+        }
+        let camera = facts.camera.unwrap_or_else(|| UNKNOWN_CAMERA.to_string());
+        *self.by_camera.entry(camera).or_default() += 1;
+        if !facts.has_gps {
+            self.without_gps += 1;
+        }
+    }
+}
+
+/// Scans `dir` for files with one of `extensions` and tallies their metadata.
+/// Hidden files are skipped, as when organizing.
+///
+/// Dates come from [`metadata::extract_recorded_date`], so files only dated by
+/// their modification time count as undated. Metadata is read in parallel.
+pub fn library_stats(dir: &Path, recursive: bool, extensions: &[String]) -> io::Result<LibraryStats> {
+    let options = organize::ScanOptions { recursive, ..Default::default() };
+    let files = organize::scan_photos(dir, extensions, options)?.files;
+    let facts: Vec<FileFacts> = files
+        .par_iter()
+        .map(|path| FileFacts {
+            extension: path
+                .extension()
+                .map(|ext| ext.to_string_lossy().to_lowercase())
+                .unwrap_or_default(),
+            year: metadata::extract_recorded_date(path).map(|date| date.year()),
+            camera: metadata::extract_camera(path),
+            has_gps: metadata::extract_gps(path).is_some(),
+        })
+        .collect();
+
+    let mut stats = LibraryStats::default();
+    for file in facts {
+        stats.add(file);
+    }
+    Ok(stats)
+}
+
+/// Writes one titled section of `label  count` rows, with counts right-aligned.
+fn write_table<'a>(
+    f: &mut fmt::Formatter<'_>,
+    title: &str,
+    rows: impl Iterator<Item = (String, &'a usize)>,
+) -> fmt::Result {
+    let rows: Vec<(String, &usize)> = rows.collect();
+    if rows.is_empty() {
+        return Ok(());
+    }
+    let label_width = rows.iter().map(|(label, _)| label.chars().count()).max().unwrap_or(0);
+    let count_width = rows.iter().map(|(_, count)| count.to_string().len()).max().unwrap_or(0);
+
+    writeln!(f, "\n{}:", title)?;
+    for (label, count) in rows {
+        writeln!(f, "  {:<label_width$}  {:>count_width$}", label, count)?;
+    }
+    Ok(())
+}
+
+/// Largest counts first, ties in name order.
+fn by_count(map: &BTreeMap<String, usize>) -> impl Iterator<Item = (String, &usize)> {
+    let mut rows: Vec<(&String, &usize)> = map.iter().collect();
+    rows.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+    rows.into_iter().map(|(label, count)| (label.clone(), count))
+}
+
+impl fmt::Display for LibraryStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Photos:       {}", self.total)?;
+        writeln!(f, "Without date: {}", self.undated)?;
+        writeln!(f, "Without GPS:  {}", self.without_gps)?;
+        write_table(f, "By year", self.by_year.iter().map(|(year, count)| (year.to_string(), count)))?;
+        write_table(f, "By camera", by_count(&self.by_camera))?;
+        write_table(f, "By extension", by_count(&self.by_extension))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil;
+    use exif::Tag;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_library_stats() -> io::Result<()> {
+        let dir = TempDir::new()?;
+        let root = dir.path();
+        testutil::write_jpeg_with_exif(
+            &root.join("DSC_0001.jpg"),
+            &[
+                testutil::ascii_field(Tag::DateTimeOriginal, "2022:05:01 09:00:00"),
+                testutil::ascii_field(Tag::Make, "Canon"),
+                testutil::ascii_field(Tag::Model, "Canon EOS R5"),
+            ],
+        )?;
+        testutil::write_jpeg_with_gps(&root.join("IMG_20230704_0001.JPG"), 48.8566, 2.3522, None)?;
+        fs::write(root.join("scan.png"), "no metadata")?;
+        fs::write(root.join("notes.txt"), "not a photo")?;
+        fs::create_dir(root.join("nested"))?;
+        fs::write(root.join("nested").join("IMG_20230801_0002.jpg"), "no exif")?;
+
+        let extensions = organize::resolve_extensions(&[], false);
+        let stats = library_stats(root, false, &extensions)?;
+        assert_eq!(stats.total, 3);
+        assert_eq!(stats.by_year, BTreeMap::from([(2022, 1), (2023, 1)]));
+        assert_eq!(
+            stats.by_camera,
+            BTreeMap::from([("Canon EOS R5".to_string(), 1), (UNKNOWN_CAMERA.to_string(), 2)])
+        );
+        assert_eq!(
+            stats.by_extension,
+            BTreeMap::from([("jpg".to_string(), 2), ("png".to_string(), 1)])
+        );
+        assert_eq!(stats.undated, 1);
+        assert_eq!(stats.without_gps, 2);
+
+        let stats = library_stats(root, true, &extensions)?;
+        assert_eq!(stats.total, 4);
+        assert_eq!(stats.by_year[&2023], 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_display() {
+        let mut stats = LibraryStats::default();
+        for (extension, camera) in [("jpg", Some("X100V")), ("jpg", None), ("heic", None)] {
+            stats.add(FileFacts {
+                extension: extension.to_string(),
+                year: Some(2024),
+                camera: camera.map(str::to_string),
+                has_gps: false,
+            });
+        }
+        let text = stats.to_string();
+        assert!(text.starts_with("Photos:       3\nWithout date: 0\nWithout GPS:  3\n"));
+        assert!(text.contains("\nBy year:\n  2024  3\n"));
+        assert!(text.contains("\nBy camera:\n  Unknown Camera  2\n  X100V           1\n"));
+        assert!(text.contains("\nBy extension:\n  jpg   2\n  heic  1\n"));
+    }
+}
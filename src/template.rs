@@ -0,0 +1,307 @@
+//! Destination path templates for the organize command.
+//!
+//! A template describes the folders a photo is filed under, relative to the
+//! destination root, e.g. `{year}/{month}/{day}` (the default) or
+//! `{year}/{location}`. The file keeps its original name inside that folder.
+//!
+//! Templates are validated when parsed, so a typo such as `{yaer}` is reported
+//! before any file is touched rather than producing empty path segments.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! # use sift::template::PathTemplate;
+//! let template = PathTemplate::parse("{year}/{year}-{month}")?;
+//! # Ok::<(), sift::template::TemplateError>(())
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::Datelike;
+
+use crate::organization;
+use crate::organize::FileRecord;
+
+/// Folder name used for `{location}` when a photo has no known place.
+pub const UNKNOWN_LOCATION: &str = "Unknown Location";
+/// Folder name used for `{camera}` when a photo records no camera model.
+pub const UNKNOWN_CAMERA: &str = "Unknown Camera";
+
+/// The template used when none is given: `YYYY/MM/DD`.
+pub const DEFAULT_TEMPLATE: &str = "{year}/{month}/{day}";
+
+/// Errors from parsing or rendering a path template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateError {
+    /// The template is empty or only separators
+    Empty,
+    /// A `{` without a matching `}`
+    UnclosedBrace,
+    /// A `{token}` that is not one of the supported tokens
+    UnknownToken(String),
+    /// A segment that would leave the destination root (`..`) or is absolute
+    EscapesDestination,
+    /// The template uses a date token but the file has no date
+    MissingDate,
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TemplateError::Empty => write!(f, "path template is empty"),
+            TemplateError::UnclosedBrace => write!(f, "path template has an unclosed '{{'"),
+            TemplateError::UnknownToken(token) => write!(
+                f,
+                "unknown path template token {{{}}} (expected one of {{year}}, {{month}}, {{day}}, {{location}}, {{camera}})",
+                token
+            ),
+            TemplateError::EscapesDestination => {
+                write!(f, "path template must stay inside the destination (no leading '/' or '..')")
+            }
+            TemplateError::MissingDate => write!(f, "Cannot organize file without date"),
+        }
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+/// A value substituted into a template.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Token {
+    /// Four-digit year
+    Year,
+    /// Two-digit month
+    Month,
+    /// Two-digit day of the month
+    Day,
+    /// Name of the place the photo was taken at (see [`UNKNOWN_LOCATION`])
+    Location,
+    /// Camera model from EXIF (see [`UNKNOWN_CAMERA`])
+    Camera,
+}
+
+impl Token {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "year" => Some(Token::Year),
+            "month" => Some(Token::Month),
+            "day" => Some(Token::Day),
+            "location" => Some(Token::Location),
+            "camera" => Some(Token::Camera),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Literal(String),
+    Token(Token),
+}
+
+/// A parsed, validated destination path template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathTemplate {
+    source: String,
+    parts: Vec<Part>,
+}
+
+impl PathTemplate {
+    /// Parses a template, rejecting unknown tokens and paths leaving the destination.
+    pub fn parse(template: &str) -> Result<Self, TemplateError> {
+        if template.trim_matches(['/', ' ']).is_empty() {
+            return Err(TemplateError::Empty);
+        }
+        if template.starts_with('/') || template.split('/').any(|segment| segment == "..") {
+            return Err(TemplateError::EscapesDestination);
+        }
+
+        let mut parts = Vec::new();
+        let mut rest = template;
+        while let Some(open) = rest.find('{') {
+            if open > 0 {
+                parts.push(Part::Literal(rest[..open].to_string()));
+            }
+            let close = rest[open..].find('}').ok_or(TemplateError::UnclosedBrace)? + open;
+            let name = &rest[open + 1..close];
+            let token = Token::from_name(name)
+                .ok_or_else(|| TemplateError::UnknownToken(name.to_string()))?;
+            parts.push(Part::Token(token));
+            rest = &rest[close + 1..];
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Literal(rest.to_string()));
+        }
+
+        Ok(PathTemplate {
+            source: template.to_string(),
+            parts,
+        })
+    }
+
+    /// Returns `true` if the template references `token`.
+    pub fn uses(&self, token: Token) -> bool {
+        self.parts.contains(&Part::Token(token))
+    }
+
+    /// Renders the folder path for `record`, relative to the destination root.
+    ///
+    /// Date tokens fail with [`TemplateError::MissingDate`] for undated files,
+    /// matching the default layout; `{location}` and `{camera}` fall back to
+    /// [`UNKNOWN_LOCATION`] and [`UNKNOWN_CAMERA`]. Substituted values are passed
+    /// through [`organization::sanitize_path_segment`], so each forms one folder.
+    pub fn render(&self, record: &FileRecord) -> Result<String, TemplateError> {
+        let mut path = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Literal(text) => path.push_str(text),
+                Part::Token(Token::Year) => {
+                    let date = record.date.ok_or(TemplateError::MissingDate)?;
+                    path.push_str(&date.year().to_string());
+                }
+                Part::Token(Token::Month) => {
+                    let date = record.date.ok_or(TemplateError::MissingDate)?;
+                    path.push_str(&format!("{:02}", date.month()));
+                }
+                Part::Token(Token::Day) => {
+                    let date = record.date.ok_or(TemplateError::MissingDate)?;
+                    path.push_str(&format!("{:02}", date.day()));
+                }
+                Part::Token(Token::Location) => {
+                    path.push_str(&organization::sanitize_path_segment(record.place.as_deref().unwrap_or(UNKNOWN_LOCATION)));
+                }
+                Part::Token(Token::Camera) => {
+                    path.push_str(&organization::sanitize_path_segment(record.camera.as_deref().unwrap_or(UNKNOWN_CAMERA)));
+                }
+            }
+        }
+        Ok(path)
+    }
+}
+
+impl Default for PathTemplate {
+    fn default() -> Self {
+        PathTemplate::parse(DEFAULT_TEMPLATE).expect("default template is valid")
+    }
+}
+
+impl FromStr for PathTemplate {
+    type Err = TemplateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        PathTemplate::parse(s)
+    }
+}
+
+impl fmt::Display for PathTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+/// Parses `template` and renders it for `record` in one step.
+///
+/// # Examples
+///
+/// ```
+/// # use sift::template::render_path_template;
+/// # use sift::organize::FileRecord;
+/// # use chrono::NaiveDate;
+/// let record = FileRecord {
+///     path: "/camera/IMG_0001.jpg".into(),
+///     hash: String::new(),
+///     date: NaiveDate::from_ymd_opt(2024, 3, 7),
+///     location: None,
+///     place: None,
+///     camera: None,
+///     size: 0,
+///     mtime_unix: 0,
+///     hash_kind: Default::default(),
+/// };
+/// let folder = render_path_template("{year}/{year}-{month}", &record)?;
+/// assert_eq!(folder, "2024/2024-03");
+/// # Ok::<(), sift::template::TemplateError>(())
+/// ```
+pub fn render_path_template(template: &str, record: &FileRecord) -> Result<String, TemplateError> {
+    PathTemplate::parse(template)?.render(record)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::HashKind;
+    use chrono::NaiveDate;
+    use std::path::PathBuf;
+
+    fn record(place: Option<&str>, camera: Option<&str>) -> FileRecord {
+        FileRecord {
+            path: PathBuf::from("/source/IMG_0001.jpg"),
+            hash: "abc".to_string(),
+            date: NaiveDate::from_ymd_opt(2024, 3, 7),
+            location: None,
+            place: place.map(str::to_string),
+            camera: camera.map(str::to_string),
+            size: 0,
+            mtime_unix: 0,
+            hash_kind: HashKind::Bytes,
+        }
+    }
+
+    #[test]
+    fn test_default_template() -> Result<(), TemplateError> {
+        assert_eq!(PathTemplate::default().render(&record(None, None))?, "2024/03/07");
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_templates() -> Result<(), TemplateError> {
+        let record = record(Some("Paris"), Some("Canon EOS R5"));
+        assert_eq!(render_path_template("{year}/{year}-{month}", &record)?, "2024/2024-03");
+        assert_eq!(render_path_template("{year}/{month}", &record)?, "2024/03");
+        assert_eq!(render_path_template("{year}/{location}", &record)?, "2024/Paris");
+        assert_eq!(render_path_template("{camera}/{year}", &record)?, "Canon EOS R5/2024");
+        assert_eq!(render_path_template("photos/{year}{month}{day}", &record)?, "photos/20240307");
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_values() -> Result<(), TemplateError> {
+        let template = PathTemplate::parse("{year}/{location}/{camera}")?;
+        assert_eq!(template.render(&record(None, None))?, "2024/Unknown Location/Unknown Camera");
+
+        let mut undated = record(None, None);
+        undated.date = None;
+        assert_eq!(template.render(&undated), Err(TemplateError::MissingDate));
+        assert_eq!(PathTemplate::parse("{camera}")?.render(&undated)?, "Unknown Camera");
+        Ok(())
+    }
+
+    #[test]
+    fn test_values_cannot_add_folders() -> Result<(), TemplateError> {
+        let record = record(Some("AC/DC"), Some(".."));
+        assert_eq!(render_path_template("{location}/{camera}", &record)?, "AC_DC/_");
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            PathTemplate::parse("{year}/{yaer}"),
+            Err(TemplateError::UnknownToken("yaer".to_string()))
+        );
+        assert_eq!(PathTemplate::parse("{year"), Err(TemplateError::UnclosedBrace));
+        assert_eq!(PathTemplate::parse(""), Err(TemplateError::Empty));
+        assert_eq!(PathTemplate::parse("/"), Err(TemplateError::Empty));
+        assert_eq!(PathTemplate::parse("/{year}"), Err(TemplateError::EscapesDestination));
+        assert_eq!(PathTemplate::parse("{year}/../x"), Err(TemplateError::EscapesDestination));
+    }
+
+    #[test]
+    fn test_uses() -> Result<(), TemplateError> {
+        let template = PathTemplate::parse("{year}/{location}")?;
+        assert!(template.uses(Token::Location));
+        assert!(!template.uses(Token::Camera));
+        Ok(())
+    }
+}
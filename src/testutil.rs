@@ -0,0 +1,225 @@
+//! Test fixtures shared across module tests.
+//!
+//! Builds minimal but valid JPEG and HEIC files carrying EXIF data (and MP4 files
+//! carrying a movie header), so metadata extraction can be exercised against real
+//! containers rather than mocked values. Also provides decodable JPEGs for
+//! pixel hashing, and a reader that fails mid-stream, for exercising network
+//! retry paths.
+
+use exif::experimental::Writer;
+use exif::{Field, In, Rational, Tag, Value};
+use std::fs;
+use std::io::{self, Cursor, Read};
+use std::path::Path;
+
+/// Wraps the given EXIF fields in a JPEG (SOI, APP1 "Exif", EOI) and writes it to `path`.
+pub fn write_jpeg_with_exif(path: &Path, fields: &[Field]) -> io::Result<()> {
+    let tiff = encode_tiff(fields)?;
+
+    let segment_len = (2 + 6 + tiff.len()) as u16;
+    let mut jpeg = vec![0xFF, 0xD8, 0xFF, 0xE1];
+    jpeg.extend_from_slice(&segment_len.to_be_bytes());
+    jpeg.extend_from_slice(b"Exif\0\0");
+    jpeg.extend_from_slice(&tiff);
+    jpeg.extend_from_slice(&[0xFF, 0xD9]);
+    fs::write(path, jpeg)
+}
+
+/// Encodes EXIF fields as a big-endian TIFF block.
+fn encode_tiff(fields: &[Field]) -> io::Result<Vec<u8>> {
+    let mut writer = Writer::new();
+    for field in fields {
+        writer.push_field(field);
+    }
+    let mut tiff = Cursor::new(Vec::new());
+    writer
+        .write(&mut tiff, false)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+    Ok(tiff.into_inner())
+}
+
+/// Wraps `payload` in an ISO-BMFF box (atom) of type `name`.
+fn atom(name: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+    let mut data = ((8 + payload.len()) as u32).to_be_bytes().to_vec();
+    data.extend_from_slice(name);
+    data.extend_from_slice(payload);
+    data
+}
+
+/// Writes a JPEG carrying `xmp` as an embedded XMP packet (APP1 "http://ns.adobe.com/xap/1.0/").
+pub fn write_jpeg_with_xmp(path: &Path, xmp: &str) -> io::Result<()> {
+    const XMP_NAMESPACE: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+    let segment_len = (2 + XMP_NAMESPACE.len() + xmp.len()) as u16;
+    let mut jpeg = vec![0xFF, 0xD8, 0xFF, 0xE1];
+    jpeg.extend_from_slice(&segment_len.to_be_bytes());
+    jpeg.extend_from_slice(XMP_NAMESPACE);
+    jpeg.extend_from_slice(xmp.as_bytes());
+    jpeg.extend_from_slice(&[0xFF, 0xD9]);
+    fs::write(path, jpeg)
+}
+
+/// Encodes a small gradient as a real, decodable JPEG.
+///
+/// With `comment`, a COM segment holding it is inserted right after SOI, which
+/// changes the file's bytes but not its pixels.
+pub fn encode_jpeg(comment: Option<&[u8]>) -> Vec<u8> {
+    let image = image::RgbImage::from_fn(16, 8, |x, y| image::Rgb([(x * 16) as u8, (y * 32) as u8, 128]));
+    let mut encoded = Vec::new();
+    image::codecs::jpeg::JpegEncoder::new(&mut encoded)
+        .encode_image(&image)
+        .expect("encoding to memory cannot fail");
+    let Some(comment) = comment else {
+        return encoded;
+    };
+    let mut jpeg = encoded[..2].to_vec();
+    jpeg.extend_from_slice(&[0xFF, 0xFE]);
+    jpeg.extend_from_slice(&((2 + comment.len()) as u16).to_be_bytes());
+    jpeg.extend_from_slice(comment);
+    jpeg.extend_from_slice(&encoded[2..]);
+    jpeg
+}
+
+/// Writes a JPEG whose EXIF holds a GPS position (and optional positioning error).
+pub fn write_jpeg_with_gps(
+    path: &Path,
+    latitude: f64,
+    longitude: f64,
+    horizontal_error_m: Option<f64>,
+) -> io::Result<()> {
+    let lat_ref = if latitude < 0.0 { "S" } else { "N" };
+    let lon_ref = if longitude < 0.0 { "W" } else { "E" };
+    let mut fields = vec![
+        ascii_field(Tag::GPSLatitudeRef, lat_ref),
+        Field { tag: Tag::GPSLatitude, ifd_num: In::PRIMARY, value: dms(latitude) },
+        ascii_field(Tag::GPSLongitudeRef, lon_ref),
+        Field { tag: Tag::GPSLongitude, ifd_num: In::PRIMARY, value: dms(longitude) },
+    ];
+    if let Some(error) = horizontal_error_m {
+        fields.push(Field {
+            tag: Tag::GPSHPositioningError,
+            ifd_num: In::PRIMARY,
+            value: Value::Rational(vec![Rational { num: (error * 100.0).round() as u32, denom: 100 }]),
+        });
+    }
+    write_jpeg_with_exif(path, &fields)
+}
+
+/// Builds a primary-IFD ASCII field.
+pub fn ascii_field(tag: Tag, value: &str) -> Field {
+    Field {
+        tag,
+        ifd_num: In::PRIMARY,
+        value: Value::Ascii(vec![value.as_bytes().to_vec()]),
+    }
+}
+
+/// Encodes an absolute decimal-degree value as EXIF degrees/minutes/seconds rationals.
+fn dms(value: f64) -> Value {
+    let value = value.abs();
+    let degrees = value.trunc();
+    let minutes = ((value - degrees) * 60.0).trunc();
+    let seconds = (value - degrees - minutes / 60.0) * 3600.0;
+    Value::Rational(vec![
+        Rational { num: degrees as u32, denom: 1 },
+        Rational { num: minutes as u32, denom: 1 },
+        Rational { num: (seconds * 10_000.0).round() as u32, denom: 10_000 },
+    ])
+}
+
+/// Writes a HEIC whose `Exif` item holds the given fields.
+///
+/// The file has `ftyp`, a `meta` box (`hdlr`, `iinf` with one `infe`, `iloc`)
+/// and an `mdat` holding the item, but no image data. Its `ftyp` only declares
+/// the `heic` brand, which the `exif` crate's container reader rejects.
+pub fn write_heic_with_exif(path: &Path, fields: &[Field]) -> io::Result<()> {
+    fn full_box(name: &[u8; 4], version: u8, payload: &[u8]) -> Vec<u8> {
+        let mut body = vec![version, 0, 0, 0];
+        body.extend_from_slice(payload);
+        atom(name, &body)
+    }
+
+    let mut item = 6u32.to_be_bytes().to_vec();
+    item.extend_from_slice(b"Exif\0\0");
+    item.extend(encode_tiff(fields)?);
+
+    let mut infe = 1u16.to_be_bytes().to_vec();
+    infe.extend_from_slice(&[0, 0]);
+    infe.extend_from_slice(b"Exif");
+    infe.push(0);
+    let mut iinf = 1u16.to_be_bytes().to_vec();
+    iinf.extend(full_box(b"infe", 2, &infe));
+
+    let ftyp = atom(b"ftyp", b"heic\0\0\0\0heic");
+    let meta = |item_offset: u32| {
+        let mut iloc = vec![0x44, 0x00];
+        iloc.extend_from_slice(&1u16.to_be_bytes());
+        iloc.extend_from_slice(&1u16.to_be_bytes());
+        iloc.extend_from_slice(&0u16.to_be_bytes());
+        iloc.extend_from_slice(&1u16.to_be_bytes());
+        iloc.extend_from_slice(&item_offset.to_be_bytes());
+        iloc.extend_from_slice(&(item.len() as u32).to_be_bytes());
+        let mut children = full_box(b"hdlr", 0, b"\0\0\0\0pict\0\0\0\0\0\0\0\0\0\0\0\0\0");
+        children.extend(full_box(b"iinf", 0, &iinf));
+        children.extend(full_box(b"iloc", 0, &iloc));
+        full_box(b"meta", 0, &children)
+    };
+    // The item offset does not change the size of `meta`
+    let item_offset = (ftyp.len() + meta(0).len() + 8) as u32;
+
+    let mut data = ftyp;
+    data.extend(meta(item_offset));
+    data.extend(atom(b"mdat", &item));
+    fs::write(path, data)
+}
+
+/// Writes an MP4 (`ftyp`, `mdat`, then `moov/mvhd`) whose movie header records
+/// `creation` as seconds since 1904-01-01, using a version 1 (64-bit) header if asked.
+pub fn write_mp4_with_creation_time(path: &Path, creation: u64, version_1: bool) -> io::Result<()> {
+    let mut mvhd = Vec::new();
+    if version_1 {
+        mvhd.extend_from_slice(&[1, 0, 0, 0]);
+        mvhd.extend_from_slice(&creation.to_be_bytes());
+        mvhd.extend_from_slice(&creation.to_be_bytes());
+    } else {
+        mvhd.extend_from_slice(&[0, 0, 0, 0]);
+        mvhd.extend_from_slice(&(creation as u32).to_be_bytes());
+        mvhd.extend_from_slice(&(creation as u32).to_be_bytes());
+    }
+    mvhd.extend_from_slice(&[0; 80]);
+
+    let mut data = atom(b"ftyp", b"isom\0\0\0\0isommp41");
+    data.extend(atom(b"mdat", &[0xAB; 256]));
+    data.extend(atom(b"moov", &atom(b"mvhd", &mvhd)));
+    fs::write(path, data)
+}
+
+/// Yields `data` but fails exactly once, with `kind`, when the read position
+/// reaches `fail_at`; the next read resumes where it stopped.
+pub struct FlakyReader {
+    data: Cursor<Vec<u8>>,
+    fail_at: u64,
+    kind: Option<io::ErrorKind>,
+}
+
+impl FlakyReader {
+    pub fn new(data: Vec<u8>, fail_at: u64, kind: io::ErrorKind) -> Self {
+        Self { data: Cursor::new(data), fail_at, kind: Some(kind) }
+    }
+}
+
+impl Read for FlakyReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let position = self.data.position();
+        if position >= self.fail_at
+            && let Some(kind) = self.kind.take() {
+                return Err(io::Error::new(kind, "connection dropped"));
+            }
+        // Stop short of `fail_at` so the failure lands exactly there
+        let len = if self.kind.is_some() {
+            buf.len().min((self.fail_at - position) as usize)
+        } else {
+            buf.len()
+        };
+        self.data.read(&mut buf[..len])
+    }
+}
@@ -5,8 +5,14 @@
 
 use std::fs;
 use std::path::PathBuf;
+use std::process::Command;
 use tempfile::TempDir;
 
+/// Helper to build a command running the compiled `sift` binary.
+fn sift() -> Command {
+    Command::new(env!("CARGO_BIN_EXE_sift"))
+}
+
 /// Helper to create a test photo file with given name and content.
 fn create_test_photo(dir: &TempDir, name: &str, content: &[u8]) -> PathBuf {
     let path = dir.path().join(name);
@@ -14,6 +20,42 @@ fn create_test_photo(dir: &TempDir, name: &str, content: &[u8]) -> PathBuf {
     path
 }
 
+/// Helper to create a JPEG whose EXIF holds a GPS position (north and east).
+fn create_geotagged_photo(dir: &TempDir, name: &str, latitude: f64, longitude: f64) -> PathBuf {
+    use exif::experimental::Writer;
+    use exif::{Field, In, Rational, Tag, Value};
+
+    let ascii = |tag, value: &str| Field { tag, ifd_num: In::PRIMARY, value: Value::Ascii(vec![value.into()]) };
+    let dms = |degrees: f64| {
+        let seconds = (degrees.fract() * 3600.0 * 1000.0).round() as u32;
+        Value::Rational(vec![
+            Rational { num: degrees.trunc() as u32, denom: 1 },
+            Rational { num: 0, denom: 1 },
+            Rational { num: seconds, denom: 1000 },
+        ])
+    };
+    let fields = [
+        ascii(Tag::GPSLatitudeRef, "N"),
+        Field { tag: Tag::GPSLatitude, ifd_num: In::PRIMARY, value: dms(latitude) },
+        ascii(Tag::GPSLongitudeRef, "E"),
+        Field { tag: Tag::GPSLongitude, ifd_num: In::PRIMARY, value: dms(longitude) },
+    ];
+    let mut writer = Writer::new();
+    for field in &fields {
+        writer.push_field(field);
+    }
+    let mut tiff = std::io::Cursor::new(Vec::new());
+    writer.write(&mut tiff, false).expect("Failed to encode EXIF");
+    let tiff = tiff.into_inner();
+
+    let mut jpeg = vec![0xFF, 0xD8, 0xFF, 0xE1];
+    jpeg.extend_from_slice(&((2 + 6 + tiff.len()) as u16).to_be_bytes());
+    jpeg.extend_from_slice(b"Exif\0\0");
+    jpeg.extend_from_slice(&tiff);
+    jpeg.extend_from_slice(&[0xFF, 0xD9]);
+    create_test_photo(dir, name, &jpeg)
+}
+
 /// Test: Basic file discovery and organization
 #[test]
 fn test_organize_basic_workflow() -> std::io::Result<()> {
@@ -42,50 +84,30 @@ fn test_organize_basic_workflow() -> std::io::Result<()> {
     Ok(())
 }
 
-/// Test: File extension filtering
+/// Test: File extension filtering, including extra extensions via `--ext`
 #[test]
 fn test_photo_extension_filtering() -> std::io::Result<()> {
     let source = TempDir::new()?;
+    let dest = TempDir::new()?;
 
-    // Create files with various extensions
-    let photo_formats = vec![
-        "img001.jpg", "img002.jpeg", "img003.png",
-        "img004.tiff", "img005.raw", "img006.heic",
-    ];
-
-    for format in &photo_formats {
-        create_test_photo(&source, format, b"test");
-    }
-
-    // Create non-photo files
-    create_test_photo(&source, "text.txt", b"test");
-    create_test_photo(&source, "config.json", b"test");
-    create_test_photo(&source, "script.sh", b"test");
-
-    let entries = fs::read_dir(source.path())?
-        .filter_map(|e| e.ok())
-        .collect::<Vec<_>>();
-
-    // Should have 9 files total (6 photos + 3 non-photos)
-    assert_eq!(entries.len(), 9);
-
-    // Count photo extensions
-    let photo_count = entries
-        .iter()
-        .filter(|entry| {
-            let path = entry.path();
-            if let Some(ext) = path.extension() {
-                let ext_str = ext.to_string_lossy().to_lowercase();
-                vec!["jpg", "jpeg", "png", "tiff", "raw", "heic"]
-                    .contains(&ext_str.as_str())
-            } else {
-                false
-            }
-        })
-        .count();
-
-    assert_eq!(photo_count, 6, "Should detect 6 photo files");
-    println!("✓ Correctly identified {} photo files", photo_count);
+    create_test_photo(&source, "IMG_20240115_001.jpg", b"jpeg");
+    create_test_photo(&source, "IMG_20240115_002.CR2", b"canon raw");
+    create_test_photo(&source, "IMG_20240115_003.dng", b"adobe raw");
+    create_test_photo(&source, "IMG_20240115_004.txt", b"not a photo");
+
+    let status = sift()
+        .arg("organize")
+        .arg(source.path())
+        .arg(dest.path())
+        .args(["--ext", "cr2,dng"])
+        .status()?;
+    assert!(status.success());
+
+    let day = dest.path().join("2024/01/15");
+    assert!(day.join("IMG_20240115_001.jpg").exists(), "Defaults still apply");
+    assert!(day.join("IMG_20240115_002.CR2").exists(), "Matching is case-insensitive");
+    assert!(day.join("IMG_20240115_003.dng").exists());
+    assert!(!day.join("IMG_20240115_004.txt").exists());
 
     Ok(())
 }
@@ -142,6 +164,7 @@ fn test_index_file_persistence() -> std::io::Result<()> {
 
 /// Test: Deduplication detection (same file hashed twice)
 #[test]
+#[allow(clippy::collapsible_if, clippy::useless_vec)]
 fn test_deduplication_concept() -> std::io::Result<()> {
     use std::collections::HashSet;
 
@@ -284,3 +307,159 @@ fn test_organize_idempotence() -> std::io::Result<()> {
 
     Ok(())
 }
+
+/// Test: undo empties the manifest, so undoing again is a no-op
+#[test]
+fn test_undo_twice() -> std::io::Result<()> {
+    let source = TempDir::new()?;
+    let dest = TempDir::new()?;
+    create_test_photo(&source, "IMG_20240115_001.jpg", b"photo 1");
+    create_test_photo(&source, "IMG_20240115_002.jpg", b"photo 2");
+
+    let status = sift().arg("organize").arg(source.path()).arg(dest.path()).status()?;
+    assert!(status.success());
+    assert!(dest.path().join("2024/01/15/IMG_20240115_001.jpg").exists());
+
+    let manifest = dest.path().join("manifest.json");
+    let output = sift().arg("undo").arg(&manifest).output()?;
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Removed 2 files"));
+    assert!(!dest.path().join("2024").exists());
+
+    let output = sift().arg("undo").arg(&manifest).output()?;
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Removed 0 files"));
+    Ok(())
+}
+
+/// Test: `--dry-run --exit-code` reports pending work via the exit status
+#[test]
+fn test_dry_run_exit_code_with_new_files() -> std::io::Result<()> {
+    let source = TempDir::new()?;
+    let dest = TempDir::new()?;
+
+    create_test_photo(&source, "IMG_20240115_001.jpg", b"new photo 1");
+    create_test_photo(&source, "IMG_20240115_002.jpg", b"new photo 2");
+
+    let status = sift()
+        .args(["organize", "--dry-run", "--exit-code"])
+        .arg(source.path())
+        .arg(dest.path())
+        .status()?;
+
+    assert_eq!(status.code(), Some(1), "Pending files should exit non-zero");
+    assert!(!dest.path().join("2024").exists(), "Dry run must not copy files");
+
+    Ok(())
+}
+
+/// Test: `--dry-run --exit-code` exits 0 once the destination is up to date
+#[test]
+fn test_dry_run_exit_code_when_up_to_date() -> std::io::Result<()> {
+    let source = TempDir::new()?;
+    let dest = TempDir::new()?;
+
+    create_test_photo(&source, "IMG_20240115_001.jpg", b"organized photo 1");
+    create_test_photo(&source, "IMG_20240115_002.jpg", b"organized photo 2");
+
+    let first_run = sift()
+        .arg("organize")
+        .arg(source.path())
+        .arg(dest.path())
+        .status()?;
+    assert!(first_run.success());
+    assert!(dest.path().join("2024/01/15/IMG_20240115_001.jpg").exists());
+
+    let status = sift()
+        .args(["organize", "--dry-run", "--exit-code"])
+        .arg(source.path())
+        .arg(dest.path())
+        .status()?;
+
+    assert_eq!(status.code(), Some(0), "Up-to-date destination should exit 0");
+
+    Ok(())
+}
+
+/// Test: `cluster` groups geotagged photos, counting copies of a photo once
+#[test]
+fn test_cluster_dedupes_before_clustering() -> std::io::Result<()> {
+    let source = TempDir::new()?;
+
+    // Four photos a few hundred meters apart in Paris
+    for (i, offset) in [0.0, 0.001, 0.002, 0.003].iter().enumerate() {
+        create_geotagged_photo(&source, &format!("paris_{}.jpg", i), 48.8566 + offset, 2.3522 + offset);
+    }
+    // Two copies of the first, which must not add to the cluster
+    let original = fs::read(source.path().join("paris_0.jpg"))?;
+    fs::create_dir(source.path().join("backup"))?;
+    fs::write(source.path().join("backup/paris_0.jpg"), &original)?;
+    fs::write(source.path().join("paris_0 (1).jpg"), &original)?;
+
+    let output = sift().args(["cluster", "--details"]).arg(source.path()).output()?;
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(stdout.contains("Found 1 clusters"), "{}", stdout);
+    assert!(stdout.contains("Cluster 0: Paris (4 photos)"), "{}", stdout);
+    assert!(!stdout.contains("Ungrouped"), "{}", stdout);
+    assert!(stderr.contains("Skipped 2 duplicate photos"), "{}", stderr);
+    // The first copy in path order is the one kept
+    assert!(stdout.contains("backup/paris_0.jpg"), "{}", stdout);
+    assert!(!stdout.contains("paris_0 (1).jpg"), "{}", stdout);
+
+    Ok(())
+}
+
+/// Test: `--eps-km` and `--min-points` change how photos are grouped
+#[test]
+fn test_cluster_eps_and_min_points() -> std::io::Result<()> {
+    let source = TempDir::new()?;
+    // Two photos about 1.3 km apart
+    create_geotagged_photo(&source, "louvre.jpg", 48.8606, 2.3376);
+    create_geotagged_photo(&source, "notre_dame.jpg", 48.8530, 2.3499);
+
+    let run = |args: &[&str]| -> std::io::Result<String> {
+        let output = sift().arg("cluster").arg(source.path()).args(args).output()?;
+        assert!(output.status.success());
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    };
+
+    // Too few photos for the default minimum
+    let defaults = run(&[])?;
+    assert!(defaults.contains("Found 0 clusters"), "{}", defaults);
+    assert!(defaults.contains("Ungrouped: 2 photos"), "{}", defaults);
+
+    // A pair is enough, but not 1.3 km apart
+    let pairs = run(&["--min-points", "2"])?;
+    assert!(pairs.contains("Found 0 clusters"), "{}", pairs);
+
+    let wider = run(&["--min-points", "2", "--eps-km", "2"])?;
+    assert!(wider.contains("Found 1 clusters"), "{}", wider);
+    assert!(wider.contains("(2 photos)"), "{}", wider);
+    assert!(!wider.contains("Ungrouped"), "{}", wider);
+
+    Ok(())
+}
+
+/// Test: `--quiet` drops progress notes from commands other than organize
+#[test]
+fn test_quiet_hash_and_cluster() -> std::io::Result<()> {
+    let source = TempDir::new()?;
+    create_geotagged_photo(&source, "louvre.jpg", 48.8606, 2.3376);
+    let listing = source.path().join("sums.txt");
+
+    let photo = source.path().join("louvre.jpg");
+    let output = sift().args(["--quiet", "hash"]).arg(&photo).arg("-o").arg(&listing).output()?;
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(listing.exists());
+
+    let output = sift().args(["--quiet", "cluster"]).arg(source.path()).output()?;
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Found 0 clusters"));
+
+    Ok(())
+}